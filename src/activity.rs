@@ -0,0 +1,162 @@
+//! What happened to a board's cards. Local boards append a line per card
+//! created, moved, edited or archived to `.flow/events.jsonl` as it happens;
+//! for other boards the TUI keeps the session's events, taken from
+//! [`events`](crate::events). `g` lists them and `t` reads the moves for
+//! throughput and time in column.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{dates, events::Event, model::Move};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// RFC 3339 UTC.
+    pub at: String,
+    pub card: String,
+    #[serde(flatten)]
+    pub kind: Kind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum Kind {
+    Created {
+        column: String,
+    },
+    /// `from` is unknown in a session log.
+    Moved {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        from: Option<String>,
+        to: String,
+    },
+    /// `what` as in [`Event::CardEdited`].
+    Edited {
+        what: String,
+    },
+    Archived,
+}
+
+impl Entry {
+    /// `kind` happening to `card` now.
+    pub fn now(card: &str, kind: Kind) -> Self {
+        Entry {
+            at: dates::format_timestamp(dates::now_secs()),
+            card: card.to_string(),
+            kind,
+        }
+    }
+
+    /// The entry for a card event, stamped now; `None` for other events.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let (card, kind) = match event.clone() {
+            Event::CardCreated { card_id, column } => (card_id, Kind::Created { column }),
+            Event::CardMoved { card_id, to } => (card_id, Kind::Moved { from: None, to }),
+            Event::CardEdited { card_id, what } => (card_id, Kind::Edited { what }),
+            Event::CardArchived { card_id } => (card_id, Kind::Archived),
+            _ => return None,
+        };
+        Some(Self::now(&card, kind))
+    }
+}
+
+pub fn path(root: &Path) -> PathBuf {
+    root.join(".flow").join("events.jsonl")
+}
+
+pub fn append(root: &Path, entry: &Entry) -> io::Result<()> {
+    let path = path(root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Every entry, oldest first; none without a log. Lines that don't parse,
+/// e.g. one cut short by a crash, are skipped.
+pub fn read(root: &Path) -> io::Result<Vec<Entry>> {
+    match fs::read_to_string(path(root)) {
+        Ok(text) => Ok(text
+            .lines()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// The entries as moves between columns, creation counting as a move in.
+pub fn moves(entries: &[Entry]) -> Vec<Move> {
+    entries
+        .iter()
+        .filter_map(|e| {
+            let (from, to) = match &e.kind {
+                Kind::Created { column } => (None, column),
+                Kind::Moved { from, to } => (from.clone(), to),
+                Kind::Edited { .. } | Kind::Archived => return None,
+            };
+            Some(Move {
+                card_id: e.card.clone(),
+                from,
+                to: to.clone(),
+                at: dates::parse_timestamp(&e.at)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_append_and_read_back_as_moves() {
+        let root = std::env::temp_dir().join(format!("flow-activity-{}", dates::now_millis()));
+        let created = Entry {
+            at: "2024-06-01T09:30:00Z".to_string(),
+            card: "A-1".to_string(),
+            kind: Kind::Created {
+                column: "todo".to_string(),
+            },
+        };
+        let moved = Entry {
+            at: "2024-06-02T09:30:00Z".to_string(),
+            card: "A-1".to_string(),
+            kind: Kind::Moved {
+                from: Some("todo".to_string()),
+                to: "doing".to_string(),
+            },
+        };
+        let archived = Entry {
+            at: "2024-06-03T09:30:00Z".to_string(),
+            card: "A-1".to_string(),
+            kind: Kind::Archived,
+        };
+        append(&root, &created).unwrap();
+        append(&root, &moved).unwrap();
+        append(&root, &archived).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path(&root))
+            .unwrap()
+            .write_all(b"{\"at\":")
+            .unwrap();
+
+        let entries = read(&root).unwrap();
+        assert_eq!(entries, [created, moved, archived]);
+        let moves = moves(&entries);
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].from, None);
+        assert_eq!(moves[1].from.as_deref(), Some("todo"));
+        assert_eq!(moves[1].at - moves[0].at, 86_400);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
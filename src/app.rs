@@ -1,4 +1,27 @@
-use crate::model::Board;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashSet, VecDeque},
+};
+
+use crossterm::event::KeyCode;
+
+use crate::{
+    activity,
+    card_template::CardTemplate,
+    config::RowStyle,
+    dates,
+    events::Event,
+    filter::{self, Expr},
+    freeze::Lock,
+    graph::LinkKind,
+    keymap::Keymap,
+    matcher::{self, Matcher, Mode},
+    model::{Board, Card, Column, Mention, Move, QuickFilter, User},
+    picker::Picker,
+    prefetch::DetailCache,
+    sla,
+    stats::{self, Stats},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Action {
@@ -10,8 +33,94 @@ pub enum Action {
     SelectDown,
     MoveLeft,
     MoveRight,
+    /// Opens a column picker; the event loop sends the move it picks.
+    MoveTo,
     ToggleDetail,
+    ToggleProblems,
+    ToggleDebug,
+    ToggleHeat,
+    ToggleDiff,
+    CreateCard,
     Refresh,
+    Search,
+    NextMatch,
+    PrevMatch,
+    /// Handled by the event loop, which owns the terminal.
+    Edit,
+    ToggleHelp,
+    ToggleGraph,
+    /// Handled by the event loop, which owns the move queue.
+    Undo,
+    SwitchBoard,
+    ToggleSlaOnly,
+    OpenMention,
+    ReplyMention,
+    Archive,
+    /// Handled by the event loop, which loads archived cards from the provider.
+    ToggleArchive,
+    /// Handled by the event loop, which reads the board's daily snapshots.
+    History,
+    /// Handled by the event loop, which owns the worker.
+    LoadMore,
+    /// Handled by the event loop, which loads the provider's quick filters.
+    QuickFilters,
+    PageUp,
+    PageDown,
+    /// Handled by the event loop, which holds the instance lock.
+    TakeOver,
+    Estimate,
+    Link,
+    Comment,
+    /// Handled by the event loop, which knows the card's URL.
+    Yank,
+    /// Handled by the event loop, which owns the move queue and runs the
+    /// `[start_work]` command.
+    StartWork,
+    ServerSearch,
+    Policy,
+    /// Handled by the event loop, which suspends the TUI for `$EDITOR`.
+    Reorder,
+    /// Handled by the event loop, which asks the provider who can be assigned.
+    Assign,
+    /// Opens the filter bar, holding the current filter to edit.
+    Filter,
+    /// Shows or hides the board statistics overlay.
+    Stats,
+    /// Lists recent activity to jump to a card from.
+    Activity,
+    /// Pulls a git-backed board's repository and reloads.
+    GitPull,
+    /// Pushes a git-backed board's commits.
+    GitPush,
+    /// Starts or ends marking a run of cards to move or archive together.
+    Visual,
+}
+
+impl Action {
+    /// Changes the board, so it's refused while another flow owns it.
+    pub fn writes(self) -> bool {
+        matches!(
+            self,
+            Action::MoveLeft
+                | Action::MoveRight
+                | Action::MoveTo
+                | Action::Undo
+                | Action::CreateCard
+                | Action::Edit
+                | Action::Archive
+                | Action::Estimate
+                | Action::Link
+                | Action::StartWork
+                | Action::Reorder
+                | Action::Assign
+        )
+    }
+}
+
+/// The `f` filter: the text as typed and what it parsed to.
+pub struct BoardFilter {
+    pub text: String,
+    expr: Expr,
 }
 
 pub struct App {
@@ -19,7 +128,395 @@ pub struct App {
     pub col: usize,
     pub row: usize,
     pub detail_open: bool,
+    /// Lines scrolled off the top of the detail popup.
+    pub detail_scroll: usize,
+    /// How far the detail popup could scroll at its last draw, which is the
+    /// only place that knows the wrapped height.
+    pub detail_overflow: Cell<usize>,
+    /// Each column's first visible row at the last draw, which is where the
+    /// column heights are known.
+    pub col_offsets: RefCell<Vec<usize>>,
+    pub problems_open: bool,
+    pub problem_row: usize,
+    pub debug_open: bool,
+    /// The `t` overlay's numbers, from when it opened.
+    pub stats: Option<Stats>,
+    /// Shade column headers by card count against their budget.
+    pub heat: bool,
+    /// Title being typed for a new card in the focused column.
+    pub create_prompt: Option<String>,
+    /// Card templates `n` offers before asking for a title.
+    pub templates: Vec<CardTemplate>,
+    /// Set while the `n` picker lists `templates`.
+    pub choosing_template: bool,
+    /// The template the card being typed is made from.
+    pub create_template: Option<CardTemplate>,
     pub banner: Option<String>,
+    /// Cards with a move in flight or queued. They can't be moved again until
+    /// the provider answers.
+    pub pending: HashSet<String>,
+    /// Cards the last refresh no longer returned, kept for one refresh cycle.
+    pub disappeared: Vec<Disappeared>,
+    /// The selected card's description as it was before a refresh changed it.
+    pub changed: Option<RemoteChange>,
+    pub diff_open: bool,
+    /// An edit that collided with a change made on disk, awaiting resolution.
+    pub conflict: Option<MergeConflict>,
+    /// `/` search. Non-matching cards are dimmed while it is set.
+    pub search: Option<Search>,
+    pub keymap: Keymap,
+    pub help_open: bool,
+    /// The blocks / parent link graph overlay.
+    pub graph_open: bool,
+    /// Reversible changes, newest last.
+    pub undo: Vec<Undo>,
+    /// `b` board switcher, the steps of `linking`, the `m` column list, or
+    /// what `y` copies.
+    pub picker: Option<Picker>,
+    /// The card the `m` column picker moves.
+    pub moving_to: Option<String>,
+    /// The card `v` started marking from; the marked cards run from it to
+    /// the cursor.
+    pub visual: Option<String>,
+    /// Marked cards the `m` column picker moves together.
+    pub batch_move: Option<Vec<String>>,
+    /// Marked cards waiting on a `y` to archive together.
+    pub batch_archive: Option<Vec<String>>,
+    /// The batch the worker is moving or archiving.
+    pub batch: Option<Batch>,
+    /// A link being made from the open card with `i`.
+    pub linking: Option<Linking>,
+    /// The texts the `y` picker offers, in its order.
+    pub yanking: Option<Vec<String>>,
+    /// Text being typed for `s` to search the provider with.
+    pub server_prompt: Option<String>,
+    /// The cards the `s` search found, in the picker's order.
+    pub found: Option<Vec<Card>>,
+    /// The card the `a` picker assigns, and the people it lists after
+    /// "Unassigned".
+    pub assigning: Option<(String, Vec<User>)>,
+    /// The cards the `g` activity list names, in its order.
+    pub browsing: Option<Vec<String>>,
+    /// Dim every card that isn't over its column's time-in-status SLA.
+    pub sla_only: bool,
+    /// Text being typed in the `f` filter bar.
+    pub filter_prompt: Option<String>,
+    /// Hides the cards it doesn't match; kept across refreshes.
+    pub filter: Option<BoardFilter>,
+    /// The last search's mode, which the next one starts in.
+    pub search_mode: Mode,
+    /// Unread mentions, oldest first; the first is shown as a toast.
+    pub mentions: VecDeque<Mention>,
+    /// Comment ids already shown, so each mention toasts once.
+    seen_mentions: HashSet<String>,
+    /// Reply being typed to the toasted mention.
+    pub reply_prompt: Option<String>,
+    /// The open card and the comment being typed on it.
+    pub comment_prompt: Option<(String, String)>,
+    /// The latest board events, newest last, for the debug panel.
+    pub events: VecDeque<Event>,
+    /// This session's card events, oldest first: the `g` list on boards
+    /// without an activity log.
+    pub session_log: Vec<activity::Entry>,
+    /// Card `d` is waiting on a `y` to archive.
+    pub archive_prompt: Option<String>,
+    /// A move into a finished-work column waiting on a `y` once its policy
+    /// has been read: the card and the column index.
+    pub done_prompt: Option<(String, usize)>,
+    /// The focused column's policy is shown under its header.
+    pub policy_open: bool,
+    /// Shown in the archive prompt; see `provider::archive_effects`.
+    pub archive_effects: Vec<String>,
+    /// The `A` view: archived cards and the selected row.
+    pub archived: Option<Vec<Card>>,
+    pub archived_row: usize,
+    /// The `z` view: the board on a past day.
+    pub history: Option<HistoryView>,
+    /// The `F` overlay, open while toggling quick filters.
+    pub quick_menu: Option<QuickFilterMenu>,
+    pub estimation: Option<Estimation>,
+    /// Quick filters the board is loaded with.
+    pub quick_filters: Vec<QuickFilter>,
+    /// Another flow owns this local board; `writes()` actions are refused.
+    pub read_only: bool,
+    /// Comments and attachments fetched for the detail popup.
+    pub details: DetailCache,
+    /// The card whose details are being fetched.
+    pub fetching: Option<String>,
+    /// The last card whose details failed to load, and why.
+    pub detail_failed: Option<(String, String)>,
+    /// The board shown is the offline cache, and moves are being queued.
+    pub offline: bool,
+    /// The board's `flow lock`, while one holds.
+    pub lock: Option<Lock>,
+    pub rows: RowStyle,
+    /// The provider and board, e.g. `jira · work`, for the status bar.
+    pub source: String,
+    /// When the board shown was loaded, in seconds since the epoch.
+    pub loaded_at: Option<u64>,
+}
+
+const UNDO_LIMIT: usize = 100;
+const EVENT_LOG: usize = 8;
+const SESSION_LOG: usize = 1000;
+/// How many entries the `g` list shows, newest first.
+const ACTIVITY_SHOWN: usize = 200;
+const DETAIL_PAGE: isize = 10;
+
+pub enum Undo {
+    /// `card_id` left column `from`, where it sat at `row`.
+    Move {
+        card_id: String,
+        from: String,
+        row: usize,
+    },
+}
+
+impl Undo {
+    pub fn card_id(&self) -> &str {
+        match self {
+            Undo::Move { card_id, .. } => card_id,
+        }
+    }
+}
+
+/// Cards moving or archiving in one provider call.
+pub struct Batch {
+    pub card_ids: Vec<String>,
+    /// The column title they're moving to; none when archiving.
+    pub to: Option<String>,
+}
+
+impl Batch {
+    /// The banner once `done` cards have landed.
+    pub fn progress(&self, done: usize) -> String {
+        let total = self.card_ids.len();
+        match &self.to {
+            Some(to) => format!("Moving {done}/{} to {to}...", cards(total)),
+            None => format!("Archiving {done}/{}...", cards(total)),
+        }
+    }
+
+    /// The banner once it's over, `error` being why it stopped short.
+    pub fn finished(&self, done: usize, error: Option<&str>) -> String {
+        let total = self.card_ids.len();
+        let did = match (&self.to, error) {
+            (Some(to), None) => return format!("Moved {} to {to}", cards(total)),
+            (None, None) => return format!("Archived {}", cards(total)),
+            (Some(_), Some(_)) => "Moved",
+            (None, Some(_)) => "Archived",
+        };
+        format!(
+            "{did} {done} of {}; {}",
+            cards(total),
+            error.unwrap_or_default()
+        )
+    }
+}
+
+/// "1 card", "3 cards".
+pub fn cards(n: usize) -> String {
+    if n == 1 {
+        "1 card".to_string()
+    } else {
+        format!("{n} cards")
+    }
+}
+
+/// A daily snapshot being shown: `board` as it was on `days[at]`.
+pub struct HistoryView {
+    /// Days with a snapshot, oldest first.
+    pub days: Vec<i64>,
+    pub at: usize,
+    pub board: Board,
+}
+
+pub struct Search {
+    pub query: String,
+    /// The prompt is still taking keystrokes.
+    pub editing: bool,
+    pub mode: Mode,
+    /// `query` as compiled by the last [`App::update_search`].
+    matcher: Result<Box<dyn Matcher>, String>,
+}
+
+impl Search {
+    pub fn new(query: &str, mode: Mode) -> Self {
+        Self {
+            query: query.to_string(),
+            editing: true,
+            mode,
+            matcher: matcher::new(mode, query),
+        }
+    }
+
+    /// Why the query matches nothing, e.g. an unfinished regex.
+    pub fn error(&self) -> Option<&str> {
+        self.matcher.as_ref().err().map(String::as_str)
+    }
+}
+
+/// The first visible row of a `len`-row list `height` rows tall: `prev`
+/// when that still fits and shows `selected`, else scrolled just enough.
+pub fn viewport(prev: usize, selected: Option<usize>, len: usize, height: usize) -> usize {
+    let mut offset = prev.min(len.saturating_sub(height));
+    if let Some(row) = selected {
+        if row < offset {
+            offset = row;
+        } else if height > 0 && row >= offset + height {
+            offset = row + 1 - height;
+        }
+    }
+    offset
+}
+
+pub struct QuickFilterMenu {
+    pub items: Vec<QuickFilter>,
+    pub on: Vec<bool>,
+    pub row: usize,
+}
+
+impl QuickFilterMenu {
+    pub fn new(items: Vec<QuickFilter>, active: &[QuickFilter]) -> Self {
+        let on = items
+            .iter()
+            .map(|q| active.iter().any(|a| a.id == q.id))
+            .collect();
+        Self { items, on, row: 0 }
+    }
+
+    /// Moves or toggles; true once the menu is closed.
+    pub fn key(&mut self, code: KeyCode) -> bool {
+        let last = self.items.len().saturating_sub(1);
+        match code {
+            KeyCode::Esc | KeyCode::Char('F') | KeyCode::Char('q') => return true,
+            KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.row = (self.row + 1).min(last),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(on) = self.on.get_mut(self.row) {
+                    *on = !*on;
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    pub fn chosen(&self) -> Vec<QuickFilter> {
+        self.items
+            .iter()
+            .zip(&self.on)
+            .filter(|(_, on)| **on)
+            .map(|(q, _)| q.clone())
+            .collect()
+    }
+}
+
+/// The picker first offers the kinds of link, then the card to link to.
+pub struct Linking {
+    pub card_id: String,
+    pub kind: Option<LinkKind>,
+    /// Card ids in the order the second picker lists them.
+    targets: Vec<String>,
+}
+
+/// Estimation mode: the open cards without an `estimate`, one at a time.
+pub struct Estimation {
+    pub queue: Vec<Card>,
+    pub at: usize,
+    /// Points typed so far.
+    pub input: String,
+    pub saved: usize,
+}
+
+pub enum EstimateKey {
+    Stay,
+    Save { card_id: String, points: f64 },
+    Close,
+}
+
+impl Estimation {
+    pub fn new(board: &Board) -> Self {
+        let queue = board
+            .columns
+            .iter()
+            .filter(|c| !c.meta.terminal)
+            .flat_map(|c| &c.cards)
+            .filter(|c| c.values("estimate").is_empty())
+            .cloned()
+            .collect();
+        Self {
+            queue,
+            at: 0,
+            input: String::new(),
+            saved: 0,
+        }
+    }
+
+    pub fn current(&self) -> Option<&Card> {
+        self.queue.get(self.at)
+    }
+
+    /// Digits and `.` type the points, Enter saves, Tab or → skips and ←
+    /// goes back. Closes after the last card.
+    pub fn key(&mut self, code: KeyCode) -> EstimateKey {
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => return EstimateKey::Close,
+            KeyCode::Char(c @ ('0'..='9' | '.')) if self.input.len() < 6 => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Tab | KeyCode::Right => self.advance(),
+            KeyCode::Left => {
+                self.at = self.at.saturating_sub(1);
+                self.input.clear();
+            }
+            KeyCode::Enter => {
+                if let (Some(card), Ok(points)) = (self.current(), self.input.parse::<f64>()) {
+                    return EstimateKey::Save {
+                        card_id: card.id.clone(),
+                        points,
+                    };
+                }
+            }
+            _ => {}
+        }
+        if self.current().is_none() {
+            EstimateKey::Close
+        } else {
+            EstimateKey::Stay
+        }
+    }
+
+    /// The current card's estimate was written; moves on.
+    pub fn saved(&mut self) {
+        self.saved += 1;
+        self.advance();
+    }
+
+    fn advance(&mut self) {
+        self.at += 1;
+        self.input.clear();
+    }
+}
+
+pub struct MergeConflict {
+    pub card_id: String,
+    /// The editor's version.
+    pub ours: String,
+    /// Both versions with conflict markers.
+    pub merged: String,
+}
+
+pub struct RemoteChange {
+    pub card_id: String,
+    pub old: String,
+    pub new: String,
+}
+
+pub struct Disappeared {
+    pub card: Card,
+    pub column: String,
 }
 
 impl App {
@@ -29,7 +526,102 @@ impl App {
             col: 0,
             row: 0,
             detail_open: false,
+            detail_scroll: 0,
+            detail_overflow: Cell::new(0),
+            col_offsets: RefCell::new(vec![]),
+            problems_open: false,
+            problem_row: 0,
+            debug_open: false,
+            stats: None,
+            heat: false,
+            create_prompt: None,
+            templates: vec![],
+            choosing_template: false,
+            create_template: None,
             banner: None,
+            pending: HashSet::new(),
+            disappeared: vec![],
+            changed: None,
+            diff_open: false,
+            conflict: None,
+            search: None,
+            keymap: Keymap::default(),
+            help_open: false,
+            graph_open: false,
+            undo: vec![],
+            picker: None,
+            moving_to: None,
+            visual: None,
+            batch_move: None,
+            batch_archive: None,
+            batch: None,
+            yanking: None,
+            server_prompt: None,
+            found: None,
+            browsing: None,
+            assigning: None,
+            linking: None,
+            sla_only: false,
+            filter_prompt: None,
+            filter: None,
+            search_mode: Mode::default(),
+            mentions: VecDeque::new(),
+            seen_mentions: HashSet::new(),
+            reply_prompt: None,
+            comment_prompt: None,
+            events: VecDeque::new(),
+            session_log: vec![],
+            archive_prompt: None,
+            done_prompt: None,
+            policy_open: false,
+            archive_effects: vec![],
+            archived: None,
+            archived_row: 0,
+            history: None,
+            quick_menu: None,
+            estimation: None,
+            quick_filters: vec![],
+            read_only: false,
+            details: DetailCache::default(),
+            fetching: None,
+            detail_failed: None,
+            offline: false,
+            lock: None,
+            rows: RowStyle::default(),
+            source: String::new(),
+            loaded_at: None,
+        }
+    }
+
+    pub fn record_events(&mut self, events: impl IntoIterator<Item = Event>) {
+        for e in events {
+            match e {
+                Event::Offline { .. } if !self.offline => {
+                    self.offline = true;
+                    self.banner =
+                        Some("Offline: showing the cached board; moves are queued".into());
+                }
+                Event::Online { replayed } => {
+                    self.offline = false;
+                    self.banner = Some(format!("Back online; sent {replayed} queued moves"));
+                }
+                _ => {}
+            }
+            self.session_log.extend(activity::Entry::from_event(&e));
+            self.events.push_back(e);
+        }
+        let excess = self.events.len().saturating_sub(EVENT_LOG);
+        self.events.drain(..excess);
+        let excess = self.session_log.len().saturating_sub(SESSION_LOG);
+        self.session_log.drain(..excess);
+    }
+
+    /// Queues mentions not seen before.
+    pub fn add_mentions(&mut self, mentions: Vec<Mention>) {
+        for m in mentions {
+            if self.seen_mentions.insert(m.id.clone()) {
+                self.mentions.push_back(m);
+            }
         }
     }
 
@@ -45,17 +637,55 @@ impl App {
         }
     }
 
-    fn col_len(&self) -> usize {
-        self.board
-            .columns
-            .get(self.col)
-            .map(|c| c.cards.len())
-            .unwrap_or(0)
+    /// Rows of column `col` the filter shows; all of them without one.
+    pub fn shown_rows(&self, col: usize) -> Vec<usize> {
+        let Some(column) = self.board.columns.get(col) else {
+            return vec![];
+        };
+        let today = dates::today();
+        (0..column.cards.len())
+            .filter(|&row| self.is_shown(column, &column.cards[row], today))
+            .collect()
+    }
+
+    fn is_shown(&self, col: &Column, card: &Card, today: i64) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|f| f.expr.matches(col, card, today))
     }
 
+    /// Keeps the cursor on a shown row: the nearest one above a hidden
+    /// card, or the first.
     fn clamp_row(&mut self) {
-        let len = self.col_len();
-        self.row = if len == 0 { 0 } else { self.row.min(len - 1) };
+        let rows = self.shown_rows(self.col);
+        self.row = rows
+            .iter()
+            .rev()
+            .find(|&&r| r <= self.row)
+            .or(rows.first())
+            .copied()
+            .unwrap_or(0);
+    }
+
+    /// Applies the filter bar's text; empty clears the filter.
+    pub fn set_filter(&mut self, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        self.filter = if text.is_empty() {
+            None
+        } else {
+            Some(BoardFilter {
+                text: text.to_string(),
+                expr: filter::parse_bar(text)?,
+            })
+        };
+        if self.shown_rows(self.col).is_empty()
+            && let Some(col) =
+                (0..self.board.columns.len()).find(|&c| !self.shown_rows(c).is_empty())
+        {
+            self.col = col;
+        }
+        self.clamp_row();
+        Ok(())
     }
 
     fn next_non_empty_col(&self, step: isize) -> Option<usize> {
@@ -71,13 +701,33 @@ impl App {
             if idx < 0 || idx >= len {
                 return None;
             }
-            let col = &self.board.columns[idx as usize];
-            if !col.cards.is_empty() {
+            if !self.shown_rows(idx as usize).is_empty() {
                 return Some(idx as usize);
             }
         }
     }
 
+    /// Whether the lock refuses `a` here. Changes must stay within its
+    /// allowed columns; undoing is left to the provider, which knows where
+    /// the card goes back to.
+    pub fn locked_out(&self, a: Action) -> bool {
+        let Some(lock) = &self.lock else {
+            return false;
+        };
+        let allowed = |i: usize| {
+            self.board
+                .columns
+                .get(i)
+                .is_some_and(|c| lock.allows(&c.id, &c.title))
+        };
+        match a {
+            Action::MoveLeft => !allowed(self.col) || self.dst_col(-1).is_some_and(|d| !allowed(d)),
+            Action::MoveRight => !allowed(self.col) || self.dst_col(1).is_some_and(|d| !allowed(d)),
+            Action::Undo => false,
+            _ => a.writes() && !allowed(self.col),
+        }
+    }
+
     fn dst_col(&self, dir: isize) -> Option<usize> {
         let dst = self.col as isize + dir;
         if dst < 0 {
@@ -106,25 +756,96 @@ impl App {
         let dir = dir.signum();
         if let Some(next) = self.next_non_empty_col(dir) {
             self.col = next;
+            self.visual = None;
             self.clamp_row();
+            self.detail_scroll = 0;
         }
     }
 
     pub fn select(&mut self, delta: isize) {
-        let len = self.col_len();
-        if len == 0 {
+        let rows = self.shown_rows(self.col);
+        if rows.is_empty() {
             self.row = 0;
             return;
         }
 
-        self.row = Self::clamp_index(self.row, delta, len - 1);
+        let at = rows
+            .iter()
+            .position(|&r| r >= self.row)
+            .unwrap_or(rows.len() - 1);
+        self.row = rows[Self::clamp_index(at, delta, rows.len() - 1)];
     }
 
     pub fn apply(&mut self, a: Action) -> bool {
+        if self.problems_open {
+            match a {
+                Action::SelectUp => return self.select_problem(-1),
+                Action::SelectDown => return self.select_problem(1),
+                Action::ToggleDetail => return self.jump_to_problem(),
+                _ => {}
+            }
+        }
+
+        if let Some(cards) = &self.archived {
+            let last = cards.len().saturating_sub(1);
+            match a {
+                Action::SelectUp => self.archived_row = self.archived_row.saturating_sub(1),
+                Action::SelectDown => self.archived_row = (self.archived_row + 1).min(last),
+                _ => {}
+            }
+            if matches!(a, Action::SelectUp | Action::SelectDown) {
+                return false;
+            }
+        }
+
+        // The open detail takes the vertical keys, so the board stays put.
+        if self.detail_open {
+            let step = match a {
+                Action::SelectUp => -1,
+                Action::SelectDown => 1,
+                Action::PageUp => -DETAIL_PAGE,
+                Action::PageDown => DETAIL_PAGE,
+                _ => 0,
+            };
+            if step != 0 {
+                self.detail_scroll =
+                    Self::clamp_index(self.detail_scroll, step, self.detail_overflow.get());
+                return false;
+            }
+        }
+
         match a {
             Action::Quit => return true,
             Action::CloseOrQuit => {
-                if self.detail_open {
+                if self.help_open {
+                    self.help_open = false;
+                } else if self.visual.is_some() {
+                    self.visual = None;
+                } else if self.archived.is_some() {
+                    self.archived = None;
+                } else if self.history.is_some() {
+                    self.history = None;
+                } else if self.graph_open {
+                    self.graph_open = false;
+                } else if self.debug_open {
+                    self.debug_open = false;
+                } else if self.stats.is_some() {
+                    self.stats = None;
+                } else if self.diff_open {
+                    self.diff_open = false;
+                } else if self.problems_open {
+                    self.problems_open = false;
+                } else if self.search.is_some() {
+                    self.search = None;
+                    self.banner = None;
+                } else if self.sla_only {
+                    self.sla_only = false;
+                    self.banner = None;
+                } else if self.filter.is_some() {
+                    self.filter = None;
+                } else if !self.mentions.is_empty() {
+                    self.mentions.pop_front();
+                } else if self.detail_open {
                     self.detail_open = false;
                 } else {
                     return true;
@@ -134,12 +855,451 @@ impl App {
             Action::FocusRight => self.focus(1),
             Action::SelectUp => self.select(-1),
             Action::SelectDown => self.select(1),
-            Action::ToggleDetail => self.detail_open = !self.detail_open,
-            Action::Refresh | Action::MoveLeft | Action::MoveRight => {}
+            Action::ToggleDetail => {
+                self.detail_open = !self.detail_open;
+                self.detail_scroll = 0;
+            }
+            Action::ToggleProblems => {
+                self.problems_open = !self.problems_open;
+                self.problem_row = 0;
+            }
+            Action::ToggleDebug => self.debug_open = !self.debug_open,
+            Action::ToggleHeat => self.heat = !self.heat,
+            Action::ToggleDiff => {
+                if self.changed.is_some() {
+                    self.diff_open = !self.diff_open;
+                } else {
+                    self.banner = Some("No remote change to show".to_string());
+                }
+            }
+            Action::CreateCard => match self.board.columns.get(self.col) {
+                None => self.banner = Some("Create failed: no column selected".to_string()),
+                Some(col) if !col.meta.can_create => {
+                    self.banner = Some(format!(
+                        "Create failed: {} does not accept new cards",
+                        col.title
+                    ))
+                }
+                Some(_) if self.templates.is_empty() => self.create_prompt = Some(String::new()),
+                Some(col) => {
+                    let items = std::iter::once("blank".to_string())
+                        .chain(self.templates.iter().map(|t| t.name.clone()))
+                        .collect();
+                    self.picker = Some(Picker::new(
+                        &format!("New card in {}", col.title),
+                        items,
+                        Some(0),
+                    ));
+                    self.choosing_template = true;
+                }
+            },
+            Action::Search => self.search = Some(Search::new("", self.search_mode)),
+            Action::ServerSearch => self.server_prompt = Some(String::new()),
+            Action::Filter => {
+                self.filter_prompt = Some(
+                    self.filter
+                        .as_ref()
+                        .map(|f| f.text.clone())
+                        .unwrap_or_default(),
+                );
+            }
+            Action::Policy => match self.board.columns.get(self.col) {
+                Some(col) if col.meta.policy.is_none() => {
+                    self.policy_open = false;
+                    self.banner = Some(format!("{} has no policy", col.title));
+                }
+                Some(_) => self.policy_open = !self.policy_open,
+                None => {}
+            },
+            Action::NextMatch => self.jump_to_match(1),
+            Action::PrevMatch => self.jump_to_match(-1),
+            Action::ToggleHelp => self.help_open = !self.help_open,
+            Action::ToggleGraph => self.graph_open = !self.graph_open,
+            Action::ToggleSlaOnly => {
+                self.sla_only = !self.sla_only;
+                self.banner = self.sla_only.then(|| {
+                    let n = self
+                        .board
+                        .columns
+                        .iter()
+                        .flat_map(|c| &c.cards)
+                        .filter(|c| sla::is_breached(c))
+                        .count();
+                    format!("{n} cards over their SLA (Esc shows all)")
+                });
+            }
+            Action::OpenMention => match self.mentions.pop_front() {
+                None => self.banner = Some("No mentions".to_string()),
+                Some(m) if self.focus_card(&m.card_id) => {
+                    self.detail_open = true;
+                    self.detail_scroll = 0;
+                }
+                Some(m) => self.banner = Some(format!("{} is not on this board", m.card_id)),
+            },
+            Action::ReplyMention => {
+                if self.mentions.is_empty() {
+                    self.banner = Some("No mentions".to_string());
+                } else {
+                    self.reply_prompt = Some(String::new());
+                }
+            }
+            Action::Estimate => {
+                let estimation = Estimation::new(&self.board);
+                if estimation.queue.is_empty() {
+                    self.banner = Some("Every open card has an estimate".to_string());
+                } else {
+                    self.estimation = Some(estimation);
+                }
+            }
+            Action::Link => match self.selected_card_id() {
+                None => self.banner = Some("Link failed: no card selected".to_string()),
+                Some(_) if !self.detail_open => {
+                    self.banner = Some("Open the card (Enter) to link it".to_string())
+                }
+                Some(id) => {
+                    let kinds = LinkKind::ALL
+                        .iter()
+                        .map(|k| format!("{id} {} ...", k.label()))
+                        .collect();
+                    self.picker = Some(Picker::new(&format!("Link {id}"), kinds, None));
+                    self.linking = Some(Linking {
+                        card_id: id,
+                        kind: None,
+                        targets: vec![],
+                    });
+                }
+            },
+            Action::Comment => match self.selected_card_id() {
+                None => self.banner = Some("Comment failed: no card selected".to_string()),
+                Some(_) if !self.detail_open => {
+                    self.banner = Some("Open the card (Enter) to comment on it".to_string())
+                }
+                Some(id) => self.comment_prompt = Some((id, String::new())),
+            },
+            Action::Visual => match self.selected_card_id() {
+                _ if self.visual.is_some() => self.visual = None,
+                None => self.banner = Some("Select failed: no card selected".to_string()),
+                Some(id) => self.visual = Some(id),
+            },
+            Action::MoveTo if self.visual.is_some() => {
+                let card_ids = self.marked();
+                self.visual = None;
+                let columns = self
+                    .board
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| format!("{} {}", i + 1, c.title))
+                    .collect();
+                self.picker = Some(Picker::new(
+                    &format!("Move {} to", cards(card_ids.len())),
+                    columns,
+                    Some(self.col),
+                ));
+                self.batch_move = Some(card_ids);
+            }
+            Action::Archive if self.visual.is_some() => {
+                let card_ids = self.marked();
+                self.visual = None;
+                match card_ids.iter().find(|id| self.pending.contains(*id)) {
+                    Some(id) => self.banner = Some(format!("Archive failed: {id} is still moving")),
+                    None => self.batch_archive = Some(card_ids),
+                }
+            }
+            Action::MoveTo => match self.selected_card_id() {
+                None => self.banner = Some("Move failed: no card selected".to_string()),
+                Some(id) => {
+                    let columns = self
+                        .board
+                        .columns
+                        .iter()
+                        .enumerate()
+                        .map(|(i, c)| format!("{} {}", i + 1, c.title))
+                        .collect();
+                    self.picker = Some(Picker::new(
+                        &format!("Move {id} to"),
+                        columns,
+                        Some(self.col),
+                    ));
+                    self.moving_to = Some(id);
+                }
+            },
+            Action::Archive => match self.selected_card_id() {
+                None => self.banner = Some("Archive failed: no card selected".to_string()),
+                Some(id) if self.pending.contains(&id) => {
+                    self.banner = Some(format!("Archive failed: {id} is still moving"))
+                }
+                Some(id) => self.archive_prompt = Some(id),
+            },
+            Action::Refresh
+            | Action::ToggleArchive
+            | Action::History
+            | Action::LoadMore
+            | Action::QuickFilters
+            | Action::PageUp
+            | Action::PageDown
+            | Action::TakeOver
+            | Action::MoveLeft
+            | Action::MoveRight
+            | Action::Edit
+            | Action::Undo
+            | Action::StartWork
+            | Action::Yank
+            | Action::Reorder
+            | Action::Assign
+            | Action::Stats
+            | Action::Activity
+            | Action::GitPull
+            | Action::GitPush
+            | Action::SwitchBoard => {}
+        }
+        false
+    }
+
+    /// Takes the `i`th item of a linking picker: a kind opens the card
+    /// picker, and a card finishes the link, returned as `(from, to, kind)`.
+    pub fn link_picked(&mut self, i: usize) -> Option<(String, String, LinkKind)> {
+        let link = self.linking.as_mut()?;
+        if let Some(kind) = link.kind {
+            let to = link.targets.get(i)?.clone();
+            let link = self.linking.take()?;
+            self.picker = None;
+            return Some((link.card_id, to, kind));
+        }
+        let kind = *LinkKind::ALL.get(i)?;
+        let cards: Vec<&Card> = self
+            .board
+            .columns
+            .iter()
+            .flat_map(|c| &c.cards)
+            .filter(|c| c.id != link.card_id)
+            .collect();
+        link.kind = Some(kind);
+        link.targets = cards.iter().map(|c| c.id.clone()).collect();
+        let items = cards
+            .iter()
+            .map(|c| format!("{} {}", c.id, c.title))
+            .collect();
+        let title = format!("{} {} ...", link.card_id, kind.label());
+        self.picker = Some(Picker::new(&title, items, None));
+        None
+    }
+
+    /// Whether `card` matches the active search. Everything matches without one.
+    pub fn is_match(&self, card: &Card) -> bool {
+        (!self.sla_only || sla::is_breached(card))
+            && self.search.as_ref().is_none_or(|s| {
+                s.matcher
+                    .as_ref()
+                    .is_ok_and(|m| matcher::card_matches(m.as_ref(), card))
+            })
+    }
+
+    /// Positions of matching cards in board order.
+    fn matches(&self) -> Vec<(usize, usize)> {
+        let today = dates::today();
+        self.board
+            .columns
+            .iter()
+            .enumerate()
+            .flat_map(|(ci, col)| {
+                col.cards
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, c)| self.is_shown(col, c, today) && self.is_match(c))
+                    .map(move |(ri, _)| (ci, ri))
+            })
+            .collect()
+    }
+
+    /// Moves to the next (`dir` 1) or previous (-1) match, wrapping around,
+    /// and reports the match count in the banner.
+    pub fn jump_to_match(&mut self, dir: isize) {
+        let Some(query) = self.search.as_ref().map(|s| s.query.clone()) else {
+            return;
+        };
+        let matches = self.matches();
+        let here = (self.col, self.row);
+        let target = if dir > 0 {
+            matches.iter().find(|m| **m > here).or(matches.first())
+        } else {
+            matches.iter().rev().find(|m| **m < here).or(matches.last())
+        };
+        let Some(&(col, row)) = target else {
+            self.banner = Some(format!("No match for /{query}"));
+            return;
+        };
+        (self.col, self.row) = (col, row);
+        let n = matches.iter().position(|m| *m == (col, row)).unwrap_or(0) + 1;
+        self.banner = Some(format!(
+            "/{query}: {n} of {} (n/N next/prev, Esc clears)",
+            matches.len()
+        ));
+    }
+
+    /// Re-runs the search after the query changed, staying on the selected
+    /// card if it still matches.
+    pub fn update_search(&mut self) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.matcher = matcher::new(search.mode, &search.query);
+        self.search_mode = search.mode;
+        if search.matcher.is_err() || self.selected_card().is_some_and(|c| self.is_match(c)) {
+            return;
+        }
+        self.jump_to_match(1);
+    }
+
+    fn select_problem(&mut self, delta: isize) -> bool {
+        let len = self.board.problems.len();
+        self.problem_row = if len == 0 {
+            0
+        } else {
+            Self::clamp_index(self.problem_row, delta, len - 1)
+        };
+        false
+    }
+
+    fn jump_to_problem(&mut self) -> bool {
+        let Some(problem) = self.board.problems.get(self.problem_row) else {
+            return false;
+        };
+        let Some(card_id) = problem.card_id.clone() else {
+            self.banner = Some("Problem is not tied to a card on the board".to_string());
+            return false;
+        };
+        if self.focus_card(&card_id) {
+            self.problems_open = false;
+        } else {
+            self.banner = Some(format!("{card_id} is not on the board"));
         }
         false
     }
 
+    /// What the status bar shows under any banner: the board, how fresh
+    /// it is, the moves still out and whether the provider is reachable.
+    pub fn status_line(&self, now: u64) -> String {
+        let mut parts = vec![];
+        if !self.source.is_empty() {
+            parts.push(self.source.clone());
+        }
+        if let Some(at) = self.loaded_at {
+            let ago = now.saturating_sub(at);
+            parts.push(match ago {
+                0..60 => "refreshed just now".to_string(),
+                60..3600 => format!("refreshed {}m ago", ago / 60),
+                3600..86_400 => format!("refreshed {}h ago", ago / 3600),
+                _ => format!("refreshed {}d ago", ago / 86_400),
+            });
+        }
+        if !self.pending.is_empty() {
+            parts.push(format!("{} moving", self.pending.len()));
+        }
+        parts.push(if self.offline { "offline" } else { "online" }.to_string());
+        parts.join(" · ")
+    }
+
+    /// The card under the cursor, if the filter shows it.
+    pub fn selected_card(&self) -> Option<&Card> {
+        let col = self.board.columns.get(self.col)?;
+        let card = col.cards.get(self.row)?;
+        self.is_shown(col, card, dates::today()).then_some(card)
+    }
+
+    pub fn selected_card_id(&self) -> Option<String> {
+        self.selected_card().map(|c| c.id.clone())
+    }
+
+    pub fn find_card(&self, card_id: &str) -> Option<&Card> {
+        self.board
+            .columns
+            .iter()
+            .flat_map(|c| &c.cards)
+            .find(|c| c.id == card_id)
+    }
+
+    /// Swaps in a board reloaded after a move. The cursor stays on the card it
+    /// was on, by id, or lands on `moved` if that card is gone; positions in
+    /// the new board may have shifted.
+    pub fn reconcile(&mut self, board: Board, moved: &str) {
+        let selected = self.selected_card_id();
+        self.board = board;
+        self.loaded_at = Some(dates::now_secs());
+        let found = selected.is_some_and(|id| self.focus_card(&id)) || self.focus_card(moved);
+        if !found {
+            self.clamp();
+        }
+        self.clamp_row();
+    }
+
+    /// Moves the cursor to the card with `card_id`. Returns false if it isn't on the board.
+    pub fn focus_card(&mut self, card_id: &str) -> bool {
+        for (col_idx, col) in self.board.columns.iter().enumerate() {
+            if let Some(row_idx) = col.cards.iter().position(|c| c.id == card_id) {
+                (self.col, self.row) = (col_idx, row_idx);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Replaces the board with a fresh load and calls out cards that vanished
+    /// since the previous one (deleted or filtered out remotely). The cursor
+    /// stays on the selected card wherever it moved to.
+    pub fn refresh_board(&mut self, board: Board) {
+        let ids: HashSet<&str> = board
+            .columns
+            .iter()
+            .flat_map(|c| &c.cards)
+            .map(|c| c.id.as_str())
+            .collect();
+        self.disappeared = self
+            .board
+            .columns
+            .iter()
+            .flat_map(|col| {
+                col.cards
+                    .iter()
+                    .filter(|c| !ids.contains(c.id.as_str()))
+                    .map(|c| Disappeared {
+                        card: c.clone(),
+                        column: col.title.clone(),
+                    })
+            })
+            .collect();
+
+        let before = self.selected_card().cloned();
+        self.board = board;
+        self.loaded_at = Some(dates::now_secs());
+        if !before.as_ref().is_some_and(|c| self.focus_card(&c.id)) {
+            self.clamp();
+        }
+        self.clamp_row();
+        self.diff_open = false;
+        self.changed = before.and_then(|old| {
+            let new = self.find_card(&old.id)?;
+            (new.description != old.description).then(|| RemoteChange {
+                card_id: old.id.clone(),
+                old: old.description,
+                new: new.description.clone(),
+            })
+        });
+
+        if let Some(c) = &self.changed {
+            self.banner = Some(format!("{} changed remotely (D shows the diff)", c.card_id));
+            return;
+        }
+        self.banner = match self.disappeared.as_slice() {
+            [] => None,
+            [d] => Some(format!("{} disappeared since the last refresh", d.card.id)),
+            all => Some(format!(
+                "{} cards disappeared since the last refresh",
+                all.len()
+            )),
+        };
+    }
+
     pub fn focus_first_non_empty(&mut self) {
         (self.col, self.row) = (first_non_empty_column(&self.board).unwrap_or(0), 0);
     }
@@ -152,22 +1312,376 @@ impl App {
         self.clamp();
 
         let dst = self.dst_col(dir)?;
+        self.optimistic_move_to(dst)
+    }
+
+    /// Holds a move of `card_id` into a finished-work column with a policy
+    /// for confirmation, returning whether it did.
+    pub fn hold_for_policy(&mut self, card_id: &str, dst: usize) -> bool {
+        let Some(col) = self.board.columns.get(dst) else {
+            return false;
+        };
+        let hold = col.meta.terminal
+            && col.meta.policy.is_some()
+            && self
+                .board
+                .column_of(card_id)
+                .is_some_and(|c| c.id != col.id);
+        if hold {
+            self.done_prompt = Some((card_id.to_string(), dst));
+        }
+        hold
+    }
+
+    /// [`App::hold_for_policy`] for `H` / `L` on the selected card.
+    pub fn hold_step_for_policy(&mut self, dir: isize) -> bool {
+        match (self.selected_card_id(), self.dst_col(dir)) {
+            (Some(card_id), Some(dst)) => self.hold_for_policy(&card_id, dst),
+            _ => false,
+        }
+    }
+
+    /// Shown cards of the focused column from the `v` mark to the cursor.
+    pub fn marked(&self) -> Vec<String> {
+        let Some(anchor) = &self.visual else {
+            return vec![];
+        };
+        let Some(column) = self.board.columns.get(self.col) else {
+            return vec![];
+        };
+        let Some(from) = column.cards.iter().position(|c| &c.id == anchor) else {
+            return vec![];
+        };
+        let rows = from.min(self.row)..=from.max(self.row);
+        self.shown_rows(self.col)
+            .into_iter()
+            .filter(|r| rows.contains(r))
+            .map(|r| column.cards[r].id.clone())
+            .collect()
+    }
+
+    /// Readies a batch moving `card_ids` to column `dst`, leaving out those
+    /// already there, or says why not. The cards are pending until the
+    /// worker's reload.
+    pub fn batch_to(&mut self, card_ids: Vec<String>, dst: usize) -> Option<(Vec<String>, String)> {
+        let target = self.board.columns.get(dst)?;
+        let card_ids: Vec<String> = card_ids
+            .into_iter()
+            .filter(|id| !target.cards.iter().any(|c| &c.id == id))
+            .collect();
+        if card_ids.is_empty() {
+            return None;
+        }
+        if let Some(limit) = target.meta.wip_limit
+            && target.cards.len() + card_ids.len() > limit
+        {
+            self.banner = Some(format!(
+                "WIP limit reached: {} holds {} of {limit}",
+                target.title,
+                target.cards.len(),
+            ));
+            return None;
+        }
+        if let Some(id) = card_ids.iter().find(|id| self.pending.contains(*id)) {
+            self.banner = Some(format!("{id} is still moving"));
+            return None;
+        }
+        let to_col_id = target.id.clone();
+        self.batch = Some(Batch {
+            card_ids: card_ids.clone(),
+            to: Some(target.title.clone()),
+        });
+        self.pending.extend(card_ids.iter().cloned());
+        self.visual = None;
+        Some((card_ids, to_col_id))
+    }
+
+    /// Takes the `i`th column of the `m` picker: moves its card straight
+    /// there, returning `(card_id, to_col_id)` as `optimistic_move` does.
+    pub fn move_picked(&mut self, dst: usize) -> Option<(String, String)> {
+        self.picker = None;
+        let card_id = self.moving_to.take()?;
+        self.move_card_to(&card_id, dst)
+    }
+
+    /// Offers the selected card's id, title, and with a `url` a markdown
+    /// link and the URL, for `y` to copy.
+    pub fn yank_menu(&mut self, url: Option<&str>) {
+        let Some(card) = self.selected_card() else {
+            self.banner = Some("Copy failed: no card selected".to_string());
+            return;
+        };
+        let mut texts = vec![("id", card.id.clone()), ("title", card.title.clone())];
+        if let Some(url) = url {
+            texts.push(("link", format!("[{} {}]({url})", card.id, card.title)));
+            texts.push(("url", url.to_string()));
+        }
+        let items = texts
+            .iter()
+            .map(|(what, text)| format!("{what:<6}{text}"))
+            .collect();
+        self.picker = Some(Picker::new(&format!("Copy {}", card.id), items, None));
+        self.yanking = Some(texts.into_iter().map(|(_, text)| text).collect());
+    }
+
+    /// Lists what the provider's search for `query` found, noting where
+    /// cards already on the board are.
+    pub fn found_menu(&mut self, query: &str, cards: Vec<Card>) {
+        if cards.is_empty() {
+            self.banner = Some(format!("Nothing found for {query:?}"));
+            return;
+        }
+        let items = cards
+            .iter()
+            .map(|card| {
+                let place = match self.board.column_of(&card.id) {
+                    Some(col) => format!("  (in {})", col.title),
+                    None => card
+                        .values("status")
+                        .first()
+                        .map(|s| format!("  [{s}]"))
+                        .unwrap_or_default(),
+                };
+                format!("{:<10} {}{place}", card.id, card.title)
+            })
+            .collect();
+        self.picker = Some(Picker::new(&format!("Found for {query:?}"), items, None));
+        self.found = Some(cards);
+    }
+
+    /// Takes the `i`th card of the `s` picker: one on the board is focused,
+    /// and one that isn't is returned for the provider to pull in.
+    pub fn found_picked(&mut self, i: usize) -> Option<Card> {
+        self.picker = None;
+        let card = self.found.take()?.into_iter().nth(i)?;
+        (!self.focus_card(&card.id)).then_some(card)
+    }
+
+    /// Lists the newest `entries` for `g`, naming columns by their titles.
+    pub fn activity_menu(&mut self, entries: &[activity::Entry]) {
+        if entries.is_empty() {
+            self.banner = Some("No activity yet".to_string());
+            return;
+        }
+        let title = |id: &str| {
+            self.board
+                .columns
+                .iter()
+                .find(|c| c.id == id)
+                .map_or(id, |c| c.title.as_str())
+                .to_string()
+        };
+        let (items, cards): (Vec<String>, Vec<String>) = entries
+            .iter()
+            .rev()
+            .take(ACTIVITY_SHOWN)
+            .map(|e| {
+                let what = match &e.kind {
+                    activity::Kind::Created { column } => format!("created in {}", title(column)),
+                    activity::Kind::Moved {
+                        from: Some(from),
+                        to,
+                    } => format!("moved {} → {}", title(from), title(to)),
+                    activity::Kind::Moved { from: None, to } => format!("moved to {}", title(to)),
+                    activity::Kind::Edited { what } => format!("edited {what}"),
+                    activity::Kind::Archived => "archived".to_string(),
+                };
+                // `2024-06-01T09:30:00Z` as `2024-06-01 09:30`.
+                let at = e.at.get(..16).unwrap_or(&e.at).replace('T', " ");
+                (format!("{at}  {} {what}", e.card), e.card.clone())
+            })
+            .unzip();
+        self.picker = Some(Picker::new("Activity", items, Some(0)));
+        self.browsing = Some(cards);
+    }
+
+    /// Takes the `i`th item of the `g` list, selecting its card if it's on
+    /// the board.
+    pub fn activity_picked(&mut self, i: usize) {
+        self.picker = None;
+        let Some(card_id) = self.browsing.take().and_then(|c| c.into_iter().nth(i)) else {
+            return;
+        };
+        if !self.focus_card(&card_id) {
+            self.banner = Some(format!("{card_id} is not on the board"));
+        }
+    }
+
+    /// Opens the `t` overlay on the board as shown, with throughput over the
+    /// last `days` and time in column from `moves` when there are any.
+    pub fn open_stats(&mut self, moves: &[Move], days: i64) {
+        self.stats = Some(
+            stats::compute(&self.board, dates::today(), days).with_moves(
+                &self.board,
+                moves,
+                dates::now_secs(),
+            ),
+        );
+    }
+
+    /// Takes the `i`th item of the `n` picker and asks for the title.
+    pub fn template_picked(&mut self, i: usize) {
+        self.picker = None;
+        self.choosing_template = false;
+        self.create_template = i
+            .checked_sub(1)
+            .and_then(|i| self.templates.get(i))
+            .cloned();
+        self.create_prompt = Some(String::new());
+    }
+
+    /// Lists `users` for `a` to assign the selected card to, the current
+    /// assignee marked.
+    pub fn assign_menu(&mut self, users: Vec<User>) {
+        let Some(card) = self.selected_card().cloned() else {
+            self.banner = Some("Assign failed: no card selected".to_string());
+            return;
+        };
+        let current = match &card.assignee {
+            None => Some(0),
+            Some(a) => users.iter().position(|u| &u.name == a).map(|i| i + 1),
+        };
+        let items = std::iter::once("Unassigned".to_string())
+            .chain(users.iter().map(|u| u.name.clone()))
+            .collect();
+        self.picker = Some(Picker::new(&format!("Assign {}", card.id), items, current));
+        self.assigning = Some((card.id, users));
+    }
+
+    /// Takes the `i`th item of the `a` picker, showing the new assignee
+    /// right away. Returns the card and who to assign it to, `None` for
+    /// nobody.
+    pub fn assign_picked(&mut self, i: usize) -> Option<(String, Option<User>)> {
+        self.picker = None;
+        let (card_id, users) = self.assigning.take()?;
+        let user = match i {
+            0 => None,
+            _ => Some(users.into_iter().nth(i - 1)?),
+        };
+        let card = self
+            .board
+            .columns
+            .iter_mut()
+            .flat_map(|c| &mut c.cards)
+            .find(|c| c.id == card_id)?;
+        card.assignee = user.as_ref().map(|u| u.name.clone());
+        Some((card_id, user))
+    }
+
+    /// Moves a card to column `dst` like [`App::move_picked`], for `S`.
+    pub fn move_card_to(&mut self, card_id: &str, dst: usize) -> Option<(String, String)> {
+        let Some((col, row)) = self.board.columns.iter().enumerate().find_map(|(i, c)| {
+            let row = c.cards.iter().position(|card| card.id == card_id)?;
+            Some((i, row))
+        }) else {
+            self.banner = Some(format!("Move failed: {card_id} is no longer on the board"));
+            return None;
+        };
+        (self.col, self.row) = (col, row);
+        let target = self.board.columns.get(dst)?;
+        if dst == col {
+            self.banner = Some(format!("{card_id} is already in {}", target.title));
+            return None;
+        }
+        if let Some(lock) = &self.lock
+            && !lock.allows(&target.id, &target.title)
+        {
+            self.banner = Some(format!(
+                "Board is locked until {}; `flow unlock` lifts it",
+                lock.until
+            ));
+            return None;
+        }
+        self.optimistic_move_to(dst)
+    }
+
+    fn optimistic_move_to(&mut self, dst: usize) -> Option<(String, String)> {
         let src = self.col;
         if self.board.columns[src].cards.is_empty() {
             return None;
         }
 
+        if let Some(card) = self.board.columns[src].cards.get(self.row)
+            && self.pending.contains(&card.id)
+        {
+            self.banner = Some(format!("{} is still moving", card.id));
+            return None;
+        }
+
+        let target = &self.board.columns[dst];
+        if target.at_wip_limit() {
+            self.banner = Some(format!(
+                "WIP limit reached: {} holds {} of {}",
+                target.title,
+                target.cards.len(),
+                target.meta.wip_limit.unwrap_or_default()
+            ));
+            return None;
+        }
+
         let card = self.board.columns[src].cards.remove(self.row);
         let card_id = card.id.clone();
         let to_col_id = self.board.columns[dst].id.clone();
 
-        self.board.columns[dst].cards.push(card);
+        let target = &mut self.board.columns[dst];
+        let at = match &target.meta.auto_sort {
+            Some(key) => key.insert_at(&target.cards, &card),
+            None => target.cards.len(),
+        };
+        target.cards.insert(at, card);
+        self.pending.insert(card_id.clone());
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(Undo::Move {
+            card_id: card_id.clone(),
+            from: self.board.columns[src].id.clone(),
+            row: self.row,
+        });
 
         self.col = dst;
-        self.row = self.board.columns[dst].cards.len() - 1;
+        self.row = at;
 
         Some((card_id, to_col_id))
     }
+
+    /// Puts the card of the newest undo entry back where it was. If its move
+    /// was still `queued` (the caller has dropped it from the queue) that is
+    /// all; otherwise returns the inverse move to send. A move in flight
+    /// can't be undone until it lands.
+    pub fn undo(&mut self, queued: bool) -> Option<(String, String)> {
+        let Some(entry) = self.undo.pop() else {
+            self.banner = Some("Nothing to undo".to_string());
+            return None;
+        };
+        let Undo::Move { card_id, from, row } = &entry;
+        if self.pending.contains(card_id) && !queued {
+            self.banner = Some(format!("{card_id} is still moving"));
+            self.undo.push(entry);
+            return None;
+        }
+        let Some(dst) = self.board.columns.iter().position(|c| c.id == *from) else {
+            self.banner = Some(format!("Undo failed: column {from} is gone"));
+            return None;
+        };
+        if !self.focus_card(card_id) {
+            self.banner = Some(format!("Undo failed: {card_id} is not on the board"));
+            return None;
+        }
+        let card = self.board.columns[self.col].cards.remove(self.row);
+        let cards = &mut self.board.columns[dst].cards;
+        let row = (*row).min(cards.len());
+        cards.insert(row, card);
+        (self.col, self.row) = (dst, row);
+
+        if queued {
+            self.pending.remove(card_id);
+            self.banner = Some(format!("Cancelled queued move of {card_id}"));
+            return None;
+        }
+        self.pending.insert(card_id.clone());
+        Some((card_id.clone(), from.clone()))
+    }
 }
 
 fn first_non_empty_column(board: &Board) -> Option<usize> {
@@ -182,7 +1696,7 @@ fn first_non_empty_column(board: &Board) -> Option<usize> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Board, Card, Column};
+    use crate::model::{Board, Card, Column, ColumnMeta, Problem};
 
     fn board_two_cols() -> Board {
         Board {
@@ -195,20 +1709,25 @@ mod tests {
                             id: "1".into(),
                             title: "t1".into(),
                             description: "d".into(),
+                            ..Default::default()
                         },
                         Card {
                             id: "2".into(),
                             title: "t2".into(),
                             description: "d".into(),
+                            ..Default::default()
                         },
                     ],
+                    meta: ColumnMeta::default(),
                 },
                 Column {
                     id: "b".into(),
                     title: "B".into(),
                     cards: vec![],
+                    meta: ColumnMeta::default(),
                 },
             ],
+            problems: vec![],
         }
     }
 
@@ -235,6 +1754,7 @@ mod tests {
             id: "3".into(),
             title: "t3".into(),
             description: "d".into(),
+            ..Default::default()
         });
         app.focus(1);
         assert_eq!(app.col, 1);
@@ -269,6 +1789,145 @@ mod tests {
         assert_eq!(app.board.columns[0].cards.len(), 1);
     }
 
+    #[test]
+    fn refresh_keeps_cursor_on_selected_card() {
+        let mut app = App::new(board_two_cols());
+        app.row = 1;
+
+        let mut next = board_two_cols();
+        let card = next.columns[0].cards.remove(1);
+        next.columns[1].cards.push(card);
+        app.refresh_board(next);
+
+        assert_eq!((app.col, app.row), (1, 0));
+    }
+
+    #[test]
+    fn refresh_remembers_vanished_cards_for_one_cycle() {
+        let mut app = App::new(board_two_cols());
+
+        let mut next = board_two_cols();
+        next.columns[0].cards.remove(0);
+        app.refresh_board(next.clone());
+
+        assert_eq!(app.disappeared.len(), 1);
+        assert_eq!(app.disappeared[0].card.id, "1");
+        assert_eq!(app.disappeared[0].column, "A");
+        assert!(app.banner.as_deref().unwrap().contains("1 disappeared"));
+
+        app.refresh_board(next);
+        assert!(app.disappeared.is_empty());
+        assert!(app.banner.is_none());
+    }
+
+    #[test]
+    fn reconcile_keeps_moved_card_selected_when_positions_shift() {
+        let mut app = App::new(board_two_cols());
+        app.optimistic_move(1).unwrap();
+        assert_eq!(app.selected_card_id().as_deref(), Some("1"));
+
+        // The provider put another card ahead of ours in the destination.
+        let mut board = app.board.clone();
+        let other = board.columns[0].cards.remove(0);
+        board.columns[1].cards.insert(0, other);
+        app.reconcile(board, "1");
+
+        assert_eq!((app.col, app.row), (1, 1));
+        assert_eq!(app.selected_card_id().as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn pending_card_cannot_move_again_but_others_can() {
+        let mut app = App::new(board_two_cols());
+
+        app.optimistic_move(1).unwrap();
+        assert!(app.pending.contains("1"));
+        assert!(app.optimistic_move(-1).is_none());
+        assert!(app.banner.as_deref().unwrap().contains("still moving"));
+
+        app.focus(-1);
+        assert_eq!(app.optimistic_move(1).unwrap().0, "2");
+
+        app.pending.remove("1");
+        app.focus_card("1");
+        assert!(app.optimistic_move(-1).is_some());
+    }
+
+    #[test]
+    fn session_events_list_newest_first_and_jump_to_their_card() {
+        let mut app = App::new(board_two_cols());
+        app.record_events([
+            Event::CardMoved {
+                card_id: "2".into(),
+                to: "a".into(),
+            },
+            Event::Refreshed { cards: 2 },
+            Event::CardArchived {
+                card_id: "9".into(),
+            },
+        ]);
+        assert_eq!(app.session_log.len(), 2);
+
+        app.activity_menu(&app.session_log.clone());
+        assert_eq!(app.browsing.as_deref(), Some(&["9".into(), "2".into()][..]));
+        app.activity_picked(1);
+        assert_eq!(app.selected_card_id().as_deref(), Some("2"));
+
+        app.activity_menu(&app.session_log.clone());
+        app.activity_picked(0);
+        assert_eq!(app.banner.as_deref(), Some("9 is not on the board"));
+        assert!(app.browsing.is_none() && app.picker.is_none());
+    }
+
+    #[test]
+    fn create_card_opens_prompt_only_where_allowed() {
+        let mut app = App::new(board_two_cols());
+        app.apply(Action::CreateCard);
+        assert_eq!(app.create_prompt.as_deref(), Some(""));
+
+        let mut app = App::new(board_two_cols());
+        app.board.columns[0].meta.can_create = false;
+        app.apply(Action::CreateCard);
+        assert!(app.create_prompt.is_none());
+        assert!(app.banner.as_deref().unwrap().contains("does not accept"));
+    }
+
+    #[test]
+    fn refresh_records_remote_description_change_of_selected_card() {
+        let mut app = App::new(board_two_cols());
+        let mut next = board_two_cols();
+        next.columns[0].cards[0].description = "edited".into();
+
+        app.refresh_board(next);
+
+        let c = app.changed.as_ref().unwrap();
+        assert_eq!(
+            (c.card_id.as_str(), c.old.as_str(), c.new.as_str()),
+            ("1", "d", "edited")
+        );
+        app.apply(Action::ToggleDiff);
+        assert!(app.diff_open);
+        app.apply(Action::CloseOrQuit);
+        assert!(!app.diff_open);
+
+        app.refresh_board(board_two_cols());
+        assert_eq!(app.changed.as_ref().unwrap().new, "d");
+        app.refresh_board(board_two_cols());
+        assert!(app.changed.is_none());
+    }
+
+    #[test]
+    fn move_into_full_column_is_refused() {
+        let mut app = App::new(board_two_cols());
+        app.board.columns[1].meta.wip_limit = Some(1);
+
+        assert!(app.optimistic_move(1).is_some());
+        app.focus(-1);
+        assert!(app.optimistic_move(1).is_none());
+        assert_eq!(app.board.columns[0].cards.len(), 1);
+        assert!(app.banner.as_deref().unwrap().contains("WIP limit"));
+    }
+
     #[test]
     fn move_out_of_bounds_is_none() {
         let mut app = App::new(board_two_cols());
@@ -279,7 +1938,10 @@ mod tests {
 
     #[test]
     fn move_with_empty_board_is_none_and_does_not_panic() {
-        let mut app = App::new(Board { columns: vec![] });
+        let mut app = App::new(Board {
+            columns: vec![],
+            problems: vec![],
+        });
 
         assert!(app.optimistic_move(1).is_none());
         assert_eq!((app.col, app.row), (0, 0));
@@ -302,6 +1964,7 @@ mod tests {
             id: "2".to_string(),
             title: "t2".to_string(),
             description: "d".to_string(),
+            ..Default::default()
         });
         app.focus_first_non_empty();
 
@@ -318,4 +1981,405 @@ mod tests {
 
         assert!(app.apply(Action::CloseOrQuit));
     }
+
+    #[test]
+    fn vertical_keys_scroll_an_open_detail_instead_of_selecting() {
+        let mut app = App::new(board_two_cols());
+        app.apply(Action::ToggleDetail);
+        app.detail_overflow.set(12);
+
+        app.apply(Action::SelectDown);
+        assert_eq!((app.row, app.detail_scroll), (0, 1));
+        app.apply(Action::PageDown);
+        assert_eq!(app.detail_scroll, 11);
+        app.apply(Action::PageDown);
+        assert_eq!(app.detail_scroll, 12);
+
+        app.apply(Action::ToggleDetail);
+        app.apply(Action::SelectDown);
+        assert_eq!((app.row, app.detail_scroll), (1, 0));
+    }
+
+    #[test]
+    fn estimation_walks_cards_without_an_estimate() {
+        let mut board = board_two_cols();
+        board.columns[0].cards[0]
+            .fields
+            .insert("estimate".into(), vec!["3".into()]);
+        board.columns[1].cards.push(Card {
+            id: "3".into(),
+            ..Default::default()
+        });
+        let mut app = App::new(board);
+        app.apply(Action::Estimate);
+        let est = app.estimation.as_mut().unwrap();
+        assert_eq!(est.queue.len(), 2);
+
+        est.key(KeyCode::Char('5'));
+        est.key(KeyCode::Char('.'));
+        est.key(KeyCode::Char('5'));
+        assert!(matches!(
+            est.key(KeyCode::Enter),
+            EstimateKey::Save { card_id, points } if card_id == "2" && points == 5.5
+        ));
+        est.saved();
+        assert_eq!(est.current().unwrap().id, "3");
+        assert!(matches!(est.key(KeyCode::Tab), EstimateKey::Close));
+    }
+
+    #[test]
+    fn linking_picks_a_kind_then_another_card() {
+        let mut app = App::new(board_two_cols());
+        app.apply(Action::Link);
+        assert!(app.picker.is_none());
+
+        app.apply(Action::ToggleDetail);
+        app.apply(Action::Link);
+        assert_eq!(app.picker.as_ref().unwrap().items.len(), 3);
+        assert!(app.link_picked(2).is_none());
+        let picker = app.picker.as_ref().unwrap();
+        assert_eq!(
+            (picker.title.as_str(), picker.items.len()),
+            ("1 duplicates ...", 1)
+        );
+
+        assert_eq!(
+            app.link_picked(0),
+            Some(("1".to_string(), "2".to_string(), LinkKind::Duplicates))
+        );
+        assert!(app.picker.is_none() && app.linking.is_none());
+    }
+
+    #[test]
+    fn the_column_picker_moves_the_card_straight_there() {
+        let mut board = board_two_cols();
+        board.columns.push(Column {
+            id: "c".into(),
+            title: "C".into(),
+            cards: vec![],
+            meta: ColumnMeta::default(),
+        });
+        let mut app = App::new(board);
+        app.row = 1;
+        app.apply(Action::MoveTo);
+        let picker = app.picker.as_ref().unwrap();
+        assert_eq!(picker.items, ["1 A", "2 B", "3 C"]);
+
+        assert_eq!(app.move_picked(2), Some(("2".to_string(), "c".to_string())));
+        assert!(app.picker.is_none() && app.moving_to.is_none());
+        assert_eq!((app.col, app.row), (2, 0));
+        assert!(app.pending.contains("2"));
+
+        app.apply(Action::MoveTo);
+        assert!(app.move_picked(2).is_none());
+        assert_eq!(app.banner.as_deref(), Some("2 is already in C"));
+    }
+
+    #[test]
+    fn visual_marks_from_the_anchor_to_the_cursor_and_moves_them_together() {
+        let mut app = App::new(board_two_cols());
+        app.row = 1;
+        app.apply(Action::Visual);
+        app.apply(Action::SelectUp);
+        assert_eq!(app.marked(), ["1", "2"]);
+
+        app.board.columns[1].meta.wip_limit = Some(1);
+        assert!(app.batch_to(app.marked(), 1).is_none());
+        assert_eq!(
+            app.banner.as_deref(),
+            Some("WIP limit reached: B holds 0 of 1")
+        );
+
+        app.board.columns[1].meta.wip_limit = None;
+        let (card_ids, dst) = app.batch_to(app.marked(), 1).unwrap();
+        assert_eq!(
+            (card_ids, dst.as_str()),
+            (vec!["1".into(), "2".into()], "b")
+        );
+        assert!(app.visual.is_none() && app.pending.contains("1") && app.pending.contains("2"));
+        let batch = app.batch.as_ref().unwrap();
+        assert_eq!(batch.progress(1), "Moving 1/2 cards to B...");
+        assert_eq!(
+            batch.finished(1, Some("card 2 not found")),
+            "Moved 1 of 2 cards; card 2 not found"
+        );
+    }
+
+    #[test]
+    fn close_or_quit_closes_problems_before_detail() {
+        let mut app = App::new(board_two_cols());
+
+        app.detail_open = true;
+        assert!(!app.apply(Action::ToggleProblems));
+        assert!(!app.apply(Action::CloseOrQuit));
+        assert!(!app.problems_open);
+        assert!(app.detail_open);
+    }
+
+    #[test]
+    fn enter_in_problems_panel_jumps_to_offending_card() {
+        let mut app = App::new(board_two_cols());
+        app.board.problems = vec![
+            Problem {
+                card_id: None,
+                msg: "cols/x is not a column".into(),
+            },
+            Problem {
+                card_id: Some("2".into()),
+                msg: "2: missing reviewer".into(),
+            },
+        ];
+
+        app.apply(Action::ToggleProblems);
+        app.apply(Action::SelectDown);
+        app.apply(Action::ToggleDetail);
+
+        assert!(!app.problems_open);
+        assert!(!app.detail_open);
+        assert_eq!((app.col, app.row), (0, 1));
+    }
+
+    #[test]
+    fn search_jumps_between_matches_and_wraps() {
+        let mut app = App::new(board_two_cols());
+        app.board.columns[1].cards.push(Card {
+            id: "3".into(),
+            title: "Move card".into(),
+            description: "".into(),
+            ..Default::default()
+        });
+
+        app.apply(Action::Search);
+        app.search.as_mut().unwrap().query = "mvcrd".into();
+        app.update_search();
+        assert_eq!((app.col, app.row), (1, 0));
+
+        app.search.as_mut().unwrap().query = "d".into();
+        app.update_search();
+        assert_eq!((app.col, app.row), (1, 0)); // "Move card" still matches.
+        app.apply(Action::NextMatch);
+        assert_eq!((app.col, app.row), (0, 0));
+        app.apply(Action::PrevMatch);
+        assert_eq!((app.col, app.row), (1, 0));
+
+        assert!(!app.apply(Action::CloseOrQuit));
+        assert!(app.search.is_none());
+    }
+
+    #[test]
+    fn undo_reverses_a_landed_move_and_cancels_a_queued_one() {
+        let mut app = App::new(board_two_cols());
+
+        assert_eq!(app.optimistic_move(1), Some(("1".into(), "b".into())));
+        app.pending.clear(); // Landed.
+        assert_eq!(app.undo(false), Some(("1".into(), "a".into())));
+        assert_eq!(app.board.columns[0].cards[0].id, "1");
+        assert_eq!((app.col, app.row), (0, 0));
+
+        app.pending.clear();
+        app.row = 1;
+        app.optimistic_move(1);
+        assert_eq!(app.undo(false), None); // Still in flight.
+        assert_eq!(app.undo(true), None);
+        assert_eq!(app.board.columns[0].cards[1].id, "2");
+        assert!(app.pending.is_empty());
+        assert!(app.undo.is_empty());
+    }
+
+    #[test]
+    fn mentions_toast_once_and_open_their_card() {
+        let mut app = App::new(board_two_cols());
+        let mention = |id: &str, card_id: &str| Mention {
+            id: id.into(),
+            card_id: card_id.into(),
+            author: "Ana".into(),
+            body: "@you ping".into(),
+        };
+
+        app.add_mentions(vec![mention("c1", "2"), mention("c2", "X-9")]);
+        app.add_mentions(vec![mention("c1", "2")]);
+        assert_eq!(app.mentions.len(), 2);
+
+        app.apply(Action::OpenMention);
+        assert_eq!((app.col, app.row), (0, 1));
+        assert!(app.detail_open);
+        app.apply(Action::OpenMention);
+        assert_eq!(app.banner.as_deref(), Some("X-9 is not on this board"));
+        assert!(app.mentions.is_empty());
+    }
+
+    #[test]
+    fn yank_offers_a_link_only_with_a_url() {
+        let mut app = App::new(board_two_cols());
+        app.yank_menu(None);
+        assert_eq!(app.yanking, Some(vec!["1".to_string(), "t1".to_string()]));
+        app.yank_menu(Some("https://x/1"));
+        let texts = app.yanking.unwrap();
+        assert_eq!(texts[2..], ["[1 t1](https://x/1)", "https://x/1"]);
+    }
+
+    #[test]
+    fn a_found_card_on_the_board_is_focused_and_others_are_pulled() {
+        let mut app = App::new(board_two_cols());
+        let card = |id: &str| Card {
+            id: id.into(),
+            title: format!("t{id}"),
+            ..Card::default()
+        };
+        app.found_menu("t", vec![card("2"), card("X-9")]);
+        assert_eq!(
+            app.picker.as_ref().unwrap().items[0],
+            "2          t2  (in A)"
+        );
+        assert!(app.found_picked(0).is_none());
+        assert_eq!((app.col, app.row), (0, 1));
+
+        app.found_menu("t", vec![card("2"), card("X-9")]);
+        assert_eq!(app.found_picked(1).map(|c| c.id), Some("X-9".to_string()));
+        assert!(app.found.is_none() && app.picker.is_none());
+    }
+
+    #[test]
+    fn the_assign_picker_marks_the_assignee_and_shows_the_pick_at_once() {
+        let mut app = App::new(board_two_cols());
+        app.board.columns[0].cards[0].assignee = Some("Ben".into());
+        let user = |name: &str| User {
+            id: format!("acc-{name}"),
+            name: name.into(),
+        };
+        app.assign_menu(vec![user("Ana"), user("Ben")]);
+        let picker = app.picker.as_ref().unwrap();
+        assert_eq!(picker.items, ["Unassigned", "Ana", "Ben"]);
+        assert_eq!(picker.current, Some(2));
+
+        let (card_id, to) = app.assign_picked(1).unwrap();
+        assert_eq!(
+            (card_id.as_str(), to.map(|u| u.id)),
+            ("1", Some("acc-Ana".to_string()))
+        );
+        assert_eq!(
+            app.board.columns[0].cards[0].assignee.as_deref(),
+            Some("Ana")
+        );
+
+        app.assign_menu(vec![user("Ana")]);
+        assert_eq!(app.assign_picked(0).unwrap().1, None);
+        assert!(app.board.columns[0].cards[0].assignee.is_none());
+        assert!(app.assigning.is_none() && app.picker.is_none());
+    }
+
+    #[test]
+    fn the_filter_hides_cards_from_view_and_navigation_until_cleared() {
+        let mut app = App::new(board_two_cols());
+        for (i, id) in ["3", "4"].into_iter().enumerate() {
+            app.board.columns[0].cards.push(Card {
+                id: id.into(),
+                title: format!("t{id}"),
+                labels: if i == 0 { vec!["bug".into()] } else { vec![] },
+                ..Card::default()
+            });
+        }
+        app.board.columns[0].cards[0].labels = vec!["bug".into()];
+        app.row = 1;
+
+        app.set_filter("label:bug").unwrap();
+        assert_eq!(app.shown_rows(0), [0, 2]);
+        assert_eq!(app.row, 0);
+        app.select(1);
+        assert_eq!(app.selected_card_id().as_deref(), Some("3"));
+        app.select(1);
+        assert_eq!(app.row, 2);
+
+        // A refresh keeps the filter, and the cursor off hidden cards.
+        let board = app.board.clone();
+        app.row = 1;
+        app.refresh_board(board);
+        assert!(app.filter.is_some());
+        assert_eq!(app.row, 0);
+
+        assert!(app.set_filter("label:").is_err());
+        app.apply(Action::CloseOrQuit);
+        assert!(app.filter.is_none());
+        assert_eq!(app.shown_rows(0), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn moves_into_a_done_column_with_a_policy_wait_for_a_yes() {
+        let mut app = App::new(board_two_cols());
+        assert!(!app.hold_step_for_policy(1));
+        app.board.columns[1].meta.policy = Some("Reviewed".into());
+        assert!(!app.hold_step_for_policy(1));
+        app.board.columns[1].meta.terminal = true;
+        assert!(app.hold_step_for_policy(1));
+        assert_eq!(app.done_prompt, Some(("1".to_string(), 1)));
+        assert!(!app.hold_for_policy("1", 0));
+    }
+
+    #[test]
+    fn status_line_shows_freshness_moves_and_connection() {
+        let mut app = App::new(board_two_cols());
+        app.source = "local · notes".into();
+        assert_eq!(app.status_line(0), "local · notes · online");
+        app.loaded_at = Some(1_000);
+        app.pending.insert("1".into());
+        app.offline = true;
+        assert_eq!(
+            app.status_line(1_000 + 150),
+            "local · notes · refreshed 2m ago · 1 moving · offline"
+        );
+    }
+
+    #[test]
+    fn commenting_needs_the_card_open() {
+        let mut app = App::new(board_two_cols());
+        app.apply(Action::Comment);
+        assert!(app.comment_prompt.is_none());
+        app.apply(Action::ToggleDetail);
+        app.apply(Action::Comment);
+        assert_eq!(app.comment_prompt, Some(("1".to_string(), String::new())));
+    }
+
+    #[test]
+    fn viewport_scrolls_only_to_keep_the_selection_visible() {
+        assert_eq!(viewport(0, Some(3), 10, 4), 0);
+        assert_eq!(viewport(0, Some(5), 10, 4), 2);
+        assert_eq!(viewport(4, Some(5), 10, 4), 4);
+        assert_eq!(viewport(4, Some(1), 10, 4), 1);
+        // A column that shrank, or an unfocused one, stays in range.
+        assert_eq!(viewport(8, None, 10, 4), 6);
+        assert_eq!(viewport(3, None, 2, 4), 0);
+    }
+
+    #[test]
+    fn quick_filter_menu_starts_from_the_active_filters() {
+        let q = |id: &str| QuickFilter {
+            id: id.into(),
+            name: format!("only {id}"),
+            query: format!("labels = {id}"),
+        };
+        let mut menu = QuickFilterMenu::new(vec![q("1"), q("2"), q("3")], &[q("2")]);
+
+        assert_eq!(menu.on, [false, true, false]);
+        menu.key(KeyCode::Char(' '));
+        menu.key(KeyCode::Char('j'));
+        menu.key(KeyCode::Enter);
+        assert!(menu.key(KeyCode::Esc));
+        assert_eq!(menu.chosen(), vec![q("1")]);
+    }
+
+    #[test]
+    fn a_lock_only_lets_cards_change_inside_its_allowed_columns() {
+        let mut app = App::new(board_two_cols());
+        app.lock = Some(Lock {
+            until: "2024-06-14".into(),
+            allow: vec!["b".into()],
+        });
+
+        assert!(app.locked_out(Action::Edit));
+        assert!(app.locked_out(Action::MoveRight));
+        assert!(!app.locked_out(Action::Search));
+        app.col = 1;
+        assert!(!app.locked_out(Action::CreateCard));
+        assert!(app.locked_out(Action::MoveLeft));
+    }
 }
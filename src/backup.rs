@@ -0,0 +1,208 @@
+//! Scheduled backups. While the TUI or `flow serve` runs, a board with a
+//! `[backup]` dir is written there every `every_hours`: one directory per
+//! backup, `<dir>/<board>/<YYYY-MM-DDTHHMMSSZ>/`, holding `board.json` (as
+//! `flow export` prints it) and, for local boards, `files/`, a copy of the
+//! board directory that `FLOW_LOCAL_PATH` can point at. Only the newest
+//! `keep` are kept.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    config::{Backup, Profile},
+    dates, freeze, log,
+    model::Board,
+    provider,
+};
+
+/// How often a running flow checks whether a backup is due.
+pub const CHECK_EVERY: Duration = Duration::from_secs(60);
+
+/// Backs up `board` if the newest backup is `every_hours` old, returning
+/// where it went.
+pub fn run_if_due(
+    cfg: &Backup,
+    key: &str,
+    board: &Board,
+    local_root: Option<&Path>,
+) -> io::Result<Option<PathBuf>> {
+    let Some(dir) = cfg.dir() else {
+        return Ok(None);
+    };
+    let dir = dir.join(freeze::file_name(key));
+    let now = dates::now_secs();
+    if !due(&dir, cfg.every_hours, now) {
+        return Ok(None);
+    }
+    write(&dir, board, local_root, now, cfg.keep).map(Some)
+}
+
+/// For `flow serve`, which has no board of its own between requests: loads
+/// the profile's board whenever a backup is due.
+pub fn spawn(cfg: Backup, profile: Profile) {
+    if cfg.dir().is_none() || profile.provider() == "demo" {
+        return;
+    }
+    let key = freeze::board_key(&profile);
+    let local_root = local_root(&profile);
+    thread::spawn(move || {
+        loop {
+            let dir = cfg.dir().unwrap_or_default().join(freeze::file_name(&key));
+            if due(&dir, cfg.every_hours, dates::now_secs()) {
+                let backed_up = provider::from_profile(&profile)
+                    .load_board()
+                    .map_err(|e| e.to_string())
+                    .and_then(|board| {
+                        run_if_due(&cfg, &key, &board, local_root.as_deref())
+                            .map_err(|e| e.to_string())
+                    });
+                if let Err(e) = backed_up {
+                    log::write(&format!("backup: {e}"));
+                }
+            }
+            thread::sleep(CHECK_EVERY);
+        }
+    });
+}
+
+/// The board directory of a local profile, whose files a backup copies.
+pub fn local_root(profile: &Profile) -> Option<PathBuf> {
+    (!matches!(profile.provider(), "jira" | "linear" | "github" | "demo")).then(|| {
+        crate::provider_local::LocalProvider::from_profile(profile)
+            .root()
+            .to_path_buf()
+    })
+}
+
+fn due(dir: &Path, every_hours: u32, now: u64) -> bool {
+    backups(dir)
+        .last()
+        .is_none_or(|&(at, _)| now >= at + u64::from(every_hours) * 3600)
+}
+
+fn write(
+    dir: &Path,
+    board: &Board,
+    local_root: Option<&Path>,
+    now: u64,
+    keep: usize,
+) -> io::Result<PathBuf> {
+    let name = dates::format_timestamp(now).replace(':', "");
+    let out = dir.join(&name);
+    // Written aside and renamed, so a backup that's there is whole.
+    let tmp = dir.join(format!(".{name}.partial"));
+    let _ = fs::remove_dir_all(&tmp);
+    fs::create_dir_all(&tmp)?;
+    let board = Board {
+        columns: board.columns.clone(),
+        problems: vec![],
+    };
+    fs::write(
+        tmp.join("board.json"),
+        serde_json::to_string_pretty(&board)?,
+    )?;
+    if let Some(root) = local_root {
+        copy_dir(root, &tmp.join("files"))?;
+    }
+    fs::rename(&tmp, &out)?;
+
+    let all = backups(dir);
+    for (_, old) in &all[..all.len().saturating_sub(keep.max(1))] {
+        fs::remove_dir_all(old)?;
+    }
+    Ok(out)
+}
+
+/// Backups in `dir` with when they were made, oldest first.
+fn backups(dir: &Path) -> Vec<(u64, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut out: Vec<(u64, PathBuf)> = entries
+        .filter_map(|e| {
+            let path = e.ok()?.path();
+            Some((parse_name(path.file_name()?.to_str()?)?, path))
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+/// `2024-06-01T093000Z` as seconds since the epoch.
+fn parse_name(name: &str) -> Option<u64> {
+    let (date, time) = name.strip_suffix('Z')?.split_once('T')?;
+    let day = u64::try_from(dates::parse_date(date)?).ok()?;
+    if time.len() != 6 || !time.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let n = |i: usize| time[i..i + 2].parse::<u64>().ok();
+    Some(day * 86_400 + n(0)? * 3600 + n(2)? * 60 + n(4)?)
+}
+
+/// Copies the board's files, leaving out the running instance's lock,
+/// flow's bookkeeping, the git repository and the `.env` of secrets.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if [crate::instance::FILE, ".flow", ".git", ".env"].contains(&&*name.to_string_lossy()) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(&name))?;
+        } else {
+            fs::copy(entry.path(), to.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn backups_are_made_when_due_and_only_the_newest_kept() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let tmp = std::env::temp_dir().join(format!("flow-backup-{nanos}"));
+        let (root, dir) = (tmp.join("board"), tmp.join("backups"));
+        fs::create_dir_all(root.join("cols/todo")).unwrap();
+        fs::write(root.join("cols/todo/A-1.md"), "# A\n").unwrap();
+        fs::write(root.join(crate::instance::FILE), "").unwrap();
+        for skipped in [".flow/lock", ".git/HEAD", ".env"] {
+            fs::create_dir_all(root.join(skipped).parent().unwrap()).unwrap();
+            fs::write(root.join(skipped), "").unwrap();
+        }
+        let board = Board {
+            columns: vec![],
+            problems: vec![],
+        };
+
+        let day = 1_717_200_000;
+        assert!(due(&dir, 24, day));
+        let first = write(&dir, &board, Some(&root), day, 2).unwrap();
+        assert!(first.ends_with("2024-06-01T000000Z"));
+        assert!(first.join("files/cols/todo/A-1.md").is_file());
+        for skipped in [crate::instance::FILE, ".flow", ".git", ".env"] {
+            assert!(!first.join("files").join(skipped).exists());
+        }
+        assert!(first.join("board.json").is_file());
+        assert!(!due(&dir, 24, day + 3600));
+        assert!(due(&dir, 24, day + 86_400));
+
+        write(&dir, &board, None, day + 86_400, 2).unwrap();
+        write(&dir, &board, None, day + 2 * 86_400, 2).unwrap();
+        let left: Vec<u64> = backups(&dir).into_iter().map(|(at, _)| at).collect();
+        assert_eq!(left, [day + 86_400, day + 2 * 86_400]);
+        fs::remove_dir_all(tmp).unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+//! Card titles from pasted notes, for `flow create --bulk` and for pasting
+//! several lines into the new-card prompt.
+
+/// One title per line, without list bullets, numbers or `[ ]` boxes.
+/// Headings, ticked `[x]` items and lines without any words are skipped.
+pub fn titles(text: &str) -> Vec<String> {
+    // Terminals paste line breaks as `\r`.
+    text.split(['\n', '\r']).filter_map(title).collect()
+}
+
+fn title(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+    let line = strip_bullet(line);
+    let line = match line.get(..4) {
+        Some("[ ] ") => &line[4..],
+        Some("[x] " | "[X] ") => return None,
+        _ => line,
+    };
+    let line = line.trim();
+    line.chars()
+        .any(char::is_alphanumeric)
+        .then(|| line.to_string())
+}
+
+/// `- `, `* `, `+ `, `1. ` or `1) `.
+fn strip_bullet(line: &str) -> &str {
+    if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|b| line.strip_prefix(b)) {
+        return rest;
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match line[digits..].get(..2) {
+        Some(". " | ") ") if digits > 0 => &line[digits + 2..],
+        _ => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meeting_notes_become_one_title_per_item() {
+        let notes = "## Action items\n\
+                     - [ ] Book the room\n\
+                     - [x] Send the agenda\n\
+                     \n\
+                     * Update the roadmap\n\
+                     2) Ping legal\n\
+                     Plain line\n\
+                     ---\n";
+
+        assert_eq!(
+            titles(notes),
+            [
+                "Book the room",
+                "Update the roadmap",
+                "Ping legal",
+                "Plain line"
+            ]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Card templates: markdown files under a board's `templates/` (or the
+//! profile's `templates` dir), one per kind of card, e.g. `bug.md`. `n`
+//! offers them before asking for a title. A template is a card file whose
+//! text may use `{{title}}`, `{{date}}`, `{{user}}` and `{{column}}`:
+//!
+//! ```text
+//! ---
+//! labels: [bug]
+//! issue_type: Bug
+//! ---
+//! # {{title}}
+//!
+//! ## Steps to reproduce
+//! ```
+//!
+//! Its front matter is copied to the new card (Jira reads `issue_type`,
+//! `labels` and `priority`), and without a heading the typed title is used.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backup,
+    config::{self, Profile},
+    frontmatter::{self, FrontMatter},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CardTemplate {
+    /// The file name without `.md`.
+    pub name: String,
+    text: String,
+}
+
+/// A card to create, with a template filled in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Draft {
+    pub title: String,
+    pub description: String,
+    pub front: FrontMatter,
+}
+
+/// What the placeholders stand for.
+pub struct Vars<'a> {
+    pub title: &'a str,
+    pub date: &'a str,
+    pub user: &'a str,
+    pub column: &'a str,
+}
+
+/// Where `profile`'s templates live, if anywhere.
+pub fn dir(profile: &Profile) -> Option<PathBuf> {
+    match &profile.templates {
+        Some(d) => Some(config::expand_home(d)),
+        None => backup::local_root(profile).map(|root| root.join("templates")),
+    }
+}
+
+/// The templates in `dir`, by name; none when it doesn't exist.
+pub fn load(dir: &Path) -> Vec<CardTemplate> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut out: Vec<CardTemplate> = entries
+        .filter_map(|e| {
+            let path = e.ok()?.path();
+            if path.extension()? != "md" {
+                return None;
+            }
+            Some(CardTemplate {
+                name: path.file_stem()?.to_str()?.to_string(),
+                text: fs::read_to_string(&path).ok()?,
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+impl CardTemplate {
+    pub fn fill(&self, vars: &Vars) -> Draft {
+        let text = [
+            ("{{title}}", vars.title),
+            ("{{date}}", vars.date),
+            ("{{user}}", vars.user),
+            ("{{column}}", vars.column),
+        ]
+        .iter()
+        .fold(self.text.clone(), |text, (key, value)| {
+            text.replace(key, value)
+        });
+        let (front, body) = frontmatter::split(&text);
+        let body = body.trim_start();
+        let (title, description) = match body.strip_prefix("# ") {
+            Some(rest) => rest.split_once('\n').unwrap_or((rest, "")),
+            None => ("", body),
+        };
+        let title = title.trim();
+        Draft {
+            title: if title.is_empty() { vars.title } else { title }.to_string(),
+            description: description.trim().to_string(),
+            front: front.unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_filled_and_front_matter_kept() {
+        let template = CardTemplate {
+            name: "bug".to_string(),
+            text: "---\nlabels: [bug]\nissue_type: Bug\n---\n# Bug: {{title}}\n\n\
+                   Seen by {{user}} on {{date}} in {{column}}.\n"
+                .to_string(),
+        };
+        let draft = template.fill(&Vars {
+            title: "Login loops",
+            date: "2024-06-01",
+            user: "Ana",
+            column: "To do",
+        });
+        assert_eq!(draft.title, "Bug: Login loops");
+        assert_eq!(draft.description, "Seen by Ana on 2024-06-01 in To do.");
+        assert_eq!(draft.front.list("labels"), ["bug"]);
+        assert_eq!(draft.front.get("issue_type"), Some("Bug"));
+
+        let plain = CardTemplate {
+            name: "chore".to_string(),
+            text: "Checklist\n".to_string(),
+        };
+        let draft = plain.fill(&Vars {
+            title: "Rotate keys",
+            date: "",
+            user: "",
+            column: "",
+        });
+        assert_eq!(draft.title, "Rotate keys");
+        assert_eq!(draft.description, "Checklist");
+        assert_eq!(draft.front, FrontMatter::default());
+    }
+}
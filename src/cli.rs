@@ -0,0 +1,1052 @@
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+
+use crate::{
+    backup, bulk, config, dates, diff, exchange, filter, freeze,
+    model::{Board, Card, Column},
+    provider::{self, ProviderError},
+    provider_local::LocalProvider,
+    rpc, serve, show, stats, store_fs, template,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "flow",
+    about = "A keyboard-first Kanban board in your terminal"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Open this profile instead of `default_profile`
+    #[arg(long, short = 'p', global = true, visible_alias = "profile")]
+    pub board: Option<String>,
+    /// How subcommands report failures on stderr
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ViewFormat {
+    /// `{"columns": [{"id", "title", "cards": [{"id", "title", ...}]}]}`
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+    /// One row per card, for spreadsheets
+    Csv,
+    /// A Trello board export
+    Trello,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// `flow export`
+    Json,
+    /// A Trello board's "Export as JSON"
+    Trello,
+    /// Jira's "Export Excel CSV (all fields)"
+    JiraCsv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    Text,
+    Json,
+}
+
+/// Failure classes with stable exit codes. `Usage` shares 2 with clap's
+/// argument errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    Other,
+    Usage,
+    Config,
+    Auth,
+    NotFound,
+    Network,
+}
+
+impl ErrorClass {
+    pub fn code(self) -> i32 {
+        match self {
+            ErrorClass::Other => 1,
+            ErrorClass::Usage => 2,
+            ErrorClass::Config => 3,
+            ErrorClass::Auth => 4,
+            ErrorClass::NotFound => 5,
+            ErrorClass::Network => 6,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorClass::Other => "other",
+            ErrorClass::Usage => "usage",
+            ErrorClass::Config => "config",
+            ErrorClass::Auth => "auth",
+            ErrorClass::NotFound => "not_found",
+            ErrorClass::Network => "network",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Failure {
+    pub class: ErrorClass,
+    pub msg: String,
+}
+
+impl Failure {
+    pub fn config(msg: impl fmt::Display) -> Self {
+        Self {
+            class: ErrorClass::Config,
+            msg: format!("config: {msg}"),
+        }
+    }
+
+    /// Prints the failure to stderr and returns the process exit code.
+    pub fn report(&self, format: ErrorFormat) -> i32 {
+        let code = self.class.code();
+        match format {
+            ErrorFormat::Text => eprintln!("flow: {}", self.msg),
+            ErrorFormat::Json => eprintln!(
+                "{}",
+                serde_json::json!({
+                    "error": { "code": code, "kind": self.class.name(), "message": self.msg }
+                })
+            ),
+        }
+        code
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Self {
+        let class = match err.kind() {
+            io::ErrorKind::NotFound => ErrorClass::NotFound,
+            _ => ErrorClass::Other,
+        };
+        Self {
+            class,
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl From<ProviderError> for Failure {
+    fn from(err: ProviderError) -> Self {
+        let class = match &err {
+            ProviderError::Config { .. } => ErrorClass::Config,
+            ProviderError::Auth { .. } => ErrorClass::Auth,
+            ProviderError::NotFound { .. } => ErrorClass::NotFound,
+            ProviderError::Network { .. } => ErrorClass::Network,
+            ProviderError::Io { source, .. } if source.kind() == io::ErrorKind::NotFound => {
+                ErrorClass::NotFound
+            }
+            _ => ErrorClass::Other,
+        };
+        Self {
+            class,
+            msg: err.to_string(),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Share board structure (columns, not cards) as TOML
+    #[command(subcommand)]
+    Template(TemplateCommand),
+    /// Rename columns or labels across the whole board
+    #[command(subcommand)]
+    Refactor(RefactorCommand),
+    /// List orphaned card files and drop order entries whose file is gone
+    Repair,
+    /// Manage secrets referenced from profiles as `keyring:NAME`
+    #[command(subcommand)]
+    Secret(SecretCommand),
+    /// Print the board's cards, one per line
+    List {
+        /// Only cards matching an expression, e.g. 'column=doing and label=bug and due<3d'
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// Stable `ID<TAB>COLUMN<TAB>TITLE` lines for scripts and fzf
+        #[arg(long)]
+        porcelain: bool,
+        /// The matching cards as board JSON, the same shape `flow view` reads
+        #[arg(long, conflicts_with = "porcelain")]
+        json: bool,
+    },
+    /// Move a card to another column, given by id or title
+    Move { card: String, column: String },
+    /// Create a card and print its id
+    #[command(visible_alias = "add")]
+    Create {
+        column: String,
+        #[arg(required_unless_present = "bulk")]
+        title: Option<String>,
+        /// One card per line of stdin, e.g. a `- [ ]` checklist from meeting notes
+        #[arg(long, conflicts_with = "title")]
+        bulk: bool,
+    },
+    /// Freeze the board until a date, e.g. for a retrospective or sprint close
+    Lock {
+        /// Last locked day, `YYYY-MM-DD`
+        #[arg(long)]
+        until: String,
+        /// A column whose cards may still change; repeatable
+        #[arg(long)]
+        allow: Vec<String>,
+    },
+    /// Lift the board's lock early
+    Unlock,
+    /// Print the board, or what changed since an earlier export
+    Export {
+        /// A saved `flow export` (or `list --json`) to compare against: lists
+        /// added, removed, moved and edited cards
+        #[arg(long)]
+        diff: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+    },
+    /// Create a local board from a flow, Trello or Jira export
+    Import {
+        /// Export file ("-" reads stdin)
+        file: PathBuf,
+        /// What the file is; guessed from its contents when unset
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Import into a board that already has a board.txt
+        #[arg(long)]
+        force: bool,
+    },
+    /// Per-column counts, WIP, average age and throughput
+    Stats {
+        /// Machine-readable output for dashboards and cron scripts
+        #[arg(long)]
+        json: bool,
+        /// Throughput window in days
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+    },
+    /// Print a card for an fzf preview pane; accepts a whole `list --porcelain` line
+    Preview { line: String },
+    /// Print one card to stdout
+    Show {
+        card: String,
+        /// Colors, wrapped text, a metadata table and checklist progress
+        #[arg(long)]
+        pretty: bool,
+        /// The card as JSON, with its column's id
+        #[arg(long, conflicts_with = "pretty")]
+        json: bool,
+        /// Wrap width for --pretty (defaults to the terminal or fzf preview width)
+        #[arg(long)]
+        width: Option<usize>,
+    },
+    /// Show a read-only board piped in on stdin, e.g. `tool | flow view`
+    View {
+        #[arg(long, value_enum, default_value_t = ViewFormat::Json)]
+        format: ViewFormat,
+    },
+    /// Open the overview of every configured board
+    Home,
+    /// Answer line-delimited JSON-RPC on stdin/stdout, for editor plugins
+    Rpc {
+        /// Serve on a Unix socket instead of stdin/stdout
+        #[arg(long, conflicts_with = "connect")]
+        listen: Option<PathBuf>,
+        /// Pass stdin/stdout through to a `--listen` server
+        #[arg(long)]
+        connect: Option<PathBuf>,
+    },
+    /// Serve the board over HTTP for dashboards and bots
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:9464")]
+        addr: String,
+        /// Expose Prometheus gauges at /metrics
+        #[arg(long)]
+        metrics: bool,
+    },
+    /// `flow NAME`: open a `[workspaces.NAME]` from the config
+    #[command(external_subcommand)]
+    Workspace(Vec<String>),
+}
+
+#[derive(Subcommand)]
+pub enum TemplateCommand {
+    /// Write the current board's structure as a TOML template
+    Export {
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Create the current board's structure from a TOML template
+    Import {
+        /// Template file ("-" reads stdin)
+        file: PathBuf,
+        /// Overwrite an existing board.txt
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RefactorCommand {
+    /// Rename a column id, its directory, and optionally its title
+    RenameColumn {
+        old: String,
+        new: String,
+        /// New display title
+        #[arg(long)]
+        title: Option<String>,
+    },
+    /// Rename a label in every card's front matter
+    RenameLabel { old: String, new: String },
+}
+
+#[derive(Subcommand)]
+pub enum SecretCommand {
+    /// Store a secret read from stdin in the OS keychain
+    Set { name: String },
+    /// Remove a stored secret
+    Delete { name: String },
+}
+
+pub fn run(cmd: Command) -> Result<(), Failure> {
+    match cmd {
+        Command::Template(cmd) => run_template(cmd)?,
+        Command::Refactor(cmd) => run_refactor(cmd)?,
+        Command::Repair => run_repair()?,
+        Command::Secret(cmd) => run_secret(cmd)?,
+        Command::List {
+            filter,
+            porcelain,
+            json,
+        } => run_list(filter.as_deref(), porcelain, json)?,
+        Command::Move { card, column } => run_move(&card, &column)?,
+        Command::Create {
+            column,
+            title,
+            bulk,
+        } => {
+            let titles = match title {
+                Some(title) if !bulk => vec![title],
+                _ => {
+                    let mut text = String::new();
+                    io::stdin().read_to_string(&mut text).map_err(|e| Failure {
+                        class: ErrorClass::Other,
+                        msg: format!("stdin: {e}"),
+                    })?;
+                    bulk::titles(&text)
+                }
+            };
+            run_create(&column, &titles)?
+        }
+        Command::Lock { until, allow } => run_lock(&until, &allow)?,
+        Command::Unlock => run_unlock()?,
+        Command::Export { diff, format } => run_export(diff.as_deref(), format)?,
+        Command::Import {
+            file,
+            format,
+            force,
+        } => run_import(&file, format, force)?,
+        Command::Stats { json, days } => run_stats(json, days)?,
+        Command::Preview { line } => {
+            let id = line.split('\t').next().unwrap_or_default().trim();
+            run_show(id, true, false, None)?
+        }
+        Command::Rpc { listen, connect } => run_rpc(listen, connect)?,
+        Command::Serve { addr, metrics } => serve::run(&addr, metrics)?,
+        Command::Show {
+            card,
+            pretty,
+            json,
+            width,
+        } => run_show(&card, pretty, json, width)?,
+        // These need the terminal; main handles them before calling `run`.
+        Command::View { .. } | Command::Home | Command::Workspace(_) => {
+            unreachable!("TUI commands are run by main")
+        }
+    }
+    Ok(())
+}
+
+/// Applies the same checks as the TUI: WIP limits and `create=false`.
+fn run_move(card_id: &str, column: &str) -> Result<(), Failure> {
+    config::resolve().map_err(Failure::config)?;
+    let mut provider = provider::from_env();
+    let board = provider.load_board()?;
+    if !board
+        .columns
+        .iter()
+        .any(|c| c.cards.iter().any(|c| c.id == card_id))
+    {
+        return Err(Failure {
+            class: ErrorClass::NotFound,
+            msg: format!("not found: {card_id}"),
+        });
+    }
+    let col = find_column(&board, column)?;
+    if col.cards.iter().any(|c| c.id == card_id) {
+        return Ok(());
+    }
+    if col.at_wip_limit() {
+        return Err(Failure {
+            class: ErrorClass::Other,
+            msg: format!(
+                "WIP limit reached: {} holds {} of {}",
+                col.title,
+                col.cards.len(),
+                col.meta.wip_limit.unwrap_or_default()
+            ),
+        });
+    }
+    provider.move_card(card_id, &col.id.clone())?;
+    Ok(())
+}
+
+/// Prints each new id as it's created, so a failure partway leaves a record
+/// of what was made.
+fn run_create(column: &str, titles: &[String]) -> Result<(), Failure> {
+    if titles.iter().all(|t| t.trim().is_empty()) {
+        return Err(Failure {
+            class: ErrorClass::Usage,
+            msg: "no title given".to_string(),
+        });
+    }
+    config::resolve().map_err(Failure::config)?;
+    let mut provider = provider::from_env();
+    let board = provider.load_board()?;
+    let col = find_column(&board, column)?;
+    if !col.meta.can_create {
+        return Err(Failure {
+            class: ErrorClass::Other,
+            msg: format!("{} doesn't take new cards", col.title),
+        });
+    }
+    let col_id = col.id.clone();
+    for title in titles.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        println!("{}", provider.create_card_with_title(&col_id, title)?);
+    }
+    Ok(())
+}
+
+/// A column by id, or else by title, ignoring case.
+fn find_column<'a>(board: &'a Board, name: &str) -> Result<&'a Column, Failure> {
+    board
+        .column_named(name)
+        .map(|i| &board.columns[i])
+        .ok_or_else(|| {
+            let ids: Vec<&str> = board.columns.iter().map(|c| c.id.as_str()).collect();
+            Failure {
+                class: ErrorClass::Usage,
+                msg: format!("no column {name:?} (columns: {})", ids.join(", ")),
+            }
+        })
+}
+
+/// Stores the allowed columns by id, so the lock survives column renames.
+fn run_lock(until: &str, allow: &[String]) -> Result<(), Failure> {
+    let last = dates::parse_date(until).ok_or_else(|| Failure {
+        class: ErrorClass::Usage,
+        msg: format!("bad date {until:?} (expected YYYY-MM-DD)"),
+    })?;
+    if last < dates::today() {
+        return Err(Failure {
+            class: ErrorClass::Usage,
+            msg: format!("{until} has already passed"),
+        });
+    }
+    config::resolve().map_err(Failure::config)?;
+    let board = provider::from_env().load_board()?;
+    let allow = allow
+        .iter()
+        .map(|name| find_column(&board, name).map(|c| c.id.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let lock = freeze::Lock {
+        until: dates::format_date(last),
+        allow,
+    };
+    freeze::set(&freeze::board_key(&config::active_profile()), Some(lock))?;
+    println!("locked through {}", dates::format_date(last));
+    Ok(())
+}
+
+fn run_unlock() -> Result<(), Failure> {
+    config::resolve().map_err(Failure::config)?;
+    freeze::set(&freeze::board_key(&config::active_profile()), None)?;
+    Ok(())
+}
+
+fn run_export(snapshot: Option<&Path>, format: ExportFormat) -> Result<(), Failure> {
+    let old = snapshot
+        .map(|path| -> Result<Board, Failure> {
+            let text = fs::read_to_string(path)?;
+            serde_json::from_str(&text).map_err(|e| Failure {
+                class: ErrorClass::Usage,
+                msg: format!("{}: not a flow export: {e}", path.display()),
+            })
+        })
+        .transpose()?;
+    config::resolve().map_err(Failure::config)?;
+    let board = provider::from_env().load_board()?;
+
+    let out = match (old, format) {
+        (None, ExportFormat::Json) => {
+            serde_json::to_string_pretty(&board).map_err(io::Error::other)?
+        }
+        (None, ExportFormat::Markdown) => board_markdown(&board),
+        (Some(old), ExportFormat::Json) => {
+            serde_json::to_string_pretty(&diff::boards(&old, &board)).map_err(io::Error::other)?
+        }
+        (Some(old), ExportFormat::Markdown) => diff_markdown(&old, &board),
+        (None, ExportFormat::Csv) => exchange::to_csv(&board),
+        (None, ExportFormat::Trello) => exchange::to_trello(&board)?,
+        (Some(_), _) => {
+            return Err(Failure {
+                class: ErrorClass::Usage,
+                msg: "--diff prints json or markdown".to_string(),
+            });
+        }
+    };
+    println!("{}", out.trim_end());
+    Ok(())
+}
+
+fn run_import(file: &Path, format: Option<ImportFormat>, force: bool) -> Result<(), Failure> {
+    let src = if file.as_os_str() == "-" {
+        let mut s = String::new();
+        io::stdin().read_to_string(&mut s)?;
+        s
+    } else {
+        fs::read_to_string(file)?
+    };
+    let source = match format {
+        Some(ImportFormat::Json) => exchange::Source::Flow,
+        Some(ImportFormat::Trello) => exchange::Source::Trello,
+        Some(ImportFormat::JiraCsv) => exchange::Source::JiraCsv,
+        None => exchange::Source::guess(&src),
+    };
+    let board = exchange::parse(&src, source).map_err(|msg| Failure {
+        class: ErrorClass::Usage,
+        msg: format!("{}: {msg}", file.display()),
+    })?;
+
+    config::resolve().map_err(Failure::config)?;
+    let profile = config::active_profile();
+    let root = backup::local_root(&profile).ok_or_else(|| {
+        Failure::config(format!(
+            "import writes a local board; the active profile is {}",
+            profile.provider()
+        ))
+    })?;
+    let imported = exchange::write_local(&root, &board, force)?;
+    for (id, why) in &imported.skipped {
+        eprintln!("skipped {id}: {why}");
+    }
+    println!(
+        "Imported {} columns and {} cards into {}",
+        board.columns.len(),
+        imported.cards,
+        root.display()
+    );
+    Ok(())
+}
+
+fn board_markdown(board: &Board) -> String {
+    let mut out = String::new();
+    for col in &board.columns {
+        out += &format!("## {} ({})\n\n", col.title, col.cards.len());
+        for c in &col.cards {
+            out += &format!("- {} {}\n", c.id, c.title);
+        }
+        out += "\n";
+    }
+    out
+}
+
+/// A report for people: columns by title, sections only when non-empty.
+fn diff_markdown(old: &Board, new: &Board) -> String {
+    let d = diff::boards(old, new);
+    if d.is_empty() {
+        return "No changes.".to_string();
+    }
+    let title = |id: &str| {
+        new.columns
+            .iter()
+            .chain(&old.columns)
+            .find(|c| c.id == id)
+            .map_or(id, |c| c.title.as_str())
+            .to_string()
+    };
+    let section = |out: &mut String, name: &str, lines: Vec<String>| {
+        if !lines.is_empty() {
+            *out += &format!("## {name} ({})\n\n{}\n\n", lines.len(), lines.join("\n"));
+        }
+    };
+    let mut out = String::new();
+    section(
+        &mut out,
+        "Added",
+        d.added
+            .iter()
+            .map(|c| format!("- {} {} (in {})", c.id, c.title, title(&c.column)))
+            .collect(),
+    );
+    section(
+        &mut out,
+        "Removed",
+        d.removed
+            .iter()
+            .map(|c| format!("- {} {} (was in {})", c.id, c.title, title(&c.column)))
+            .collect(),
+    );
+    section(
+        &mut out,
+        "Moved",
+        d.moved
+            .iter()
+            .map(|c| {
+                format!(
+                    "- {} {}: {} → {}",
+                    c.id,
+                    c.title,
+                    title(&c.from),
+                    title(&c.to)
+                )
+            })
+            .collect(),
+    );
+    section(
+        &mut out,
+        "Edited",
+        d.edited
+            .iter()
+            .map(|c| format!("- {} {}: {}", c.id, c.title, c.changed.join(", ")))
+            .collect(),
+    );
+    out
+}
+
+fn run_stats(json: bool, days: i64) -> Result<(), Failure> {
+    config::resolve().map_err(Failure::config)?;
+    let mut provider = provider::from_env();
+    let board = provider.load_board()?;
+    let now = dates::now_secs();
+    // Providers without history fall back to `updated` dates.
+    let moves = provider
+        .moves(now.saturating_sub(days.max(0) as u64 * 86_400))
+        .unwrap_or_default();
+    let stats = stats::compute(&board, dates::today(), days).with_moves(&board, &moves, now);
+
+    if json {
+        let out = serde_json::to_string_pretty(&stats).map_err(io::Error::other)?;
+        println!("{out}");
+        return Ok(());
+    }
+    let title_w = stats
+        .columns
+        .iter()
+        .map(|c| c.title.chars().count())
+        .max()
+        .unwrap_or(0);
+    println!("{:<title_w$}  CARDS  WIP  AVG AGE  IN COLUMN", "COLUMN");
+    for c in &stats.columns {
+        let wip = c.wip_limit.map_or("-".to_string(), |n| n.to_string());
+        let age = c.avg_age_days.map_or("-".to_string(), |d| format!("{d}d"));
+        let within = c.avg_days_in.map_or("-".to_string(), |d| format!("{d}d"));
+        let flag = if c.over_wip { "  over WIP" } else { "" };
+        println!(
+            "{:<title_w$}  {:>5}  {wip:>3}  {age:>7}  {within:>9}{flag}",
+            c.title, c.count
+        );
+    }
+    println!(
+        "\n{} cards; {} done in the last {} days",
+        stats.total, stats.throughput.done, stats.throughput.days
+    );
+    Ok(())
+}
+
+fn run_show(card_id: &str, pretty: bool, json: bool, width: Option<usize>) -> Result<(), Failure> {
+    config::resolve().map_err(Failure::config)?;
+    let board = provider::from_env().load_board()?;
+    let (col, card) = board
+        .columns
+        .iter()
+        .find_map(|col| col.cards.iter().find(|c| c.id == card_id).map(|c| (col, c)))
+        .ok_or_else(|| Failure {
+            class: ErrorClass::NotFound,
+            msg: format!("not found: {card_id}"),
+        })?;
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct Shown<'a> {
+            column: &'a str,
+            #[serde(flatten)]
+            card: &'a Card,
+        }
+        let shown = Shown {
+            column: &col.id,
+            card,
+        };
+        let out = serde_json::to_string_pretty(&shown).map_err(io::Error::other)?;
+        println!("{out}");
+    } else if pretty {
+        let width = width.unwrap_or_else(output_width);
+        let color = std::env::var_os("NO_COLOR").is_none();
+        print!("{}", show::pretty(col, card, width, color));
+    } else {
+        print!("{}", show::plain(col, card));
+    }
+    Ok(())
+}
+
+/// fzf's preview pane, then `$COLUMNS`, then the terminal, then 80.
+fn output_width() -> usize {
+    ["FZF_PREVIEW_COLUMNS", "COLUMNS"]
+        .iter()
+        .find_map(|v| std::env::var(v).ok()?.parse().ok())
+        .or_else(|| crossterm::terminal::size().ok().map(|(w, _)| w as usize))
+        .unwrap_or(80)
+}
+
+/// Reads the board for `flow view` from stdin.
+pub fn read_view(format: ViewFormat) -> Result<Board, Failure> {
+    let mut src = String::new();
+    io::stdin().read_to_string(&mut src)?;
+    parse_view(format, &src)
+}
+
+fn parse_view(format: ViewFormat, src: &str) -> Result<Board, Failure> {
+    match format {
+        ViewFormat::Json => serde_json::from_str(src).map_err(|e| Failure {
+            class: ErrorClass::Usage,
+            msg: format!("stdin is not a board: {e}"),
+        }),
+    }
+}
+
+fn run_rpc(listen: Option<PathBuf>, connect: Option<PathBuf>) -> Result<(), Failure> {
+    if let Some(path) = connect {
+        return Ok(rpc_connect(&path)?);
+    }
+    config::resolve().map_err(Failure::config)?;
+    let mut provider = provider::from_env();
+    match listen {
+        Some(path) => rpc_listen(provider.as_mut(), &path)?,
+        None => rpc::serve(provider.as_mut(), io::stdin().lock(), io::stdout())?,
+    }
+    Ok(())
+}
+
+/// Clients are served one at a time; editors keep a single connection.
+#[cfg(unix)]
+fn rpc_listen(provider: &mut dyn provider::Provider, path: &Path) -> io::Result<()> {
+    use std::os::unix::{fs::FileTypeExt, net::UnixListener};
+
+    // A socket left by an earlier server goes; anything else is a typo.
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let listener = UnixListener::bind(path)?;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let reader = io::BufReader::new(stream.try_clone()?);
+        if let Err(e) = rpc::serve(provider, reader, stream) {
+            eprintln!("flow rpc: client error: {e}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn rpc_connect(path: &Path) -> io::Result<()> {
+    use std::{net::Shutdown, os::unix::net::UnixStream};
+
+    let stream = UnixStream::connect(path)?;
+    let mut from_server = stream.try_clone()?;
+    let replies = std::thread::spawn(move || io::copy(&mut from_server, &mut io::stdout()));
+    io::copy(&mut io::stdin().lock(), &mut &stream)?;
+    stream.shutdown(Shutdown::Write)?;
+    replies
+        .join()
+        .map_err(|_| io::Error::other("reply thread panicked"))??;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn rpc_listen(_provider: &mut dyn provider::Provider, _path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "rpc sockets are only supported on Unix",
+    ))
+}
+
+#[cfg(not(unix))]
+fn rpc_connect(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "rpc sockets are only supported on Unix",
+    ))
+}
+
+fn run_list(filter: Option<&str>, porcelain: bool, json: bool) -> Result<(), Failure> {
+    let filter = filter.map(filter::parse).transpose().map_err(|e| Failure {
+        class: ErrorClass::Usage,
+        msg: format!("filter: {e}"),
+    })?;
+    config::resolve().map_err(Failure::config)?;
+    let board = provider::from_env().load_board()?;
+    let board = matching(board, filter.as_ref(), dates::today());
+
+    if json {
+        let out = serde_json::to_string_pretty(&board).map_err(io::Error::other)?;
+        println!("{out}");
+        return Ok(());
+    }
+    let rows: Vec<[&str; 3]> = board
+        .columns
+        .iter()
+        .flat_map(|col| {
+            col.cards
+                .iter()
+                .map(move |c| [c.id.as_str(), col.id.as_str(), c.title.as_str()])
+        })
+        .collect();
+
+    if porcelain {
+        for row in &rows {
+            println!("{}", porcelain_line(row));
+        }
+        return Ok(());
+    }
+    let id_w = rows.iter().map(|r| r[0].chars().count()).max().unwrap_or(0);
+    let col_w = rows.iter().map(|r| r[1].chars().count()).max().unwrap_or(0);
+    for [id, col, title] in rows {
+        println!("{id:<id_w$}  {col:<col_w$}  {title}");
+    }
+    Ok(())
+}
+
+/// The board with only the cards `filter` matches. Every column stays, so
+/// the result has the board's shape.
+fn matching(mut board: Board, filter: Option<&filter::Expr>, today: i64) -> Board {
+    if let Some(filter) = filter {
+        for col in &mut board.columns {
+            let cards = std::mem::take(&mut col.cards);
+            col.cards = cards
+                .into_iter()
+                .filter(|c| filter.matches(col, c, today))
+                .collect();
+        }
+    }
+    board
+}
+
+/// Tabs and newlines inside fields become spaces so every card is exactly
+/// one line of three fields.
+fn porcelain_line(fields: &[&str; 3]) -> String {
+    fields
+        .iter()
+        .map(|f| f.replace(['\t', '\n', '\r'], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+fn run_secret(cmd: SecretCommand) -> io::Result<()> {
+    match cmd {
+        SecretCommand::Set { name } => {
+            let mut secret = String::new();
+            io::stdin().read_line(&mut secret)?;
+            let secret = secret.trim_end_matches(['\r', '\n']);
+            if secret.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no secret on stdin",
+                ));
+            }
+            config::store_secret(&name, secret)?;
+            println!("Stored secret {name}; reference it as \"keyring:{name}\"");
+        }
+        SecretCommand::Delete { name } => {
+            config::delete_secret(&name)?;
+            println!("Deleted secret {name}");
+        }
+    }
+    Ok(())
+}
+
+fn run_refactor(cmd: RefactorCommand) -> io::Result<()> {
+    let provider = LocalProvider::from_env();
+    let root = provider.root();
+
+    match cmd {
+        RefactorCommand::RenameColumn { old, new, title } => {
+            store_fs::rename_column(root, &old, &new, title.as_deref())?;
+            println!("Renamed column {old} -> {new}");
+        }
+        RefactorCommand::RenameLabel { old, new } => {
+            let n = store_fs::rename_label(root, &old, &new)?;
+            println!("Renamed label {old} -> {new} on {n} cards");
+        }
+    }
+    Ok(())
+}
+
+fn run_repair() -> io::Result<()> {
+    let provider = LocalProvider::from_env();
+    let repaired = store_fs::repair(provider.root())?;
+    for id in &repaired.listed {
+        println!("listed {id}");
+    }
+    for entry in &repaired.dropped {
+        println!("dropped {entry}");
+    }
+    if repaired == store_fs::Repaired::default() {
+        println!("Nothing to repair");
+    }
+    Ok(())
+}
+
+fn run_template(cmd: TemplateCommand) -> io::Result<()> {
+    let provider = LocalProvider::from_env();
+    let root = provider.root();
+
+    match cmd {
+        TemplateCommand::Export { out } => {
+            let toml = template::export(root)?;
+            match out {
+                Some(path) => fs::write(path, toml),
+                None => {
+                    print!("{toml}");
+                    Ok(())
+                }
+            }
+        }
+        TemplateCommand::Import { file, force } => {
+            let src = if file.as_os_str() == "-" {
+                let mut s = String::new();
+                io::stdin().read_to_string(&mut s)?;
+                s
+            } else {
+                fs::read_to_string(&file)?
+            };
+            template::import(root, &src, force)?;
+            println!("Imported template into {}", root.display());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porcelain_lines_always_have_three_fields() {
+        assert_eq!(
+            porcelain_line(&["A-1", "todo", "Fix\tthe\nthing"]),
+            "A-1\ttodo\tFix the thing"
+        );
+    }
+
+    #[test]
+    fn view_json_needs_only_ids_and_titles() {
+        let board = parse_view(
+            ViewFormat::Json,
+            r#"{"columns": [{"id": "todo", "title": "TODO", "cards": [{"id": "A-1", "title": "One"}]},
+                            {"id": "done", "title": "DONE", "meta": {"terminal": true}}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(board.columns[0].cards[0].title, "One");
+        assert!(board.columns[1].meta.terminal);
+        assert!(board.columns[1].meta.can_create);
+        assert!(parse_view(ViewFormat::Json, "[]").is_err());
+    }
+
+    #[test]
+    fn list_json_keeps_columns_and_reads_back_as_a_view() {
+        let board = parse_view(
+            ViewFormat::Json,
+            r#"{"columns": [{"id": "todo", "title": "TODO", "cards": [
+                {"id": "A-1", "title": "One", "labels": ["bug"]}, {"id": "A-2", "title": "Two"}]},
+                {"id": "done", "title": "DONE"}]}"#,
+        )
+        .unwrap();
+        let filter = filter::parse("label=bug").unwrap();
+
+        let json = serde_json::to_string(&matching(board, Some(&filter), 0)).unwrap();
+        let back = parse_view(ViewFormat::Json, &json).unwrap();
+        assert_eq!(back.columns.len(), 2);
+        assert_eq!(back.columns[0].cards.len(), 1);
+        assert_eq!(back.columns[0].cards[0].labels, ["bug"]);
+    }
+
+    #[test]
+    fn columns_resolve_by_id_or_title() {
+        let board = parse_view(
+            ViewFormat::Json,
+            r#"{"columns": [{"id": "todo", "title": "To Do"}, {"id": "done", "title": "DONE"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(find_column(&board, "done").unwrap().id, "done");
+        assert_eq!(find_column(&board, "to do").unwrap().id, "todo");
+        let Err(err) = find_column(&board, "doing") else {
+            panic!("found a column that doesn't exist");
+        };
+        assert_eq!(err.class, ErrorClass::Usage);
+        assert!(err.msg.contains("todo, done"));
+    }
+
+    #[test]
+    fn board_flag_works_before_or_after_the_subcommand() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+
+        let board = |args: &[&str]| Cli::try_parse_from(args).unwrap().board;
+        assert_eq!(board(&["flow", "-p", "work"]).as_deref(), Some("work"));
+        assert_eq!(
+            board(&["flow", "list", "--board", "notes"]).as_deref(),
+            Some("notes")
+        );
+    }
+
+    #[test]
+    fn provider_errors_map_to_stable_exit_codes() {
+        let code = |err| Failure::from(err).class.code();
+
+        assert_eq!(code(ProviderError::Config { msg: "x".into() }), 3);
+        assert_eq!(code(ProviderError::Auth { msg: "x".into() }), 4);
+        assert_eq!(code(ProviderError::NotFound { id: "X-1".into() }), 5);
+        assert_eq!(
+            code(ProviderError::Network {
+                op: "jira_search".into(),
+                url: "https://example.atlassian.net".into(),
+                msg: "timed out".into(),
+            }),
+            6
+        );
+        assert_eq!(code(ProviderError::Parse { msg: "x".into() }), 1);
+    }
+}
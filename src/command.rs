@@ -0,0 +1,223 @@
+//! Shell commands templated on a card, e.g. `git switch -c feat/{id}-{slug}`.
+
+use std::{io, process::Command};
+
+use crate::model::Card;
+
+/// A templated command: the shell text, which reads the card's values from
+/// the environment, and those values.
+#[derive(Default)]
+pub struct Rendered {
+    pub cmd: String,
+    pub env: Vec<(String, String)>,
+    /// The command with the values written in, to show.
+    pub shown: String,
+}
+
+impl Rendered {
+    fn push(&mut self, c: char) {
+        self.cmd.push(c);
+        self.shown.push(c);
+    }
+}
+
+/// Fills each `{name}` in `template` from `card`: `id`, `title`, `slug` (the
+/// title lowercased with dashes), `column`, or any field (`assignee`,
+/// `labels`, front matter), empty when the card has none. A value never
+/// goes into the command line itself: `{title}` becomes `"${FLOW_TITLE}"`,
+/// set in the command's environment, so a title can't run commands of its
+/// own whether or not the template puts it in double quotes. Placeholders
+/// inside single quotes, which the shell wouldn't expand, are refused.
+/// `{{` and `}}` are literal braces.
+pub fn render(template: &str, card: &Card, column: &str) -> Result<Rendered, String> {
+    let mut out = Rendered::default();
+    let (mut single, mut double) = (false, false);
+    let mut chars = template.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' | '}' if chars.next_if(|&(_, next)| next == c).is_some() => out.push(c),
+            '}' => return Err(format!("unmatched }} in {template:?}")),
+            '{' => {
+                let Some(len) = template[i + 1..].find('}') else {
+                    return Err(format!("unclosed {{ in {template:?}"));
+                };
+                let end = i + 1 + len;
+                while chars.next_if(|&(j, _)| j <= end).is_some() {}
+                let name = template[i + 1..end].trim();
+                let value = match name {
+                    "" => return Err(format!("empty {{}} in {template:?}")),
+                    "id" => card.id.clone(),
+                    "title" => card.title.clone(),
+                    "slug" => slug(&card.title),
+                    "column" => column.to_string(),
+                    key => card.values(key).join(","),
+                };
+                if single {
+                    return Err(format!(
+                        "{{{name}}} is inside single quotes in {template:?}"
+                    ));
+                }
+                // cmd expands variables before it parses, so `&` in a title
+                // would still chain a command there.
+                if cfg!(windows) {
+                    return Err("card values need sh; cmd can't keep them apart".to_string());
+                }
+                let var = env_name(name);
+                out.cmd.push_str(&if double {
+                    format!("${{{var}}}")
+                } else {
+                    format!("\"${{{var}}}\"")
+                });
+                out.shown.push_str(&value);
+                if !out.env.iter().any(|(k, _)| *k == var) {
+                    out.env.push((var, value));
+                }
+            }
+            '\\' if !single => {
+                out.push(c);
+                if let Some((_, next)) = chars.next() {
+                    out.push(next);
+                }
+            }
+            '\'' if !double => {
+                single = !single;
+                out.push(c);
+            }
+            '"' if !single => {
+                double = !double;
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// `{story points}` is read from `FLOW_STORY_POINTS`.
+fn env_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("FLOW_{name}")
+}
+
+/// `Add counts to columns!` becomes `add-counts-to-columns`, cut to 40
+/// characters on a word boundary where it can be.
+pub fn slug(title: &str) -> String {
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let mut out = String::new();
+    for w in words {
+        if !out.is_empty() && out.chars().count() + 1 + w.chars().count() > 40 {
+            break;
+        }
+        if !out.is_empty() {
+            out.push('-');
+        }
+        out.push_str(&w);
+    }
+    out.chars().take(40).collect()
+}
+
+/// Runs `cmd` through the shell with no input, returning its output when it
+/// succeeds and its last error line (or exit status) when it doesn't.
+pub fn run(cmd: &Rendered) -> io::Result<Result<String, String>> {
+    let mut shell = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    let out = shell
+        .arg(&cmd.cmd)
+        .envs(cmd.env.iter().map(|(k, v)| (k, v)))
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    let text = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .rfind(|l| !l.trim().is_empty())
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    Ok(if out.status.success() {
+        Ok(text(&out.stdout))
+    } else {
+        let err = text(&out.stderr);
+        Err(if err.is_empty() {
+            out.status.to_string()
+        } else {
+            err
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_passes_card_values_through_the_environment() {
+        let mut card = Card {
+            id: "FLOW-7".into(),
+            title: "Fix the user's $(login) redirect".into(),
+            ..Card::default()
+        };
+        card.assignee = Some("ana".into());
+        let r = render(
+            "git switch -c feat/{id}-{slug} && echo \"{title}\" {assignee}{{x}}",
+            &card,
+            "In Progress",
+        )
+        .unwrap();
+        assert_eq!(
+            r.cmd,
+            r#"git switch -c feat/"${FLOW_ID}"-"${FLOW_SLUG}" && echo "${FLOW_TITLE}" "${FLOW_ASSIGNEE}"{x}"#
+        );
+        assert_eq!(
+            r.shown,
+            r#"git switch -c feat/FLOW-7-fix-the-user-s-login-redirect && echo "Fix the user's $(login) redirect" ana{x}"#
+        );
+        assert_eq!(r.env[2], ("FLOW_TITLE".into(), card.title.clone()));
+        assert!(render("echo '{title}'", &card, "").is_err());
+        assert!(render("echo {id", &card, "").is_err());
+        assert!(render("echo }", &card, "").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_title_in_double_quotes_runs_nothing() {
+        let card = Card {
+            title: "$(echo pwned) & `id`".into(),
+            ..Card::default()
+        };
+        let r = render("printf %s \"{title}\" {title}", &card, "").unwrap();
+        assert_eq!(
+            run(&r).unwrap(),
+            Ok("$(echo pwned) & `id`$(echo pwned) & `id`".to_string())
+        );
+    }
+
+    #[test]
+    fn slug_stops_at_a_word_boundary() {
+        assert_eq!(slug("  Add counts: to columns! "), "add-counts-to-columns");
+        assert_eq!(
+            slug("Render Jira mentions, issue cards and emoji in descriptions"),
+            "render-jira-mentions-issue-cards-and"
+        );
+    }
+}
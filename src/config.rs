@@ -0,0 +1,1048 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dates::DateStyle;
+
+/// `~/.config/flow/config.toml` (or `$FLOW_CONFIG`).
+///
+/// ```toml
+/// default_profile = "work"
+///
+/// [profiles.work]
+/// provider = "jira"
+/// base_url = "https://example.atlassian.net"
+/// email = "me@example.com"
+/// api_token = "..."
+/// board_id = "123"
+///
+/// [profiles.work.sla]
+/// "In Review" = 3
+///
+/// [profiles.notes]
+/// provider = "local"
+/// path = "~/boards/notes"
+/// ```
+#[derive(Serialize, Deserialize, Default)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default, skip_serializing_if = "UiConfig::is_default")]
+    pub ui: UiConfig,
+    #[serde(default, skip_serializing_if = "KeysConfig::is_empty")]
+    pub keys: KeysConfig,
+    #[serde(default, skip_serializing_if = "UserConfig::is_empty")]
+    pub user: UserConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub workspaces: BTreeMap<String, Workspace>,
+    #[serde(default, skip_serializing_if = "StartWork::is_default")]
+    pub start_work: StartWork,
+    #[serde(default, skip_serializing_if = "Backup::is_default")]
+    pub backup: Backup,
+}
+
+/// `[start_work]`: what `S` does to the selected card. It moves to `column`
+/// (matched by id or title, any case), then `command` runs through the shell
+/// with the card's fields filled in (see [`crate::command::render`]).
+///
+/// ```toml
+/// [start_work]
+/// column = "In Progress"
+/// command = "git switch -c feat/{id}-{slug}"
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct StartWork {
+    pub column: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl Default for StartWork {
+    fn default() -> Self {
+        Self {
+            column: "In Progress".to_string(),
+            command: None,
+        }
+    }
+}
+
+impl StartWork {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `[backup]`: where and how often a running flow backs up the board it has
+/// open (see [`crate::backup`]). Nothing is backed up without `dir`.
+///
+/// ```toml
+/// [backup]
+/// dir = "~/flow-backups"
+/// every_hours = 24
+/// keep = 14
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Backup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    pub every_hours: u32,
+    pub keep: usize,
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            every_hours: 24,
+            keep: 14,
+        }
+    }
+}
+
+impl Backup {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.dir
+            .as_deref()
+            .filter(|d| !d.trim().is_empty())
+            .map(|d| expand_home(d.trim()))
+    }
+}
+
+/// `[profiles.NAME.http]`: how hard flow may lean on a remote backend.
+///
+/// ```toml
+/// [profiles.work.http]
+/// max_in_flight = 2    # requests at once, across flow's workers (4)
+/// pool_size = 2        # idle connections kept per host
+/// keep_alive_secs = 30 # how long they're kept; 0 closes each after use
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct HttpConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive_secs: Option<u64>,
+}
+
+impl HttpConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `[workspaces.NAME]`: a board together with how to show it, opened with
+/// `flow NAME`. Its `ui` and `keys` tables are laid over `[ui]` and `[keys]`
+/// key by key.
+///
+/// ```toml
+/// [workspaces.work]
+/// board = "work"
+/// search = "checkout"
+/// sla_only = true
+/// ui = { rows = "roomy" }
+/// keys = { preset = "arrows" }
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Workspace {
+    /// The profile to open; `default_profile` when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+    /// Search the board opens with, as typed after `/`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    pub sla_only: bool,
+    pub heatmap: bool,
+    #[serde(skip_serializing_if = "toml::Table::is_empty")]
+    pub ui: toml::Table,
+    #[serde(skip_serializing_if = "toml::Table::is_empty")]
+    pub keys: toml::Table,
+}
+
+/// `[keys]` bindings. Each listed action gets exactly the given keys.
+///
+/// ```toml
+/// [keys]
+/// preset = "arrows"   # or "vim" (default)
+/// move_left = ["<", "H"]
+/// quit = ["q", "f10"]
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct KeysConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    #[serde(flatten)]
+    pub bind: BTreeMap<String, Vec<String>>,
+}
+
+impl KeysConfig {
+    fn is_empty(&self) -> bool {
+        self.preset.is_none() && self.bind.is_empty()
+    }
+}
+
+/// `[user]`: who you are, for boards that have no accounts. New local cards
+/// are stamped `created_by` with the name, and `@me` in filters matches it.
+///
+/// ```toml
+/// [user]
+/// name = "Ana Lopez"
+/// email = "ana@example.com"
+/// initials = "AL"     # taken from the name when unset
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct UserConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initials: Option<String>,
+}
+
+impl UserConfig {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The name, or the email without one.
+    pub fn author(&self) -> Option<&str> {
+        self.name.as_deref().or(self.email.as_deref())
+    }
+
+    /// `initials`, or the first letter of each word of the name.
+    pub fn initials(&self) -> Option<String> {
+        self.initials.clone().or_else(|| {
+            let initials = initials(self.name.as_deref()?);
+            (!initials.is_empty()).then_some(initials)
+        })
+    }
+
+    /// Whether `who`, an assignee or author, is this user by name, email or
+    /// initials, ignoring case.
+    pub fn is(&self, who: &str) -> bool {
+        let who = who.trim();
+        [self.name.clone(), self.email.clone(), self.initials()]
+            .into_iter()
+            .flatten()
+            .any(|me| me.eq_ignore_ascii_case(who))
+    }
+}
+
+/// The first letter of each word of `name`, capitalized: `Ana Lopez` is `AL`.
+pub fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|w| w.chars().next())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// The `[user]` section, read once.
+pub fn user() -> &'static UserConfig {
+    static USER: OnceLock<UserConfig> = OnceLock::new();
+    USER.get_or_init(|| load().map(|cfg| cfg.user).unwrap_or_default())
+}
+
+/// `[ui]` settings.
+///
+/// ```toml
+/// [ui]
+/// poll_ms = 50        # input poll interval
+/// low_power = true    # poll slowly while the terminal is unfocused
+/// idle_poll_ms = 1000
+/// refresh_on_focus = true
+/// refresh_secs = 60   # reload in the background; 0 turns it off
+/// watch = false       # don't reload local boards when their files change
+/// mentions_secs = 120 # check Jira for mentions; 0 turns it off
+/// stats_days = 14     # the `t` overlay's throughput window
+/// dates = "relative"  # "in 3d" and "2d ago" instead of 2024-06-01
+/// timezone = "Europe/Berlin" # defaults to the system's
+/// rows = "roomy"      # two lines per card on tall terminals
+/// charset = "ascii"   # plain borders and badges; "auto" goes by the locale
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct UiConfig {
+    pub poll_ms: u64,
+    pub low_power: bool,
+    pub idle_poll_ms: u64,
+    /// Reload the board in the background when the terminal regains focus.
+    pub refresh_on_focus: bool,
+    pub refresh_secs: u64,
+    /// Reload a local board when its files change outside flow.
+    pub watch: bool,
+    pub mentions_secs: u64,
+    pub stats_days: i64,
+    pub dates: DateStyle,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    pub rows: RowStyle,
+    pub charset: Charset,
+}
+
+/// How much room each card gets in a column.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RowStyle {
+    /// One line: id, title, then the badges.
+    #[default]
+    Compact,
+    /// The id and title on one line and the badges under them, on columns
+    /// tall enough to spare it.
+    Roomy,
+}
+
+/// What the TUI draws borders, arrows and badges with.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Charset {
+    /// ASCII unless the locale is UTF-8.
+    #[default]
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    pub fn ascii(self) -> bool {
+        match self {
+            Charset::Unicode => false,
+            Charset::Ascii => true,
+            Charset::Auto => {
+                // The order the C library reads them in.
+                let var = |k| std::env::var(k).ok().filter(|v| !v.is_empty());
+                let locale = var("LC_ALL")
+                    .or_else(|| var("LC_CTYPE"))
+                    .or_else(|| var("LANG"));
+                !utf8_locale(locale.as_deref())
+            }
+        }
+    }
+}
+
+/// Windows terminals don't set a locale and take Unicode; elsewhere none
+/// means the ASCII-only C locale.
+fn utf8_locale(locale: Option<&str>) -> bool {
+    match locale {
+        Some(l) => {
+            let l = l.to_ascii_lowercase();
+            l.contains("utf-8") || l.contains("utf8")
+        }
+        None => cfg!(windows),
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            poll_ms: 50,
+            low_power: false,
+            idle_poll_ms: 1000,
+            refresh_on_focus: true,
+            refresh_secs: 0,
+            watch: true,
+            mentions_secs: 120,
+            stats_days: 7,
+            dates: DateStyle::default(),
+            timezone: None,
+            rows: RowStyle::default(),
+            charset: Charset::default(),
+        }
+    }
+}
+
+impl UiConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Focus is only known on terminals that report focus events; others
+    /// always count as focused.
+    pub fn poll_interval(&self, focused: bool) -> Duration {
+        let ms = if self.low_power && !focused {
+            self.idle_poll_ms
+        } else {
+            self.poll_ms
+        };
+        Duration::from_millis(ms.max(1))
+    }
+
+    /// `FLOW_REFRESH_SECS` overrides `refresh_secs`.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        let secs = std::env::var("FLOW_REFRESH_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(self.refresh_secs);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+
+    pub fn mentions_interval(&self) -> Option<Duration> {
+        (self.mentions_secs > 0).then(|| Duration::from_secs(self.mentions_secs))
+    }
+}
+
+/// Everything needed to open one board. Env vars override these fields,
+/// values may reference variables as `${NAME}`, and a value of
+/// `keyring:NAME` is read from the OS keychain.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board_id: Option<String>,
+    /// Days a card may stay in a column (by id or title) before it is
+    /// flagged. Needs a provider that reports `status_since` (Jira).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sla: BTreeMap<String, u32>,
+    /// Newest cards fetched per column (by id or title); `+` loads as many
+    /// again. For Jira columns too big to load whole.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fetch_limits: BTreeMap<String, usize>,
+    /// Jira transition `d` applies; without it, any transition into Done.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_transition: Option<String>,
+    /// Jira field holding story points; `customfield_10016` ("Story point
+    /// estimate") when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate_field: Option<String>,
+    /// JQL choosing the issues of the board's filter that show, in place of
+    /// `assignee = currentUser() AND sprint in openSprints()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jql: Option<String>,
+    /// People `a` offers to assign cards to on a local board; without it,
+    /// whoever the board's cards are assigned to already.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<String>,
+    /// Jira issue type `n` creates; `Task` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue_type: Option<String>,
+    /// Directory of card templates `n` offers; `templates/` in a local
+    /// board's directory when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates: Option<String>,
+    /// Commit every change to a local board to the git repository it's in
+    /// (one is created if needed), and offer `U` / `W` to pull and push.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub git: bool,
+    /// Connection tuning for Jira, GitHub and Linear.
+    #[serde(default, skip_serializing_if = "HttpConfig::is_default")]
+    pub http: HttpConfig,
+    /// Linear team key, e.g. `ENG`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    /// Linear project name; without it, the whole team's issues.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// GitHub repository, `owner/name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    /// GitHub labels that stand for columns, in board order; the last is
+    /// done. `status:todo`, `status:doing` and `status:done` when unset.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_labels: Vec<String>,
+}
+
+pub fn path() -> Option<PathBuf> {
+    if let Ok(p) = std::env::var("FLOW_CONFIG") {
+        return Some(PathBuf::from(p));
+    }
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("flow/config.toml"));
+    }
+    let home = std::env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join(".config/flow/config.toml"))
+}
+
+pub fn exists() -> bool {
+    path().is_some_and(|p| p.exists())
+}
+
+/// The profile picked with `--board`, which stands in for `default_profile`.
+static CHOSEN: OnceLock<String> = OnceLock::new();
+
+/// The workspace picked with `flow NAME`, laid over the config on every load.
+static WORKSPACE: OnceLock<String> = OnceLock::new();
+
+/// Opens workspace `name` for the rest of the run: its board, unless
+/// `--board` already picked one, and its `ui` and `keys` settings.
+pub fn choose_workspace(name: &str) -> Result<(), String> {
+    let cfg = load()?;
+    let Some(ws) = cfg.workspaces.get(name) else {
+        let known: Vec<&str> = cfg.workspaces.keys().map(String::as_str).collect();
+        return Err(format!(
+            "unknown command or workspace {name:?} (workspaces: {})",
+            if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            }
+        ));
+    };
+    if let Some(board) = &ws.board
+        && CHOSEN.get().is_none()
+    {
+        choose_profile(board)?;
+    }
+    let _ = WORKSPACE.set(name.to_string());
+    Ok(())
+}
+
+/// The workspace this run was opened in.
+pub fn workspace() -> Option<Workspace> {
+    load().ok()?.workspaces.remove(WORKSPACE.get()?)
+}
+
+/// Opens `name` instead of the default profile for the rest of the run.
+pub fn choose_profile(name: &str) -> Result<(), String> {
+    let cfg = load()?;
+    if !cfg.profiles.contains_key(name) {
+        let known: Vec<&str> = cfg.profiles.keys().map(String::as_str).collect();
+        return Err(format!(
+            "no profile named {name:?} (configured: {})",
+            if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            }
+        ));
+    }
+    let _ = CHOSEN.set(name.to_string());
+    Ok(())
+}
+
+/// Loads the config file. A missing file is an empty config.
+pub fn load() -> Result<Config, String> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+    let mut cfg = match fs::read_to_string(&path) {
+        Ok(s) => parse(&s).map_err(|e| format!("{}: {e}", path.display()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Config::default(),
+        Err(e) => return Err(format!("{}: {e}", path.display())),
+    };
+    if let Some(name) = CHOSEN.get() {
+        cfg.default_profile = Some(name.clone());
+    }
+    Ok(cfg)
+}
+
+pub fn parse(s: &str) -> Result<Config, String> {
+    match WORKSPACE.get() {
+        Some(name) => parse_in(s, name),
+        None => toml::from_str(s).map_err(|e| e.to_string()),
+    }
+}
+
+/// Parses with workspace `name`'s `ui` and `keys` laid over the top-level ones.
+fn parse_in(s: &str, name: &str) -> Result<Config, String> {
+    let mut table: toml::Table = toml::from_str(s).map_err(|e| e.to_string())?;
+    let ws = table
+        .get("workspaces")
+        .and_then(|w| w.get(name))
+        .and_then(toml::Value::as_table)
+        .cloned()
+        .unwrap_or_default();
+    for section in ["ui", "keys"] {
+        if let Some(over) = ws.get(section).and_then(toml::Value::as_table)
+            && let Some(base) = table
+                .entry(section)
+                .or_insert_with(|| toml::Table::new().into())
+                .as_table_mut()
+        {
+            base.extend(over.clone());
+        }
+    }
+    table.try_into().map_err(|e: toml::de::Error| e.to_string())
+}
+
+pub fn save(cfg: &Config) -> io::Result<PathBuf> {
+    let path = path().ok_or_else(|| io::Error::other("cannot locate config dir (HOME unset)"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let s = toml::to_string_pretty(cfg).map_err(io::Error::other)?;
+    write_private(&path, &s)?;
+    Ok(path)
+}
+
+/// The file may hold API tokens, so it's written to a fresh temp file that
+/// only the user can read from the start, then renamed over `path`.
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+    let tmp = path.with_file_name(format!(".{name}.tmp"));
+    match fs::remove_file(&tmp) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+    let mut file = opts.open(&tmp)?;
+    io::Write::write_all(&mut file, contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)
+}
+
+impl Config {
+    /// The default profile, or the only one if there is exactly one.
+    pub fn selected(&self) -> Option<&Profile> {
+        match &self.default_profile {
+            Some(name) => self.profiles.get(name),
+            None if self.profiles.len() == 1 => self.profiles.values().next(),
+            None => None,
+        }
+    }
+}
+
+impl Profile {
+    pub fn provider(&self) -> &str {
+        self.provider.as_deref().unwrap_or("local")
+    }
+
+    fn apply_env(&mut self, vars: &Vars) {
+        if let Some(p) = vars.get("FLOW_BOARD_PATH") {
+            self.path = Some(p);
+            if vars.get("FLOW_PROVIDER").is_none() {
+                self.provider = Some("local".to_string());
+            }
+        }
+        if let Some(p) = vars.get("FLOW_PROVIDER") {
+            self.provider = Some(p);
+        }
+        for (name, field) in [
+            ("JIRA_BASE_URL", &mut self.base_url),
+            ("JIRA_EMAIL", &mut self.email),
+            ("JIRA_API_TOKEN", &mut self.api_token),
+            ("JIRA_BOARD_ID", &mut self.board_id),
+            ("JIRA_JQL", &mut self.jql),
+            ("JIRA_ISSUE_TYPE", &mut self.issue_type),
+        ] {
+            if let Some(v) = vars.get(name) {
+                *field = Some(v);
+            }
+        }
+        if self.provider() == "github" {
+            for (name, field) in [
+                ("GITHUB_TOKEN", &mut self.api_token),
+                ("GITHUB_REPOSITORY", &mut self.repo),
+            ] {
+                if let Some(v) = vars.get(name) {
+                    *field = Some(v);
+                }
+            }
+            if let Some(v) = vars.get("GITHUB_STATUS_LABELS") {
+                self.status_labels = v.split(',').map(|l| l.trim().to_string()).collect();
+            }
+        }
+        if self.provider() == "linear" {
+            for (name, field) in [
+                ("LINEAR_API_KEY", &mut self.api_token),
+                ("LINEAR_TEAM", &mut self.team),
+                ("LINEAR_PROJECT", &mut self.project),
+            ] {
+                if let Some(v) = vars.get(name) {
+                    *field = Some(v);
+                }
+            }
+        }
+    }
+
+    fn resolved(&self, vars: &Vars) -> Result<Profile, String> {
+        let mut p = self.clone();
+        p.apply_env(vars);
+        p.expanded(vars)
+    }
+
+    /// Expands `${NAME}` references and reads `keyring:` secrets.
+    fn expanded(mut self, vars: &Vars) -> Result<Profile, String> {
+        let p = &mut self;
+        for (name, field) in [
+            ("provider", &mut p.provider),
+            ("path", &mut p.path),
+            ("base_url", &mut p.base_url),
+            ("email", &mut p.email),
+            ("api_token", &mut p.api_token),
+            ("board_id", &mut p.board_id),
+            ("team", &mut p.team),
+            ("project", &mut p.project),
+            ("repo", &mut p.repo),
+        ] {
+            if let Some(v) = field {
+                let expanded =
+                    expand_vars(v, |k| vars.get(k)).map_err(|e| format!("{name}: {e}"))?;
+                *v = resolve_secret(&expanded, read_secret).map_err(|e| format!("{name}: {e}"))?;
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Process environment layered over values read from `.env` files.
+#[derive(Default)]
+struct Vars {
+    dotenv: HashMap<String, String>,
+}
+
+impl Vars {
+    fn get(&self, name: &str) -> Option<String> {
+        std::env::var(name)
+            .ok()
+            .or_else(|| self.dotenv.get(name).cloned())
+    }
+
+    /// Later files override earlier ones; a missing file is skipped.
+    fn load_dotenv(&mut self, path: &Path) -> Result<(), String> {
+        match fs::read_to_string(path) {
+            Ok(s) => {
+                self.dotenv.extend(parse_dotenv(&s));
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("{}: {e}", path.display())),
+        }
+    }
+}
+
+/// Parses `KEY=value` lines, allowing `export`, comments, and quoted values.
+fn parse_dotenv(s: &str) -> Vec<(String, String)> {
+    s.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Replaces every `${NAME}` in `s`. Unset variables are an error so a typo
+/// doesn't silently become an empty token.
+fn expand_vars(s: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("unterminated ${{ in {s:?}"))?;
+        let name = &after[..end];
+        out.push_str(&lookup(name).ok_or_else(|| format!("${{{name}}} is not set"))?);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+const KEYRING_SERVICE: &str = "flow";
+const KEYRING_PREFIX: &str = "keyring:";
+
+fn resolve_secret(
+    value: &str,
+    read: impl Fn(&str) -> Result<String, String>,
+) -> Result<String, String> {
+    match value.strip_prefix(KEYRING_PREFIX) {
+        Some(name) if !name.trim().is_empty() => read(name.trim()),
+        Some(_) => Err("empty keyring reference".to_string()),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Secrets already read this process. Profiles are resolved over and over
+/// (every provider, backup and board switch), and each keychain read may
+/// prompt the user.
+static SECRETS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+fn read_secret(name: &str) -> Result<String, String> {
+    let mut cache = SECRETS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(secret) = cache.get(name) {
+        return Ok(secret.clone());
+    }
+    let secret = keyring::Entry::new(KEYRING_SERVICE, name)
+        .and_then(|e| e.get_password())
+        .map_err(|e| format!("keyring entry {name:?}: {e}"))?;
+    cache.insert(name.to_string(), secret.clone());
+    Ok(secret)
+}
+
+/// Stores a secret that profiles can reference as `keyring:NAME`.
+pub fn store_secret(name: &str, secret: &str) -> io::Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, name)
+        .and_then(|e| e.set_password(secret))
+        .map_err(io::Error::other)?;
+    SECRETS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), secret.to_string());
+    Ok(())
+}
+
+pub fn delete_secret(name: &str) -> io::Result<()> {
+    SECRETS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(name);
+    keyring::Entry::new(KEYRING_SERVICE, name)
+        .and_then(|e| e.delete_credential())
+        .map_err(io::Error::other)
+}
+
+/// The profile flow should open: the config's selected profile with env
+/// vars layered on top and `${NAME}` references expanded.
+///
+/// Variables come from the process environment, then a `.env` inside the
+/// board directory (which git history leaves out), then a `.env` next to
+/// the config file.
+pub fn resolve() -> Result<Profile, String> {
+    let cfg = load()?;
+    let raw = cfg.selected().cloned().unwrap_or_default();
+    raw.resolved(&vars_for(&raw)?)
+}
+
+/// A configured board: a profile name and its resolved settings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoardHandle {
+    pub name: String,
+    pub profile: Profile,
+}
+
+/// The profile `name` from `cfg`, expanded like [`resolve`] but without
+/// env var overrides, so `FLOW_PROVIDER` in the shell doesn't turn every
+/// configured board into the same one.
+pub fn board(cfg: &Config, name: &str) -> Result<BoardHandle, String> {
+    let raw = cfg
+        .profiles
+        .get(name)
+        .ok_or_else(|| format!("no profile named {name:?}"))?;
+    Ok(BoardHandle {
+        name: name.to_string(),
+        profile: raw.clone().expanded(&vars_for(raw)?)?,
+    })
+}
+
+fn vars_for(raw: &Profile) -> Result<Vars, String> {
+    let mut vars = Vars::default();
+    if let Some(dir) = path().as_deref().and_then(Path::parent) {
+        vars.load_dotenv(&dir.join(".env"))?;
+    }
+    // Only the path is needed here; expanding the whole profile would read
+    // its keyring secrets a second time.
+    let mut p = raw.clone();
+    p.apply_env(&vars);
+    if let Some(path) = p.path.and_then(|p| expand_vars(&p, |k| vars.get(k)).ok()) {
+        vars.load_dotenv(&expand_home(&path).join(".env"))?;
+    }
+    Ok(vars)
+}
+
+/// Like [`resolve`], but falls back to plain env vars on error. Callers that
+/// need to report config problems use [`resolve`] directly.
+pub fn active_profile() -> Profile {
+    resolve().unwrap_or_else(|_| {
+        let mut p = Profile::default();
+        p.apply_env(&Vars::default());
+        p
+    })
+}
+
+/// Expands a leading `~/` to `$HOME`.
+pub fn expand_home(p: &str) -> PathBuf {
+    match (p.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(p),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_profiles_and_selects_default() {
+        let cfg = parse(
+            "default_profile = \"work\"\n\n[profiles.work]\nprovider = \"jira\"\nboard_id = \"7\"\n\n[profiles.notes]\npath = \"/tmp/notes\"\n",
+        )
+        .unwrap();
+
+        let p = cfg.selected().unwrap();
+        assert_eq!(p.provider(), "jira");
+        assert_eq!(p.board_id.as_deref(), Some("7"));
+        assert_eq!(cfg.profiles["notes"].provider(), "local");
+    }
+
+    #[test]
+    fn a_workspace_lays_its_ui_and_keys_over_the_top_level_ones() {
+        let s = "[ui]\nrows = \"compact\"\nrefresh_secs = 30\n\n[workspaces.work]\nboard = \"jira\"\nsearch = \"checkout\"\nui = { rows = \"roomy\" }\nkeys = { quit = [\"Q\"] }\n";
+        let cfg = parse_in(s, "work").unwrap();
+        assert_eq!(cfg.ui.rows, RowStyle::Roomy);
+        assert_eq!(cfg.ui.refresh_secs, 30);
+        assert_eq!(cfg.keys.bind["quit"], ["Q"]);
+        assert_eq!(cfg.workspaces["work"].search.as_deref(), Some("checkout"));
+
+        let cfg = parse_in(s, "personal").unwrap();
+        assert_eq!(cfg.ui.rows, RowStyle::Compact);
+        assert!(cfg.keys.bind.is_empty());
+    }
+
+    #[test]
+    fn selected_needs_default_when_several_profiles_exist() {
+        let cfg = parse("[profiles.a]\n[profiles.b]\n").unwrap();
+        assert!(cfg.selected().is_none());
+
+        let cfg = parse("[profiles.a]\npath = \"/x\"\n").unwrap();
+        assert_eq!(cfg.selected().unwrap().path.as_deref(), Some("/x"));
+    }
+
+    #[test]
+    fn expand_vars_substitutes_and_rejects_unset() {
+        let lookup = |k: &str| (k == "TOKEN").then(|| "s3cret".to_string());
+
+        assert_eq!(
+            expand_vars("Bearer ${TOKEN}!", lookup).unwrap(),
+            "Bearer s3cret!"
+        );
+        assert_eq!(expand_vars("no refs", lookup).unwrap(), "no refs");
+        assert!(
+            expand_vars("${MISSING}", lookup)
+                .unwrap_err()
+                .contains("MISSING")
+        );
+        assert!(expand_vars("${TOKEN", lookup).is_err());
+    }
+
+    #[test]
+    fn keyring_references_are_resolved_by_name() {
+        let read = |name: &str| match name {
+            "jira-work" => Ok("tok".to_string()),
+            _ => Err(format!("no entry {name}")),
+        };
+
+        assert_eq!(resolve_secret("keyring:jira-work", read).unwrap(), "tok");
+        assert_eq!(resolve_secret("plain", read).unwrap(), "plain");
+        assert!(resolve_secret("keyring:other", read).is_err());
+        assert!(resolve_secret("keyring:", read).is_err());
+    }
+
+    #[test]
+    fn parse_dotenv_handles_export_comments_and_quotes() {
+        let vars = parse_dotenv("# secrets\nexport A=1\nB = \"two words\"\nC='x'\n\nnot a pair\n");
+        assert_eq!(
+            vars,
+            vec![
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "two words".to_string()),
+                ("C".to_string(), "x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn low_power_slows_polling_only_when_unfocused() {
+        let cfg = parse("[ui]\npoll_ms = 20\nlow_power = true\nrows = \"roomy\"\n").unwrap();
+
+        assert_eq!(cfg.ui.rows, RowStyle::Roomy);
+        assert_eq!(cfg.ui.poll_interval(true), Duration::from_millis(20));
+        assert_eq!(cfg.ui.poll_interval(false), Duration::from_millis(1000));
+        assert_eq!(
+            UiConfig::default().poll_interval(false),
+            Duration::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn only_utf8_locales_get_unicode_drawing() {
+        assert!(utf8_locale(Some("en_US.UTF-8")));
+        assert!(utf8_locale(Some("de_DE.utf8")));
+        assert!(!utf8_locale(Some("C")));
+        assert!(!utf8_locale(Some("en_US.ISO-8859-1")));
+        assert!(Charset::Ascii.ascii());
+        assert!(!Charset::Unicode.ascii());
+    }
+
+    #[test]
+    fn keys_section_collects_bindings_beside_the_preset() {
+        let cfg = parse("[keys]\npreset = \"arrows\"\nquit = [\"x\", \"f10\"]\n").unwrap();
+
+        assert_eq!(cfg.keys.preset.as_deref(), Some("arrows"));
+        assert_eq!(cfg.keys.bind["quit"], vec!["x", "f10"]);
+    }
+
+    #[test]
+    fn saved_config_round_trips() {
+        let mut cfg = Config {
+            default_profile: Some("default".into()),
+            ..Default::default()
+        };
+        cfg.profiles.insert(
+            "default".into(),
+            Profile {
+                provider: Some("local".into()),
+                path: Some("/tmp/b".into()),
+                ..Default::default()
+            },
+        );
+
+        let s = toml::to_string_pretty(&cfg).unwrap();
+        assert_eq!(
+            parse(&s).unwrap().profiles["default"],
+            cfg.profiles["default"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saved_config_is_never_readable_by_others() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("flow-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, "api_token = \"t\"\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "api_token = \"t\"\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+//! Dates as whole days since the Unix epoch, in the user's time zone.
+//!
+//! Cards carry dates as text: plain `2024-06-01` dates, or timestamps such as
+//! Jira's `2024-06-01T09:30:00.000+0000`. A timestamp counts on the day it
+//! falls on locally, so "today", due dates and ages agree with the wall
+//! clock. The zone is `[ui] timezone` if set, otherwise the system's (`TZ`).
+
+use std::{
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use jiff::{Timestamp, tz::TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// How dates are shown: `2024-06-01`, or `in 3d` / `2d ago`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DateStyle {
+    #[default]
+    Absolute,
+    Relative,
+}
+
+/// Fields holding dates, shown with [`show`].
+pub const FIELDS: [&str; 4] = ["due", "created", "updated", "status_since"];
+
+static SETTINGS: OnceLock<(TimeZone, DateStyle)> = OnceLock::new();
+
+/// Sets the zone (an IANA name like `Europe/Berlin`, or the system's when
+/// `None`) and display style for the rest of the run.
+pub fn configure(zone: Option<&str>, style: DateStyle) -> Result<(), String> {
+    let zone = match zone {
+        Some(name) => TimeZone::get(name).map_err(|e| format!("timezone {name:?}: {e}"))?,
+        None => TimeZone::system(),
+    };
+    let _ = SETTINGS.set((zone, style));
+    Ok(())
+}
+
+fn settings() -> &'static (TimeZone, DateStyle) {
+    SETTINGS.get_or_init(|| (TimeZone::system(), DateStyle::default()))
+}
+
+/// Today's local date.
+pub fn today() -> i64 {
+    day_of(SystemTime::now())
+}
+
+/// The local date at `time`.
+pub fn day_of(time: SystemTime) -> i64 {
+    Timestamp::try_from(time).map_or(0, |ts| local_day(ts, &settings().0))
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+pub fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// The day of a date, or the local day of a timestamp starting with one
+/// (`2024-06-01`, `2024-06-01T09:30:00.000+0000`).
+pub fn parse_date(s: &str) -> Option<i64> {
+    parse_date_in(s, &settings().0)
+}
+
+fn parse_date_in(s: &str, zone: &TimeZone) -> Option<i64> {
+    let s = s.trim();
+    if s.len() > 10
+        && let Ok(ts) = s.parse::<Timestamp>()
+    {
+        return Some(local_day(ts, zone));
+    }
+    let date = s.get(..10)?;
+    let mut parts = date.split('-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: u32 = parts.next()?.parse().ok()?;
+    let d: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    Some(days_from_civil(y, m, d))
+}
+
+fn local_day(ts: Timestamp, zone: &TimeZone) -> i64 {
+    let date = ts.to_zoned(zone.clone()).date();
+    days_from_civil(
+        i64::from(date.year()),
+        date.month() as u32,
+        date.day() as u32,
+    )
+}
+
+pub fn format_date(days: i64) -> String {
+    let (y, m, d) = civil_from_days(days);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// An RFC 3339 UTC timestamp, e.g. `2024-06-01T09:30:00Z`, for storing.
+pub fn format_timestamp(secs: u64) -> String {
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        format_date(days as i64),
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Seconds since the epoch of an RFC 3339 or Jira timestamp.
+pub fn parse_timestamp(s: &str) -> Option<u64> {
+    let ts = s.trim().parse::<Timestamp>().ok()?;
+    u64::try_from(ts.as_second()).ok()
+}
+
+/// A date field for display in the configured style. Text that isn't a
+/// date is shown as written.
+pub fn show(s: &str, today: i64) -> String {
+    match parse_date(s) {
+        Some(day) => match settings().1 {
+            DateStyle::Absolute => format_date(day),
+            DateStyle::Relative => relative(day - today),
+        },
+        None => s.to_string(),
+    }
+}
+
+/// `today`, `tomorrow`, `in 3d`, `2w ago`, ...
+pub fn relative(days: i64) -> String {
+    let n = days.abs();
+    let amount = match n {
+        0 => return "today".to_string(),
+        1 if days > 0 => return "tomorrow".to_string(),
+        1 => return "yesterday".to_string(),
+        2..14 => format!("{n}d"),
+        14..60 => format!("{}w", n / 7),
+        60..730 => format!("{}mo", n / 30),
+        _ => format!("{}y", n / 365),
+    };
+    if days > 0 {
+        format!("in {amount}")
+    } else {
+        format!("{amount} ago")
+    }
+}
+
+// Howard Hinnant's civil-to-days algorithm.
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Howard Hinnant's days-to-civil algorithm.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        let utc = TimeZone::UTC;
+        assert_eq!(parse_date_in("2024-06-01", &utc), Some(19_875));
+        assert_eq!(
+            parse_date_in("2024-06-01T09:30:00.000+0000", &utc),
+            Some(19_875)
+        );
+        assert_eq!(
+            format_timestamp(19_875 * 86_400 + 34_200),
+            "2024-06-01T09:30:00Z"
+        );
+        assert_eq!(parse_date_in("1969-12-31", &utc), Some(-1));
+        assert_eq!(format_date(19_875), "2024-06-01");
+        assert_eq!(parse_date_in("June 1st", &utc), None);
+        assert_eq!(
+            parse_timestamp("2024-06-01T09:30:00.000+0000"),
+            parse_timestamp("2024-06-01T09:30:00Z")
+        );
+        assert_eq!(parse_timestamp("2024-06-01"), None);
+    }
+
+    #[test]
+    fn timestamps_land_on_the_local_day() {
+        let late = "2024-06-01T23:30:00.000+0000";
+        let berlin = TimeZone::get("Europe/Berlin").unwrap();
+        let new_york = TimeZone::get("America/New_York").unwrap();
+
+        assert_eq!(parse_date_in(late, &berlin), Some(19_876));
+        assert_eq!(parse_date_in(late, &new_york), Some(19_875));
+        // A plain date has no time to shift.
+        assert_eq!(parse_date_in("2024-06-01", &berlin), Some(19_875));
+
+        assert_eq!(relative(0), "today");
+        assert_eq!(relative(-1), "yesterday");
+        assert_eq!(relative(3), "in 3d");
+        assert_eq!(relative(-20), "2w ago");
+        assert_eq!(relative(90), "in 3mo");
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::model::{Board, Card};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Line diff of `old` against `new` via longest common subsequence. Card
+/// bodies are small, so the quadratic table is fine.
+pub fn lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let lcs = lcs_table(&a, &b);
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push(DiffLine::Same(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(DiffLine::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            out.push(DiffLine::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+    out.extend(b[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+    out
+}
+
+// lcs[i][j] = LCS length of a[i..] and b[j..]
+fn lcs_table(a: &[&str], b: &[&str]) -> Vec<Vec<usize>> {
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    lcs
+}
+
+/// For each line of `a`, the index of the line it is matched with in `b`.
+fn align(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let lcs = lcs_table(a, b);
+    let mut out = vec![None; a.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Merge {
+    Clean(String),
+    /// Merged text with `<<<<<<<`/`=======`/`>>>>>>>` markers around conflicts.
+    Conflict(String),
+}
+
+/// Three-way merge of two edits of `base`. Regions changed on only one side
+/// take that side; regions changed identically on both are taken once.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Merge {
+    let b: Vec<&str> = base.lines().collect();
+    let o: Vec<&str> = ours.lines().collect();
+    let t: Vec<&str> = theirs.lines().collect();
+    let to_o = align(&b, &o);
+    let to_t = align(&b, &t);
+
+    let mut out: Vec<&str> = Vec::new();
+    let mut conflict = false;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // Next base line kept by both sides, or the end of all three.
+        let sync = (i..b.len()).find_map(|x| match (to_o[x], to_t[x]) {
+            (Some(y), Some(z)) if y >= j && z >= k => Some((x, y, z)),
+            _ => None,
+        });
+        let (x, y, z) = sync.unwrap_or((b.len(), o.len(), t.len()));
+
+        let (cb, co, ct) = (&b[i..x], &o[j..y], &t[k..z]);
+        if co == cb || co == ct {
+            out.extend(ct);
+        } else if ct == cb {
+            out.extend(co);
+        } else {
+            conflict = true;
+            out.push("<<<<<<< yours");
+            out.extend(co);
+            out.push("=======");
+            out.extend(ct);
+            out.push(">>>>>>> on disk");
+        }
+
+        let Some(_) = sync else { break };
+        out.push(b[x]);
+        (i, j, k) = (x + 1, y + 1, z + 1);
+    }
+
+    let mut text = out.join("\n");
+    if ours.ends_with('\n') || theirs.ends_with('\n') {
+        text.push('\n');
+    }
+    if conflict {
+        Merge::Conflict(text)
+    } else {
+        Merge::Clean(text)
+    }
+}
+
+/// What changed on a board since a snapshot of it. A card that was both
+/// moved and edited is listed under each.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct BoardDiff {
+    pub added: Vec<Placed>,
+    pub removed: Vec<Placed>,
+    pub moved: Vec<Moved>,
+    pub edited: Vec<Edited>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Placed {
+    pub id: String,
+    pub title: String,
+    pub column: String,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Moved {
+    pub id: String,
+    pub title: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Edited {
+    pub id: String,
+    pub title: String,
+    /// `title`, `description`, `labels`, `priority`, `assignee`, `due`, or
+    /// the name of another field.
+    pub changed: Vec<String>,
+}
+
+impl BoardDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.edited.is_empty()
+    }
+}
+
+/// Compares `new` against `old` card by card. Columns are given by id.
+pub fn boards(old: &Board, new: &Board) -> BoardDiff {
+    let index = |b: &Board| -> HashMap<String, (String, Card)> {
+        b.columns
+            .iter()
+            .flat_map(|col| {
+                col.cards
+                    .iter()
+                    .map(|c| (c.id.clone(), (col.id.clone(), c.clone())))
+            })
+            .collect()
+    };
+    let (before, after) = (index(old), index(new));
+    let mut diff = BoardDiff::default();
+    for col in &new.columns {
+        for card in &col.cards {
+            let Some((from, was)) = before.get(&card.id) else {
+                diff.added.push(Placed {
+                    id: card.id.clone(),
+                    title: card.title.clone(),
+                    column: col.id.clone(),
+                });
+                continue;
+            };
+            if *from != col.id {
+                diff.moved.push(Moved {
+                    id: card.id.clone(),
+                    title: card.title.clone(),
+                    from: from.clone(),
+                    to: col.id.clone(),
+                });
+            }
+            let changed = changed_fields(was, card);
+            if !changed.is_empty() {
+                diff.edited.push(Edited {
+                    id: card.id.clone(),
+                    title: card.title.clone(),
+                    changed,
+                });
+            }
+        }
+    }
+    for col in &old.columns {
+        for card in col.cards.iter().filter(|c| !after.contains_key(&c.id)) {
+            diff.removed.push(Placed {
+                id: card.id.clone(),
+                title: card.title.clone(),
+                column: col.id.clone(),
+            });
+        }
+    }
+    diff
+}
+
+fn changed_fields(old: &Card, new: &Card) -> Vec<String> {
+    let mut changed: Vec<String> = [
+        ("title", old.title != new.title),
+        ("description", old.description != new.description),
+        ("labels", old.labels != new.labels),
+        ("priority", old.priority != new.priority),
+        ("assignee", old.assignee != new.assignee),
+        ("due", old.due != new.due),
+    ]
+    .into_iter()
+    .filter(|(_, differs)| *differs)
+    .map(|(name, _)| name.to_string())
+    .collect();
+    let mut keys: Vec<&String> = old.fields.keys().chain(new.fields.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    changed.extend(
+        keys.into_iter()
+            .filter(|k| old.fields.get(*k) != new.fields.get(*k))
+            .cloned(),
+    );
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Column, ColumnMeta};
+
+    #[test]
+    fn lines_marks_added_and_removed() {
+        let d = lines("a\nb\nc\n", "a\nx\nc\nd\n");
+        assert_eq!(
+            d,
+            vec![
+                DiffLine::Same("a".into()),
+                DiffLine::Removed("b".into()),
+                DiffLine::Added("x".into()),
+                DiffLine::Same("c".into()),
+                DiffLine::Added("d".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge3_combines_edits_to_different_lines() {
+        let base = "# T\n\none\ntwo\nthree\n";
+        let ours = "# T\n\nONE\ntwo\nthree\n";
+        let theirs = "# T\n\none\ntwo\nthree\nfour\n";
+
+        assert_eq!(
+            merge3(base, ours, theirs),
+            Merge::Clean("# T\n\nONE\ntwo\nthree\nfour\n".into())
+        );
+    }
+
+    #[test]
+    fn merge3_marks_overlapping_edits() {
+        let Merge::Conflict(text) = merge3("a\nb\nc\n", "a\nmine\nc\n", "a\ntheirs\nc\n") else {
+            panic!("expected conflict");
+        };
+        assert_eq!(
+            text,
+            "a\n<<<<<<< yours\nmine\n=======\ntheirs\n>>>>>>> on disk\nc\n"
+        );
+    }
+
+    #[test]
+    fn identical_text_has_no_changes() {
+        assert!(
+            lines("one\ntwo", "one\ntwo")
+                .iter()
+                .all(|l| matches!(l, DiffLine::Same(_)))
+        );
+    }
+
+    #[test]
+    fn boards_lists_added_removed_moved_and_edited_cards() {
+        let board = |cards: &[(&str, &str, &str)]| Board {
+            columns: ["todo", "done"]
+                .map(|col| Column {
+                    id: col.into(),
+                    title: col.into(),
+                    cards: cards
+                        .iter()
+                        .filter(|(_, c, _)| *c == col)
+                        .map(|(id, _, title)| Card {
+                            id: id.to_string(),
+                            title: title.to_string(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    meta: ColumnMeta::default(),
+                })
+                .into(),
+            problems: vec![],
+        };
+        let old = board(&[
+            ("1", "todo", "One"),
+            ("2", "todo", "Two"),
+            ("3", "todo", "Three"),
+        ]);
+        let new = board(&[
+            ("1", "todo", "One"),
+            ("2", "done", "Two!"),
+            ("4", "done", "Four"),
+        ]);
+
+        let d = boards(&old, &new);
+        assert_eq!(d.added[0].id, "4");
+        assert_eq!(d.removed[0].id, "3");
+        assert_eq!((d.added.len(), d.removed.len()), (1, 1));
+        assert_eq!(
+            d.moved,
+            [Moved {
+                id: "2".into(),
+                title: "Two!".into(),
+                from: "todo".into(),
+                to: "done".into(),
+            }]
+        );
+        assert_eq!(d.edited[0].changed, ["title"]);
+        assert!(boards(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+//! Board events, published by every wrapped provider as its calls complete.
+//! The TUI, `flow serve` and embedders each [`subscribe`] and read them at
+//! their own pace.
+
+use std::{
+    fmt,
+    sync::{
+        Mutex, OnceLock,
+        mpsc::{self, Receiver, Sender},
+    },
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    CardMoved {
+        card_id: String,
+        to: String,
+    },
+    CardCreated {
+        card_id: String,
+        column: String,
+    },
+    CardArchived {
+        card_id: String,
+    },
+    /// `what` changed: `text`, `comment` or a field such as `estimate`.
+    CardEdited {
+        card_id: String,
+        what: String,
+    },
+    /// A board load finished.
+    Refreshed {
+        cards: usize,
+    },
+    /// The backend can't be reached; the cached board is shown and moves
+    /// are queued.
+    Offline {
+        queued: usize,
+    },
+    /// A call got through again after `Offline`, sending queued moves first.
+    Online {
+        replayed: usize,
+    },
+    /// A provider call failed.
+    Error {
+        op: &'static str,
+        msg: String,
+    },
+}
+
+impl Event {
+    /// A stable name, used as the `kind` metrics label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::CardMoved { .. } => "card_moved",
+            Event::CardCreated { .. } => "card_created",
+            Event::CardArchived { .. } => "card_archived",
+            Event::CardEdited { .. } => "card_edited",
+            Event::Refreshed { .. } => "refreshed",
+            Event::Offline { .. } => "offline",
+            Event::Online { .. } => "online",
+            Event::Error { .. } => "error",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::CardMoved { card_id, to } => write!(f, "moved {card_id} to {to}"),
+            Event::CardCreated { card_id, column } => write!(f, "created {card_id} in {column}"),
+            Event::CardArchived { card_id } => write!(f, "archived {card_id}"),
+            Event::CardEdited { card_id, what } => write!(f, "edited {card_id}: {what}"),
+            Event::Refreshed { cards } => write!(f, "loaded {cards} cards"),
+            Event::Offline { queued } => write!(f, "offline, {queued} moves queued"),
+            Event::Online { replayed } => write!(f, "back online, sent {replayed} queued moves"),
+            Event::Error { op, msg } => write!(f, "{op} failed: {msg}"),
+        }
+    }
+}
+
+// Shared like the metrics registry, so providers made on worker threads
+// publish to the same subscribers.
+fn subscribers() -> &'static Mutex<Vec<Sender<Event>>> {
+    static SUBSCRIBERS: OnceLock<Mutex<Vec<Sender<Event>>>> = OnceLock::new();
+    SUBSCRIBERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Every event published from now on. Dropping the receiver unsubscribes.
+pub fn subscribe() -> Receiver<Event> {
+    let (tx, rx) = mpsc::channel();
+    if let Ok(mut subs) = subscribers().lock() {
+        subs.push(tx);
+    }
+    rx
+}
+
+pub fn publish(event: Event) {
+    if let Ok(mut subs) = subscribers().lock() {
+        subs.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_subscriber_gets_each_event_until_it_drops() {
+        let a = subscribe();
+        let b = subscribe();
+        let moved = Event::CardMoved {
+            card_id: "EV-1".to_string(),
+            to: "done".to_string(),
+        };
+
+        publish(moved.clone());
+        drop(b);
+        publish(moved.clone());
+
+        // Other tests publish too; count only this one's events.
+        let seen = a.try_iter().filter(|e| *e == moved).count();
+        assert_eq!(seen, 2);
+        assert_eq!(moved.to_string(), "moved EV-1 to done");
+    }
+}
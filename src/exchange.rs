@@ -0,0 +1,561 @@
+//! Whole boards in and out of flow: `flow export --format csv|trello`, and
+//! `flow import` of flow's own JSON, a Trello JSON export or a Jira CSV
+//! export into a local board.
+
+use std::{collections::BTreeMap, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card_template::Draft,
+    dates,
+    frontmatter::FrontMatter,
+    model::{Board, Card, Column, ColumnMeta},
+    store_fs::{self, ColumnDef},
+};
+
+/// One row per card: `id,column,title,labels,priority,assignee,due,description`,
+/// labels separated by `;` and the column by title.
+pub fn to_csv(board: &Board) -> String {
+    let mut out = String::from("id,column,title,labels,priority,assignee,due,description\n");
+    for col in &board.columns {
+        for c in &col.cards {
+            let row = [
+                c.id.as_str(),
+                col.title.as_str(),
+                c.title.as_str(),
+                &c.labels.join(";"),
+                c.priority.as_deref().unwrap_or(""),
+                c.assignee.as_deref().unwrap_or(""),
+                c.due.as_deref().unwrap_or(""),
+                c.description.as_str(),
+            ];
+            let row: Vec<String> = row.iter().map(|v| csv_field(v)).collect();
+            out += &row.join(",");
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn csv_field(v: &str) -> String {
+    if v.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v.to_string()
+    }
+}
+
+/// The parts of a Trello board export flow reads and writes.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Trello {
+    name: String,
+    lists: Vec<TrelloList>,
+    cards: Vec<TrelloCard>,
+    members: Vec<TrelloMember>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TrelloList {
+    id: String,
+    name: String,
+    closed: bool,
+    pos: f64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TrelloCard {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_short: Option<u64>,
+    name: String,
+    desc: String,
+    id_list: String,
+    closed: bool,
+    pos: f64,
+    labels: Vec<TrelloLabel>,
+    due: Option<String>,
+    id_members: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct TrelloLabel {
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TrelloMember {
+    id: String,
+    full_name: String,
+    username: String,
+}
+
+/// The board in the shape of a Trello JSON export, for tools that read
+/// those. Card ids are kept, so importing it again gives the same board.
+pub fn to_trello(board: &Board) -> io::Result<String> {
+    let mut members: Vec<String> = vec![];
+    let mut cards = vec![];
+    for col in &board.columns {
+        for (i, c) in col.cards.iter().enumerate() {
+            if let Some(a) = &c.assignee
+                && !members.contains(a)
+            {
+                members.push(a.clone());
+            }
+            let id_members = c.assignee.iter().cloned().collect();
+            cards.push(TrelloCard {
+                id: c.id.clone(),
+                name: c.title.clone(),
+                desc: c.description.clone(),
+                id_list: col.id.clone(),
+                pos: i as f64,
+                labels: c
+                    .labels
+                    .iter()
+                    .map(|name| TrelloLabel {
+                        name: name.clone(),
+                        color: None,
+                    })
+                    .collect(),
+                due: c.due.as_ref().map(|d| format!("{d}T00:00:00.000Z")),
+                id_members,
+                ..TrelloCard::default()
+            });
+        }
+    }
+    let trello = Trello {
+        name: "flow".to_string(),
+        lists: board
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| TrelloList {
+                id: col.id.clone(),
+                name: col.title.clone(),
+                closed: false,
+                pos: i as f64,
+            })
+            .collect(),
+        cards,
+        members: members
+            .into_iter()
+            .map(|name| TrelloMember {
+                id: name.clone(),
+                full_name: name,
+                ..TrelloMember::default()
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&trello).map_err(io::Error::other)
+}
+
+/// What an import file holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// `flow export`.
+    Flow,
+    Trello,
+    JiraCsv,
+}
+
+impl Source {
+    /// JSON with `lists` is Trello's, other JSON flow's, anything else CSV.
+    pub fn guess(src: &str) -> Self {
+        match serde_json::from_str::<serde_json::Value>(src) {
+            Ok(v) if v.get("lists").is_some() => Source::Trello,
+            Ok(_) => Source::Flow,
+            Err(_) => Source::JiraCsv,
+        }
+    }
+}
+
+pub fn parse(src: &str, source: Source) -> Result<Board, String> {
+    match source {
+        Source::Flow => serde_json::from_str(src).map_err(|e| format!("not a flow export: {e}")),
+        Source::Trello => from_trello(src),
+        Source::JiraCsv => from_jira_csv(src),
+    }
+}
+
+/// Open lists become columns and open cards theirs, both in Trello's order.
+/// Cards are numbered `T-<idShort>`, the number Trello shows in card URLs.
+fn from_trello(src: &str) -> Result<Board, String> {
+    let mut trello: Trello =
+        serde_json::from_str(src).map_err(|e| format!("not a Trello export: {e}"))?;
+    trello.lists.retain(|l| !l.closed);
+    trello.lists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    trello.cards.retain(|c| !c.closed);
+    trello.cards.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+
+    let mut columns: Vec<Column> = vec![];
+    for list in &trello.lists {
+        columns.push(new_column(&list.name, &columns));
+    }
+    for card in trello.cards {
+        let Some(col) = trello.lists.iter().position(|l| l.id == card.id_list) else {
+            continue;
+        };
+        let member = |id: &String| {
+            let m = trello.members.iter().find(|m| &m.id == id)?;
+            Some(
+                if m.full_name.is_empty() {
+                    &m.username
+                } else {
+                    &m.full_name
+                }
+                .clone(),
+            )
+        };
+        columns[col].cards.push(Card {
+            id: card.id_short.map_or(card.id, |n| format!("T-{n}")),
+            title: card.name,
+            description: card.desc,
+            labels: card
+                .labels
+                .into_iter()
+                .filter_map(|l| Some(l.name).filter(|n| !n.is_empty()).or(l.color))
+                .collect(),
+            assignee: card.id_members.iter().find_map(member),
+            due: card
+                .due
+                .as_deref()
+                .and_then(dates::parse_date)
+                .map(dates::format_date),
+            ..Card::default()
+        });
+    }
+    Ok(Board {
+        columns,
+        problems: vec![],
+    })
+}
+
+/// Columns come from `Status`, in the order the statuses first appear.
+/// Jira repeats the `Labels` header once per label.
+fn from_jira_csv(src: &str) -> Result<Board, String> {
+    let mut rows = parse_csv(src).into_iter();
+    let header = rows.next().ok_or("empty CSV")?;
+    let col = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let key = col("Issue key").ok_or("not a Jira CSV export: no \"Issue key\" column")?;
+    let summary = col("Summary").ok_or("not a Jira CSV export: no \"Summary\" column")?;
+    let status = col("Status").ok_or("not a Jira CSV export: no \"Status\" column")?;
+    let labels: Vec<usize> = (0..header.len())
+        .filter(|&i| header[i].eq_ignore_ascii_case("Labels"))
+        .collect();
+    let (description, priority, assignee, due, issue_type) = (
+        col("Description"),
+        col("Priority"),
+        col("Assignee"),
+        col("Due date"),
+        col("Issue Type"),
+    );
+    let estimate = header
+        .iter()
+        .position(|h| h.contains("Story Points") || h.contains("Story point estimate"));
+
+    let mut columns: Vec<Column> = vec![];
+    for row in rows {
+        let get = |i: Option<usize>| {
+            i.and_then(|i| row.get(i))
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let (Some(id), Some(status)) = (get(Some(key)), get(Some(status))) else {
+            continue;
+        };
+        let col = match columns.iter().position(|c| c.title == status) {
+            Some(i) => i,
+            None => {
+                columns.push(new_column(&status, &columns));
+                columns.len() - 1
+            }
+        };
+        let mut fields = BTreeMap::new();
+        if let Some(t) = get(issue_type) {
+            fields.insert("issue_type".to_string(), vec![t]);
+        }
+        if let Some(points) = get(estimate) {
+            fields.insert("estimate".to_string(), vec![points]);
+        }
+        columns[col].cards.push(Card {
+            id,
+            title: get(Some(summary)).unwrap_or_default(),
+            description: get(description).unwrap_or_default(),
+            labels: labels.iter().filter_map(|&i| get(Some(i))).collect(),
+            priority: get(priority),
+            assignee: get(assignee),
+            due: get(due).and_then(|d| jira_date(&d)),
+            fields,
+        });
+    }
+    Ok(Board {
+        columns,
+        problems: vec![],
+    })
+}
+
+/// A column titled `title` with an id made from it, unique among `existing`.
+fn new_column(title: &str, existing: &[Column]) -> Column {
+    let base: String = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_");
+    let base = if base.is_empty() {
+        "column".to_string()
+    } else {
+        base
+    };
+    let mut id = base.clone();
+    let mut n = 2;
+    while existing.iter().any(|c| c.id == id) {
+        id = format!("{base}_{n}");
+        n += 1;
+    }
+    Column {
+        id,
+        title: title.to_string(),
+        cards: vec![],
+        meta: ColumnMeta::default(),
+    }
+}
+
+/// Jira's `14/Jun/24 5:00 PM`, or an ISO date, as `YYYY-MM-DD`.
+fn jira_date(s: &str) -> Option<String> {
+    if let Some(day) = dates::parse_date(s) {
+        return Some(dates::format_date(day));
+    }
+    let date = s.split_whitespace().next()?;
+    let mut parts = date.split('/');
+    let d: u32 = parts.next()?.parse().ok()?;
+    let month = parts.next()?.to_ascii_lowercase();
+    let y: u32 = parts.next()?.parse().ok()?;
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let m = MONTHS.iter().position(|&m| month.starts_with(m))? + 1;
+    let y = if y < 100 { 2000 + y } else { y };
+    Some(format!("{y:04}-{m:02}-{d:02}"))
+}
+
+/// RFC 4180: quoted fields may hold commas, newlines and `""` for a quote.
+fn parse_csv(src: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = src.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// What [`write_local`] did.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub cards: usize,
+    /// Cards left out, with why, e.g. an id the board already has.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Writes `board` as a local board at `root`: its columns (with their
+/// limits and settings) become board.txt and its cards card files. An
+/// existing board is only imported into with `force`: columns are matched
+/// by id or title, the rest added after them, and cards it already has are
+/// skipped.
+pub fn write_local(root: &Path, board: &Board, force: bool) -> io::Result<Imported> {
+    let mut defs = vec![];
+    if root.join("board.txt").exists() {
+        if !force {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already has a board.txt (use --force to import into it)",
+                    root.display()
+                ),
+            ));
+        }
+        defs = store_fs::read_columns(root)?;
+    }
+    let mut targets = vec![];
+    for col in &board.columns {
+        let existing = defs
+            .iter()
+            .find(|d| d.id == col.id || d.title.eq_ignore_ascii_case(&col.title));
+        let id = match existing {
+            Some(def) => def.id.clone(),
+            None => {
+                defs.push(column_def(col));
+                col.id.clone()
+            }
+        };
+        targets.push(id);
+    }
+    store_fs::write_columns(root, &defs)?;
+
+    let mut imported = Imported::default();
+    for (col, col_id) in board.columns.iter().zip(&targets) {
+        std::fs::create_dir_all(root.join("cols").join(col_id))?;
+        for card in &col.cards {
+            let draft = Draft {
+                title: card.title.clone(),
+                description: card.description.clone(),
+                front: front_matter(card),
+            };
+            match store_fs::create_card_from(root, col_id, &card.id, &draft, None) {
+                Ok(()) => imported.cards += 1,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::AlreadyExists | io::ErrorKind::InvalidInput
+                    ) =>
+                {
+                    imported.skipped.push((card.id.clone(), e.to_string()));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    Ok(imported)
+}
+
+fn column_def(col: &Column) -> ColumnDef {
+    let mut def = ColumnDef::new(&col.id, &col.title);
+    let meta = &col.meta;
+    let mut attr = |k: &str, v: String| def.extra.push((k.to_string(), v));
+    if let Some(n) = meta.wip_limit {
+        attr("wip", n.to_string());
+    }
+    if let Some(n) = meta.budget {
+        attr("budget", n.to_string());
+    }
+    if meta.terminal {
+        attr("terminal", "true".to_string());
+    }
+    if let Some(color) = &meta.color {
+        attr("color", color.clone());
+    }
+    if let Some(key) = &meta.auto_sort {
+        let dir = if key.desc { "desc" } else { "asc" };
+        attr("auto-sort", format!("{} {dir}", key.field));
+    }
+    if let Some(policy) = &meta.policy {
+        attr("policy", policy.clone());
+    }
+    def
+}
+
+fn front_matter(card: &Card) -> FrontMatter {
+    let mut fm = FrontMatter::default();
+    if !card.labels.is_empty() {
+        fm.set_list("labels", &card.labels);
+    }
+    for (key, value) in [
+        ("priority", &card.priority),
+        ("assignee", &card.assignee),
+        ("due", &card.due),
+    ] {
+        if let Some(v) = value {
+            fm.set(key, v);
+        }
+    }
+    for (key, values) in &card.fields {
+        match values.as_slice() {
+            [one] => fm.set(key, one),
+            many => fm.set_list(key, many),
+        }
+    }
+    fm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jira_csv_keeps_statuses_in_order_and_every_label() {
+        let csv = "\u{feff}Summary,Issue key,Status,Labels,Labels,Due date,Description\n\
+                   Fix login,ENG-1,In Progress,auth,bug,14/Jun/24 5:00 PM,\"Steps:\n1, then 2 \"\"quoted\"\"\"\n\
+                   Docs,ENG-2,To Do,,,,\n\
+                   Deploy,ENG-3,In Progress,,,2024-07-01,\n";
+        let board = parse(csv, Source::guess(csv)).unwrap();
+        let cols: Vec<_> = board
+            .columns
+            .iter()
+            .map(|c| (c.id.as_str(), c.cards.len()))
+            .collect();
+        assert_eq!(cols, [("in_progress", 2), ("to_do", 1)]);
+        let fix = &board.columns[0].cards[0];
+        assert_eq!(fix.labels, ["auth", "bug"]);
+        assert_eq!(fix.due.as_deref(), Some("2024-06-14"));
+        assert_eq!(fix.description, "Steps:\n1, then 2 \"quoted\"");
+        assert_eq!(board.columns[0].cards[1].due.as_deref(), Some("2024-07-01"));
+    }
+
+    #[test]
+    fn trello_export_imports_back_to_the_same_board() {
+        let src = r#"{"lists": [{"id": "l2", "name": "Done", "pos": 2},
+                                {"id": "l1", "name": "To Do", "pos": 1},
+                                {"id": "l3", "name": "Old", "closed": true}],
+                      "cards": [{"id": "c1", "idShort": 7, "name": "Ship", "idList": "l1",
+                                 "labels": [{"name": "", "color": "red"}],
+                                 "idMembers": ["m1"], "due": "2024-06-01T12:00:00.000Z"},
+                                {"id": "c2", "idShort": 8, "name": "Gone", "idList": "l1", "closed": true}],
+                      "members": [{"id": "m1", "fullName": "Ada", "username": "ada"}]}"#;
+        assert_eq!(Source::guess(src), Source::Trello);
+        let board = parse(src, Source::Trello).unwrap();
+        let card = &board.columns[0].cards[0];
+        assert_eq!(board.columns[0].title, "To Do");
+        assert_eq!(card.id, "T-7");
+        assert_eq!(card.labels, ["red"]);
+        assert_eq!(card.assignee.as_deref(), Some("Ada"));
+        assert_eq!(card.due.as_deref(), Some("2024-06-01"));
+        assert!(board.columns[1].cards.is_empty());
+
+        let again = parse(&to_trello(&board).unwrap(), Source::Trello).unwrap();
+        let json = |b: &Board| serde_json::to_string(&b.columns).unwrap();
+        assert_eq!(json(&again), json(&board));
+    }
+
+    #[test]
+    fn imported_columns_stay_inside_the_board() {
+        let root = std::env::temp_dir().join(format!("flow-exchange-{}", dates::now_millis()));
+        let board = |id: &str, title: &str| {
+            let src = format!(
+                r#"{{"columns": [{{"id": {id:?}, "title": {title:?}, "cards": [{{"id": "X-1", "title": "x"}}]}}]}}"#
+            );
+            parse(&src, Source::Flow).unwrap()
+        };
+
+        assert!(write_local(&root.join("b"), &board("../../escaped", "Evil"), false).is_err());
+        assert!(write_local(&root.join("b"), &board("evil", "Evil \"Col\""), false).is_err());
+        assert!(!root.exists());
+    }
+}
@@ -0,0 +1,535 @@
+//! Filter expressions over cards, e.g. `column=doing and label=bug and due<3d`.
+//!
+//! Grammar (lowest precedence first): `or`, `and`, `not`, parentheses, and
+//! comparisons `key op value` with `=`, `!=`, `<`, `<=`, `>`, `>=`, `~`
+//! (case-insensitive substring). Keys are `column`, `id`, `title`, `label`
+//! or any front matter field. Values are bare words or quoted strings; a
+//! relative duration like `3d` or `2w` means that many days from today, and
+//! `@me` is the `[user]` from the config (`assignee=@me`).
+//!
+//! The board's filter bar (`f`) takes a shorter form, [`parse_bar`]:
+//! predicates separated by spaces that must all hold, such as
+//! `assignee:me label:bug text:"payment"`.
+
+use crate::{
+    config::{self, UserConfig},
+    dates::format_date,
+    model::{Card, Column},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Cmp { key: String, op: Op, value: Value },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Text(String),
+    /// Days from today, compared as a `YYYY-MM-DD` date.
+    Days(i64),
+    /// `@me`: equal to the user's name, email or initials.
+    Me,
+}
+
+pub fn parse(s: &str) -> Result<Expr, String> {
+    let tokens = tokenize(s)?;
+    let mut p = Parser { tokens, pos: 0 };
+    let expr = p.or()?;
+    match p.peek() {
+        None => Ok(expr),
+        Some(t) => Err(format!("unexpected {}", t.describe())),
+    }
+}
+
+/// The filter bar's predicates, all of which must hold: `key:value` (equal,
+/// or containing for `text`, `title` and `description`; `me` is `@me`), a
+/// comparison such as `due<3d`, or a bare word or quoted phrase to find in
+/// the id, title or description. A leading `-` negates one.
+pub fn parse_bar(s: &str) -> Result<Expr, String> {
+    let mut all: Option<Expr> = None;
+    for term in bar_terms(s)? {
+        let (negated, term) = match term.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, term.as_str()),
+        };
+        let key_end = term.find(|c: char| !(c.is_alphanumeric() || c == '_'));
+        let expr = match key_end.map(|i| term.split_at(i)) {
+            Some((key, rest)) if !key.is_empty() && rest.starts_with(':') => {
+                let value = unquote(&rest[1..]);
+                if value.is_empty() {
+                    return Err(format!("expected a value after `{key}:`"));
+                }
+                let key = key.to_ascii_lowercase();
+                let (op, value) = match (key.as_str(), value) {
+                    (_, v) if v.eq_ignore_ascii_case("me") || v.eq_ignore_ascii_case("@me") => {
+                        (Op::Eq, Value::Me)
+                    }
+                    ("text" | "title" | "description", v) => (Op::Contains, Value::Text(v.into())),
+                    (_, v) => (
+                        Op::Eq,
+                        relative_days(v).map_or(Value::Text(v.into()), Value::Days),
+                    ),
+                };
+                Expr::Cmp { key, op, value }
+            }
+            _ if term.contains(['=', '<', '>', '~']) => parse(term)?,
+            _ => Expr::Cmp {
+                key: "text".to_string(),
+                op: Op::Contains,
+                value: Value::Text(unquote(term).to_string()),
+            },
+        };
+        let expr = if negated {
+            Expr::Not(Box::new(expr))
+        } else {
+            expr
+        };
+        all = Some(match all {
+            None => expr,
+            Some(prev) => Expr::And(Box::new(prev), Box::new(expr)),
+        });
+    }
+    all.ok_or_else(|| "empty filter".to_string())
+}
+
+/// Splits at spaces outside quotes.
+fn bar_terms(s: &str) -> Result<Vec<String>, String> {
+    let mut terms = vec![];
+    let mut term = String::new();
+    let mut quote = None;
+    for c in s.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+                continue;
+            }
+            None => {}
+        }
+        term.push(c);
+    }
+    if quote.is_some() {
+        return Err("unterminated string".to_string());
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+fn unquote(s: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|q| s.strip_prefix(q)?.strip_suffix(q))
+        .unwrap_or(s)
+}
+
+impl Expr {
+    pub fn matches(&self, col: &Column, card: &Card, today: i64) -> bool {
+        self.matches_as(config::user(), col, card, today)
+    }
+
+    fn matches_as(&self, me: &UserConfig, col: &Column, card: &Card, today: i64) -> bool {
+        match self {
+            Expr::And(a, b) => {
+                a.matches_as(me, col, card, today) && b.matches_as(me, col, card, today)
+            }
+            Expr::Or(a, b) => {
+                a.matches_as(me, col, card, today) || b.matches_as(me, col, card, today)
+            }
+            Expr::Not(e) => !e.matches_as(me, col, card, today),
+            Expr::Cmp { key, op, value } => {
+                let want = match value {
+                    Value::Text(s) => s.clone(),
+                    Value::Days(n) => format_date(today + n),
+                    Value::Me => {
+                        let mine = values(key, col, card).iter().any(|v| me.is(v));
+                        return match op {
+                            Op::Eq | Op::Contains => mine,
+                            Op::Ne => !mine,
+                            _ => false,
+                        };
+                    }
+                };
+                let have = values(key, col, card);
+                match op {
+                    // Absent fields are "not equal" to anything.
+                    Op::Ne => !have.iter().any(|v| compare(v, Op::Eq, &want)),
+                    _ => have.iter().any(|v| compare(v, *op, &want)),
+                }
+            }
+        }
+    }
+}
+
+fn values<'a>(key: &str, col: &'a Column, card: &'a Card) -> Vec<&'a str> {
+    match key.to_ascii_lowercase().as_str() {
+        "column" | "col" => vec![col.id.as_str(), col.title.as_str()],
+        "id" => vec![card.id.as_str()],
+        "title" => vec![card.title.as_str()],
+        "description" => vec![card.description.as_str()],
+        "text" => vec![
+            card.id.as_str(),
+            card.title.as_str(),
+            card.description.as_str(),
+        ],
+        other => card.values(other),
+    }
+}
+
+fn compare(have: &str, op: Op, want: &str) -> bool {
+    use std::cmp::Ordering;
+
+    if op == Op::Contains {
+        return have.to_lowercase().contains(&want.to_lowercase());
+    }
+    let ord = match (have.parse::<f64>(), want.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => have.to_lowercase().cmp(&want.to_lowercase()),
+    };
+    match op {
+        Op::Eq => ord == Ordering::Equal,
+        Op::Ne => ord != Ordering::Equal,
+        Op::Lt => ord == Ordering::Less,
+        Op::Le => ord != Ordering::Greater,
+        Op::Gt => ord == Ordering::Greater,
+        Op::Ge => ord != Ordering::Less,
+        Op::Contains => unreachable!(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(w) => format!("`{w}`"),
+            Token::Quoted(s) => format!("\"{s}\""),
+            Token::Op(_) => "operator".to_string(),
+            Token::Open => "`(`".to_string(),
+            Token::Close => "`)`".to_string(),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut out = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                out.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                out.push(Token::Close);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(ch) => text.push(ch),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                out.push(Token::Quoted(text));
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                let op = match (c, eq) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    ('~', false) => Op::Contains,
+                    _ => return Err(format!("unknown operator `{c}`")),
+                };
+                out.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(ch) =
+                    chars.next_if(|ch| !ch.is_whitespace() && !"()=!<>~\"'".contains(*ch))
+                {
+                    word.push(ch);
+                }
+                out.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(out)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn keyword(&mut self, kw: &str) -> bool {
+        if let Some(Token::Word(w)) = self.peek()
+            && w.eq_ignore_ascii_case(kw)
+        {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.keyword("or") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while self.keyword("and") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let e = self.or()?;
+            return match self.next() {
+                Some(Token::Close) => Ok(e),
+                _ => Err("missing `)`".to_string()),
+            };
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let key = match self.next() {
+            Some(Token::Word(w)) => w,
+            Some(t) => return Err(format!("expected a field name, found {}", t.describe())),
+            None => return Err("expected a field name".to_string()),
+        };
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => return Err(format!("expected an operator after `{key}`")),
+        };
+        let value = match self.next() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("@me") => Value::Me,
+            Some(Token::Word(w)) => relative_days(&w).map_or(Value::Text(w), Value::Days),
+            Some(Token::Quoted(s)) => Value::Text(s),
+            _ => return Err(format!("expected a value after `{key}`")),
+        };
+        Ok(Expr::Cmp { key, op, value })
+    }
+}
+
+/// `3d` -> 3, `2w` -> 14, `-1d` -> -1, `today` -> 0.
+fn relative_days(w: &str) -> Option<i64> {
+    if w.eq_ignore_ascii_case("today") {
+        return Some(0);
+    }
+    if let Some(n) = w.strip_suffix('d') {
+        n.parse().ok()
+    } else {
+        w.strip_suffix('w')?.parse::<i64>().ok().map(|n| n * 7)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ColumnMeta;
+    use std::collections::BTreeMap;
+
+    fn card(id: &str, fields: &[(&str, &[&str])]) -> Card {
+        let mut card = Card {
+            id: id.to_string(),
+            title: format!("Card {id}"),
+            ..Card::default()
+        };
+        card.set_fields(
+            fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect()))
+                .collect::<BTreeMap<_, _>>(),
+        );
+        card
+    }
+
+    fn col(id: &str) -> Column {
+        Column {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            cards: Vec::new(),
+            meta: ColumnMeta::default(),
+        }
+    }
+
+    // 2024-06-01
+    const TODAY: i64 = 19_875;
+
+    #[test]
+    fn matches_column_label_and_relative_due_date() {
+        let f = parse("column=doing and label=bug and due<3d").unwrap();
+        let due_soon = card(
+            "A-1",
+            &[("labels", &["bug", "ui"]), ("due", &["2024-06-02"])],
+        );
+        let due_later = card("A-2", &[("labels", &["bug"]), ("due", &["2024-06-10"])]);
+
+        assert!(f.matches(&col("doing"), &due_soon, TODAY));
+        assert!(!f.matches(&col("doing"), &due_later, TODAY));
+        assert!(!f.matches(&col("todo"), &due_soon, TODAY));
+    }
+
+    #[test]
+    fn or_not_and_parentheses() {
+        let f = parse("not (label=bug or title~'a-2')").unwrap();
+
+        assert!(!f.matches(&col("todo"), &card("A-1", &[("labels", &["bug"])]), TODAY));
+        assert!(!f.matches(&col("todo"), &card("A-2", &[]), TODAY));
+        assert!(f.matches(&col("todo"), &card("A-3", &[]), TODAY));
+    }
+
+    #[test]
+    fn missing_fields_are_unequal_and_unordered() {
+        let c = card("A-1", &[]);
+
+        assert!(
+            parse("owner!=ann")
+                .unwrap()
+                .matches(&col("todo"), &c, TODAY)
+        );
+        assert!(!parse("due<1w").unwrap().matches(&col("todo"), &c, TODAY));
+    }
+
+    #[test]
+    fn numbers_compare_numerically() {
+        let c = card("A-1", &[("points", &["10"])]);
+
+        assert!(parse("points>=9").unwrap().matches(&col("todo"), &c, TODAY));
+    }
+
+    #[test]
+    fn values_may_end_in_any_character() {
+        let c = card("A-1", &[("labels", &["café"])]);
+
+        assert!(
+            parse("label=café")
+                .unwrap()
+                .matches(&col("todo"), &c, TODAY)
+        );
+        assert!(parse_bar("label:café").is_ok_and(|f| f.matches(&col("todo"), &c, TODAY)));
+        assert_eq!(relative_days("2w"), Some(14));
+    }
+
+    #[test]
+    fn me_is_the_configured_user_by_name_email_or_initials() {
+        let me = UserConfig {
+            name: Some("Ana Lopez".to_string()),
+            email: Some("ana@example.com".to_string()),
+            initials: None,
+        };
+        let mine = parse("assignee=@me").unwrap();
+        let theirs = parse("assignee!=@me").unwrap();
+        let matches = |f: &Expr, who: &str| {
+            f.matches_as(
+                &me,
+                &col("todo"),
+                &card("A-1", &[("assignee", &[who])]),
+                TODAY,
+            )
+        };
+
+        assert!(matches(&mine, "ana lopez"));
+        assert!(matches(&mine, "ANA@example.com"));
+        assert!(matches(&mine, "AL"));
+        assert!(!matches(&mine, "Bo"));
+        assert!(matches(&theirs, "Bo"));
+    }
+
+    #[test]
+    fn bar_predicates_all_hold() {
+        let me = UserConfig {
+            name: Some("Ana Lopez".to_string()),
+            ..UserConfig::default()
+        };
+        let f =
+            parse_bar(r#"assignee:me label:bug text:"payment failed" -label:ui due<1w"#).unwrap();
+        let mut c = card(
+            "A-1",
+            &[
+                ("assignee", &["Ana Lopez"]),
+                ("labels", &["bug"]),
+                ("due", &["2024-06-03"]),
+            ],
+        );
+        c.description = "Payment failed on retry".to_string();
+        let todo = col("todo");
+        assert!(f.matches_as(&me, &todo, &c, TODAY));
+
+        c.labels.push("ui".to_string());
+        assert!(!f.matches_as(&me, &todo, &c, TODAY));
+        assert!(parse_bar("label:bugfix").is_ok_and(|f| !f.matches(&todo, &c, TODAY)));
+        assert!(parse_bar("A-1").is_ok_and(|f| f.matches(&todo, &c, TODAY)));
+        assert!(parse_bar("label:").is_err());
+        assert!(parse_bar("text:\"open").is_err());
+        assert!(parse_bar("  ").is_err());
+    }
+
+    #[test]
+    fn parse_reports_errors() {
+        assert!(parse("column=").is_err());
+        assert!(parse("(label=bug").is_err());
+        assert!(parse("label bug").is_err());
+        assert!(parse("label=bug extra").is_err());
+    }
+}
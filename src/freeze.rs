@@ -0,0 +1,386 @@
+//! Board locks from `flow lock --until`, for retrospectives and sprint
+//! close. While a lock holds, changes to the board are refused except to
+//! cards in its allowed columns. Locks are kept client-side in `locks.toml`
+//! next to the config, keyed by board, so every provider honours them.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activity,
+    card_template::Draft,
+    config::{self, Profile},
+    dates,
+    graph::LinkKind,
+    model::{Board, Card, CardDetail, Mention, Move, QuickFilter, User},
+    provider::{Provider, ProviderError},
+    provider_local::LocalProvider,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Lock {
+    /// The last locked day, `YYYY-MM-DD`.
+    pub until: String,
+    /// Column ids whose cards may still change.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+}
+
+impl Lock {
+    pub fn active(&self, today: i64) -> bool {
+        dates::parse_date(&self.until).is_some_and(|last| today <= last)
+    }
+
+    /// Whether cards may change in the column with this id or title.
+    pub fn allows(&self, col_id: &str, col_title: &str) -> bool {
+        self.allow
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(col_id) || a.eq_ignore_ascii_case(col_title))
+    }
+}
+
+/// Names the board a profile opens, whichever profile it's opened through.
+pub fn board_key(profile: &Profile) -> String {
+    let field = |v: &Option<String>| v.clone().unwrap_or_default();
+    match profile.provider() {
+        "jira" => format!(
+            "jira:{}#{}",
+            field(&profile.base_url),
+            field(&profile.board_id)
+        ),
+        "linear" => format!(
+            "linear:{}/{}",
+            field(&profile.team),
+            field(&profile.project)
+        ),
+        "github" => format!("github:{}", field(&profile.repo)),
+        "demo" => "demo".to_string(),
+        _ => format!(
+            "local:{}",
+            LocalProvider::from_profile(profile).root().display()
+        ),
+    }
+}
+
+/// A board key made safe as a file name, for per-board caches.
+pub fn file_name(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn path() -> Option<PathBuf> {
+    Some(config::path()?.parent()?.join("locks.toml"))
+}
+
+fn load_all() -> io::Result<BTreeMap<String, Lock>> {
+    let Some(path) = path() else {
+        return Ok(BTreeMap::new());
+    };
+    match fs::read_to_string(&path) {
+        Ok(s) => toml::from_str(&s).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {e}", path.display()),
+            )
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The board's lock, if one holds today.
+pub fn active(key: &str) -> Option<Lock> {
+    let lock = load_all().ok()?.remove(key)?;
+    lock.active(dates::today()).then_some(lock)
+}
+
+/// Sets or, with `None`, lifts the board's lock. Expired locks are dropped
+/// on the way.
+pub fn set(key: &str, lock: Option<Lock>) -> io::Result<()> {
+    let path = path().ok_or_else(|| io::Error::other("cannot locate config dir (HOME unset)"))?;
+    let today = dates::today();
+    let mut locks = load_all()?;
+    locks.retain(|_, l| l.active(today));
+    match lock {
+        Some(lock) => locks.insert(key.to_string(), lock),
+        None => locks.remove(key),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(
+        &path,
+        toml::to_string_pretty(&locks).map_err(io::Error::other)?,
+    )
+}
+
+/// Refuses changes a lock forbids. The lock is read on every change, so
+/// `flow lock` applies to boards that are already open.
+pub struct Frozen {
+    inner: Box<dyn Provider>,
+    key: String,
+    /// From the last load: each column's id and title, and each card's column.
+    columns: Vec<(String, String)>,
+    cards: HashMap<String, String>,
+}
+
+impl Frozen {
+    pub fn new(inner: Box<dyn Provider>, key: String) -> Self {
+        Self {
+            inner,
+            key,
+            columns: vec![],
+            cards: HashMap::new(),
+        }
+    }
+
+    fn remember(&mut self, board: &Board) {
+        self.columns = board
+            .columns
+            .iter()
+            .map(|c| (c.id.clone(), c.title.clone()))
+            .collect();
+        self.cards = board
+            .columns
+            .iter()
+            .flat_map(|col| col.cards.iter().map(|c| (c.id.clone(), col.id.clone())))
+            .collect();
+    }
+
+    /// Checks a change to `card_id` where it is now, and into `to_col_id`.
+    fn check(
+        &mut self,
+        card_id: Option<&str>,
+        to_col_id: Option<&str>,
+    ) -> Result<(), ProviderError> {
+        let Some(lock) = active(&self.key) else {
+            return Ok(());
+        };
+        if let Some(id) = card_id
+            && !self.cards.contains_key(id)
+        {
+            let board = self.inner.load_board()?;
+            self.remember(&board);
+        }
+        let from = card_id
+            .and_then(|id| self.cards.get(id))
+            .map(String::as_str);
+        let allowed = |col_id: &str| {
+            let title = self
+                .columns
+                .iter()
+                .find(|(id, _)| id == col_id)
+                .map_or("", |(_, title)| title.as_str());
+            lock.allows(col_id, title)
+        };
+        let ok = card_id.is_none_or(|_| from.is_some_and(allowed)) && to_col_id.is_none_or(allowed);
+        if ok {
+            Ok(())
+        } else {
+            Err(ProviderError::Locked { until: lock.until })
+        }
+    }
+}
+
+impl Provider for Frozen {
+    fn load_board(&mut self) -> Result<Board, ProviderError> {
+        let board = self.inner.load_board()?;
+        self.remember(&board);
+        Ok(board)
+    }
+
+    fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        self.check(Some(card_id), Some(to_col_id))?;
+        self.inner.move_card(card_id, to_col_id)?;
+        self.cards
+            .insert(card_id.to_string(), to_col_id.to_string());
+        Ok(())
+    }
+
+    fn create_card_with_id(
+        &mut self,
+        to_col_id: &str,
+        card_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        self.check(None, Some(to_col_id))?;
+        self.inner.create_card_with_id(to_col_id, card_id, title)
+    }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        self.check(None, Some(to_col_id))?;
+        self.inner.create_card_with_title(to_col_id, title)
+    }
+
+    fn create_card_from(
+        &mut self,
+        to_col_id: &str,
+        draft: &Draft,
+    ) -> Result<String, ProviderError> {
+        self.check(None, Some(to_col_id))?;
+        self.inner.create_card_from(to_col_id, draft)
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        self.check(Some(card_id), None)?;
+        self.inner.update_card(card_id, title, description)
+    }
+
+    fn card_path(&self, card_id: &str) -> Result<PathBuf, ProviderError> {
+        self.inner.card_path(card_id)
+    }
+
+    fn save_card(&mut self, card_id: &str, text: &str) -> Result<(), ProviderError> {
+        self.check(Some(card_id), None)?;
+        self.inner.save_card(card_id, text)
+    }
+
+    fn load_more(&mut self, col_id: &str) -> Result<(), ProviderError> {
+        self.inner.load_more(col_id)
+    }
+
+    fn quick_filters(&mut self) -> Result<Vec<QuickFilter>, ProviderError> {
+        self.inner.quick_filters()
+    }
+
+    fn set_quick_filters(&mut self, filters: &[QuickFilter]) -> Result<(), ProviderError> {
+        self.inner.set_quick_filters(filters)
+    }
+
+    fn set_estimate(&mut self, card_id: &str, points: f64) -> Result<(), ProviderError> {
+        self.check(Some(card_id), None)?;
+        self.inner.set_estimate(card_id, points)
+    }
+
+    fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+        self.check(Some(card_id), None)?;
+        self.inner.archive_card(card_id)
+    }
+
+    /// Refused whole when any card is frozen, rather than part way.
+    fn move_cards(
+        &mut self,
+        card_ids: &[String],
+        to_col_id: &str,
+        moved: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        for card_id in card_ids {
+            self.check(Some(card_id), Some(to_col_id))?;
+        }
+        let cards = &mut self.cards;
+        self.inner.move_cards(card_ids, to_col_id, &mut |card_id| {
+            cards.insert(card_id.to_string(), to_col_id.to_string());
+            moved(card_id);
+        })
+    }
+
+    fn archive_cards(
+        &mut self,
+        card_ids: &[String],
+        archived: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        for card_id in card_ids {
+            self.check(Some(card_id), None)?;
+        }
+        self.inner.archive_cards(card_ids, archived)
+    }
+
+    fn archived_cards(&mut self) -> Result<Vec<Card>, ProviderError> {
+        self.inner.archived_cards()
+    }
+
+    fn card_detail(&mut self, card_id: &str) -> Result<CardDetail, ProviderError> {
+        self.inner.card_detail(card_id)
+    }
+
+    fn mentions(&mut self, minutes: u64) -> Result<Vec<Mention>, ProviderError> {
+        self.inner.mentions(minutes)
+    }
+
+    fn moves(&mut self, since: u64) -> Result<Vec<Move>, ProviderError> {
+        self.inner.moves(since)
+    }
+
+    fn activity(&mut self) -> Result<Vec<activity::Entry>, ProviderError> {
+        self.inner.activity()
+    }
+
+    fn search(&mut self, query: &str) -> Result<Vec<Card>, ProviderError> {
+        self.inner.search(query)
+    }
+
+    fn pull_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        self.check(None, Some(to_col_id))?;
+        self.inner.pull_card(card_id, to_col_id)
+    }
+
+    fn reorder_column(&mut self, col_id: &str, order: &[String]) -> Result<(), ProviderError> {
+        self.check(None, Some(col_id))?;
+        self.inner.reorder_column(col_id, order)
+    }
+
+    fn assignable_users(&mut self, card_id: &str) -> Result<Vec<User>, ProviderError> {
+        self.inner.assignable_users(card_id)
+    }
+
+    fn set_assignee(&mut self, card_id: &str, user: Option<&User>) -> Result<(), ProviderError> {
+        self.check(Some(card_id), None)?;
+        self.inner.set_assignee(card_id, user)
+    }
+
+    /// Comments are discussion, not board changes, so a lock allows them.
+    fn add_comment(&mut self, card_id: &str, body: &str) -> Result<(), ProviderError> {
+        self.inner.add_comment(card_id, body)
+    }
+
+    fn link_cards(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        kind: LinkKind,
+    ) -> Result<(), ProviderError> {
+        self.check(Some(from_id), None)?;
+        self.inner.link_cards(from_id, to_id, kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_lock_holds_through_its_last_day_for_columns_not_allowed() {
+        let lock = Lock {
+            until: "2024-06-14".to_string(),
+            allow: vec!["done".to_string()],
+        };
+        // 2024-06-14 and the day after.
+        assert!(lock.active(19_888));
+        assert!(!lock.active(19_889));
+
+        assert!(lock.allows("done", "Done"));
+        assert!(lock.allows("d1", "DONE"));
+        assert!(!lock.allows("doing", "Doing"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Frontends draw the same [`App`] state in different places: the ratatui
+//! TUI, plain text for dumb terminals, and the HTML page `flow serve` shows
+//! at `/`.
+
+use std::{
+    io::{self, Write},
+    sync::OnceLock,
+};
+
+use ratatui::{Frame, Terminal, backend::CrosstermBackend, buffer::Buffer};
+
+use crate::{app::App, config::Charset, dates};
+
+static ASCII: OnceLock<bool> = OnceLock::new();
+
+/// Picks the charset for the rest of the run.
+pub fn configure(charset: Charset) {
+    let _ = ASCII.set(charset.ascii());
+}
+
+fn ascii() -> bool {
+    *ASCII.get_or_init(|| Charset::default().ascii())
+}
+
+/// Call at the end of every TUI draw: swaps box drawing, arrows and badge
+/// glyphs for ASCII when the terminal can't be trusted with them.
+pub fn finish(f: &mut Frame) {
+    if ascii() {
+        to_ascii(f.buffer_mut());
+    }
+}
+
+fn to_ascii(buf: &mut Buffer) {
+    for cell in &mut buf.content {
+        let mut chars = cell.symbol().chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && let Some(plain) = ascii_glyph(c)
+        {
+            cell.set_char(plain);
+        }
+    }
+}
+
+/// The glyphs flow and ratatui draw with; other text, card titles
+/// included, is left alone.
+fn ascii_glyph(c: char) -> Option<char> {
+    Some(match c {
+        '─' | '━' | '═' | '—' | '–' => '-',
+        '│' | '┃' | '║' | '▌' | '▐' => '|',
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '╭' | '╮' | '╰' | '╯' | '╔' | '╗'
+        | '╚' | '╝' => '+',
+        '↑' | '▲' => '^',
+        '↓' | '▼' => 'v',
+        '←' | '◀' => '<',
+        '→' | '▶' => '>',
+        '•' | '·' | '●' => '*',
+        '█' | '■' => '#',
+        '░' | '▒' => '.',
+        '↻' => '@',
+        '⏱' => '!',
+        '…' => '.',
+        _ => return None,
+    })
+}
+
+pub trait Frontend {
+    /// Shows the current state of `app`.
+    fn draw(&mut self, app: &App) -> io::Result<()>;
+}
+
+pub struct Tui<'a>(pub &'a mut Terminal<CrosstermBackend<io::Stdout>>);
+
+impl Frontend for Tui<'_> {
+    fn draw(&mut self, app: &App) -> io::Result<()> {
+        self.0
+            .draw(|f| {
+                crate::render(f, app);
+                finish(f);
+            })
+            .map(|_| ())
+    }
+}
+
+/// One text snapshot per draw: a heading per column, one line per card, `>`
+/// on the selected card and its description below when the detail is open.
+pub struct Plain<W: Write>(pub W);
+
+impl<W: Write> Frontend for Plain<W> {
+    fn draw(&mut self, app: &App) -> io::Result<()> {
+        let today = dates::today();
+        let out = &mut self.0;
+        for (ci, col) in app.board.columns.iter().enumerate() {
+            let more = if col.meta.more { "+" } else { "" };
+            match col.meta.wip_limit {
+                Some(limit) => writeln!(out, "{} ({}{more}/{limit})", col.title, col.cards.len())?,
+                None => writeln!(out, "{} ({}{more})", col.title, col.cards.len())?,
+            }
+            for (ri, card) in col.cards.iter().enumerate() {
+                let selected = (ci, ri) == (app.col, app.row);
+                writeln!(
+                    out,
+                    "{} {} {}{}",
+                    if selected { ">" } else { " " },
+                    card.id,
+                    card.title,
+                    badge_text(card, today)
+                )?;
+                if selected && app.detail_open && !card.description.is_empty() {
+                    for line in card.description.lines() {
+                        writeln!(out, "    {line}")?;
+                    }
+                }
+            }
+            writeln!(out)?;
+        }
+        if let Some(banner) = &app.banner {
+            writeln!(out, "{banner}")?;
+        }
+        out.flush()
+    }
+}
+
+/// Renders into `page`, replacing whatever the last draw left there.
+#[derive(Default)]
+pub struct Html {
+    pub page: String,
+}
+
+impl Frontend for Html {
+    fn draw(&mut self, app: &App) -> io::Result<()> {
+        let today = dates::today();
+        let mut body = String::new();
+        for col in &app.board.columns {
+            body.push_str(&format!(
+                "<section><h2>{} <small>{}</small></h2><ul>",
+                escape(&col.title),
+                col.cards.len()
+            ));
+            for card in &col.cards {
+                body.push_str(&format!(
+                    "<li title=\"{}\"><b>{}</b> {}<small>{}</small></li>",
+                    escape(&card.description),
+                    escape(&card.id),
+                    escape(&card.title),
+                    escape(&badge_text(card, today))
+                ));
+            }
+            body.push_str("</ul></section>");
+        }
+        self.page = format!(
+            "<!doctype html><html><head><meta charset=\"utf-8\"><title>flow</title><style>\
+             body{{font-family:monospace;display:flex;gap:1em;align-items:flex-start}}\
+             section{{flex:1;border:1px solid #888;padding:0 .5em}}\
+             ul{{list-style:none;padding:0}}li{{margin:.3em 0}}small{{color:#888}}\
+             </style></head><body>{body}</body></html>"
+        );
+        Ok(())
+    }
+}
+
+fn badge_text(card: &crate::model::Card, today: i64) -> String {
+    let text: String = crate::badges(card, today, false)
+        .into_iter()
+        .map(|s| s.content.into_owned())
+        .collect();
+    if ascii() {
+        text.chars().map(|c| ascii_glyph(c).unwrap_or(c)).collect()
+    } else {
+        text
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Board, Card, Column, ColumnMeta};
+
+    fn app() -> App {
+        let mut app = App::new(Board {
+            columns: vec![Column {
+                id: "todo".to_string(),
+                title: "TODO".to_string(),
+                cards: vec![
+                    Card {
+                        id: "A-1".to_string(),
+                        title: "Fix <b>".to_string(),
+                        description: "line one".to_string(),
+                        labels: vec!["ui".to_string()],
+                        ..Card::default()
+                    },
+                    Card {
+                        id: "A-2".to_string(),
+                        title: "Two".to_string(),
+                        ..Card::default()
+                    },
+                ],
+                meta: ColumnMeta::default(),
+            }],
+            problems: vec![],
+        });
+        app.detail_open = true;
+        app
+    }
+
+    #[test]
+    fn plain_marks_the_selection_and_shows_its_detail() {
+        let mut plain = Plain(Vec::new());
+        plain.draw(&app()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(plain.0).unwrap(),
+            "TODO (2)\n> A-1 Fix <b> #ui\n    line one\n  A-2 Two\n\n"
+        );
+    }
+
+    #[test]
+    fn html_escapes_card_text() {
+        let mut html = Html::default();
+        html.draw(&app()).unwrap();
+
+        assert!(
+            html.page
+                .contains("<b>A-1</b> Fix &lt;b&gt;<small> #ui</small>")
+        );
+    }
+
+    #[test]
+    fn ascii_swaps_borders_and_glyphs_but_not_text() {
+        use ratatui::{
+            layout::Rect,
+            widgets::{Block, Paragraph, Widget},
+        };
+
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        Paragraph::new("café ▲")
+            .block(Block::bordered())
+            .render(area, &mut buf);
+        to_ascii(&mut buf);
+
+        let rows: Vec<String> = (0..3)
+            .map(|y| (0..12).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(rows, ["+----------+", "|café ^    |", "+----------+"]);
+    }
+}
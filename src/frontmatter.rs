@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+
+/// A `---`-delimited front matter block at the top of a card file.
+///
+/// Only flat `key: value` pairs (plus inline `[a, b]` or block `- a` lists) are
+/// understood. Every other line is kept verbatim so rewriting a card never
+/// drops data the user or another tool put there.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    lines: Vec<FmLine>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum FmLine {
+    Entry { key: String, value: String },
+    Other(String),
+}
+
+/// Splits a card file into its front matter (if any) and the markdown body.
+pub fn split(raw: &str) -> (Option<FrontMatter>, &str) {
+    let Some(rest) = raw
+        .strip_prefix("---\n")
+        .or_else(|| raw.strip_prefix("---\r\n"))
+    else {
+        return (None, raw);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let block = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(FrontMatter::parse(block)), body);
+        }
+        offset += line.len();
+    }
+
+    (None, raw)
+}
+
+/// Joins front matter and body back into a card file.
+pub fn join(fm: Option<&FrontMatter>, body: &str) -> String {
+    match fm {
+        Some(fm) if !fm.lines.is_empty() => format!("{}{body}", fm.render()),
+        _ => body.to_string(),
+    }
+}
+
+impl FrontMatter {
+    fn parse(block: &str) -> Self {
+        let lines = block
+            .lines()
+            .map(|line| match parse_entry(line) {
+                Some((key, value)) => FmLine::Entry {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                },
+                None => FmLine::Other(line.to_string()),
+            })
+            .collect();
+        Self { lines }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|l| match l {
+            FmLine::Entry { key: k, value } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// True if `key` is present with a non-empty value or list.
+    pub fn has(&self, key: &str) -> bool {
+        self.get(key).is_some_and(|v| !unquote(v).is_empty()) || !self.list(key).is_empty()
+    }
+
+    /// Reads `key: [a, b]`, `key: a, b`, or a block list under `key:`.
+    pub fn list(&self, key: &str) -> Vec<String> {
+        let Some(idx) = self.entry_index(key) else {
+            return vec![];
+        };
+        let FmLine::Entry { value, .. } = &self.lines[idx] else {
+            return vec![];
+        };
+
+        if value.trim().is_empty() {
+            return self.lines[idx + 1..]
+                .iter()
+                .map_while(|l| match l {
+                    FmLine::Other(s) => block_item(s),
+                    FmLine::Entry { .. } => None,
+                })
+                .map(|s| unquote(s).to_string())
+                .collect();
+        }
+
+        let v = value.trim();
+        let v = v
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(v);
+        v.split(',')
+            .map(|s| unquote(s.trim()).to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    /// Every entry, unquoted. `[a, b]` and block lists keep their items;
+    /// scalars become one-element lists.
+    pub fn fields(&self) -> BTreeMap<String, Vec<String>> {
+        let mut out = BTreeMap::new();
+        for line in &self.lines {
+            let FmLine::Entry { key, value } = line else {
+                continue;
+            };
+            let v = value.trim();
+            let items = if v.is_empty() || (v.starts_with('[') && v.ends_with(']')) {
+                self.list(key)
+            } else {
+                vec![unquote(v).to_string()]
+            };
+            out.insert(key.clone(), items);
+        }
+        out
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.entry_index(key) {
+            Some(idx) => {
+                self.drop_block_items(idx);
+                self.lines[idx] = FmLine::Entry {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                };
+            }
+            None => self.lines.push(FmLine::Entry {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        if let Some(idx) = self.entry_index(key) {
+            self.drop_block_items(idx);
+            self.lines.remove(idx);
+        }
+    }
+
+    pub fn set_list(&mut self, key: &str, items: &[String]) {
+        self.set(key, &format!("[{}]", items.join(", ")));
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::from("---\n");
+        for line in &self.lines {
+            match line {
+                FmLine::Entry { key, value } if value.is_empty() => {
+                    out.push_str(&format!("{key}:\n"))
+                }
+                FmLine::Entry { key, value } => out.push_str(&format!("{key}: {value}\n")),
+                FmLine::Other(s) => {
+                    out.push_str(s);
+                    out.push('\n');
+                }
+            }
+        }
+        out.push_str("---\n");
+        out
+    }
+
+    fn entry_index(&self, key: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|l| matches!(l, FmLine::Entry { key: k, .. } if k == key))
+    }
+
+    fn drop_block_items(&mut self, idx: usize) {
+        let n = self.lines[idx + 1..]
+            .iter()
+            .take_while(|l| matches!(l, FmLine::Other(s) if block_item(s).is_some()))
+            .count();
+        self.lines.drain(idx + 1..idx + 1 + n);
+    }
+}
+
+fn parse_entry(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with([' ', '\t', '#', '-']) {
+        return None;
+    }
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim();
+    if key.is_empty() || key.contains(' ') {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+fn block_item(line: &str) -> Option<&str> {
+    line.trim_start().strip_prefix("- ").map(str::trim)
+}
+
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+        .unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_separates_front_matter_from_body() {
+        let raw = "---\nlabels: [bug, ui]\nreviewer: ann\n---\n# Title\n\nBody\n";
+        let (fm, body) = split(raw);
+        let fm = fm.unwrap();
+
+        assert_eq!(body, "# Title\n\nBody\n");
+        assert_eq!(fm.list("labels"), vec!["bug", "ui"]);
+        assert_eq!(fm.get("reviewer"), Some("ann"));
+        assert!(fm.has("reviewer"));
+        assert!(!fm.has("completed"));
+    }
+
+    #[test]
+    fn fields_collects_scalars_and_lists() {
+        let raw = "---\ndue: \"2024-06-01\"\nlabels: [bug, ui]\nowners:\n  - ann\n---\n# T\n";
+        let fields = split(raw).0.unwrap().fields();
+
+        assert_eq!(fields["due"], vec!["2024-06-01"]);
+        assert_eq!(fields["labels"], vec!["bug", "ui"]);
+        assert_eq!(fields["owners"], vec!["ann"]);
+    }
+
+    #[test]
+    fn split_without_front_matter_returns_raw() {
+        let (fm, body) = split("# Title\n");
+        assert!(fm.is_none());
+        assert_eq!(body, "# Title\n");
+    }
+
+    #[test]
+    fn block_lists_are_read_and_replaced() {
+        let raw = "---\nlabels:\n  - bug\n  - \"ui\"\nowner: bo\n---\n# T\n";
+        let (fm, body) = split(raw);
+        let mut fm = fm.unwrap();
+        assert_eq!(fm.list("labels"), vec!["bug", "ui"]);
+
+        fm.set_list("labels", &["feature".to_string()]);
+        assert_eq!(
+            join(Some(&fm), body),
+            "---\nlabels: [feature]\nowner: bo\n---\n# T\n"
+        );
+    }
+
+    #[test]
+    fn unknown_lines_survive_round_trip() {
+        let raw = "---\n# a comment\nnested:\n  key: value\ncustom: 1\n---\nBody\n";
+        let (fm, body) = split(raw);
+
+        assert_eq!(join(fm.as_ref(), body), raw);
+    }
+}
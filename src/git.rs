@@ -0,0 +1,180 @@
+//! Git history for local boards with `git = true` in their profile: each
+//! change flow makes is committed, card history comes from `git log`, and
+//! `U` / `W` pull and push so a team can share a board through a repo.
+
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::model::Revision;
+
+/// Flow's own bookkeeping (activity log, write lock, instance claim) and
+/// the board's `.env` of secrets stay out of the shared history.
+const EXCLUDE: [&str; 3] = [
+    ":(exclude).flow",
+    ":(exclude).flow-instance",
+    ":(exclude).env",
+];
+
+/// Written by `init` so plain `git add -A` by hand skips the same files.
+const GITIGNORE: &str = ".env\n.flow/\n.flow-instance\n";
+
+/// Puts a board not yet in a repository in one of its own, with the board
+/// as it is as the first commit, so flow's commits hold only their change.
+pub fn init(root: &Path) -> io::Result<()> {
+    if git(root, &["rev-parse", "--is-inside-work-tree"]).is_ok() {
+        return Ok(());
+    }
+    git(root, &["init", "-q"])?;
+    let ignore = root.join(".gitignore");
+    if !ignore.exists() {
+        std::fs::write(ignore, GITIGNORE)?;
+    }
+    commit(root, "init", None, "Start board history").map(drop)
+}
+
+/// Commits everything changed under `root`. The subject says what happened;
+/// `Flow-Op` and `Flow-Card` trailers say it for scripts. Returns false when
+/// nothing changed.
+pub fn commit(root: &Path, op: &str, card: Option<&str>, subject: &str) -> io::Result<bool> {
+    let mut add = vec!["add", "-A", "--", "."];
+    add.extend(EXCLUDE);
+    git(root, &add)?;
+    let staged = command(root, &["diff", "--cached", "--quiet", "--", "."])
+        .status()?
+        .success();
+    if staged {
+        return Ok(false);
+    }
+    let mut trailers = format!("Flow-Op: {op}");
+    if let Some(card) = card {
+        trailers.push_str(&format!("\nFlow-Card: {card}"));
+    }
+    git(
+        root,
+        &["commit", "-q", "-m", subject, "-m", &trailers, "--", "."],
+    )?;
+    Ok(true)
+}
+
+/// Commits touching the card's file in any column or the archive, newest
+/// first. Hand edits show up too, not just flow's own.
+pub fn history(root: &Path, card_id: &str) -> io::Result<Vec<Revision>> {
+    let cols = format!(":(glob)cols/*/{card_id}.md");
+    let archive = format!(":(glob)archive/{card_id}.md");
+    let out = git(
+        root,
+        &[
+            "log",
+            "--date=format:%Y-%m-%d %H:%M",
+            "--format=%h%x1f%ad%x1f%an%x1f%s",
+            "--",
+            &cols,
+            &archive,
+        ],
+    )?;
+    Ok(out
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\x1f');
+            Some(Revision {
+                commit: parts.next()?.to_string(),
+                at: parts.next()?.to_string(),
+                author: parts.next()?.to_string(),
+                subject: parts.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Rebases the board's commits onto the remote's.
+pub fn pull(root: &Path) -> io::Result<()> {
+    git(root, &["pull", "-q", "--rebase", "--autostash"]).map(drop)
+}
+
+pub fn push(root: &Path) -> io::Result<()> {
+    git(root, &["push", "-q"]).map(drop)
+}
+
+fn command(root: &Path, args: &[&str]) -> Command {
+    let mut c = Command::new("git");
+    c.arg("-C")
+        .arg(root)
+        .args(args)
+        .stdin(Stdio::null())
+        // A credential prompt would hang under the TUI; fail instead.
+        .env("GIT_TERMINAL_PROMPT", "0");
+    c
+}
+
+/// Runs git, returning its output, or as the error its first `fatal:` or
+/// `error:` line (the hints after it are about the command line).
+fn git(root: &Path, args: &[&str]) -> io::Result<String> {
+    let out = command(root, args).output()?;
+    if out.status.success() {
+        return Ok(String::from_utf8_lossy(&out.stdout).into_owned());
+    }
+    let err = String::from_utf8_lossy(&out.stderr);
+    let mut lines = err.lines().map(str::trim).filter(|l| !l.is_empty());
+    let msg = lines
+        .clone()
+        .find_map(|l| l.strip_prefix("fatal: ").or(l.strip_prefix("error: ")))
+        .or_else(|| lines.next_back())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("git {} exited with {}", args[0], out.status));
+    Err(io::Error::other(msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    fn tmp_root() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("flow-git-{}-{nanos}", std::process::id()))
+    }
+
+    #[test]
+    fn commits_follow_a_card_across_columns() {
+        let root = tmp_root();
+        fs::create_dir_all(root.join("cols/todo")).unwrap();
+        fs::create_dir_all(root.join("cols/done")).unwrap();
+        fs::create_dir_all(root.join(".flow")).unwrap();
+        fs::write(root.join("board.txt"), "col todo\ncol done\n").unwrap();
+        git(&root, &["init", "-q"]).unwrap();
+        git(&root, &["config", "user.name", "Ada"]).unwrap();
+        git(&root, &["config", "user.email", "ada@example.com"]).unwrap();
+        assert!(commit(&root, "init", None, "Start board history").unwrap());
+        assert!(history(&root, "A-1").unwrap().is_empty());
+
+        fs::write(root.join("cols/todo/A-1.md"), "# A\n").unwrap();
+        fs::write(root.join(".flow/events.jsonl"), "{}\n").unwrap();
+        fs::write(root.join(".env"), "JIRA_TOKEN=secret\n").unwrap();
+        assert!(commit(&root, "create", Some("A-1"), "Create A-1 in todo").unwrap());
+        fs::rename(root.join("cols/todo/A-1.md"), root.join("cols/done/A-1.md")).unwrap();
+        assert!(commit(&root, "move", Some("A-1"), "Move A-1 to done").unwrap());
+        assert!(!commit(&root, "move", Some("A-1"), "Move A-1 to done").unwrap());
+
+        let history = history(&root, "A-1").unwrap();
+        let subjects: Vec<_> = history.iter().map(|r| r.subject.as_str()).collect();
+        assert_eq!(subjects, ["Move A-1 to done", "Create A-1 in todo"]);
+        assert_eq!(history[0].author, "Ada");
+        let tracked = git(&root, &["ls-files"]).unwrap();
+        assert!(!tracked.contains(".flow"));
+        assert!(!tracked.contains(".env"));
+        let body = git(&root, &["log", "-1", "--format=%b"]).unwrap();
+        assert!(body.contains("Flow-Op: move\nFlow-Card: A-1"));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+//! The blocks / blocked-by / parent links between cards, drawn as trees.
+//! Links come from the `blocks`, `blocked_by` and `parent` card fields.
+//! Other link fields, like `relates_to`, are listed on a card's detail only.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::model::Board;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Link {
+    /// `from` blocks `to`.
+    Blocks,
+    /// `from` is the parent of `to`.
+    Parent,
+}
+
+/// Card fields holding links, with how the detail view words them.
+pub const LINK_FIELDS: [(&str, &str); 6] = [
+    ("blocks", "blocks"),
+    ("blocked_by", "is blocked by"),
+    ("relates_to", "relates to"),
+    ("duplicates", "duplicates"),
+    ("duplicated_by", "is duplicated by"),
+    ("parent", "parent"),
+];
+
+/// The links a card can be given from its detail view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkKind {
+    Blocks,
+    RelatesTo,
+    Duplicates,
+}
+
+impl LinkKind {
+    pub const ALL: [LinkKind; 3] = [Self::Blocks, Self::RelatesTo, Self::Duplicates];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Blocks => "blocks",
+            Self::RelatesTo => "relates to",
+            Self::Duplicates => "duplicates",
+        }
+    }
+
+    /// The fields recording the link on the linking card and on its target.
+    pub fn fields(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Blocks => ("blocks", "blocked_by"),
+            Self::RelatesTo => ("relates_to", "relates_to"),
+            Self::Duplicates => ("duplicates", "duplicated_by"),
+        }
+    }
+}
+
+/// Records a new link on both cards, for cards on the board, until the next
+/// load brings it back from the provider.
+pub fn add_link(board: &mut Board, from: &str, to: &str, kind: LinkKind) {
+    let (forward, back) = kind.fields();
+    for (id, key, other) in [(from, forward, to), (to, back, from)] {
+        let Some(card) = board
+            .columns
+            .iter_mut()
+            .flat_map(|c| &mut c.cards)
+            .find(|c| c.id == id)
+        else {
+            continue;
+        };
+        let values = card.fields.entry(key.to_string()).or_default();
+        if !values.iter().any(|v| v == other) {
+            values.push(other.to_string());
+        }
+    }
+}
+
+/// Every link on the board, each once, however many cards mention it.
+pub fn edges(board: &Board) -> BTreeSet<(String, String, Link)> {
+    let mut edges = BTreeSet::new();
+    for card in board.columns.iter().flat_map(|c| &c.cards) {
+        let field = |key: &str| card.fields.get(key).into_iter().flatten();
+        for to in field("blocks") {
+            edges.insert((card.id.clone(), to.clone(), Link::Blocks));
+        }
+        for from in field("blocked_by") {
+            edges.insert((from.clone(), card.id.clone(), Link::Blocks));
+        }
+        for parent in field("parent") {
+            edges.insert((parent.clone(), card.id.clone(), Link::Parent));
+        }
+    }
+    edges
+}
+
+/// Text lines for the graph: one tree per root (a linked card nothing points
+/// at), then any cycles found.
+pub fn render(board: &Board) -> Vec<String> {
+    let edges = edges(board);
+    if edges.is_empty() {
+        return vec!["No blocks, blocked_by or parent links on this board.".to_string()];
+    }
+
+    let mut out_edges: BTreeMap<&str, Vec<(&str, Link)>> = BTreeMap::new();
+    let mut targets = HashSet::new();
+    for (from, to, link) in &edges {
+        out_edges.entry(from).or_default().push((to, *link));
+        targets.insert(to.as_str());
+    }
+    // Board order first, then ids that only appear in links.
+    let mut nodes: Vec<&str> = board
+        .columns
+        .iter()
+        .flat_map(|c| &c.cards)
+        .map(|c| c.id.as_str())
+        .filter(|id| out_edges.contains_key(id) || targets.contains(id))
+        .collect();
+    for (from, to, _) in &edges {
+        for id in [from.as_str(), to.as_str()] {
+            if !nodes.contains(&id) {
+                nodes.push(id);
+            }
+        }
+    }
+
+    let mut walk = Walk {
+        board,
+        out_edges,
+        visited: HashSet::new(),
+        stack: vec![],
+        lines: vec![],
+        cycles: vec![],
+    };
+    let roots = nodes.iter().filter(|id| !targets.contains(*id));
+    // Nodes left over after the roots are only reachable around a cycle.
+    for &id in roots.chain(nodes.iter()) {
+        if walk.visited.insert(id) {
+            walk.lines.push(walk.label(id));
+            walk.stack.push(id);
+            walk.children(id, "");
+            walk.stack.pop();
+        }
+    }
+
+    let mut lines = walk.lines;
+    if !walk.cycles.is_empty() {
+        lines.push(String::new());
+        lines.push("Cycles:".to_string());
+        for cycle in walk.cycles {
+            lines.push(format!("  {}", cycle.join(" → ")));
+        }
+    }
+    lines
+}
+
+struct Walk<'a> {
+    board: &'a Board,
+    out_edges: BTreeMap<&'a str, Vec<(&'a str, Link)>>,
+    visited: HashSet<&'a str>,
+    stack: Vec<&'a str>,
+    lines: Vec<String>,
+    cycles: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Walk<'a> {
+    fn label(&self, id: &str) -> String {
+        self.board
+            .columns
+            .iter()
+            .find_map(|col| {
+                let card = col.cards.iter().find(|c| c.id == id)?;
+                Some(format!("{id} {} [{}]", card.title, col.title))
+            })
+            .unwrap_or_else(|| format!("{id} (not on this board)"))
+    }
+
+    fn children(&mut self, id: &'a str, prefix: &str) {
+        let children = self.out_edges.get(id).cloned().unwrap_or_default();
+        for (i, (child, link)) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let branch = if last { "└─" } else { "├─" };
+            let verb = match link {
+                Link::Blocks => "blocks",
+                Link::Parent => "child",
+            };
+            if let Some(pos) = self.stack.iter().position(|s| s == child) {
+                let mut cycle = self.stack[pos..].to_vec();
+                cycle.push(child);
+                self.cycles.push(cycle);
+                self.lines
+                    .push(format!("{prefix}{branch} {verb} {child} ↻ cycle"));
+                continue;
+            }
+            if !self.visited.insert(child) {
+                self.lines
+                    .push(format!("{prefix}{branch} {verb} {child} (see above)"));
+                continue;
+            }
+            self.lines
+                .push(format!("{prefix}{branch} {verb} {}", self.label(child)));
+            self.stack.push(child);
+            let pad = if last { "   " } else { "│  " };
+            self.children(child, &format!("{prefix}{pad}"));
+            self.stack.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Card, Column, ColumnMeta};
+
+    fn card(id: &str, links: &[(&str, &str)]) -> Card {
+        let mut fields = BTreeMap::new();
+        for (k, v) in links {
+            fields
+                .entry(k.to_string())
+                .or_insert_with(Vec::new)
+                .push(v.to_string());
+        }
+        Card {
+            id: id.to_string(),
+            title: id.to_lowercase(),
+            fields,
+            ..Card::default()
+        }
+    }
+
+    fn board(cards: Vec<Card>) -> Board {
+        Board {
+            columns: vec![Column {
+                id: "todo".to_string(),
+                title: "TODO".to_string(),
+                cards,
+                meta: ColumnMeta::default(),
+            }],
+            problems: vec![],
+        }
+    }
+
+    #[test]
+    fn renders_trees_from_both_link_directions() {
+        let b = board(vec![
+            card("A-1", &[("blocks", "A-2")]),
+            card("A-2", &[]),
+            card("A-3", &[("blocked_by", "A-1"), ("parent", "EPIC-9")]),
+            card("A-4", &[]),
+        ]);
+
+        assert_eq!(
+            render(&b),
+            vec![
+                "A-1 a-1 [TODO]",
+                "├─ blocks A-2 a-2 [TODO]",
+                "└─ blocks A-3 a-3 [TODO]",
+                "EPIC-9 (not on this board)",
+                "└─ child A-3 (see above)",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let b = board(vec![
+            card("A-1", &[("blocks", "A-2")]),
+            card("A-2", &[("blocks", "A-3")]),
+            card("A-3", &[("blocks", "A-1")]),
+        ]);
+
+        let lines = render(&b);
+
+        assert_eq!(lines[0], "A-1 a-1 [TODO]");
+        assert!(
+            lines.contains(&"      └─ blocks A-1 ↻ cycle".to_string()),
+            "{lines:#?}"
+        );
+        assert_eq!(lines.last().unwrap(), "  A-1 → A-2 → A-3 → A-1");
+    }
+
+    #[test]
+    fn added_links_show_on_both_cards_once() {
+        let mut b = board(vec![card("A-1", &[]), card("A-2", &[])]);
+
+        add_link(&mut b, "A-1", "A-2", LinkKind::Blocks);
+        add_link(&mut b, "A-1", "A-2", LinkKind::Blocks);
+        add_link(&mut b, "A-2", "A-9", LinkKind::Duplicates);
+
+        let cards = &b.columns[0].cards;
+        assert_eq!(cards[0].values("blocks"), ["A-2"]);
+        assert_eq!(cards[1].values("blocked_by"), ["A-1"]);
+        assert_eq!(cards[1].values("duplicates"), ["A-9"]);
+        assert_eq!(edges(&b).len(), 1);
+    }
+}
@@ -0,0 +1,104 @@
+//! Daily board snapshots, so `z` can show how the board looked on a past
+//! day. Every successful load overwrites the day's file in
+//! `~/.local/share/flow/history/<board>/`, leaving the board as last seen
+//! that day; snapshots older than [`KEEP_DAYS`] are dropped.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{dates, freeze, model::Board};
+
+pub const KEEP_DAYS: i64 = 90;
+
+/// `$XDG_DATA_HOME/flow/history/<key>/`, or under `~/.local/share`.
+pub fn dir(key: &str) -> Option<PathBuf> {
+    let data = match std::env::var("XDG_DATA_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(std::env::var("HOME").ok()?).join(".local/share"),
+    };
+    Some(data.join("flow/history").join(freeze::file_name(key)))
+}
+
+fn dir_or_err(key: &str) -> io::Result<PathBuf> {
+    dir(key).ok_or_else(|| io::Error::other("cannot locate data dir (HOME unset)"))
+}
+
+fn file(dir: &Path, day: i64) -> PathBuf {
+    dir.join(format!("{}.json", dates::format_date(day)))
+}
+
+/// Saves `board` as today's snapshot.
+pub fn record(key: &str, board: &Board) -> io::Result<()> {
+    record_in(&dir_or_err(key)?, board, dates::today())
+}
+
+/// The days with a snapshot, oldest first.
+pub fn days(key: &str) -> Vec<i64> {
+    dir(key).map_or_else(Vec::new, |d| days_in(&d))
+}
+
+pub fn load(key: &str, day: i64) -> io::Result<Board> {
+    let raw = fs::read(file(&dir_or_err(key)?, day))?;
+    Ok(serde_json::from_slice(&raw)?)
+}
+
+fn record_in(dir: &Path, board: &Board, today: i64) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let board = Board {
+        columns: board.columns.clone(),
+        problems: vec![],
+    };
+    fs::write(file(dir, today), serde_json::to_vec(&board)?)?;
+    for day in days_in(dir) {
+        if day <= today - KEEP_DAYS {
+            let _ = fs::remove_file(file(dir, day));
+        }
+    }
+    Ok(())
+}
+
+fn days_in(dir: &Path) -> Vec<i64> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut days: Vec<i64> = entries
+        .filter_map(|e| {
+            let name = e.ok()?.file_name();
+            dates::parse_date(name.to_str()?.strip_suffix(".json")?)
+        })
+        .collect();
+    days.sort_unstable();
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn snapshots_are_kept_per_day_and_old_ones_pruned() {
+        let n = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("flow-history-test-{n}"));
+        fs::create_dir_all(&dir).unwrap();
+        // 2024-06-14, with snapshots from 3 and 90 days before.
+        let today = 19_888;
+        for day in [today - KEEP_DAYS, today - 3] {
+            fs::write(file(&dir, day), "{}").unwrap();
+        }
+
+        let board = Board {
+            columns: vec![],
+            problems: vec![],
+        };
+        record_in(&dir, &board, today).unwrap();
+        assert_eq!(days_in(&dir), [today - 3, today]);
+        assert!(file(&dir, today).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
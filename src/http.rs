@@ -0,0 +1,133 @@
+//! The HTTP side of the remote providers: clients tuned by a profile's
+//! `[profiles.NAME.http]`, and a cap on requests in flight to one backend
+//! from all of flow's workers together, so prefetching and background
+//! checks can't swamp an API gateway.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Condvar, Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+
+use crate::config::HttpConfig;
+
+/// Requests in flight to a backend when the profile doesn't say.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+/// A client pooling connections as `cfg` asks.
+pub fn client(cfg: &HttpConfig) -> Client {
+    let mut builder = Client::builder();
+    if let Some(n) = cfg.pool_size {
+        builder = builder.pool_max_idle_per_host(n);
+    }
+    match cfg.keep_alive_secs {
+        Some(0) => builder = builder.pool_max_idle_per_host(0),
+        Some(secs) => {
+            builder = builder
+                .pool_idle_timeout(Duration::from_secs(secs))
+                .tcp_keepalive(Duration::from_secs(secs));
+        }
+        None => {}
+    }
+    builder.build().unwrap_or_else(|_| Client::new())
+}
+
+/// How many requests may be in flight to one backend.
+pub struct Limit {
+    max: AtomicUsize,
+    in_flight: Mutex<usize>,
+    freed: Condvar,
+}
+
+/// The limit for `backend` (its base URL), shared by every provider talking
+/// to it. The profile opened last sets its size.
+pub fn limit(backend: &str, cfg: &HttpConfig) -> Arc<Limit> {
+    static LIMITS: OnceLock<Mutex<HashMap<String, Arc<Limit>>>> = OnceLock::new();
+    let max = cfg.max_in_flight.unwrap_or(DEFAULT_MAX_IN_FLIGHT).max(1);
+    let mut limits = LIMITS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let limit = limits.entry(backend.to_string()).or_insert_with(|| {
+        Arc::new(Limit {
+            max: AtomicUsize::new(max),
+            in_flight: Mutex::new(0),
+            freed: Condvar::new(),
+        })
+    });
+    limit.max.store(max, Ordering::Relaxed);
+    limit.freed.notify_all();
+    limit.clone()
+}
+
+impl Limit {
+    fn acquire(&self) -> Permit<'_> {
+        let mut n = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        while *n >= self.max.load(Ordering::Relaxed) {
+            n = self.freed.wait(n).unwrap_or_else(|e| e.into_inner());
+        }
+        *n += 1;
+        Permit(self)
+    }
+}
+
+struct Permit<'a>(&'a Limit);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.0.in_flight.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+        self.0.freed.notify_one();
+    }
+}
+
+pub trait SendWithin {
+    /// Sends once fewer than the limit's requests are in flight.
+    fn send_within(self, limit: &Limit) -> reqwest::Result<Response>;
+}
+
+impl SendWithin for RequestBuilder {
+    fn send_within(self, limit: &Limit) -> reqwest::Result<Response> {
+        let _permit = limit.acquire();
+        self.send()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn no_more_than_the_limit_are_in_flight_at_once() {
+        let cfg = HttpConfig {
+            max_in_flight: Some(2),
+            ..HttpConfig::default()
+        };
+        let limit = limit("test://limit", &cfg);
+        let peak = Arc::new(Mutex::new((0, 0)));
+        let workers: Vec<_> = (0..6)
+            .map(|_| {
+                let (limit, peak) = (limit.clone(), peak.clone());
+                thread::spawn(move || {
+                    let _permit = limit.acquire();
+                    {
+                        let mut p = peak.lock().unwrap();
+                        p.0 += 1;
+                        p.1 = p.1.max(p.0);
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                    peak.lock().unwrap().0 -= 1;
+                })
+            })
+            .collect();
+        for w in workers {
+            w.join().unwrap();
+        }
+        assert_eq!(peak.lock().unwrap().1, 2);
+    }
+}
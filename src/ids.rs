@@ -0,0 +1,136 @@
+use std::time::SystemTime;
+
+use crate::dates;
+
+/// How new card ids are chosen, configured via `FLOW_ID_STRATEGY`:
+///
+/// - `provider` (default) — let the backend pick (local: `CARD-<millis>`)
+/// - `seq:<PREFIX>` — `PREFIX-1`, `PREFIX-2`, ...
+/// - `ulid` — a ULID, sortable by creation time
+/// - `date` — `2024-06-12-a`, `2024-06-12-b`, ...
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdStrategy {
+    Provider,
+    Sequential { prefix: String },
+    Ulid,
+    Date,
+}
+
+impl IdStrategy {
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("FLOW_ID_STRATEGY") {
+            Ok(v) => Self::parse(&v),
+            Err(_) => Ok(Self::Provider),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        match s {
+            "" | "provider" => Ok(Self::Provider),
+            "ulid" => Ok(Self::Ulid),
+            "date" => Ok(Self::Date),
+            _ => match s.strip_prefix("seq:") {
+                Some(prefix) if valid_prefix(prefix) => Ok(Self::Sequential {
+                    prefix: prefix.to_string(),
+                }),
+                _ => Err(format!("invalid FLOW_ID_STRATEGY: {s}")),
+            },
+        }
+    }
+
+    /// Returns `None` for [`IdStrategy::Provider`]; the provider picks the id.
+    pub fn generate(&self, existing: &[String], now: SystemTime) -> Option<String> {
+        match self {
+            Self::Provider => None,
+            Self::Sequential { prefix } => Some(next_sequential(prefix, existing)),
+            Self::Ulid => Some(ulid::Ulid::from_datetime(now).to_string()),
+            Self::Date => Some(next_dated(now, existing)),
+        }
+    }
+}
+
+fn valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn next_sequential(prefix: &str, existing: &[String]) -> String {
+    let max = existing
+        .iter()
+        .filter_map(|id| id.strip_prefix(prefix)?.strip_prefix('-'))
+        .filter_map(|n| n.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("{prefix}-{}", max + 1)
+}
+
+fn next_dated(now: SystemTime, existing: &[String]) -> String {
+    let date = dates::format_date(dates::day_of(now));
+
+    (0..)
+        .map(|n| format!("{date}-{}", letters(n)))
+        .find(|id| !existing.contains(id))
+        .unwrap()
+}
+
+/// 0 -> "a", 25 -> "z", 26 -> "aa", ...
+fn letters(mut n: usize) -> String {
+    let mut out = Vec::new();
+    loop {
+        out.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn ids(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_accepts_known_strategies() {
+        assert_eq!(IdStrategy::parse("provider").unwrap(), IdStrategy::Provider);
+        assert_eq!(IdStrategy::parse("ulid").unwrap(), IdStrategy::Ulid);
+        assert_eq!(
+            IdStrategy::parse("seq:FLOW").unwrap(),
+            IdStrategy::Sequential {
+                prefix: "FLOW".to_string()
+            }
+        );
+        assert!(IdStrategy::parse("seq:").is_err());
+        assert!(IdStrategy::parse("random").is_err());
+    }
+
+    #[test]
+    fn sequential_continues_after_highest_existing_id() {
+        let s = IdStrategy::parse("seq:FLOW").unwrap();
+        let existing = ids(&["FLOW-2", "FLOW-10", "OTHER-99", "FLOW-x"]);
+
+        assert_eq!(s.generate(&existing, SystemTime::now()).unwrap(), "FLOW-11");
+    }
+
+    #[test]
+    fn date_ids_pick_next_free_suffix() {
+        // 2024-06-12T12:00:00Z, the 12th in nearly every time zone
+        let now = UNIX_EPOCH + Duration::from_secs(1_718_193_600);
+        let existing = ids(&["2024-06-12-a", "2024-06-12-b"]);
+
+        assert_eq!(
+            IdStrategy::Date.generate(&existing, now).unwrap(),
+            "2024-06-12-c"
+        );
+        assert_eq!(letters(26), "aa");
+    }
+}
@@ -0,0 +1,162 @@
+//! One editing flow per local board. The first instance claims
+//! `.flow-instance` in the board directory and rewrites it every
+//! [`HEARTBEAT`]; later ones open read-only until the claim goes stale or
+//! they take it over.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const HEARTBEAT: Duration = Duration::from_secs(2);
+/// A claim not refreshed for this long belongs to a flow that died.
+const STALE_SECS: u64 = 10;
+pub const FILE: &str = ".flow-instance";
+
+/// Another live instance's claim.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Holder {
+    pub pid: u32,
+}
+
+pub struct InstanceLock {
+    path: PathBuf,
+    token: String,
+    owned: bool,
+}
+
+struct Claim {
+    token: String,
+    pid: u32,
+    at: u64,
+}
+
+impl InstanceLock {
+    /// Claims the board unless a live instance already has it.
+    pub fn open(root: &Path) -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let mut lock = Self {
+            path: root.join(FILE),
+            token: format!("{}-{nanos}", std::process::id()),
+            owned: false,
+        };
+        if lock.live_claim()?.is_none() {
+            lock.claim()?;
+        }
+        Ok(lock)
+    }
+
+    pub fn owned(&self) -> bool {
+        self.owned
+    }
+
+    /// The instance holding the board, when it isn't this one.
+    pub fn holder(&self) -> io::Result<Option<Holder>> {
+        Ok(self
+            .live_claim()?
+            .filter(|c| c.token != self.token)
+            .map(|c| Holder { pid: c.pid }))
+    }
+
+    pub fn take_over(&mut self) -> io::Result<()> {
+        self.claim()
+    }
+
+    /// Refreshes this instance's claim, or claims a stale one. Returns true
+    /// when ownership changed: lost to a takeover, or gained from a flow
+    /// that went away.
+    pub fn tick(&mut self) -> io::Result<bool> {
+        let claim = self.live_claim()?;
+        let ours = claim.as_ref().is_some_and(|c| c.token == self.token);
+        if self.owned && claim.is_some() && !ours {
+            self.owned = false;
+            return Ok(true);
+        }
+        if self.owned || claim.is_none() {
+            let gained = !self.owned;
+            self.claim()?;
+            return Ok(gained);
+        }
+        Ok(false)
+    }
+
+    fn claim(&mut self) -> io::Result<()> {
+        let line = format!("{} {} {}\n", self.token, std::process::id(), now());
+        fs::write(&self.path, line)?;
+        self.owned = true;
+        Ok(())
+    }
+
+    fn live_claim(&self) -> io::Result<Option<Claim>> {
+        let raw = match fs::read_to_string(&self.path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut parts = raw.split_whitespace();
+        let claim = (|| {
+            Some(Claim {
+                token: parts.next()?.to_string(),
+                pid: parts.next()?.parse().ok()?,
+                at: parts.next()?.parse().ok()?,
+            })
+        })();
+        Ok(claim.filter(|c| now().saturating_sub(c.at) < STALE_SECS))
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if self.owned
+            && let Ok(Some(claim)) = self.live_claim()
+            && claim.token == self.token
+        {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_instance_reads_until_it_takes_over() {
+        let root = std::env::temp_dir().join(format!("flow-instance-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+
+        let mut first = InstanceLock::open(&root).unwrap();
+        let mut second = InstanceLock::open(&root).unwrap();
+        assert!(first.owned());
+        assert!(!second.owned());
+        assert_eq!(
+            second.holder().unwrap(),
+            Some(Holder {
+                pid: std::process::id()
+            })
+        );
+        assert!(!second.tick().unwrap());
+
+        second.take_over().unwrap();
+        assert!(first.tick().unwrap());
+        assert!(!first.owned());
+
+        drop(second);
+        assert!(first.tick().unwrap());
+        assert!(first.owned());
+        drop(first);
+        assert!(!root.join(FILE).exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,377 @@
+//! Key to action bindings, built from the `[keys]` config section.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crossterm::event::KeyCode;
+
+use crate::{app::Action, config::KeysConfig};
+
+/// Every bindable action: config name, action, help text.
+const ACTIONS: &[(&str, Action, &str)] = &[
+    ("focus_left", Action::FocusLeft, "focus column left"),
+    ("focus_right", Action::FocusRight, "focus column right"),
+    ("select_up", Action::SelectUp, "select card above"),
+    ("select_down", Action::SelectDown, "select card below"),
+    ("move_left", Action::MoveLeft, "move card left"),
+    ("move_right", Action::MoveRight, "move card right"),
+    ("move_to", Action::MoveTo, "move card to a chosen column"),
+    ("undo", Action::Undo, "undo last move"),
+    ("create", Action::CreateCard, "new card"),
+    ("edit", Action::Edit, "edit card in $EDITOR"),
+    ("archive", Action::Archive, "archive card"),
+    ("archived", Action::ToggleArchive, "browse archived cards"),
+    ("history", Action::History, "the board on a past day"),
+    ("detail", Action::ToggleDetail, "toggle description"),
+    ("page_up", Action::PageUp, "scroll description up a page"),
+    (
+        "page_down",
+        Action::PageDown,
+        "scroll description down a page",
+    ),
+    ("problems", Action::ToggleProblems, "problems panel"),
+    ("search", Action::Search, "search cards"),
+    (
+        "filter",
+        Action::Filter,
+        "filter cards by assignee, label or text",
+    ),
+    ("prev_match", Action::PrevMatch, "previous search match"),
+    ("sla", Action::ToggleSlaOnly, "only cards over their SLA"),
+    (
+        "quick_filters",
+        Action::QuickFilters,
+        "toggle the board's quick filters",
+    ),
+    (
+        "estimate",
+        Action::Estimate,
+        "estimate unestimated cards one by one",
+    ),
+    ("link", Action::Link, "link the open card to another"),
+    ("comment", Action::Comment, "comment on the open card"),
+    (
+        "yank",
+        Action::Yank,
+        "copy the card's id, title, link or URL",
+    ),
+    (
+        "start_work",
+        Action::StartWork,
+        "move to In Progress and run the start command",
+    ),
+    (
+        "server_search",
+        Action::ServerSearch,
+        "search the whole project and pull in a card",
+    ),
+    ("policy", Action::Policy, "show the focused column's policy"),
+    ("reorder", Action::Reorder, "reorder the column in $EDITOR"),
+    ("assign", Action::Assign, "assign or unassign the card"),
+    ("heatmap", Action::ToggleHeat, "heatmap"),
+    ("diff", Action::ToggleDiff, "diff of a remote change"),
+    ("graph", Action::ToggleGraph, "blocks / parent links"),
+    (
+        "stats",
+        Action::Stats,
+        "cards, throughput and time in column",
+    ),
+    (
+        "activity",
+        Action::Activity,
+        "recent activity; jump to a card",
+    ),
+    ("pull", Action::GitPull, "git pull the board"),
+    ("push", Action::GitPush, "git push the board"),
+    (
+        "visual",
+        Action::Visual,
+        "mark several cards to move or archive",
+    ),
+    ("refresh", Action::Refresh, "reload board"),
+    (
+        "load_more",
+        Action::LoadMore,
+        "fetch more of a capped column",
+    ),
+    ("boards", Action::SwitchBoard, "switch board"),
+    (
+        "take_over",
+        Action::TakeOver,
+        "edit a board another flow has open",
+    ),
+    ("open_mention", Action::OpenMention, "open mentioned card"),
+    ("reply_mention", Action::ReplyMention, "reply to mention"),
+    ("debug", Action::ToggleDebug, "provider metrics"),
+    ("help", Action::ToggleHelp, "this help"),
+    ("close", Action::CloseOrQuit, "close panel / quit"),
+    ("quit", Action::Quit, "quit"),
+];
+
+/// The default preset: vim keys plus arrows.
+const VIM: &[(&str, &[&str])] = &[
+    ("focus_left", &["h", "left"]),
+    ("focus_right", &["l", "right"]),
+    ("select_up", &["k", "up"]),
+    ("select_down", &["j", "down"]),
+    ("move_left", &["H"]),
+    ("move_right", &["L"]),
+    ("move_to", &["m"]),
+    ("undo", &["u"]),
+    ("create", &["n"]),
+    ("edit", &["e"]),
+    ("archive", &["d"]),
+    ("archived", &["A"]),
+    ("history", &["z"]),
+    ("detail", &["enter"]),
+    ("page_up", &["pageup"]),
+    ("page_down", &["pagedown"]),
+    ("problems", &["p"]),
+    ("search", &["/"]),
+    ("filter", &["f"]),
+    ("prev_match", &["N"]),
+    ("sla", &["!"]),
+    ("quick_filters", &["F"]),
+    ("estimate", &["E"]),
+    ("link", &["i"]),
+    ("comment", &["c"]),
+    ("yank", &["y"]),
+    ("start_work", &["S"]),
+    ("server_search", &["s"]),
+    ("policy", &["P"]),
+    ("reorder", &["O"]),
+    ("assign", &["a"]),
+    ("heatmap", &["w"]),
+    ("diff", &["D"]),
+    ("graph", &["G"]),
+    ("stats", &["t"]),
+    ("activity", &["g"]),
+    ("pull", &["U"]),
+    ("push", &["W"]),
+    ("visual", &["v"]),
+    ("refresh", &["r"]),
+    ("load_more", &["+"]),
+    ("boards", &["b"]),
+    ("take_over", &["T"]),
+    ("open_mention", &["o"]),
+    ("reply_mention", &["R"]),
+    ("debug", &["f12"]),
+    ("help", &["?"]),
+    ("close", &["esc"]),
+    ("quit", &["q"]),
+];
+
+/// Arrow keys only for navigation; letters are free for custom bindings.
+const ARROWS: &[(&str, &[&str])] = &[
+    ("focus_left", &["left"]),
+    ("focus_right", &["right"]),
+    ("select_up", &["up"]),
+    ("select_down", &["down"]),
+    ("move_left", &["<"]),
+    ("move_right", &[">"]),
+];
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    keys: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&KeysConfig::default()).expect("default bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// Starts from the preset, then replaces the keys of every action the
+    /// config lists. Two actions on one key is an error.
+    pub fn from_config(cfg: &KeysConfig) -> Result<Self, String> {
+        let mut bindings: BTreeMap<&str, Vec<String>> = VIM
+            .iter()
+            .map(|(name, keys)| (*name, keys.iter().map(|k| k.to_string()).collect()))
+            .collect();
+        match cfg.preset.as_deref() {
+            None | Some("vim") => {}
+            Some("arrows") => {
+                for (name, keys) in ARROWS {
+                    bindings.insert(name, keys.iter().map(|k| k.to_string()).collect());
+                }
+            }
+            Some(other) => return Err(format!("unknown key preset {other:?} (vim, arrows)")),
+        }
+        for (name, keys) in &cfg.bind {
+            let Some((known, _, _)) = ACTIONS.iter().find(|(n, _, _)| n == name) else {
+                return Err(format!("unknown action {name:?} in [keys]"));
+            };
+            bindings.insert(known, keys.clone());
+        }
+
+        let mut keys = HashMap::new();
+        let mut owner: HashMap<KeyCode, &str> = HashMap::new();
+        for (name, list) in &bindings {
+            let action = action_named(name);
+            for key in list {
+                let code =
+                    parse_key(key).ok_or_else(|| format!("unknown key {key:?} for {name}"))?;
+                if let Some(other) = owner.insert(code, name)
+                    && other != *name
+                {
+                    return Err(format!("key {key:?} is bound to both {other} and {name}"));
+                }
+                keys.insert(code, action);
+            }
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn get(&self, code: KeyCode) -> Option<Action> {
+        self.keys.get(&code).copied()
+    }
+
+    /// The keys bound to `action`, formatted for display, in a stable order.
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .keys
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(k, _)| format_key(*k))
+            .collect();
+        keys.sort_by_key(|k| (k.chars().count() > 1, k.clone()));
+        keys
+    }
+
+    /// `(keys, description)` for every action, for the `?` overlay.
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        ACTIONS
+            .iter()
+            .map(|(_, action, desc)| {
+                let keys = self.keys_for(*action);
+                let keys = if keys.is_empty() {
+                    "(unbound)".to_string()
+                } else {
+                    keys.join(" / ")
+                };
+                (keys, *desc)
+            })
+            .collect()
+    }
+
+    /// One-line hint for the footer.
+    pub fn footer(&self) -> String {
+        [
+            (Action::MoveLeft, Action::MoveRight, "move"),
+            (Action::CreateCard, Action::CreateCard, "new"),
+            (Action::Edit, Action::Edit, "edit"),
+            (Action::Search, Action::Search, "search"),
+            (Action::ToggleHelp, Action::ToggleHelp, "keys"),
+            (Action::Quit, Action::Quit, "quit"),
+        ]
+        .iter()
+        .filter_map(|(a, b, label)| {
+            let first = self.keys_for(*a).into_iter().next()?;
+            if a == b {
+                return Some(format!("{first} {label}"));
+            }
+            let second = self.keys_for(*b).into_iter().next()?;
+            Some(format!("{first}/{second} {label}"))
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+    }
+}
+
+fn action_named(name: &str) -> Action {
+    ACTIONS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, a, _)| *a)
+        .expect("binding names come from ACTIONS")
+}
+
+fn parse_key(s: &str) -> Option<KeyCode> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    Some(match s.to_ascii_lowercase().as_str() {
+        "esc" | "escape" => KeyCode::Esc,
+        "enter" | "return" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        f => KeyCode::F(f.strip_prefix('f')?.parse().ok()?),
+    })
+}
+
+fn format_key(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        other => format!("{other:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(preset: Option<&str>, bind: &[(&str, &[&str])]) -> KeysConfig {
+        KeysConfig {
+            preset: preset.map(str::to_string),
+            bind: bind
+                .iter()
+                .map(|(a, keys)| (a.to_string(), keys.iter().map(|k| k.to_string()).collect()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn defaults_match_vim_bindings() {
+        let keys = Keymap::default();
+
+        assert_eq!(keys.get(KeyCode::Char('H')), Some(Action::MoveLeft));
+        assert_eq!(keys.get(KeyCode::Left), Some(Action::FocusLeft));
+        assert_eq!(keys.get(KeyCode::F(12)), Some(Action::ToggleDebug));
+        assert_eq!(keys.keys_for(Action::FocusLeft), vec!["h", "←"]);
+    }
+
+    #[test]
+    fn config_replaces_an_actions_keys() {
+        let keys = Keymap::from_config(&cfg(Some("arrows"), &[("quit", &["x"])])).unwrap();
+
+        assert_eq!(keys.get(KeyCode::Char('h')), None);
+        assert_eq!(keys.get(KeyCode::Char('>')), Some(Action::MoveRight));
+        assert_eq!(keys.get(KeyCode::Char('x')), Some(Action::Quit));
+        assert_eq!(keys.get(KeyCode::Char('q')), None);
+    }
+
+    #[test]
+    fn conflicts_and_unknown_names_are_errors() {
+        let err = Keymap::from_config(&cfg(None, &[("refresh", &["q"])])).unwrap_err();
+        assert!(err.contains("bound to both"), "{err}");
+
+        assert!(Keymap::from_config(&cfg(None, &[("fly", &["f"])])).is_err());
+        assert!(Keymap::from_config(&cfg(None, &[("quit", &["ctrl-q"])])).is_err());
+        assert!(Keymap::from_config(&cfg(Some("emacs"), &[])).is_err());
+    }
+}
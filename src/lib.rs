@@ -0,0 +1,53 @@
+//! Board providers, the card store and the board event bus behind the `flow`
+//! binary, for embedding in other tools.
+//!
+//! ```no_run
+//! let mut provider = flow::provider::from_env();
+//! let events = flow::events::subscribe();
+//! let board = provider.load_board().expect("board");
+//! provider.move_card(&board.columns[0].cards[0].id, "done").ok();
+//! for event in events.try_iter() {
+//!     println!("{event}");
+//! }
+//! ```
+
+pub mod activity;
+pub mod backup;
+pub mod bulk;
+pub mod card_template;
+pub mod clipboard;
+pub mod command;
+pub mod config;
+pub mod dates;
+pub mod diff;
+pub mod events;
+pub mod exchange;
+pub mod filter;
+pub mod freeze;
+pub mod frontmatter;
+pub mod git;
+pub mod graph;
+pub mod history;
+pub mod http;
+pub mod ids;
+pub mod instance;
+pub mod log;
+pub mod matcher;
+pub mod model;
+pub mod offline;
+pub mod prefetch;
+pub mod provider;
+pub mod provider_demo;
+pub mod provider_github;
+pub mod provider_jira;
+pub mod provider_linear;
+pub mod provider_local;
+pub mod provider_metrics;
+pub mod reorder;
+pub mod sla;
+pub mod sort;
+pub mod stats;
+pub mod store_fs;
+pub mod template;
+pub mod watch;
+pub mod worker;
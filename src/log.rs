@@ -0,0 +1,19 @@
+use std::{fs::OpenOptions, io::Write, sync::Mutex};
+
+use crate::dates;
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Appends a line to the file named by `FLOW_LOG`. Logging is off when unset;
+/// write failures are ignored so logging can never break the UI.
+pub fn write(line: &str) {
+    let Ok(path) = std::env::var("FLOW_LOG") else {
+        return;
+    };
+    let ts = dates::now_millis();
+
+    let _guard = LOCK.lock();
+    if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(f, "{ts} {line}");
+    }
+}
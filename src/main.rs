@@ -1,289 +1,1767 @@
 use std::{
     collections::VecDeque,
-    io, panic,
-    path::Path,
+    fs, io,
+    path::{Path, PathBuf},
     process::Command,
-    sync::mpsc::{self, Receiver, TryRecvError},
-    thread,
-    time::Duration,
+    sync::mpsc::TryRecvError,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange,
+        Event, KeyCode, KeyEventKind,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{
     Frame, Terminal,
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Wrap,
+    },
 };
 
 mod app;
-mod model;
-mod provider;
-mod provider_jira;
-mod provider_local;
-mod store_fs;
+mod cli;
+mod frontend;
+mod keymap;
+mod onboarding;
+mod overview;
+mod picker;
+mod render_md;
+mod rpc;
+mod serve;
+mod show;
 
 use app::{Action, App};
+use clap::Parser;
+use flow::{
+    activity, backup, bulk, card_template, clipboard, command, config, dates, diff, events,
+    exchange, filter, freeze, git, graph, history, instance, log, matcher, model, offline,
+    prefetch, provider, provider_local, provider_metrics, reorder, sla, stats, store_fs, template,
+    watch, worker,
+};
+use frontend::Frontend;
+use keymap::Keymap;
+use worker::{BatchOp, Job, MoveOutcome, Reply, Worker};
 
-fn help_text() -> &'static str {
-    "h/l or ←/→ focus  j/k or ↑/↓ select  H/L move  n new  e edit  Enter detail  r refresh  Esc close/quit  q quit"
-}
-
-fn action_from_key(code: KeyCode) -> Option<Action> {
-    Some(match code {
-        KeyCode::Char('q') => Action::Quit,
-        KeyCode::Esc => Action::CloseOrQuit,
-
-        KeyCode::Char('h') | KeyCode::Left => Action::FocusLeft,
-        KeyCode::Char('l') | KeyCode::Right => Action::FocusRight,
-
-        KeyCode::Char('j') | KeyCode::Down => Action::SelectDown,
-        KeyCode::Char('k') | KeyCode::Up => Action::SelectUp,
-
-        KeyCode::Char('H') => Action::MoveLeft,
-        KeyCode::Char('L') => Action::MoveRight,
-
-        KeyCode::Enter => Action::ToggleDetail,
-        KeyCode::Char('r') => Action::Refresh,
+fn main() -> io::Result<()> {
+    let args = cli::Cli::parse();
+    if let Some(name) = &args.board
+        && let Err(e) = config::choose_profile(name)
+    {
+        std::process::exit(cli::Failure::config(e).report(args.error_format));
+    }
+    if let Some(cli::Command::Workspace(words)) = &args.command {
+        let chosen = match words.as_slice() {
+            [name] => config::choose_workspace(name),
+            _ => Err(format!("unexpected arguments: {}", words.join(" "))),
+        };
+        if let Err(msg) = chosen {
+            let failure = cli::Failure {
+                class: cli::ErrorClass::Usage,
+                msg,
+            };
+            std::process::exit(failure.report(args.error_format));
+        }
+    }
+    let ui = config::load().map(|cfg| cfg.ui).unwrap_or_default();
+    if let Err(e) = dates::configure(ui.timezone.as_deref(), ui.dates) {
+        std::process::exit(cli::Failure::config(e).report(args.error_format));
+    }
+    frontend::configure(ui.charset);
+    let mut home = false;
+    let view = match args.command {
+        Some(cli::Command::View { format }) => match cli::read_view(format) {
+            Ok(board) => Some(board),
+            Err(e) => std::process::exit(e.report(args.error_format)),
+        },
+        Some(cli::Command::Home) => {
+            home = true;
+            None
+        }
+        Some(cli::Command::Workspace(_)) => None,
+        Some(cmd) => {
+            if let Err(e) = cli::run(cmd) {
+                std::process::exit(e.report(args.error_format));
+            }
+            return Ok(());
+        }
+        None => None,
+    };
 
-        _ => return None,
-    })
-}
+    if std::env::var("TERM").is_ok_and(|t| t == "dumb") {
+        return run_plain(view);
+    }
 
-fn main() -> io::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableFocusChange,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let res = run(&mut terminal);
+    let res = match view {
+        Some(board) => run_view(&mut terminal, board),
+        None => run_boards(&mut terminal, home),
+    };
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableFocusChange,
+        DisableBracketedPaste
+    )?;
     terminal.show_cursor()?;
     res
 }
 
-fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
-    let mut provider = provider::from_env();
+/// Dumb terminals can't take the TUI, so the board is printed once instead.
+fn run_plain(board: Option<model::Board>) -> io::Result<()> {
+    let board = match board {
+        Some(b) => b,
+        None => {
+            config::resolve().map_err(io::Error::other)?;
+            provider::from_env()
+                .load_board()
+                .map_err(|e| io::Error::other(e.to_string()))?
+        }
+    };
+    let mut app = App::new(board);
+    app.focus_first_non_empty();
+    frontend::Plain(io::stdout().lock()).draw(&app)
+}
+
+/// `flow view`: browse a piped-in board. Keys come from the tty since stdin
+/// was the pipe; anything that would write to a provider is ignored.
+fn run_view(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    board: model::Board,
+) -> io::Result<()> {
+    let mut app = App::new(board);
+    app.focus_first_non_empty();
+    app.banner = Some("Read-only view".to_string());
+    if let Ok(cfg) = config::load() {
+        app.rows = cfg.ui.rows;
+        if let Ok(keymap) = Keymap::from_config(&cfg.keys) {
+            app.keymap = keymap;
+        }
+    }
+
+    loop {
+        frontend::Tui(terminal).draw(&app)?;
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        if let Event::Key(k) = event::read()?
+            && k.kind == KeyEventKind::Press
+            && let Some(a) = app.keymap.get(k.code)
+            && !matches!(
+                a,
+                Action::MoveLeft
+                    | Action::MoveRight
+                    | Action::MoveTo
+                    | Action::Undo
+                    | Action::SwitchBoard
+                    | Action::ReplyMention
+                    | Action::Archive
+                    | Action::ToggleArchive
+                    | Action::History
+                    | Action::LoadMore
+                    | Action::QuickFilters
+                    | Action::TakeOver
+                    | Action::Estimate
+                    | Action::Link
+                    | Action::Comment
+                    | Action::StartWork
+                    | Action::Yank
+                    | Action::ServerSearch
+                    | Action::Reorder
+                    | Action::Assign
+                    | Action::Stats
+                    | Action::Activity
+                    | Action::GitPull
+                    | Action::GitPush
+                    | Action::Visual
+                    | Action::Filter
+                    | Action::CreateCard
+                    | Action::Refresh
+                    | Action::Edit
+            )
+            && app.apply(a)
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// Opens the configured board, or the overview first when asked for or when
+/// several profiles exist and none is the default. Quitting a board opened
+/// from the overview goes back to it.
+fn run_boards(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, home: bool) -> io::Result<()> {
+    if onboarding::needs_onboarding()
+        && let onboarding::Outcome::Quit = onboarding::run(terminal)?
+    {
+        return Ok(());
+    }
+
+    let cfg = config::load().unwrap_or_default();
+    let mut overview = (home || (cfg.profiles.len() > 1 && cfg.default_profile.is_none()))
+        .then(|| overview::Overview::new(&cfg));
+    let mut board = None;
+    if let Some(o) = overview.as_mut() {
+        match overview::run(terminal, o)? {
+            Some(b) => board = Some(b),
+            None => return Ok(()),
+        }
+    }
+    loop {
+        match run(terminal, board.take())? {
+            Exit::Switch(b) => board = Some(*b),
+            Exit::Quit => match overview.as_mut() {
+                Some(o) => match overview::run(terminal, o)? {
+                    Some(b) => board = Some(b),
+                    None => return Ok(()),
+                },
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+enum Exit {
+    Quit,
+    /// `b` picked another board.
+    Switch(Box<config::BoardHandle>),
+}
+
+/// Runs one board: `board`, or the env/config selection when `None`.
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    board: Option<config::BoardHandle>,
+) -> io::Result<Exit> {
+    let cfg = config::load().unwrap_or_default();
+    let current = board
+        .as_ref()
+        .map(|b| b.name.clone())
+        .or_else(|| cfg.default_profile.clone());
+    // A configured board was already resolved.
+    let checked = match board {
+        Some(_) => Ok(()),
+        None => config::resolve().map(|_| ()),
+    };
+    let profile = match &board {
+        Some(b) => b.profile.clone(),
+        None => config::active_profile(),
+    };
+    let is_jira = profile.provider() == "jira";
+    // Only local boards need it; Jira takes concurrent edits itself.
+    let mut instance = (!matches!(profile.provider(), "jira" | "linear" | "github" | "demo"))
+        .then(|| provider_local::LocalProvider::from_profile(&profile))
+        .and_then(|p| instance::InstanceLock::open(p.root()).ok());
+    let lock_key = freeze::board_key(&profile);
+    // Remote boards stay usable through an outage from their last load.
+    let cache = matches!(profile.provider(), "jira" | "linear" | "github")
+        .then(|| offline::cache_path(&lock_key))
+        .flatten();
+    let make = move || {
+        let inner = match &board {
+            Some(b) => provider::from_profile(&b.profile),
+            None => provider::from_env(),
+        };
+        match &cache {
+            Some(path) => Box::new(offline::Offline::new(inner, Some(path.clone()))),
+            None => inner,
+        }
+    };
+    // Before the first load, so starting offline is seen.
+    let board_events = events::subscribe();
+    let mut provider = make();
 
-    let board = match provider.load_board() {
+    let loaded = checked
+        .map_err(|e| format!("config: {e}"))
+        .and_then(|_| provider.load_board().map_err(|e| e.to_string()));
+    let board = match loaded {
         Ok(b) => b,
         Err(e) => {
-            let mut app = App::new(model::Board { columns: vec![] });
+            let mut app = App::new(model::Board {
+                columns: vec![],
+                problems: vec![],
+            });
             app.banner = Some(format!("Load failed: {e}"));
             loop {
-                terminal.draw(|f| render(f, &app))?;
-                if event::poll(Duration::from_millis(50))? {
-                    if let Event::Key(k) = event::read()? {
-                        if k.kind == KeyEventKind::Press
-                            && matches!(k.code, KeyCode::Char('q') | KeyCode::Esc)
-                        {
-                            break;
-                        }
-                    }
+                frontend::Tui(terminal).draw(&app)?;
+                if event::poll(Duration::from_millis(50))?
+                    && let Event::Key(k) = event::read()?
+                    && k.kind == KeyEventKind::Press
+                    && matches!(k.code, KeyCode::Char('q') | KeyCode::Esc)
+                {
+                    break;
                 }
             }
-            return Ok(());
+            return Ok(Exit::Quit);
         }
     };
 
     let mut app = App::new(board);
     app.focus_first_non_empty();
-    type MoveOutcome = Result<Option<model::Board>, String>;
-    let mut move_rx: Option<Receiver<MoveOutcome>> = None;
-    let mut move_queue: VecDeque<(String, String)> = VecDeque::new();
+    app.source = format!(
+        "{} · {}",
+        profile.provider(),
+        current.clone().unwrap_or_else(|| lock_key
+            .split_once(':')
+            .map_or(&*lock_key, |(_, k)| k)
+            .to_string())
+    );
+    app.loaded_at = Some(dates::now_secs());
+    app.lock = freeze::active(&lock_key);
+    app.archive_effects = provider::archive_effects(&profile);
+    app.templates = card_template::dir(&profile)
+        .map(|d| card_template::load(&d))
+        .unwrap_or_default();
+    if let Some(lock) = instance.as_ref().filter(|l| !l.owned()) {
+        app.read_only = true;
+        app.banner = Some(match lock.holder() {
+            Ok(Some(h)) => format!(
+                "Read-only: flow (pid {}) has this board open; T takes over",
+                h.pid
+            ),
+            _ => "Read-only: another flow has this board open; T takes over".to_string(),
+        });
+    }
+    let mut last_beat = Instant::now();
+    let mentions_every = cfg.ui.mentions_interval().filter(|_| is_jira);
+    // Its own worker, so a slow scan never holds up moves.
+    let inbox = mentions_every.map(|_| Worker::spawn(make.clone()));
+    // Jira comments and local front matter go beyond the board load; they're
+    // fetched on a third worker, ahead of the popup opening.
+    let details = (!matches!(profile.provider(), "linear" | "github" | "demo"))
+        .then(|| Worker::spawn(make.clone()));
+    let mut resting_on: (Option<String>, Instant) = (None, Instant::now());
+    // The demo board is made up afresh each run, so it has no history.
+    let keep_history = profile.provider() != "demo";
+    let mut snapshot_due = true;
+    let backup_root = backup::local_root(&profile);
+    let git_root = backup_root.clone().filter(|_| profile.git);
+    let mut last_backup_check: Option<Instant> = None;
+    let worker = Worker::spawn(make);
+    let mut last_mentions: Option<Instant> = None;
+    let mut checking_mentions = false;
+    let mut moving = false;
+    let mut move_queue: VecDeque<QueuedMove> = VecDeque::new();
+    // A queued move held back because its card moved meanwhile, with the
+    // board that showed it.
+    let mut conflict: Option<(QueuedMove, model::Board)> = None;
+    // The banner on screen and since when, so it can fade.
+    let mut shown_banner: (Option<String>, Instant) = (None, Instant::now());
     const MAX_QUEUE_SIZE: usize = 64;
     let mut quitting = false;
+    let mut switch_to = None;
+    let ui = cfg.ui.clone();
+    app.rows = ui.rows;
+    let watcher = backup_root
+        .as_deref()
+        .filter(|_| ui.watch)
+        .and_then(|root| {
+            watch::watch(root)
+                .map_err(|e| log::write(&format!("watch {}: {e}", root.display())))
+                .ok()
+        });
+    // When the board's files last changed outside a reload; the reload waits
+    // for them to settle, as editors and `git` write in bursts.
+    let mut changed_at: Option<Instant> = None;
+    const WATCH_SETTLE: Duration = Duration::from_millis(250);
+    match Keymap::from_config(&cfg.keys) {
+        Ok(keymap) => app.keymap = keymap,
+        Err(e) => app.banner = Some(format!("[keys] ignored: {e}")),
+    }
+    // What failed to load is in the problems panel; the rest of the board is up.
+    let warnings = app.board.problems.len();
+    if warnings > 0 && app.banner.is_none() {
+        let key = app.keymap.keys_for(Action::ToggleProblems);
+        app.banner = Some(format!(
+            "Loaded with {warnings} warning{}; {} lists them",
+            if warnings == 1 { "" } else { "s" },
+            key.first().map_or("the problems panel", String::as_str)
+        ));
+    }
+    // The workspace's view comes back whenever its board is opened.
+    if let Some(ws) = config::workspace()
+        && ws.board.is_none_or(|b| current.as_ref() == Some(&b))
+    {
+        app.sla_only = ws.sla_only;
+        app.heat = ws.heatmap;
+        if let Some(query) = ws.search {
+            let mut search = app::Search::new(&query, app.search_mode);
+            search.editing = false;
+            app.search = Some(search);
+            app.update_search();
+        }
+    }
+    let mut focused = true;
+    let mut refreshing = false;
+    let auto_refresh = ui.refresh_interval();
+    // Offline boards retry even with auto-refresh off, to send queued moves.
+    const OFFLINE_RETRY: Duration = Duration::from_secs(30);
+    // How long the selection rests on a card before its details are fetched.
+    const PREFETCH_AFTER: Duration = Duration::from_millis(400);
+    let mut last_refresh = Instant::now();
 
     loop {
-        if let Some(rx) = move_rx.as_ref() {
-            match rx.try_recv() {
-                Ok(Ok(Some(board))) => {
-                    app.board = board;
-                    app.clamp();
-                    app.banner = Some(
-                        "Move failed: reloaded board (optimistic state corrected)".to_string(),
-                    );
+        loop {
+            let reply = match worker.try_recv() {
+                Ok(reply) => reply,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if moving {
+                        app.banner = Some("Move failed: worker disconnected".to_string());
+                        move_queue.clear();
+                        app.pending.clear();
+                        app.undo.clear();
+                        app.batch = None;
+                        moving = false;
+                        update_quit_banner(&mut app, quitting, &move_queue, moving);
+                    }
+                    refreshing = false;
+                    break;
+                }
+            };
+            match reply {
+                Reply::Moved(MoveOutcome::Failed {
+                    card_id,
+                    msg,
+                    board: Some(board),
+                }) => {
+                    app.reconcile(board, &card_id);
+                    app.banner = Some(format!("Move failed: {msg} (board reloaded)"));
                     move_queue.clear(); // Drop queued moves after a failure to avoid compounding errors.
-                    move_rx = None;
-                    update_quit_banner(&mut app, quitting, &move_queue, move_rx.is_some());
+                    app.pending.clear();
+                    app.undo.clear();
+                    moving = false;
+                    worker.cancel(); // A queued refresh predates the reload above.
+                    refreshing = false;
+                    update_quit_banner(&mut app, quitting, &move_queue, moving);
                 }
-                Ok(Ok(None)) => {
-                    move_rx = None;
-                    if let Some((card_id, dst)) = move_queue.pop_front() {
-                        move_rx = Some(spawn_move(card_id, dst));
-                        app.banner = Some(format!("Moving... ({} queued)", move_queue.len()));
-                    } else {
-                        app.banner = None;
+                Reply::Moved(
+                    outcome @ (MoveOutcome::Done { .. } | MoveOutcome::Reconciled { .. }),
+                ) => {
+                    if let MoveOutcome::Done { card_id } | MoveOutcome::Reconciled { card_id, .. } =
+                        &outcome
+                    {
+                        app.pending.remove(card_id);
                     }
-                    update_quit_banner(&mut app, quitting, &move_queue, move_rx.is_some());
+                    moving = send_next(&worker, &mut move_queue, &mut app);
+                    if !moving {
+                        if let MoveOutcome::Reconciled { card_id, board } = outcome {
+                            app.reconcile(board, &card_id);
+                        }
+                        // Only the progress note; e.g. a start command's outcome stays.
+                        if app
+                            .banner
+                            .as_deref()
+                            .is_some_and(|b| b.starts_with("Moving"))
+                        {
+                            app.banner = None;
+                        }
+                    }
+                    update_quit_banner(&mut app, quitting, &move_queue, moving);
                 }
-                Ok(Err(msg)) => {
+                Reply::Moved(MoveOutcome::Conflict {
+                    card_id,
+                    from,
+                    dst,
+                    found,
+                    board,
+                }) => {
+                    moving = false;
+                    let m = QueuedMove {
+                        card_id,
+                        from: Some(from),
+                        dst,
+                    };
+                    if quitting {
+                        // Nobody is left to choose, so settle on the provider's board.
+                        reload_after_conflict(&mut app, &mut move_queue, &m, board);
+                        update_quit_banner(&mut app, quitting, &move_queue, moving);
+                        continue;
+                    }
+                    app.banner = Some(conflict_banner(&m, found.as_deref(), &board));
+                    conflict = Some((m, board));
+                }
+                Reply::Moved(MoveOutcome::Failed {
+                    msg, board: None, ..
+                }) => {
                     app.banner = Some(format!("Move failed: {msg}"));
                     move_queue.clear();
-                    move_rx = None;
-                    update_quit_banner(&mut app, quitting, &move_queue, move_rx.is_some());
+                    app.pending.clear();
+                    app.undo.clear();
+                    moving = false;
+                    update_quit_banner(&mut app, quitting, &move_queue, moving);
                 }
-                Err(TryRecvError::Empty) => {}
-                Err(TryRecvError::Disconnected) => {
-                    app.banner = Some("Move failed: worker disconnected".to_string());
-                    move_rx = None;
-                    update_quit_banner(&mut app, quitting, &move_queue, move_rx.is_some());
+                Reply::Refreshed(Ok(board)) if !moving && move_queue.is_empty() => {
+                    app.refresh_board(board);
+                    snapshot_due = true;
+                    app.lock = freeze::active(&lock_key);
+                    refreshing = false;
+                    // Comments may have changed too.
+                    app.details.clear();
+                    app.fetching = None;
+                    app.detail_failed = None;
+                    if let Some(details) = &details {
+                        details.cancel();
+                    }
+                }
+                Reply::Refreshed(Ok(_)) => refreshing = false, // A move started meanwhile; its reload wins.
+                Reply::Refreshed(Err(msg)) => {
+                    app.banner = Some(format!("Refresh failed: {msg}"));
+                    refreshing = false;
+                }
+                Reply::BatchProgress { done } => {
+                    if let Some(batch) = &app.batch {
+                        let mut banner = batch.progress(done);
+                        if !move_queue.is_empty() {
+                            banner.push_str(&format!(" ({} queued)", move_queue.len()));
+                        }
+                        app.banner = Some(banner);
+                    }
+                }
+                Reply::Batched { done, error, board } => {
+                    if let Some(batch) = app.batch.take() {
+                        for card_id in &batch.card_ids {
+                            app.pending.remove(card_id);
+                        }
+                        app.banner = Some(batch.finished(done.len(), error.as_deref()));
+                    }
+                    app.undo
+                        .retain(|u| !done.iter().any(|id| id == u.card_id()));
+                    // Moves queued behind the batch reload when they're done.
+                    moving = send_next(&worker, &mut move_queue, &mut app);
+                    match board {
+                        _ if moving => {}
+                        Some(board) => {
+                            app.reconcile(board, done.first().map_or("", String::as_str))
+                        }
+                        None if !refreshing => {
+                            worker.submit(Job::Refresh);
+                            refreshing = true;
+                            last_refresh = Instant::now();
+                        }
+                        None => {}
+                    }
+                    update_quit_banner(&mut app, quitting, &move_queue, moving);
                 }
+                // Only the inbox and details workers are sent these.
+                Reply::Mentions(_) | Reply::Detail { .. } => {}
             }
         }
 
-        if quitting && move_rx.is_none() && move_queue.is_empty() {
-            return Ok(());
+        app.record_events(board_events.try_iter());
+        // After the events, so a board served from the offline cache isn't
+        // taken for today's.
+        if std::mem::take(&mut snapshot_due)
+            && keep_history
+            && !app.offline
+            && let Err(e) = history::record(&lock_key, &app.board)
+        {
+            log::write(&format!("history snapshot: {e}"));
+        }
+        if keep_history
+            && !app.offline
+            && last_backup_check.is_none_or(|at| at.elapsed() >= backup::CHECK_EVERY)
+        {
+            last_backup_check = Some(Instant::now());
+            if let Err(e) =
+                backup::run_if_due(&cfg.backup, &lock_key, &app.board, backup_root.as_deref())
+            {
+                log::write(&format!("backup: {e}"));
+            }
         }
 
-        terminal.draw(|f| render(f, &app))?;
+        if let Some(inbox) = &inbox {
+            while let Ok(reply) = inbox.try_recv() {
+                if let Reply::Mentions(res) = reply {
+                    checking_mentions = false;
+                    match res {
+                        Ok(mentions) => app.add_mentions(mentions),
+                        Err(msg) => log::write(&format!("mentions: {msg}")),
+                    }
+                }
+            }
+            if let Some(every) = mentions_every
+                && !checking_mentions
+                && last_mentions.is_none_or(|t| t.elapsed() >= every)
+            {
+                // The first check looks back a day; later ones overlap slightly.
+                let minutes = match last_mentions {
+                    None => 24 * 60,
+                    Some(_) => every.as_secs().div_ceil(60) + 1,
+                };
+                inbox.submit(Job::Mentions { minutes });
+                checking_mentions = true;
+                last_mentions = Some(Instant::now());
+            }
+        }
 
-        if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(k) = event::read()? {
-                if k.kind == KeyEventKind::Press {
-                    if matches!(k.code, KeyCode::Char('n')) {
-                        if quitting {
+        if let Some(details) = &details {
+            while let Ok(reply) = details.try_recv() {
+                if let Reply::Detail { card_id, detail } = reply {
+                    app.fetching = None;
+                    match detail {
+                        Ok(d) => app.details.insert(card_id, d),
+                        Err(msg) => {
+                            log::write(&format!("card detail {card_id}: {msg}"));
+                            app.detail_failed = Some((card_id, msg));
+                        }
+                    }
+                }
+            }
+            let selected = app.selected_card_id();
+            if selected != resting_on.0 {
+                resting_on = (selected.clone(), Instant::now());
+            }
+            if let Some(id) = selected {
+                if app.detail_open {
+                    app.details.touch(&id);
+                }
+                if app.fetching.is_none()
+                    && !app.details.contains(&id)
+                    && app
+                        .detail_failed
+                        .as_ref()
+                        .is_none_or(|(failed, _)| *failed != id)
+                    && (app.detail_open || resting_on.1.elapsed() >= PREFETCH_AFTER)
+                {
+                    details.submit(Job::Detail {
+                        card_id: id.clone(),
+                    });
+                    app.fetching = Some(id);
+                }
+            }
+        }
+
+        if watcher.as_ref().is_some_and(|w| w.changed()) {
+            changed_at = Some(Instant::now());
+        }
+        if changed_at.is_some_and(|at| at.elapsed() >= WATCH_SETTLE)
+            && !refreshing
+            && !moving
+            && move_queue.is_empty()
+            && !quitting
+        {
+            changed_at = None;
+            worker.submit(Job::Refresh);
+            refreshing = true;
+            last_refresh = Instant::now();
+        }
+
+        // Like focus refreshes, never while moves are outstanding.
+        if let Some(every) = auto_refresh.or(app.offline.then_some(OFFLINE_RETRY))
+            && last_refresh.elapsed() >= every
+            && !refreshing
+            && !moving
+            && move_queue.is_empty()
+            && !quitting
+        {
+            worker.submit(Job::Refresh);
+            refreshing = true;
+            last_refresh = Instant::now();
+        }
+
+        if let Some(lock) = instance.as_mut()
+            && last_beat.elapsed() >= instance::HEARTBEAT
+        {
+            last_beat = Instant::now();
+            match lock.tick() {
+                Ok(true) if lock.owned() => {
+                    app.read_only = false;
+                    app.banner = Some("The other flow closed this board; editing enabled".into());
+                }
+                Ok(true) => {
+                    app.read_only = true;
+                    app.banner = Some(
+                        "Another flow took over this board; read-only, T takes it back".into(),
+                    );
+                }
+                Ok(false) => {}
+                Err(e) => log::write(&format!("instance lock: {e}")),
+            }
+            // The owner's writes reach a read-only flow by reloading.
+            if app.read_only && !refreshing && !quitting {
+                worker.submit(Job::Refresh);
+                refreshing = true;
+                last_refresh = Instant::now();
+            }
+        }
+
+        if quitting && !moving && move_queue.is_empty() {
+            return Ok(switch_to.map_or(Exit::Quit, |b| Exit::Switch(Box::new(b))));
+        }
+
+        // Messages fade so the status bar shows through again; a conflict's
+        // or a quit's stays, as it says what happens next.
+        if app.banner != shown_banner.0 {
+            shown_banner = (app.banner.clone(), Instant::now());
+        } else if app.banner.is_some()
+            && conflict.is_none()
+            && !quitting
+            && shown_banner.1.elapsed() >= BANNER_FOR
+        {
+            app.banner = None;
+        }
+
+        frontend::Tui(terminal).draw(&app)?;
+
+        if !event::poll(ui.poll_interval(focused))? {
+            continue;
+        }
+        let ev = event::read()?;
+        match &ev {
+            Event::FocusLost => focused = false,
+            Event::FocusGained => {
+                focused = true;
+                // Skip while moves are outstanding so the reload can't undo optimistic state.
+                if ui.refresh_on_focus && !refreshing && !moving && move_queue.is_empty() {
+                    worker.submit(Job::Refresh);
+                    refreshing = true;
+                    last_refresh = Instant::now();
+                }
+            }
+            // Several lines pasted into the new-card prompt make a card each.
+            Event::Paste(text) if app.create_prompt.is_some() => {
+                let mut typed = app.create_prompt.take().unwrap_or_default();
+                typed.push_str(text);
+                let titles = bulk::titles(&typed);
+                if titles.len() > 1 {
+                    if let Err(msg) = create_cards(provider.as_mut(), &mut app, &titles) {
+                        app.banner = Some(msg);
+                    }
+                } else {
+                    app.create_prompt = Some(typed.replace(['\r', '\n'], " "));
+                }
+            }
+            _ => {}
+        }
+        if let Event::Key(k) = ev
+            && k.kind == KeyEventKind::Press
+        {
+            if app.conflict.is_some() {
+                if let Err(msg) = resolve_conflict(terminal, provider.as_mut(), &mut app, k.code) {
+                    app.banner = Some(msg);
+                }
+                continue;
+            }
+            if let Some(estimation) = app.estimation.as_mut() {
+                let mut closed = false;
+                match estimation.key(k.code) {
+                    app::EstimateKey::Stay => {}
+                    app::EstimateKey::Close => closed = true,
+                    app::EstimateKey::Save { card_id, points } => {
+                        match provider.set_estimate(&card_id, points) {
+                            Ok(()) => {
+                                estimation.saved();
+                                closed = estimation.current().is_none();
+                                if let Some(card) = app
+                                    .board
+                                    .columns
+                                    .iter_mut()
+                                    .flat_map(|c| &mut c.cards)
+                                    .find(|c| c.id == card_id)
+                                {
+                                    card.fields
+                                        .insert("estimate".to_string(), vec![points.to_string()]);
+                                }
+                            }
+                            Err(e) => app.banner = Some(format!("Estimate failed: {e}")),
+                        }
+                    }
+                }
+                if closed {
+                    let saved = app.estimation.take().map_or(0, |e| e.saved);
+                    app.banner = Some(format!(
+                        "Estimated {saved} card{}",
+                        if saved == 1 { "" } else { "s" }
+                    ));
+                }
+                continue;
+            }
+            if let Some(menu) = app.quick_menu.as_mut() {
+                if !menu.key(k.code) {
+                    continue;
+                }
+                let chosen = menu.chosen();
+                app.quick_menu = None;
+                let ids =
+                    |qs: &[model::QuickFilter]| qs.iter().map(|q| q.id.clone()).collect::<Vec<_>>();
+                if ids(&chosen) == ids(&app.quick_filters) {
+                    continue;
+                }
+                // Both providers load boards, so both take the new filters.
+                match provider.set_quick_filters(&chosen) {
+                    Ok(()) => {
+                        app.banner = Some(if chosen.is_empty() {
+                            "Quick filters off".to_string()
+                        } else {
+                            let names: Vec<&str> = chosen.iter().map(|q| q.name.as_str()).collect();
+                            format!("Quick filters: {}", names.join(", "))
+                        });
+                        app.quick_filters = chosen.clone();
+                        worker.submit(Job::QuickFilters { filters: chosen });
+                        refreshing = true;
+                        last_refresh = Instant::now();
+                    }
+                    Err(e) => app.banner = Some(format!("Quick filters failed: {e}")),
+                }
+                continue;
+            }
+            if let Some(picker) = app.picker.as_mut() {
+                match picker.key(k.code) {
+                    picker::Picked::Pending => {}
+                    picker::Picked::Cancelled => {
+                        app.picker = None;
+                        app.linking = None;
+                        app.moving_to = None;
+                        app.batch_move = None;
+                        app.yanking = None;
+                        app.found = None;
+                        app.assigning = None;
+                        app.choosing_template = false;
+                        app.browsing = None;
+                    }
+                    picker::Picked::Chosen(i) if app.browsing.is_some() => app.activity_picked(i),
+                    picker::Picked::Chosen(i) if app.choosing_template => app.template_picked(i),
+                    picker::Picked::Chosen(i) if app.yanking.is_some() => {
+                        app.picker = None;
+                        let texts = app.yanking.take().unwrap_or_default();
+                        if let Some(text) = texts.get(i) {
+                            app.banner = Some(match clipboard::copy(text) {
+                                Ok(()) => format!("Copied {text}"),
+                                Err(e) => format!("Copy failed: {e}"),
+                            });
+                        }
+                    }
+                    picker::Picked::Chosen(i) if app.found.is_some() => {
+                        let Some(card) = app.found_picked(i) else {
+                            continue;
+                        };
+                        if app.read_only {
+                            app.banner = Some(
+                                "Read-only: another flow has this board open; T takes over".into(),
+                            );
                             continue;
                         }
                         let Some(col) = app.board.columns.get(app.col) else {
-                            app.banner = Some("Create failed: no column selected".to_string());
                             continue;
                         };
-                        let card_id = match provider.create_card(&col.id) {
-                            Ok(id) => id,
-                            Err(e) => {
-                                app.banner = Some(format!("Create failed: {e}"));
-                                continue;
+                        let col_title = col.title.clone();
+                        match provider.pull_card(&card.id, &col.id.clone()) {
+                            Ok(()) => {
+                                app.banner = Some(format!("Pulled {} into {col_title}", card.id));
+                                if !refreshing {
+                                    worker.submit(Job::Refresh);
+                                    refreshing = true;
+                                    last_refresh = Instant::now();
+                                }
                             }
+                            Err(e) => app.banner = Some(format!("Pull failed: {e}")),
+                        }
+                    }
+                    picker::Picked::Chosen(i) if app.assigning.is_some() => {
+                        let Some((card_id, user)) = app.assign_picked(i) else {
+                            continue;
                         };
-                        if let Err(msg) = edit_card_in_editor(
-                            terminal,
-                            provider.as_mut(),
-                            &mut app,
-                            card_id,
-                            "Create failed",
-                        ) {
-                            app.banner = Some(msg);
+                        app.banner = Some(match provider.set_assignee(&card_id, user.as_ref()) {
+                            Ok(()) => match &user {
+                                Some(u) => format!("Assigned {card_id} to {}", u.name),
+                                None => format!("Unassigned {card_id}"),
+                            },
+                            Err(e) => {
+                                // Back to what the provider has.
+                                if !refreshing {
+                                    worker.submit(Job::Refresh);
+                                    refreshing = true;
+                                    last_refresh = Instant::now();
+                                }
+                                format!("Assign failed: {e}")
+                            }
+                        });
+                    }
+                    picker::Picked::Chosen(i) if app.batch_move.is_some() => {
+                        app.picker = None;
+                        let card_ids = app.batch_move.take().unwrap_or_default();
+                        if moving || !move_queue.is_empty() {
+                            app.banner = Some("Move failed: moves are still pending".to_string());
+                            continue;
+                        }
+                        if let Some((card_ids, dst)) = app.batch_to(card_ids, i) {
+                            worker.submit(Job::Batch {
+                                card_ids,
+                                op: BatchOp::Move { dst },
+                            });
+                            moving = true;
                         }
-                        continue;
                     }
-                    if matches!(k.code, KeyCode::Char('e')) {
-                        if quitting {
+                    picker::Picked::Chosen(i) if app.moving_to.is_some() => {
+                        if moving && move_queue.len() >= MAX_QUEUE_SIZE {
+                            app.picker = None;
+                            app.moving_to = None;
+                            app.banner =
+                                Some("Move queue full — too many pending moves".to_string());
                             continue;
                         }
-                        let Some(card_id) = selected_card_id(&app) else {
-                            app.banner = Some("Edit failed: no card selected".to_string());
+                        if let Some(card_id) = app.moving_to.clone()
+                            && app.hold_for_policy(&card_id, i)
+                        {
+                            app.picker = None;
+                            app.moving_to = None;
                             continue;
-                        };
-                        if let Err(msg) = edit_card_in_editor(
-                            terminal,
-                            provider.as_mut(),
-                            &mut app,
-                            card_id,
-                            "Edit failed",
-                        ) {
-                            app.banner = Some(msg);
                         }
-                        continue;
+                        let from = app
+                            .moving_to
+                            .as_deref()
+                            .and_then(|id| app.board.column_of(id));
+                        let from = from.map(|c| c.id.clone());
+                        if let Some((card_id, dst)) = app.move_picked(i) {
+                            queue_move(
+                                &worker,
+                                &mut move_queue,
+                                &mut moving,
+                                &mut app,
+                                QueuedMove { card_id, from, dst },
+                            );
+                        }
                     }
-
-                    if let Some(a) = action_from_key(k.code) {
-                        if quitting {
-                            if matches!(a, Action::MoveLeft | Action::MoveRight) {
-                                continue;
+                    picker::Picked::Chosen(i) if app.linking.is_some() => {
+                        let Some((from, to, kind)) = app.link_picked(i) else {
+                            continue;
+                        };
+                        match provider.link_cards(&from, &to, kind) {
+                            Ok(()) => {
+                                graph::add_link(&mut app.board, &from, &to, kind);
+                                app.banner = Some(format!("{from} {} {to}", kind.label()));
                             }
+                            Err(e) => app.banner = Some(format!("Link failed: {e}")),
                         }
-
-                        match a {
-                            Action::MoveLeft => {
-                                if move_rx.is_some() {
-                                    if move_queue.len() >= MAX_QUEUE_SIZE {
-                                        app.banner = Some(
-                                            "Move queue full — too many pending moves".to_string(),
-                                        );
-                                    } else if let Some((card_id, dst)) = app.optimistic_move(-1) {
-                                        move_queue.push_back((card_id, dst));
-                                        app.banner = Some(format!(
-                                            "Moving... ({} queued)",
-                                            move_queue.len()
-                                        ));
-                                    }
-                                } else if let Some((card_id, dst)) = app.optimistic_move(-1) {
-                                    move_rx = Some(spawn_move(card_id, dst));
-                                    app.banner = Some("Moving...".to_string());
-                                }
+                    }
+                    picker::Picked::Chosen(i) => {
+                        let name = picker.items[i].clone();
+                        app.picker = None;
+                        match config::board(&cfg, &name) {
+                            Ok(b) => {
+                                // Like quitting: outstanding moves land first.
+                                switch_to = Some(b);
+                                quitting = true;
+                                update_quit_banner(&mut app, quitting, &move_queue, moving);
                             }
-                            Action::MoveRight => {
-                                if move_rx.is_some() {
-                                    if move_queue.len() >= MAX_QUEUE_SIZE {
-                                        app.banner = Some(
-                                            "Move queue full — too many pending moves".to_string(),
-                                        );
-                                    } else if let Some((card_id, dst)) = app.optimistic_move(1) {
-                                        move_queue.push_back((card_id, dst));
-                                        app.banner = Some(format!(
-                                            "Moving... ({} queued)",
-                                            move_queue.len()
-                                        ));
-                                    }
-                                } else if let Some((card_id, dst)) = app.optimistic_move(1) {
-                                    move_rx = Some(spawn_move(card_id, dst));
-                                    app.banner = Some("Moving...".to_string());
-                                }
+                            Err(e) => app.banner = Some(format!("Switch failed: {e}")),
+                        }
+                    }
+                }
+                continue;
+            }
+            if let Some(search) = app.search.as_mut()
+                && search.editing
+            {
+                match k.code {
+                    KeyCode::Esc => {
+                        app.search = None;
+                        app.banner = None;
+                    }
+                    KeyCode::Backspace => {
+                        search.query.pop();
+                        app.update_search();
+                    }
+                    KeyCode::Char(c) => {
+                        search.query.push(c);
+                        app.update_search();
+                    }
+                    KeyCode::Tab => {
+                        search.mode = search.mode.next();
+                        app.update_search();
+                    }
+                    KeyCode::Enter => {
+                        search.editing = false;
+                        if search.query.trim().is_empty() {
+                            app.search = None;
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(title) = app.create_prompt.as_mut() {
+                match k.code {
+                    KeyCode::Esc => {
+                        app.create_prompt = None;
+                        app.create_template = None;
+                    }
+                    KeyCode::Backspace => {
+                        title.pop();
+                    }
+                    KeyCode::Char(c) => title.push(c),
+                    KeyCode::Enter => {
+                        let title = app.create_prompt.take().unwrap_or_default();
+                        if let Err(msg) = create_card(provider.as_mut(), &mut app, title.trim()) {
+                            app.banner = Some(msg);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(text) = app.filter_prompt.as_mut() {
+                match k.code {
+                    KeyCode::Esc => app.filter_prompt = None,
+                    KeyCode::Backspace => {
+                        text.pop();
+                    }
+                    KeyCode::Char(c) => text.push(c),
+                    KeyCode::Enter => {
+                        let text = app.filter_prompt.take().unwrap_or_default();
+                        match app.set_filter(&text) {
+                            Ok(()) => app.banner = None,
+                            Err(e) => {
+                                app.banner = Some(format!("Filter failed: {e}"));
+                                app.filter_prompt = Some(text);
                             }
-                            Action::Refresh => {
-                                if quitting {
-                                    continue;
-                                }
-                                match provider.load_board() {
-                                    Ok(b) => {
-                                        app.board = b;
-                                        app.focus_first_non_empty();
-                                        app.banner = None;
-                                    }
-                                    Err(e) => app.banner = Some(format!("Refresh failed: {e}")),
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(query) = app.server_prompt.as_mut() {
+                match k.code {
+                    KeyCode::Esc => app.server_prompt = None,
+                    KeyCode::Backspace => {
+                        query.pop();
+                    }
+                    KeyCode::Char(c) => query.push(c),
+                    KeyCode::Enter if query.trim().is_empty() => app.server_prompt = None,
+                    KeyCode::Enter => {
+                        let query = app.server_prompt.take().unwrap_or_default();
+                        match provider.search(query.trim()) {
+                            Ok(cards) => app.found_menu(query.trim(), cards),
+                            Err(e) => app.banner = Some(format!("Search failed: {e}")),
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some((m, board)) = conflict.take() {
+                match k.code {
+                    KeyCode::Char('r') => {
+                        worker.submit(Job::Move {
+                            card_id: m.card_id,
+                            from: None,
+                            dst: m.dst,
+                            reconcile: move_queue.is_empty(),
+                        });
+                        moving = true;
+                        app.banner = Some(format!("Moving... ({} queued)", move_queue.len()));
+                    }
+                    KeyCode::Char('s') => {
+                        app.pending.remove(&m.card_id);
+                        moving = send_next(&worker, &mut move_queue, &mut app);
+                        if !moving {
+                            app.reconcile(board, &m.card_id);
+                            app.banner = Some(format!("Skipped the move of {}", m.card_id));
+                        }
+                    }
+                    KeyCode::Esc => {
+                        reload_after_conflict(&mut app, &mut move_queue, &m, board);
+                    }
+                    _ => conflict = Some((m, board)),
+                }
+                continue;
+            }
+            if let Some(card_ids) = app.batch_archive.take() {
+                if k.code != KeyCode::Char('y') {
+                    continue;
+                }
+                if moving || !move_queue.is_empty() {
+                    app.banner = Some("Archive failed: moves are still pending".to_string());
+                    continue;
+                }
+                app.pending.extend(card_ids.iter().cloned());
+                app.batch = Some(app::Batch {
+                    card_ids: card_ids.clone(),
+                    to: None,
+                });
+                worker.submit(Job::Batch {
+                    card_ids,
+                    op: BatchOp::Archive,
+                });
+                moving = true;
+                continue;
+            }
+            if let Some(card_id) = app.archive_prompt.take() {
+                if k.code == KeyCode::Char('y')
+                    && let Err(msg) = archive_card(provider.as_mut(), &mut app, &card_id)
+                {
+                    app.banner = Some(msg);
+                }
+                continue;
+            }
+            if let Some((card_id, dst)) = app.done_prompt.take() {
+                if k.code != KeyCode::Char('y') {
+                    continue;
+                }
+                if moving && move_queue.len() >= MAX_QUEUE_SIZE {
+                    app.banner = Some("Move queue full — too many pending moves".to_string());
+                    continue;
+                }
+                let from = app.board.column_of(&card_id).map(|c| c.id.clone());
+                if let Some((card_id, dst)) = app.move_card_to(&card_id, dst) {
+                    queue_move(
+                        &worker,
+                        &mut move_queue,
+                        &mut moving,
+                        &mut app,
+                        QueuedMove { card_id, from, dst },
+                    );
+                }
+                continue;
+            }
+            if let Some(text) = app.reply_prompt.as_mut() {
+                match k.code {
+                    KeyCode::Esc => app.reply_prompt = None,
+                    KeyCode::Backspace => {
+                        text.pop();
+                    }
+                    KeyCode::Char(c) => text.push(c),
+                    KeyCode::Enter => {
+                        let text = app.reply_prompt.take().unwrap_or_default();
+                        if let Some(m) = app.mentions.front()
+                            && !text.trim().is_empty()
+                        {
+                            let card_id = m.card_id.clone();
+                            match provider.add_comment(&card_id, text.trim()) {
+                                Ok(()) => {
+                                    app.mentions.pop_front();
+                                    app.banner = Some(format!("Replied on {card_id}"));
                                 }
+                                Err(e) => app.banner = Some(format!("Reply failed: {e}")),
                             }
-                            _ => {
-                                if app.apply(a) {
-                                    if move_rx.is_some() || !move_queue.is_empty() {
-                                        quitting = true;
-                                        update_quit_banner(
-                                            &mut app,
-                                            quitting,
-                                            &move_queue,
-                                            move_rx.is_some(),
-                                        );
-                                    } else {
-                                        break;
-                                    }
-                                }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some((card_id, text)) = app.comment_prompt.as_mut() {
+                match k.code {
+                    KeyCode::Esc => app.comment_prompt = None,
+                    KeyCode::Backspace => {
+                        text.pop();
+                    }
+                    KeyCode::Char(c) => text.push(c),
+                    KeyCode::Enter if text.trim().is_empty() => app.comment_prompt = None,
+                    KeyCode::Enter => {
+                        let (card_id, text) = (card_id.clone(), text.trim().to_string());
+                        match provider.add_comment(&card_id, &text) {
+                            Ok(()) => {
+                                app.comment_prompt = None;
+                                app.banner = Some(format!("Commented on {card_id}"));
+                                // Fetched again while the popup stays open.
+                                app.details.remove(&card_id);
                             }
+                            // The text stays, to retry or copy out.
+                            Err(e) => app.banner = Some(format!("Comment failed: {e}")),
                         }
                     }
+                    _ => {}
                 }
+                continue;
             }
-        }
+            if app.read_only
+                && let Some(a) = app.keymap.get(k.code)
+                && a.writes()
+                && !(a == Action::CreateCard && app.search.is_some())
+            {
+                app.banner =
+                    Some("Read-only: another flow has this board open; T takes over".into());
+                continue;
+            }
+            // Re-read, so a `flow lock` from another terminal applies at once.
+            if let Some(a) = app.keymap.get(k.code)
+                && a.writes()
+                && !(a == Action::CreateCard && app.search.is_some())
+            {
+                app.lock = freeze::active(&lock_key);
+                if app.locked_out(a)
+                    && let Some(lock) = &app.lock
+                {
+                    app.banner = Some(format!(
+                        "Board is locked until {}; `flow unlock` lifts it",
+                        lock.until
+                    ));
+                    continue;
+                }
+            }
+            if app.keymap.get(k.code) == Some(Action::Edit) {
+                if quitting {
+                    continue;
+                }
+                let Some(card_id) = app.selected_card_id() else {
+                    app.banner = Some("Edit failed: no card selected".to_string());
+                    continue;
+                };
+                if let Err(msg) = edit_card_in_editor(
+                    terminal,
+                    provider.as_mut(),
+                    &mut app,
+                    card_id,
+                    "Edit failed",
+                ) {
+                    app.banner = Some(msg);
+                }
+                continue;
+            }
+
+            if let Some(a) = app.keymap.get(k.code) {
+                // While a search is active `n` steps through matches instead of creating.
+                let a = if a == Action::CreateCard && app.search.is_some() {
+                    Action::NextMatch
+                } else {
+                    a
+                };
+                // The history view steps through days instead of columns.
+                if let Some(view) = &app.history
+                    && matches!(a, Action::FocusLeft | Action::FocusRight)
+                {
+                    let at = match a {
+                        Action::FocusLeft => view.at.checked_sub(1),
+                        _ => Some(view.at + 1).filter(|&i| i < view.days.len()),
+                    };
+                    if let Some(at) = at {
+                        match history::load(&lock_key, view.days[at]) {
+                            Ok(board) => {
+                                app.history = Some(app::HistoryView {
+                                    days: view.days.clone(),
+                                    at,
+                                    board,
+                                })
+                            }
+                            Err(e) => app.banner = Some(format!("History failed: {e}")),
+                        }
+                    }
+                    continue;
+                }
+                if quitting
+                    && matches!(
+                        a,
+                        Action::MoveLeft
+                            | Action::MoveRight
+                            | Action::MoveTo
+                            | Action::Undo
+                            | Action::StartWork
+                    )
+                {
+                    continue;
+                }
+
+                match a {
+                    // The marked cards go together, once single moves are done.
+                    Action::MoveLeft | Action::MoveRight if app.visual.is_some() => {
+                        if moving || !move_queue.is_empty() {
+                            app.banner = Some("Move failed: moves are still pending".to_string());
+                            continue;
+                        }
+                        let step = if a == Action::MoveLeft { -1 } else { 1 };
+                        let Some(dst) = app.col.checked_add_signed(step) else {
+                            continue;
+                        };
+                        let card_ids = app.marked();
+                        if let Some((card_ids, dst)) = app.batch_to(card_ids, dst) {
+                            worker.submit(Job::Batch {
+                                card_ids,
+                                op: BatchOp::Move { dst },
+                            });
+                            moving = true;
+                        }
+                    }
+                    Action::MoveLeft | Action::MoveRight | Action::Undo => {
+                        if moving && move_queue.len() >= MAX_QUEUE_SIZE {
+                            app.banner =
+                                Some("Move queue full — too many pending moves".to_string());
+                            continue;
+                        }
+                        let step = match a {
+                            Action::MoveLeft => -1,
+                            Action::MoveRight => 1,
+                            _ => 0,
+                        };
+                        if step != 0 && app.hold_step_for_policy(step) {
+                            continue;
+                        }
+                        // Where the card is now, for a queued move to check before it's sent.
+                        let from = match a {
+                            Action::Undo => app.undo.last().map(|u| u.card_id().to_string()),
+                            _ => app.selected_card_id(),
+                        }
+                        .and_then(|id| app.board.column_of(&id))
+                        .map(|c| c.id.clone());
+                        let next = match a {
+                            Action::MoveLeft => app.optimistic_move(-1),
+                            Action::MoveRight => app.optimistic_move(1),
+                            _ => {
+                                // Undoing a move that hasn't been sent just drops it.
+                                let card_id = app.undo.last().map(|u| u.card_id().to_string());
+                                let queued = card_id
+                                    .as_deref()
+                                    .is_some_and(|id| unqueue_last(&mut move_queue, id));
+                                let next = app.undo(queued);
+                                // An earlier hop of the card still has to go.
+                                if let Some(id) = card_id
+                                    && queued
+                                    && move_queue.iter().any(|m| m.card_id == id)
+                                {
+                                    app.pending.insert(id);
+                                }
+                                next
+                            }
+                        };
+                        let Some((card_id, dst)) = next else {
+                            continue;
+                        };
+                        queue_move(
+                            &worker,
+                            &mut move_queue,
+                            &mut moving,
+                            &mut app,
+                            QueuedMove { card_id, from, dst },
+                        );
+                    }
+                    Action::Reorder => {
+                        if moving || !move_queue.is_empty() {
+                            app.banner =
+                                Some("Reorder failed: moves are still pending".to_string());
+                            continue;
+                        }
+                        let Some(col) = app.board.columns.get(app.col).cloned() else {
+                            continue;
+                        };
+                        let script = reorder::script(&col, &app.board);
+                        app.banner = Some(
+                            match edit_text(terminal, &format!("order-{}", col.id), &script)
+                                .and_then(|text| reorder::parse(&text, &col, &app.board))
+                            {
+                                Err(msg) => format!("Reorder failed: {msg}"),
+                                Ok(None) => "Reorder cancelled".to_string(),
+                                Ok(Some(plan)) if plan.is_noop(&col) => {
+                                    format!("{} is unchanged", col.title)
+                                }
+                                Ok(Some(plan)) => {
+                                    let done = apply_reorder(provider.as_mut(), &col, &plan);
+                                    match provider.load_board() {
+                                        Ok(b) => app.refresh_board(b),
+                                        Err(e) => app.banner = Some(format!("Reload failed: {e}")),
+                                    }
+                                    done
+                                }
+                            },
+                        );
+                    }
+                    // Boards without a log of their own list this session's.
+                    Action::Activity => match provider.activity() {
+                        Ok(entries) => app.activity_menu(&entries),
+                        Err(provider::ProviderError::Unsupported { .. }) => {
+                            app.activity_menu(&app.session_log.clone())
+                        }
+                        Err(e) => app.banner = Some(format!("Activity failed: {e}")),
+                    },
+                    Action::GitPull | Action::GitPush if git_root.is_none() => {
+                        app.banner = Some(
+                            "Not a git-backed board; set git = true on a local profile".into(),
+                        );
+                    }
+                    Action::GitPull => {
+                        let root = git_root.as_deref().expect("checked above");
+                        match git::pull(root) {
+                            Ok(()) => match provider.load_board() {
+                                Ok(b) => {
+                                    app.refresh_board(b);
+                                    app.banner = Some("Pulled".to_string());
+                                }
+                                Err(e) => app.banner = Some(format!("Refresh failed: {e}")),
+                            },
+                            Err(e) => app.banner = Some(format!("Pull failed: {e}")),
+                        }
+                    }
+                    Action::GitPush => {
+                        let root = git_root.as_deref().expect("checked above");
+                        app.banner = Some(match git::push(root) {
+                            Ok(()) => "Pushed".to_string(),
+                            Err(e) => format!("Push failed: {e}"),
+                        });
+                    }
+                    Action::Stats if app.stats.is_some() => app.stats = None,
+                    Action::Stats => {
+                        let days = ui.stats_days;
+                        let since = dates::now_secs().saturating_sub(days.max(0) as u64 * 86_400);
+                        // Without history the overlay still shows the board's
+                        // own numbers.
+                        let moves = match provider.moves(since) {
+                            Ok(moves) => moves,
+                            Err(provider::ProviderError::Unsupported { .. }) => vec![],
+                            Err(e) => {
+                                app.banner = Some(format!("History failed: {e}"));
+                                vec![]
+                            }
+                        };
+                        app.open_stats(&moves, days);
+                    }
+                    Action::Assign => {
+                        let Some(card_id) = app.selected_card_id() else {
+                            app.banner = Some("Assign failed: no card selected".to_string());
+                            continue;
+                        };
+                        match provider.assignable_users(&card_id) {
+                            Ok(users) => app.assign_menu(users),
+                            Err(e) => app.banner = Some(format!("Assign failed: {e}")),
+                        }
+                    }
+                    Action::Yank => {
+                        // Local cards have no web page, so the file stands in.
+                        let url = app.selected_card().and_then(|card| {
+                            provider::card_url(&profile, card).or_else(|| {
+                                provider
+                                    .card_path(&card.id)
+                                    .ok()
+                                    .map(|p| format!("file://{}", p.display()))
+                            })
+                        });
+                        app.yank_menu(url.as_deref());
+                    }
+                    Action::StartWork => {
+                        let start = &cfg.start_work;
+                        let Some(card) = app.selected_card().cloned() else {
+                            app.banner = Some("Start failed: no card selected".to_string());
+                            continue;
+                        };
+                        let Some(dst) = app.board.column_named(&start.column) else {
+                            app.banner = Some(format!(
+                                "Start failed: no {:?} column; set column under [start_work]",
+                                start.column
+                            ));
+                            continue;
+                        };
+                        let cmd = match start
+                            .command
+                            .as_deref()
+                            .map(|t| command::render(t, &card, &app.board.columns[dst].title))
+                            .transpose()
+                        {
+                            Ok(cmd) => cmd,
+                            Err(msg) => {
+                                app.banner = Some(format!("[start_work] command: {msg}"));
+                                continue;
+                            }
+                        };
+                        if dst == app.col {
+                            app.banner = Some(format!(
+                                "{} is already in {}",
+                                card.id, app.board.columns[dst].title
+                            ));
+                        } else {
+                            if moving && move_queue.len() >= MAX_QUEUE_SIZE {
+                                app.banner =
+                                    Some("Move queue full — too many pending moves".to_string());
+                                continue;
+                            }
+                            let from = Some(app.board.columns[app.col].id.clone());
+                            let Some((card_id, dst)) = app.move_card_to(&card.id, dst) else {
+                                continue;
+                            };
+                            queue_move(
+                                &worker,
+                                &mut move_queue,
+                                &mut moving,
+                                &mut app,
+                                QueuedMove { card_id, from, dst },
+                            );
+                        }
+                        if let Some(cmd) = cmd {
+                            app.banner = Some(match command::run(&cmd) {
+                                Ok(Ok(out)) if out.is_empty() => {
+                                    format!("Started {}: {}", card.id, cmd.shown)
+                                }
+                                Ok(Ok(out)) => format!("Started {}: {out}", card.id),
+                                Ok(Err(err)) => format!("Start command failed: {err}"),
+                                Err(e) => format!("Start command failed: {e}"),
+                            });
+                        }
+                    }
+                    Action::SwitchBoard => {
+                        if cfg.profiles.is_empty() {
+                            app.banner =
+                                Some("No boards configured; add [profiles] to config.toml".into());
+                            continue;
+                        }
+                        let names: Vec<String> = cfg.profiles.keys().cloned().collect();
+                        let current = names.iter().position(|n| Some(n) == current.as_ref());
+                        app.picker = Some(picker::Picker::new("Switch board", names, current));
+                    }
+                    Action::LoadMore => {
+                        let Some(col) = app.board.columns.get(app.col) else {
+                            continue;
+                        };
+                        if !col.meta.more {
+                            app.banner = Some(format!("All of {} is loaded", col.title));
+                            continue;
+                        }
+                        if refreshing || moving || !move_queue.is_empty() {
+                            app.banner = Some("Load more: wait for pending changes".into());
+                            continue;
+                        }
+                        // Both providers load boards, so both take the new limit.
+                        let col_id = col.id.clone();
+                        match provider.load_more(&col_id) {
+                            Ok(()) => {
+                                worker.submit(Job::LoadMore { col_id });
+                                refreshing = true;
+                                last_refresh = Instant::now();
+                            }
+                            Err(e) => app.banner = Some(format!("Load more failed: {e}")),
+                        }
+                    }
+                    Action::QuickFilters => {
+                        if refreshing || moving || !move_queue.is_empty() {
+                            app.banner = Some("Quick filters: wait for pending changes".into());
+                            continue;
+                        }
+                        match provider.quick_filters() {
+                            Ok(items) if items.is_empty() => {
+                                app.banner = Some("This board has no quick filters".into())
+                            }
+                            Ok(items) => {
+                                app.quick_menu =
+                                    Some(app::QuickFilterMenu::new(items, &app.quick_filters))
+                            }
+                            Err(e) => app.banner = Some(format!("Quick filters failed: {e}")),
+                        }
+                    }
+                    Action::TakeOver => match instance.as_mut() {
+                        None => app.banner = Some("Take over: only local boards are locked".into()),
+                        Some(lock) if lock.owned() => {
+                            app.banner = Some("This flow already edits the board".into())
+                        }
+                        Some(lock) => match lock.take_over() {
+                            Ok(()) => {
+                                app.read_only = false;
+                                app.banner = Some(
+                                    "Took over the board; the other flow is read-only now".into(),
+                                );
+                                if !refreshing {
+                                    worker.submit(Job::Refresh);
+                                    refreshing = true;
+                                    last_refresh = Instant::now();
+                                }
+                            }
+                            Err(e) => app.banner = Some(format!("Take over failed: {e}")),
+                        },
+                    },
+                    Action::History => {
+                        if app.history.take().is_some() {
+                            continue;
+                        }
+                        let days = history::days(&lock_key);
+                        // Yesterday, or the latest day before it.
+                        let today = dates::today();
+                        let Some(at) = days
+                            .iter()
+                            .rposition(|&d| d < today)
+                            .or(days.len().checked_sub(1))
+                        else {
+                            app.banner = Some(
+                                "No history yet: flow keeps a snapshot of each day it loads the board"
+                                    .to_string(),
+                            );
+                            continue;
+                        };
+                        match history::load(&lock_key, days[at]) {
+                            Ok(board) => app.history = Some(app::HistoryView { days, at, board }),
+                            Err(e) => app.banner = Some(format!("History failed: {e}")),
+                        }
+                    }
+                    Action::ToggleArchive => {
+                        if app.archived.take().is_some() {
+                            continue;
+                        }
+                        match provider.archived_cards() {
+                            Ok(cards) => {
+                                app.archived = Some(cards);
+                                app.archived_row = 0;
+                            }
+                            Err(e) => app.banner = Some(format!("Archive failed: {e}")),
+                        }
+                    }
+                    Action::Refresh => {
+                        if quitting {
+                            continue;
+                        }
+                        match provider.load_board() {
+                            Ok(b) => app.refresh_board(b),
+                            Err(e) => app.banner = Some(format!("Refresh failed: {e}")),
+                        }
+                    }
+                    _ => {
+                        if app.apply(a) {
+                            if moving || !move_queue.is_empty() {
+                                quitting = true;
+                                update_quit_banner(&mut app, quitting, &move_queue, moving);
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
     }
 
-    Ok(())
+    Ok(Exit::Quit)
 }
 
-fn selected_card_id(app: &App) -> Option<String> {
-    app.board
+fn create_card(
+    provider: &mut dyn provider::Provider,
+    app: &mut App,
+    title: &str,
+) -> Result<(), String> {
+    if title.is_empty() {
+        app.create_template = None;
+        return Err("Create cancelled: empty title".to_string());
+    }
+    create_cards(provider, app, &[title.to_string()])
+}
+
+/// Creates the cards in order in the selected column, from the chosen
+/// template if any, stopping at the first failure, then reloads once and
+/// selects the last one created.
+fn create_cards(
+    provider: &mut dyn provider::Provider,
+    app: &mut App,
+    titles: &[String],
+) -> Result<(), String> {
+    let template = app.create_template.take();
+    let (col_id, col_title) = app
+        .board
         .columns
         .get(app.col)
-        .and_then(|col| col.cards.get(app.row))
-        .map(|card| card.id.clone())
+        .map(|c| (c.id.clone(), c.title.clone()))
+        .ok_or_else(|| "Create failed: no column selected".to_string())?;
+    let date = dates::format_date(dates::today());
+    let user = config::user()
+        .author()
+        .map(str::to_string)
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_default();
+    let mut created = vec![];
+    let mut failed = None;
+    for title in titles {
+        let made = match &template {
+            Some(t) => {
+                let draft = t.fill(&card_template::Vars {
+                    title,
+                    date: &date,
+                    user: &user,
+                    column: &col_title,
+                });
+                provider.create_card_from(&col_id, &draft)
+            }
+            None => provider.create_card_with_title(&col_id, title),
+        };
+        match made {
+            Ok(id) => created.push(id),
+            Err(e) => {
+                failed = Some(e);
+                break;
+            }
+        }
+    }
+
+    if !created.is_empty() {
+        let board = provider
+            .load_board()
+            .map_err(|e| format!("Reload failed: {e}"))?;
+        app.board = board;
+    }
+    if !created.last().is_some_and(|id| app.focus_card(id)) {
+        app.clamp();
+    }
+    match (failed, created.as_slice()) {
+        (Some(e), []) => Err(format!("Create failed: {e}")),
+        (Some(e), _) => Err(format!(
+            "Created {} of {} cards, then failed: {e}",
+            created.len(),
+            titles.len()
+        )),
+        (None, [id]) => {
+            app.banner = Some(format!("Created {id}"));
+            Ok(())
+        }
+        (None, _) => {
+            app.banner = Some(format!("Created {} cards", created.len()));
+            Ok(())
+        }
+    }
+}
+
+fn archive_card(
+    provider: &mut dyn provider::Provider,
+    app: &mut App,
+    card_id: &str,
+) -> Result<(), String> {
+    provider
+        .archive_card(card_id)
+        .map_err(|e| format!("Archive failed: {e}"))?;
+    app.undo.retain(|u| u.card_id() != card_id);
+
+    let board = provider
+        .load_board()
+        .map_err(|e| format!("Reload failed: {e}"))?;
+    app.board = board;
+    app.clamp();
+    app.banner = Some(format!("Archived {card_id}"));
+    Ok(())
+}
+
+/// Applies an `O` plan: the new order, then each move and archive. Stops
+/// at the first failure, saying what was done before it.
+fn apply_reorder(
+    provider: &mut dyn provider::Provider,
+    col: &model::Column,
+    plan: &reorder::Plan,
+) -> String {
+    if let Err(e) = provider.reorder_column(&col.id, &plan.order) {
+        return format!("Reorder failed: {e}");
+    }
+    let mut done = vec![format!("Reordered {}", col.title)];
+    for (card_id, dst) in &plan.moves {
+        if let Err(e) = provider.move_card(card_id, dst) {
+            return format!("{}; moving {card_id} failed: {e}", done.join(", "));
+        }
+        done.push(format!("moved {card_id}"));
+    }
+    for card_id in &plan.drops {
+        if let Err(e) = provider.archive_card(card_id) {
+            return format!("{}; archiving {card_id} failed: {e}", done.join(", "));
+        }
+        done.push(format!("archived {card_id}"));
+    }
+    done.join(", ")
 }
 
 fn edit_card_in_editor(
@@ -293,231 +1771,1669 @@ fn edit_card_in_editor(
     card_id: String,
     err_prefix: &str,
 ) -> Result<(), String> {
-    let path = provider
-        .card_path(&card_id)
-        .map_err(|e| format!("{err_prefix}: {e}"))?;
-    open_in_editor(terminal, &path).map_err(|e| format!("Open editor failed: {e}"))?;
+    let mut banner = None;
+    match provider.card_path(&card_id) {
+        Ok(path) => banner = edit_file_with_merge(terminal, provider, app, &card_id, &path)?,
+        Err(provider::ProviderError::Unsupported { .. }) => {
+            edit_via_temp_file(terminal, provider, app, &card_id)
+                .map_err(|e| format!("{err_prefix}: {e}"))?
+        }
+        Err(e) => return Err(format!("{err_prefix}: {e}")),
+    }
 
     let board = provider
         .load_board()
         .map_err(|e| format!("Reload failed: {e}"))?;
     app.board = board;
-    focus_card_by_id(app, &card_id);
+    if !app.focus_card(&card_id) {
+        app.focus_first_non_empty();
+    }
+    app.banner = banner;
+    Ok(())
+}
+
+/// Edits a copy of the card so a change written to the file meanwhile (by a
+/// teammate's sync or an agent) is merged instead of clobbered. Conflicts
+/// are parked in `app.conflict` for the resolve prompt.
+fn edit_file_with_merge(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    provider: &mut dyn provider::Provider,
+    app: &mut App,
+    card_id: &str,
+    path: &Path,
+) -> Result<Option<String>, String> {
+    let base = fs::read_to_string(path).map_err(|e| format!("Edit failed: {e}"))?;
+    let ours = edit_text(terminal, card_id, &base)?;
+    if ours == base {
+        return Ok(None);
+    }
+
+    let theirs = fs::read_to_string(path).map_err(|e| format!("Edit failed: {e}"))?;
+    if theirs == base {
+        save_edit(provider, card_id, &ours)?;
+        return Ok(None);
+    }
+    match diff::merge3(&base, &ours, &theirs) {
+        diff::Merge::Clean(merged) => {
+            save_edit(provider, card_id, &merged)?;
+            Ok(Some(format!(
+                "{card_id} changed on disk while editing; changes merged"
+            )))
+        }
+        diff::Merge::Conflict(merged) => {
+            app.conflict = Some(app::MergeConflict {
+                card_id: card_id.to_string(),
+                ours,
+                merged,
+            });
+            Ok(None)
+        }
+    }
+}
+
+/// Writes an edited card file, stamping `updated`, however the edit was
+/// settled.
+fn save_edit(
+    provider: &mut dyn provider::Provider,
+    card_id: &str,
+    text: &str,
+) -> Result<(), String> {
+    provider
+        .save_card(card_id, &store_fs::touch(text))
+        .map_err(|e| format!("Save failed: {e}"))
+}
+
+/// Opens `text` in `$EDITOR` via a temp file and returns the result.
+fn edit_text(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    card_id: &str,
+    text: &str,
+) -> Result<String, String> {
+    let (path, mut file) = create_temp(card_id).map_err(|e| e.to_string())?;
+    let written = io::Write::write_all(&mut file, text.as_bytes());
+    drop(file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&path);
+        return Err(e.to_string());
+    }
+    let opened = open_in_editor(terminal, &path);
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    opened.map_err(|e| format!("Open editor failed: {e}"))?;
+    edited.map_err(|e| e.to_string())
+}
+
+/// A new temp file only the user can read. The name isn't guessable and the
+/// file must not exist yet, so nothing planted there (say a symlink to
+/// another file) gets written through.
+fn create_temp(card_id: &str) -> io::Result<(PathBuf, fs::File)> {
+    let safe: String = card_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    loop {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path =
+            std::env::temp_dir().join(format!("flow-{}-{nanos}-{safe}.md", std::process::id()));
+        let mut opts = fs::OpenOptions::new();
+        opts.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+        match opts.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Handles a key in the merge-conflict prompt.
+fn resolve_conflict(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    provider: &mut dyn provider::Provider,
+    app: &mut App,
+    code: KeyCode,
+) -> Result<(), String> {
+    let Some(conflict) = app.conflict.take() else {
+        return Ok(());
+    };
+    let text = match code {
+        KeyCode::Char('e') => {
+            let resolved = edit_text(terminal, &conflict.card_id, &conflict.merged)?;
+            if resolved.lines().any(|l| l.starts_with("<<<<<<< ")) {
+                app.conflict = Some(app::MergeConflict {
+                    merged: resolved,
+                    ..conflict
+                });
+                return Err("Conflict markers remain".to_string());
+            }
+            Some(resolved)
+        }
+        KeyCode::Char('o') => Some(conflict.ours.clone()),
+        KeyCode::Char('t') | KeyCode::Esc => None,
+        _ => {
+            app.conflict = Some(conflict);
+            return Ok(());
+        }
+    };
+
+    if let Some(text) = text {
+        save_edit(provider, &conflict.card_id, &text)?;
+    }
+    let board = provider
+        .load_board()
+        .map_err(|e| format!("Reload failed: {e}"))?;
+    app.board = board;
+    if !app.focus_card(&conflict.card_id) {
+        app.clamp();
+    }
     app.banner = None;
     Ok(())
 }
 
-fn focus_card_by_id(app: &mut App, card_id: &str) {
-    for (col_idx, col) in app.board.columns.iter().enumerate() {
-        if let Some(row_idx) = col.cards.iter().position(|c| c.id == card_id) {
-            app.col = col_idx;
-            app.row = row_idx;
-            app.clamp();
-            return;
-        }
-    }
-    app.focus_first_non_empty();
+/// For providers without card files: edit a temp copy, then push the title
+/// and description back.
+fn edit_via_temp_file(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    provider: &mut dyn provider::Provider,
+    app: &App,
+    card_id: &str,
+) -> Result<(), String> {
+    let card = app
+        .find_card(card_id)
+        .ok_or_else(|| format!("{card_id} is not on the board"))?;
+    let original = format!("# {}\n\n{}\n", card.title, card.description);
+    let edited = edit_text(terminal, card_id, &original)?;
+
+    if edited == original {
+        return Ok(());
+    }
+    let (title, body) = store_fs::parse_md(&edited, &card.title);
+    provider
+        .update_card(card_id, &title, &body)
+        .map_err(|e| e.to_string())
+}
+
+fn open_in_editor(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    path: &Path,
+) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableFocusChange,
+        DisableBracketedPaste
+    )?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    let status = Command::new(editor).arg(path).status();
+
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableFocusChange,
+        EnableBracketedPaste
+    )?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+    terminal.show_cursor()?;
+
+    let status = status?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other("editor exited with non-zero status"))
+    }
+}
+
+/// A move waiting behind the one in flight. `from` is the card's column
+/// when it was queued.
+struct QueuedMove {
+    card_id: String,
+    from: Option<String>,
+    dst: String,
+}
+
+/// Drops the latest queued move of `card_id`, the one its newest undo entry
+/// is for, returning whether there was one.
+fn unqueue_last(move_queue: &mut VecDeque<QueuedMove>, card_id: &str) -> bool {
+    let Some(pos) = move_queue.iter().rposition(|m| m.card_id == card_id) else {
+        return false;
+    };
+    move_queue.remove(pos);
+    true
+}
+
+/// Sends `m` now, or queues it behind the move in flight.
+fn queue_move(
+    worker: &Worker,
+    move_queue: &mut VecDeque<QueuedMove>,
+    moving: &mut bool,
+    app: &mut App,
+    m: QueuedMove,
+) {
+    if *moving {
+        move_queue.push_back(m);
+        app.banner = Some(format!("Moving... ({} queued)", move_queue.len()));
+    } else {
+        worker.submit(Job::Move {
+            card_id: m.card_id,
+            from: None,
+            dst: m.dst,
+            reconcile: true,
+        });
+        *moving = true;
+        app.banner = Some("Moving...".to_string());
+    }
+}
+
+/// Sends the next queued move, returning whether there was one.
+fn send_next(worker: &Worker, move_queue: &mut VecDeque<QueuedMove>, app: &mut App) -> bool {
+    let Some(m) = move_queue.pop_front() else {
+        return false;
+    };
+    worker.submit(Job::Move {
+        card_id: m.card_id,
+        from: m.from,
+        dst: m.dst,
+        reconcile: move_queue.is_empty(),
+    });
+    app.banner = Some(format!("Moving... ({} queued)", move_queue.len()));
+    true
+}
+
+fn conflict_banner(m: &QueuedMove, found: Option<&str>, board: &model::Board) -> String {
+    let title = |id: &str| {
+        board
+            .columns
+            .iter()
+            .find(|c| c.id == id)
+            .map_or_else(|| id.to_string(), |c| c.title.clone())
+    };
+    let what = match found {
+        Some(col) => format!(
+            "{} was moved to {} meanwhile, not {}",
+            m.card_id,
+            title(col),
+            title(m.from.as_deref().unwrap_or_default())
+        ),
+        None => format!("{} is no longer on the board", m.card_id),
+    };
+    format!("{what}: r retry, s skip, Esc reload")
+}
+
+/// Drops the rest of the queue and shows the board the conflict was found on.
+fn reload_after_conflict(
+    app: &mut App,
+    move_queue: &mut VecDeque<QueuedMove>,
+    m: &QueuedMove,
+    board: model::Board,
+) {
+    app.reconcile(board, &m.card_id);
+    app.banner = Some(format!(
+        "Reloaded; dropped the move of {} and {} queued after it",
+        m.card_id,
+        move_queue.len()
+    ));
+    move_queue.clear();
+    app.pending.clear();
+    app.undo.clear();
+}
+
+fn update_quit_banner(
+    app: &mut App,
+    quitting: bool,
+    move_queue: &VecDeque<QueuedMove>,
+    move_in_flight: bool,
+) {
+    if !quitting {
+        return;
+    }
+    let pending = move_queue.len() + if move_in_flight { 1 } else { 0 };
+    app.banner = if pending == 0 {
+        None
+    } else {
+        Some(format!("Finishing {pending} pending moves before quit..."))
+    };
+}
+
+fn render(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(2),
+        ])
+        .split(f.area());
+    let (status, main, help) = (chunks[0], chunks[1], chunks[2]);
+    draw_status(f, app, status);
+
+    let main = if app.disappeared.is_empty() {
+        main
+    } else {
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(app.disappeared.len().min(4) as u16 + 2),
+            ])
+            .split(main);
+        draw_disappeared(f, app, parts[1]);
+        parts[0]
+    };
+
+    if app.board.columns.is_empty() {
+        f.render_widget(
+            Paragraph::new("No columns found. Check board.txt.")
+                .block(Block::default().borders(Borders::ALL)),
+            main,
+        );
+    } else {
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Ratio(1, app.board.columns.len() as u32);
+                app.board.columns.len()
+            ])
+            .split(main);
+
+        for (i, r) in rects.iter().enumerate() {
+            draw_col(f, app, i, *r);
+        }
+    }
+
+    let mut footer = vec![];
+    if app.read_only {
+        let take_over = app.keymap.keys_for(Action::TakeOver).into_iter().next();
+        footer.push(Span::styled(
+            format!("READ-ONLY ({} take over)  ", take_over.unwrap_or_default()),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let Some(lock) = &app.lock {
+        footer.push(Span::styled(
+            format!("LOCKED until {}  ", lock.until),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let Some(text) = &app.filter_prompt {
+        footer.push(Span::styled(
+            format!("filter: {text}_"),
+            Style::default().fg(Color::Cyan),
+        ));
+        footer.push(Span::styled(
+            "  (Enter applies, empty clears, Esc cancels)",
+            Style::default().fg(Color::DarkGray),
+        ));
+    } else {
+        if let Some(filter) = &app.filter {
+            footer.push(Span::styled(
+                format!("FILTER {}  ", filter.text),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        footer.push(Span::raw(app.keymap.footer()));
+    }
+    f.render_widget(
+        Paragraph::new(Line::from(footer)).block(Block::default().borders(Borders::TOP)),
+        help,
+    );
+
+    if app.detail_open {
+        let Some(col) = app.board.columns.get(app.col) else {
+            return;
+        };
+        let Some(card) = col.cards.get(app.row) else {
+            return;
+        };
+
+        let area = centered(70, 45, f.area());
+        f.render_widget(Clear, area);
+        // A comment being typed docks under the card, which stays readable.
+        let (area, compose) = match &app.comment_prompt {
+            Some(prompt) => {
+                let rects = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(5)])
+                    .split(area);
+                (rects[0], Some((rects[1], prompt)))
+            }
+            None => (area, None),
+        };
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                &card.id,
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(card.title.clone()),
+            Line::from(""),
+        ];
+        let meta = badges(card, dates::today(), false);
+        if !meta.is_empty() {
+            lines.insert(3, Line::from(meta));
+        }
+
+        if card.description.trim().is_empty() {
+            lines.push(Line::from(Span::styled(
+                "No description",
+                Style::default().fg(Color::DarkGray),
+            )));
+        } else {
+            lines.extend(render_md::render(&card.description));
+        }
+        lines.extend(link_lines(app, card));
+        lines.extend(detail_lines(app, card));
+
+        let block = Block::default()
+            .title("Detail")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray));
+        let inner = block.inner(area);
+        let para = Paragraph::new(lines).wrap(Wrap { trim: false });
+        let overflow = para
+            .line_count(inner.width)
+            .saturating_sub(inner.height as usize);
+        app.detail_overflow.set(overflow);
+        let scroll = app.detail_scroll.min(overflow);
+        f.render_widget(para.scroll((scroll as u16, 0)).block(block), area);
+        if overflow > 0 {
+            let mut state = ScrollbarState::new(overflow).position(scroll);
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                area.inner(Margin::new(0, 1)),
+                &mut state,
+            );
+        }
+        if let Some((rect, (card_id, text))) = compose {
+            f.render_widget(
+                Paragraph::new(format!("{text}_"))
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::default()
+                            .title(format!("Comment on {card_id} (Enter send, Esc cancel)"))
+                            .borders(Borders::ALL)
+                            .border_style(Style::default().fg(Color::Cyan)),
+                    ),
+                rect,
+            );
+        }
+    }
+
+    if app.problems_open {
+        draw_problems(f, app);
+    }
+
+    if let Some(mention) = app.mentions.front() {
+        draw_mention(f, mention, app.mentions.len());
+    }
+
+    if let Some(title) = app.create_prompt.as_deref() {
+        draw_create_prompt(f, app, title);
+    }
+
+    if let Some(query) = app.server_prompt.as_deref() {
+        draw_server_prompt(f, query);
+    }
+
+    if let Some(card_id) = app.archive_prompt.as_deref() {
+        draw_archive_prompt(f, card_id, &app.archive_effects);
+    }
+
+    if let Some(card_ids) = &app.batch_archive {
+        draw_archive_prompt(f, &app::cards(card_ids.len()), &app.archive_effects);
+    }
+
+    if let Some((card_id, dst)) = &app.done_prompt
+        && let Some(col) = app.board.columns.get(*dst)
+    {
+        draw_done_prompt(f, card_id, col);
+    }
+
+    if let Some(view) = &app.history {
+        draw_history(f, view);
+    }
+    if let Some(cards) = &app.archived {
+        draw_archived(f, cards, app.archived_row);
+    }
+
+    if let (Some(text), Some(mention)) = (app.reply_prompt.as_deref(), app.mentions.front()) {
+        draw_reply_prompt(f, &mention.card_id, text);
+    }
+
+    if let Some(search) = app.search.as_ref().filter(|s| s.editing) {
+        draw_search_prompt(f, search);
+    }
+
+    if let Some(picker) = &app.picker {
+        draw_picker(f, picker);
+    }
+
+    if let Some(menu) = &app.quick_menu {
+        draw_quick_filters(f, menu);
+    }
+
+    if let Some(estimation) = &app.estimation {
+        draw_estimation(f, estimation);
+    }
+
+    if let Some(conflict) = &app.conflict {
+        draw_conflict(f, conflict);
+    }
+
+    if app.diff_open
+        && let Some(change) = &app.changed
+    {
+        draw_diff(f, change);
+    }
+
+    if app.graph_open {
+        draw_graph(f, app);
+    }
+
+    if let Some(stats) = &app.stats {
+        draw_stats(f, stats);
+    }
+
+    if app.debug_open {
+        draw_debug(f, app);
+    }
+
+    if app.help_open {
+        draw_help(f, app);
+    }
+}
+
+fn draw_help(f: &mut Frame, app: &App) {
+    let help = app.keymap.help();
+    let key_w = help
+        .iter()
+        .map(|(k, _)| k.chars().count())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = help
+        .into_iter()
+        .map(|(keys, desc)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{keys:>key_w$}  "),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(desc),
+            ])
+        })
+        .collect();
+
+    let area = centered(60, 80, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title("Keys (Esc closes; remap under [keys] in config.toml)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
+}
+
+/// The detail's "Links" section: each linked card with its title when it's
+/// on the board.
+fn link_lines(app: &App, card: &model::Card) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    for (key, label) in graph::LINK_FIELDS {
+        for id in card.values(key) {
+            let title = app.find_card(id).map(|c| c.title.as_str()).unwrap_or("");
+            lines.push(Line::from(vec![
+                Span::styled(format!("{label} "), Style::default().fg(Color::DarkGray)),
+                Span::raw(format!("{id} {title}")),
+            ]));
+        }
+    }
+    if !lines.is_empty() {
+        lines.splice(
+            0..0,
+            [
+                Line::from(""),
+                Line::from(Span::styled(
+                    "Links",
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+            ],
+        );
+    }
+    lines
+}
+
+/// The detail's comments and attachments, once fetched.
+fn detail_lines(app: &App, card: &model::Card) -> Vec<Line<'static>> {
+    let heading = |text: String| {
+        [
+            Line::from(""),
+            Line::from(Span::styled(
+                text,
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ]
+    };
+    let dim = Style::default().fg(Color::DarkGray);
+    let Some(detail) = app.details.peek(&card.id) else {
+        let note = match &app.detail_failed {
+            Some((id, msg)) if *id == card.id => format!("Details failed to load: {msg}"),
+            _ if app.fetching.as_deref() == Some(card.id.as_str()) => "Loading details...".into(),
+            _ => return vec![],
+        };
+        return vec![Line::from(""), Line::from(Span::styled(note, dim))];
+    };
+    let mut lines = vec![];
+    if !detail.fields.is_empty() {
+        lines.extend(heading("Fields".to_string()));
+        for (key, value) in &detail.fields {
+            let value = if dates::FIELDS.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                dates::show(value, dates::today())
+            } else {
+                value.clone()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{key}: "), dim),
+                Span::raw(value),
+            ]));
+        }
+    }
+    if !detail.attachments.is_empty() {
+        lines.extend(heading(format!(
+            "Attachments ({})",
+            detail.attachments.len()
+        )));
+        for a in &detail.attachments {
+            lines.push(Line::from(vec![
+                Span::raw(a.name.clone()),
+                Span::styled(format!(" {} KB", a.bytes.div_ceil(1024)), dim),
+            ]));
+        }
+    }
+    lines.extend(heading(format!("Comments ({})", detail.comments.len())));
+    if detail.comments.is_empty() {
+        lines.push(Line::from(Span::styled("None yet", dim)));
+    }
+    for c in &detail.comments {
+        lines.push(Line::from(vec![
+            Span::styled(
+                c.author.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" {}", dates::show(&c.created, dates::today())), dim),
+        ]));
+        lines.extend(c.body.lines().map(|l| Line::from(l.to_string())));
+    }
+    if !detail.history.is_empty() {
+        lines.extend(heading(format!("History ({})", detail.history.len())));
+        for r in &detail.history {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} {} ", r.commit, r.at), dim),
+                Span::raw(r.subject.clone()),
+                Span::styled(format!(" ({})", r.author), dim),
+            ]));
+        }
+    }
+    lines
+}
+
+fn draw_picker(f: &mut Frame, picker: &picker::Picker) {
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw(format!("> {}_", picker.query)),
+            Span::styled(
+                format!("  {} (Tab)", picker.mode.label()),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]),
+        Line::from(""),
+    ];
+    for (row, &i) in picker.visible().iter().enumerate() {
+        let mark = if Some(i) == picker.current {
+            "* "
+        } else {
+            "  "
+        };
+        let style = if row == picker.selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{mark}{}", picker.items[i]),
+            style,
+        )));
+    }
+
+    let area = centered(40, 50, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(picker.title.as_str())
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
+}
+
+fn draw_graph(f: &mut Frame, app: &App) {
+    let lines: Vec<Line> = graph::render(&app.board)
+        .into_iter()
+        .map(|l| {
+            if l.ends_with("↻ cycle") || l.starts_with("  ") && l.contains(" → ") {
+                Line::from(Span::styled(l, Style::default().fg(Color::Red)))
+            } else {
+                Line::from(l)
+            }
+        })
+        .collect();
+
+    let area = centered(80, 80, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title("Relations (experimental)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
+}
+
+fn draw_diff(f: &mut Frame, change: &app::RemoteChange) {
+    let area = centered(80, 70, f.area());
+    f.render_widget(Clear, area);
+
+    let lines: Vec<Line> = diff::lines(&change.old, &change.new)
+        .into_iter()
+        .map(|l| match l {
+            diff::DiffLine::Same(s) => Line::from(format!("  {s}")),
+            diff::DiffLine::Added(s) => Line::from(Span::styled(
+                format!("+ {s}"),
+                Style::default().fg(Color::Green),
+            )),
+            diff::DiffLine::Removed(s) => Line::from(Span::styled(
+                format!("- {s}"),
+                Style::default().fg(Color::Red),
+            )),
+        })
+        .collect();
+
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title(format!("{} changed remotely (Esc closes)", change.card_id))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        ),
+        area,
+    );
+}
+
+fn draw_stats(f: &mut Frame, stats: &stats::Stats) {
+    let area = centered(70, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let title_w = stats
+        .columns
+        .iter()
+        .map(|c| c.title.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("column".len());
+    let days = |d: Option<f64>| d.map_or("-".to_string(), |d| format!("{d}d"));
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:<title_w$}{:>7}{:>6}{:>9}{:>11}",
+            "column", "cards", "wip", "avg age", "in column"
+        ),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    for c in &stats.columns {
+        let wip = c.wip_limit.map_or("-".to_string(), |n| n.to_string());
+        let (style, flag) = if c.over_wip {
+            (Style::default().fg(Color::Red), "  over WIP")
+        } else {
+            (Style::default(), "")
+        };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{:<title_w$}{:>7}{wip:>6}{:>9}{:>11}{flag}",
+                c.title,
+                c.count,
+                days(c.avg_age_days),
+                days(c.avg_days_in)
+            ),
+            style,
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!(
+        "{} cards; {} done in the last {} days",
+        stats.total, stats.throughput.done, stats.throughput.days
+    )));
+    let over: Vec<&str> = stats
+        .columns
+        .iter()
+        .filter(|c| c.over_wip)
+        .map(|c| c.title.as_str())
+        .collect();
+    lines.push(if over.is_empty() {
+        Line::from("No column over its WIP limit")
+    } else {
+        Line::from(Span::styled(
+            format!("Over WIP: {}", over.join(", ")),
+            Style::default().fg(Color::Red),
+        ))
+    });
+    if stats.columns.iter().all(|c| c.avg_days_in.is_none()) {
+        lines.push(Line::from(Span::styled(
+            "No history yet: throughput counts done cards by their updated date",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title("Board stats (t or Esc closes)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
+}
+
+fn draw_debug(f: &mut Frame, app: &App) {
+    let area = centered(70, 45, f.area());
+    f.render_widget(Clear, area);
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:<22}{:>7}{:>8}{:>10}{:>10}",
+            "operation", "calls", "errors", "avg ms", "max ms"
+        ),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    for (op, s) in provider_metrics::snapshot() {
+        let style = if s.errors > 0 {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{op:<22}{:>7}{:>8}{:>10}{:>10}",
+                s.count,
+                s.errors,
+                s.avg().as_millis(),
+                s.max.as_millis()
+            ),
+            style,
+        )));
+    }
+    if !app.events.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "recent events",
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        for e in &app.events {
+            let style = match e {
+                events::Event::Error { .. } => Style::default().fg(Color::Red),
+                _ => Style::default(),
+            };
+            lines.push(Line::from(Span::styled(e.to_string(), style)));
+        }
+    }
+
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title("Provider metrics (F12)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        ),
+        area,
+    );
+}
+
+fn draw_conflict(f: &mut Frame, conflict: &app::MergeConflict) {
+    let area = centered(80, 70, f.area());
+    f.render_widget(Clear, area);
+
+    let mut lines = vec![
+        Line::from(format!(
+            "{} changed on disk while you were editing, and both edits touch the same lines.",
+            conflict.card_id
+        )),
+        Line::from(Span::styled(
+            "e resolve in editor  o keep yours  t keep the version on disk (Esc)",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from(""),
+    ];
+    for l in conflict.merged.lines() {
+        let style = if l.starts_with("<<<<<<< ") || l == "=======" || l.starts_with(">>>>>>> ") {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(l.to_string(), style)));
+    }
+
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title("Merge conflict")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red)),
+        ),
+        area,
+    );
+}
+
+fn draw_create_prompt(f: &mut Frame, app: &App, title: &str) {
+    let col = app
+        .board
+        .columns
+        .get(app.col)
+        .map(|c| c.title.as_str())
+        .unwrap_or_default();
+    let area = centered(50, 20, f.area());
+    let area = Rect {
+        height: area.height.min(3),
+        ..area
+    };
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(format!("{title}_")).block(
+            Block::default()
+                .title(format!(
+                    "New {} in {col} (Enter create, Esc cancel)",
+                    app.create_template
+                        .as_ref()
+                        .map_or("card", |t| t.name.as_str())
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
 }
 
-fn open_in_editor(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    path: &Path,
-) -> io::Result<()> {
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-
-    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-    let status = Command::new(editor).arg(path).status();
-
-    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    terminal.clear()?;
-    terminal.show_cursor()?;
-
-    let status = status?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(io::Error::other("editor exited with non-zero status"))
-    }
+/// The oldest unread mention, in the top right corner.
+fn draw_mention(f: &mut Frame, mention: &model::Mention, unread: usize) {
+    let full = f.area();
+    let width = full.width.min(48);
+    let area = Rect {
+        x: full.right().saturating_sub(width),
+        y: full.y,
+        width,
+        height: full.height.min(6),
+    };
+    let title = match unread {
+        1 => format!("@ {} on {}", mention.author, mention.card_id),
+        n => format!("@ {} on {} (+{})", mention.author, mention.card_id, n - 1),
+    };
+    let lines = vec![
+        Line::from(mention.body.lines().collect::<Vec<_>>().join(" ")),
+        Line::from(Span::styled(
+            "o open  R reply  Esc dismiss",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Magenta)),
+        ),
+        area,
+    );
 }
 
-fn update_quit_banner(
-    app: &mut App,
-    quitting: bool,
-    move_queue: &VecDeque<(String, String)>,
-    move_in_flight: bool,
-) {
-    if !quitting {
-        return;
-    }
-    let pending = move_queue.len() + if move_in_flight { 1 } else { 0 };
-    app.banner = if pending == 0 {
-        None
-    } else {
-        Some(format!("Finishing {pending} pending moves before quit..."))
+fn draw_server_prompt(f: &mut Frame, query: &str) {
+    let area = centered(60, 20, f.area());
+    let area = Rect {
+        height: area.height.min(3),
+        ..area
     };
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(format!("{query}_")).block(
+            Block::default()
+                .title("Search the whole project (Enter search, Esc cancel)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
 }
 
-fn spawn_move(card_id: String, dst: String) -> Receiver<Result<Option<model::Board>, String>> {
-    let (tx, rx) = mpsc::channel::<Result<Option<model::Board>, String>>();
-    thread::spawn(move || {
-        let res = panic::catch_unwind(|| {
-            let mut p = provider::from_env();
-            match p.move_card(&card_id, &dst) {
-                Ok(()) => {
-                    let _ = tx.send(Ok(None));
-                }
-                Err(move_err) => match p.load_board() {
-                    Ok(board) => {
-                        let _ = tx.send(Ok(Some(board)));
-                    }
-                    Err(_) => {
-                        let _ = tx.send(Err(move_err.to_string()));
-                    }
-                },
-            }
-        });
-        if res.is_err() {
-            let _ = tx.send(Err("worker panicked".to_string()));
-        }
-    });
-    rx
+fn draw_reply_prompt(f: &mut Frame, card_id: &str, text: &str) {
+    let area = centered(60, 20, f.area());
+    let area = Rect {
+        height: area.height.min(3),
+        ..area
+    };
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(format!("{text}_")).block(
+            Block::default()
+                .title(format!("Reply on {card_id} (Enter send, Esc cancel)"))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
 }
 
-fn render(f: &mut Frame, app: &App) {
-    let chunks = if app.banner.is_some() {
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Min(1),
-                Constraint::Length(2),
-            ])
-            .split(f.area())
-    } else {
-        Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(2)])
-            .split(f.area())
+/// Asks before a card goes into a finished-work column, with the column's
+/// policy to check it against.
+fn draw_done_prompt(f: &mut Frame, card_id: &str, col: &model::Column) {
+    let policy = col.meta.policy.as_deref().unwrap_or_default();
+    let area = centered(50, 20, f.area());
+    let text = vec![
+        Line::from(format!("Move {card_id} to {}?", col.title)),
+        Line::styled(policy.to_string(), Style::default().fg(Color::Yellow)),
+        Line::styled(
+            "y moves it / any other key cancels",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    let inner = area.width.saturating_sub(2).max(1) as usize;
+    let wrapped = policy.chars().count().div_ceil(inner).max(1) as u16;
+    let area = Rect {
+        height: (4 + wrapped).min(f.area().bottom().saturating_sub(area.y)),
+        ..area
     };
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(text).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .title(format!("Policy for {}", col.title))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        ),
+        area,
+    );
+}
 
-    let (banner_area, main, help) = if app.banner.is_some() {
-        (Some(chunks[0]), chunks[1], chunks[2])
-    } else {
-        (None, chunks[0], chunks[1])
+fn draw_archive_prompt(f: &mut Frame, card_id: &str, effects: &[String]) {
+    let area = centered(50, 20, f.area());
+    let area = Rect {
+        height: area.height.min(3 + effects.len() as u16),
+        ..area
     };
+    let mut lines = vec![Line::from(format!(
+        "Archive {card_id}? (y / any other key cancels)"
+    ))];
+    lines.extend(
+        effects
+            .iter()
+            .map(|e| Line::styled(format!("  • {e}"), Style::default().fg(Color::DarkGray))),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title("Archive")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        ),
+        area,
+    );
+}
+
+fn draw_archived(f: &mut Frame, cards: &[model::Card], selected: usize) {
+    let area = centered(80, 80, f.area());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .title(format!(
+            "Archived ({}) (j/k select, Esc close)",
+            cards.len()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    if cards.is_empty() {
+        f.render_widget(Paragraph::new("No archived cards").block(block), area);
+        return;
+    }
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let [list_area, detail_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .areas(inner);
 
-    if let (Some(a), Some(text)) = (banner_area, app.banner.as_deref()) {
+    let items: Vec<ListItem> = cards
+        .iter()
+        .map(|c| ListItem::new(format!("{} {}", c.id, c.title)))
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    f.render_stateful_widget(
+        List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+        list_area,
+        &mut state,
+    );
+    if let Some(card) = cards.get(selected) {
         f.render_widget(
-            Paragraph::new(Span::styled(text, Style::default().fg(Color::Yellow))),
-            a,
+            Paragraph::new(card.description.as_str())
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::TOP)),
+            detail_area,
         );
     }
+}
 
-    if app.board.columns.is_empty() {
+fn draw_history(f: &mut Frame, view: &app::HistoryView) {
+    let area = centered(90, 90, f.area());
+    f.render_widget(Clear, area);
+    let day = view.days[view.at];
+    let block = Block::default()
+        .title(format!(
+            "Board on {} ({}), snapshot {} of {} (←/→ day, Esc close)",
+            dates::format_date(day),
+            dates::relative(day - dates::today()),
+            view.at + 1,
+            view.days.len()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let columns = &view.board.columns;
+    if columns.is_empty() {
+        f.render_widget(Paragraph::new("No columns that day"), inner);
+        return;
+    }
+    let rects = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, columns.len() as u32);
+            columns.len()
+        ])
+        .split(inner);
+    for (col, rect) in columns.iter().zip(rects.iter()) {
+        let style = if col.meta.terminal {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        let lines: Vec<Line> = col
+            .cards
+            .iter()
+            .map(|c| Line::styled(format!("{} {}", c.id, c.title), style))
+            .collect();
         f.render_widget(
-            Paragraph::new("No columns found. Check board.txt.")
-                .block(Block::default().borders(Borders::ALL)),
-            main,
+            Paragraph::new(lines).block(
+                Block::default()
+                    .title(format!("{} ({})", col.title, col.cards.len()))
+                    .borders(Borders::ALL),
+            ),
+            *rect,
         );
-    } else {
-        let rects = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(vec![
-                Constraint::Ratio(1, app.board.columns.len() as u32);
-                app.board.columns.len()
-            ])
-            .split(main);
+    }
+}
 
-        for (i, r) in rects.iter().enumerate() {
-            draw_col(f, app, i, *r);
-        }
+fn draw_quick_filters(f: &mut Frame, menu: &app::QuickFilterMenu) {
+    let area = centered(60, 60, f.area());
+    f.render_widget(Clear, area);
+    let items: Vec<ListItem> = menu
+        .items
+        .iter()
+        .zip(&menu.on)
+        .map(|(q, on)| {
+            let mark = if *on { "[x]" } else { "[ ]" };
+            ListItem::new(format!("{mark} {}", q.name))
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(menu.row));
+    f.render_stateful_widget(
+        List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(
+                Block::default()
+                    .title("Quick filters (Space toggle, Esc apply)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            ),
+        area,
+        &mut state,
+    );
+}
+
+fn draw_estimation(f: &mut Frame, estimation: &app::Estimation) {
+    let Some(card) = estimation.current() else {
+        return;
+    };
+    let area = centered(60, 50, f.area());
+    f.render_widget(Clear, area);
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            &card.id,
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(card.title.clone()),
+    ];
+    let meta = badges(card, dates::today(), false);
+    if !meta.is_empty() {
+        lines.push(Line::from(meta));
     }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::raw("Points: "),
+        Span::styled(
+            format!("{}_", estimation.input),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]));
+    lines.push(Line::from(""));
+    lines.extend(render_md::render(&card.description));
 
+    let block = Block::default()
+        .title(format!(
+            "Estimate {}/{}",
+            estimation.at + 1,
+            estimation.queue.len()
+        ))
+        .title_bottom(Line::from(Span::styled(
+            " Enter save  Tab skip  ← back  Esc done ",
+            dim,
+        )))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
     f.render_widget(
-        Paragraph::new(help_text()).block(Block::default().borders(Borders::TOP)),
-        help,
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block),
+        area,
     );
+}
 
-    if app.detail_open {
-        let Some(col) = app.board.columns.get(app.col) else {
-            return;
-        };
-        let Some(card) = col.cards.get(app.row) else {
-            return;
-        };
+fn draw_search_prompt(f: &mut Frame, search: &app::Search) {
+    let area = f.area();
+    let area = Rect {
+        y: area.bottom().saturating_sub(3),
+        height: area.height.min(3),
+        ..area
+    };
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::raw(format!("/{}_", search.query)),
+            Span::styled(
+                search.error().map(|e| format!("  {e}")).unwrap_or_default(),
+                Style::default().fg(Color::Red),
+            ),
+        ]))
+        .block(
+            Block::default()
+                .title(format!(
+                    "Search id, title, description, {} (Tab mode, Enter keep, Esc clear)",
+                    search.mode.label()
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        ),
+        area,
+    );
+}
 
-        let area = centered(70, 45, f.area());
-        f.render_widget(Clear, area);
+fn draw_disappeared(f: &mut Frame, app: &App, area: Rect) {
+    let style = Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::CROSSED_OUT);
+    let items: Vec<ListItem> = app
+        .disappeared
+        .iter()
+        .map(|d| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} {}", d.card.id, d.card.title), style),
+                Span::styled(
+                    format!("  (was in {})", d.column),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
 
-        let mut lines = Vec::new();
-        lines.push(Line::from(Span::styled(
-            &card.id,
-            Style::default().add_modifier(Modifier::BOLD),
-        )));
-        lines.push(Line::from(""));
-        lines.push(Line::from(card.title.clone()));
-        lines.push(Line::from(""));
+    f.render_widget(
+        List::new(items).block(
+            Block::default()
+                .title("Recently disappeared")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        ),
+        area,
+    );
+}
 
-        if card.description.trim().is_empty() {
-            lines.push(Line::from(Span::styled(
-                "No description",
-                Style::default().fg(Color::DarkGray),
-            )));
-        } else {
-            for l in card.description.lines() {
-                lines.push(Line::from(l.to_string()));
-            }
-        }
+fn draw_problems(f: &mut Frame, app: &App) {
+    let area = centered(70, 45, f.area());
+    f.render_widget(Clear, area);
+
+    let lines: Vec<Line> = if app.board.problems.is_empty() {
+        vec![Line::from(Span::styled(
+            "No problems",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        app.board
+            .problems
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let style = if i == app.problem_row {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Line::from(Span::styled(p.msg.clone(), style))
+            })
+            .collect()
+    };
+
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title(format!("Problems ({})", app.board.problems.len()))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        ),
+        area,
+    );
+}
 
+/// The status on the right of the top line, and the banner over it from
+/// the left while there is one.
+fn draw_status(f: &mut Frame, app: &App, area: Rect) {
+    let style = if app.offline {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    f.render_widget(
+        Paragraph::new(Line::styled(app.status_line(dates::now_secs()), style).right_aligned()),
+        area,
+    );
+    if let Some(text) = app.banner.as_deref() {
+        let width = (Line::raw(text).width() as u16 + 2).min(area.width);
+        let area = Rect { width, ..area };
+        f.render_widget(Clear, area);
         f.render_widget(
-            Paragraph::new(lines).wrap(Wrap { trim: false }).block(
-                Block::default()
-                    .title("Detail")
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::DarkGray)),
-            ),
+            Paragraph::new(Span::styled(text, Style::default().fg(Color::Yellow))),
             area,
         );
     }
 }
 
+/// How long a banner stays over the status bar.
+const BANNER_FOR: Duration = Duration::from_secs(8);
+
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Roomy rows fall back to one line in columns shorter than this.
+const ROOMY_MIN_HEIGHT: usize = 16;
+
 fn draw_col(f: &mut Frame, app: &App, idx: usize, rect: Rect) {
     let col = &app.board.columns[idx];
     let focused = idx == app.col;
+    // `P` opens the policy above the cards, at most half the column.
+    let rect = match col.meta.policy.as_deref() {
+        Some(policy) if focused && app.policy_open => {
+            let inner = rect.width.saturating_sub(2).max(1) as usize;
+            let lines = policy.chars().count().div_ceil(inner).max(1) as u16;
+            let height = (lines + 2).min(rect.height / 2);
+            f.render_widget(
+                Paragraph::new(policy).wrap(Wrap { trim: true }).block(
+                    Block::default()
+                        .title("Policy (P closes)")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Yellow)),
+                ),
+                Rect { height, ..rect },
+            );
+            Rect {
+                y: rect.y + height,
+                height: rect.height - height,
+                ..rect
+            }
+        }
+        _ => rect,
+    };
+
+    let border = if focused {
+        Color::Cyan
+    } else {
+        col.meta
+            .color
+            .as_deref()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(Color::Gray)
+    };
+    let card_style = if col.meta.terminal {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
 
-    let border = if focused { Color::Cyan } else { Color::Gray };
+    let spinner = SPINNER[(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        / 100) as usize
+        % SPINNER.len()];
 
-    let items: Vec<ListItem> = col
-        .cards
+    let height = rect.height.saturating_sub(2) as usize;
+    let roomy = app.rows == config::RowStyle::Roomy && height >= ROOMY_MIN_HEIGHT;
+
+    let today = dates::today();
+    let marked = if idx == app.col { app.marked() } else { vec![] };
+    // The `f` filter hides the rest.
+    let rows = app.shown_rows(idx);
+    let items: Vec<ListItem> = rows
         .iter()
-        .map(|c| {
-            ListItem::new(Line::from(vec![
+        .map(|&r| {
+            let c = &col.cards[r];
+            let pending = app.pending.contains(&c.id);
+            let mut spans = Vec::new();
+            if pending {
+                spans.push(Span::styled(
+                    format!("{spinner} "),
+                    Style::default().fg(Color::Cyan),
+                ));
+            }
+            if marked.contains(&c.id) {
+                spans.push(Span::styled("+ ", Style::default().fg(Color::Magenta)));
+            }
+            if app
+                .board
+                .problems
+                .iter()
+                .any(|p| p.card_id.as_deref() == Some(c.id.as_str()))
+            {
+                spans.push(Span::styled("! ", Style::default().fg(Color::Yellow)));
+            }
+            if sla::is_breached(c) {
+                spans.push(Span::styled("⏱ ", Style::default().fg(Color::Red)));
+            }
+            spans.extend([
                 Span::styled(&c.id, Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" "),
                 Span::raw(c.title.clone()),
-            ]))
+            ]);
+            let style = if pending || !app.is_match(c) {
+                card_style.fg(Color::DarkGray)
+            } else {
+                card_style
+            };
+            if roomy {
+                let mut second = vec![Span::raw(" ")];
+                second.extend(badges(c, today, true));
+                return ListItem::new(vec![Line::from(spans), Line::from(second)]).style(style);
+            }
+            spans.extend(badges(c, today, true));
+            ListItem::new(Line::from(spans)).style(style)
         })
         .collect();
 
+    let more = if col.meta.more { "+" } else { "" };
+    let count = match (col.meta.wip_limit, &app.filter) {
+        (Some(limit), None) => format!("{}{more}/{limit}", col.cards.len()),
+        (None, None) => format!("{}{more}", col.cards.len()),
+        (limit, Some(_)) => format!(
+            "{}/{}{more}{}",
+            rows.len(),
+            col.cards.len(),
+            limit.map(|n| format!(", wip {n}")).unwrap_or_default()
+        ),
+    };
+    let title_style = match col.heat_budget() {
+        Some(budget) if app.heat => Style::default()
+            .fg(Color::Black)
+            .bg(heat_color(col.cards.len(), budget)),
+        _ if col.meta.wip_limit.is_some_and(|n| col.cards.len() > n) => {
+            Style::default().fg(Color::Red)
+        }
+        _ => Style::default(),
+    };
+
+    let selected = focused
+        .then(|| rows.iter().position(|&r| r == app.row))
+        .flatten();
+    // The viewport counts cards, not lines.
+    let height = if roomy { height / 2 } else { height };
+    let mut offsets = app.col_offsets.borrow_mut();
+    if offsets.len() <= idx {
+        offsets.resize(idx + 1, 0);
+    }
+    let offset = app::viewport(offsets[idx], selected, rows.len(), height);
+    offsets[idx] = offset;
+    let below = rows.len().saturating_sub(offset + height);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut block = Block::default()
+        .title(Span::styled(
+            format!(
+                "{} ({count}){}",
+                col.title,
+                if col.meta.policy.is_some() { " §" } else { "" }
+            ),
+            title_style,
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border));
+    if offset > 0 {
+        block = block.title(Line::styled(format!("↑ {offset} more"), dim).right_aligned());
+    }
+    if below > 0 {
+        block = block.title_bottom(Line::styled(format!("{below} more ↓"), dim).right_aligned());
+    }
     let list = List::new(items)
-        .block(
-            Block::default()
-                .title(format!("{} ({})", col.title, col.cards.len()))
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(border)),
-        )
+        .block(block)
         .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
 
-    let mut state = ListState::default();
-    if focused && !col.cards.is_empty() {
-        state.select(Some(app.row.min(col.cards.len() - 1)));
-    }
+    let mut state = ListState::default().with_offset(offset);
+    state.select(selected);
 
     f.render_stateful_widget(list, rect, &mut state);
 }
 
+/// Labels, priority, assignee and due date as colored spans, each with a
+/// leading space. `short` gives the assignee's initials, for board rows.
+fn badges(card: &model::Card, today: i64, short: bool) -> Vec<Span<'static>> {
+    const LABEL_COLORS: [Color; 6] = [
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Green,
+        Color::LightBlue,
+        Color::LightMagenta,
+    ];
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![];
+    if let Some(p) = &card.priority {
+        let color = match p.to_lowercase().as_str() {
+            "blocker" | "critical" | "urgent" | "highest" | "high" | "p0" | "p1" => Color::Red,
+            "medium" | "p2" => Color::Yellow,
+            _ => Color::DarkGray,
+        };
+        spans.push(Span::styled(format!(" ▲{p}"), Style::default().fg(color)));
+    }
+    for label in &card.labels {
+        let hash = label
+            .bytes()
+            .fold(0usize, |h, b| h.wrapping_mul(31) + b as usize);
+        let color = LABEL_COLORS[hash % LABEL_COLORS.len()];
+        spans.push(Span::styled(
+            format!(" #{label}"),
+            Style::default().fg(color),
+        ));
+    }
+    if let Some(a) = &card.assignee {
+        let who = if short {
+            config::initials(a)
+        } else {
+            a.clone()
+        };
+        spans.push(Span::styled(format!(" @{who}"), dim));
+    }
+    if let Some(due) = &card.due {
+        let style = match dates::parse_date(due) {
+            Some(d) if d < today => Style::default().fg(Color::Red),
+            Some(d) if d - today <= 2 => Style::default().fg(Color::Yellow),
+            _ => dim,
+        };
+        spans.push(Span::styled(
+            format!(" due {}", dates::show(due, today)),
+            style,
+        ));
+    }
+    spans
+}
+
+/// Green when empty, yellow at half the budget, red at or over it.
+fn heat_color(count: usize, budget: usize) -> Color {
+    let t = if budget == 0 {
+        1.0
+    } else {
+        (count as f32 / budget as f32).min(1.0)
+    };
+    let (r, g) = if t < 0.5 {
+        ((t * 2.0 * 255.0) as u8, 200)
+    } else {
+        (255, ((1.0 - t) * 2.0 * 200.0) as u8)
+    };
+    Color::Rgb(r, g, 0)
+}
+
 fn centered(px: u16, py: u16, r: Rect) -> Rect {
     let v = Layout::default()
         .direction(Direction::Vertical)
@@ -537,3 +3453,46 @@ fn centered(px: u16, py: u16, r: Rect) -> Rect {
         ])
         .split(v[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_drops_the_latest_queued_hop_of_a_card() {
+        let hop = |card_id: &str, from: &str, dst: &str| QueuedMove {
+            card_id: card_id.to_string(),
+            from: Some(from.to_string()),
+            dst: dst.to_string(),
+        };
+        let mut queue = VecDeque::from([
+            hop("A-1", "todo", "doing"),
+            hop("B-1", "todo", "doing"),
+            hop("A-1", "doing", "done"),
+        ]);
+
+        assert!(unqueue_last(&mut queue, "A-1"));
+        let left: Vec<_> = queue
+            .iter()
+            .map(|m| (m.card_id.as_str(), m.dst.as_str()))
+            .collect();
+        assert_eq!(left, [("A-1", "doing"), ("B-1", "doing")]);
+        assert!(!unqueue_last(&mut queue, "C-1"));
+    }
+
+    #[test]
+    fn editor_temp_files_are_new_and_private() {
+        let (a, _) = create_temp("../A-1").unwrap();
+        let (b, _) = create_temp("../A-1").unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), Some(std::env::temp_dir().as_path()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&a).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+//! Text matching shared by the board search and the pickers, in three modes
+//! cycled with `Tab` while typing.
+
+use regex::{Regex, RegexBuilder};
+
+use crate::model::Card;
+
+pub trait Matcher {
+    fn is_match(&self, text: &str) -> bool;
+
+    /// Matching in prose such as a description, where a loose match would
+    /// find nearly anything.
+    fn is_match_prose(&self, text: &str) -> bool {
+        self.is_match(text)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// A substring, or the query's characters in order (`mvcrd` finds
+    /// "Move card").
+    #[default]
+    Fuzzy,
+    /// A substring.
+    Exact,
+    Regex,
+}
+
+impl Mode {
+    pub fn next(self) -> Self {
+        match self {
+            Mode::Fuzzy => Mode::Exact,
+            Mode::Exact => Mode::Regex,
+            Mode::Regex => Mode::Fuzzy,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Mode::Fuzzy => "fuzzy",
+            Mode::Exact => "exact",
+            Mode::Regex => "regex",
+        }
+    }
+}
+
+/// A case-insensitive matcher for `query`, or why it isn't a valid regex.
+pub fn new(mode: Mode, query: &str) -> Result<Box<dyn Matcher>, String> {
+    let query = query.trim();
+    Ok(match mode {
+        Mode::Fuzzy => Box::new(Fuzzy(query.to_lowercase())),
+        Mode::Exact => Box::new(Exact(query.to_lowercase())),
+        Mode::Regex => Box::new(
+            RegexBuilder::new(query)
+                .case_insensitive(true)
+                .build()
+                .map(Pattern)
+                .map_err(|e| match e {
+                    regex::Error::Syntax(msg) => msg.lines().last().unwrap_or("").to_string(),
+                    e => e.to_string(),
+                })?,
+        ),
+    })
+}
+
+/// The card's id, title or description matches.
+pub fn card_matches(m: &dyn Matcher, card: &Card) -> bool {
+    m.is_match(&card.id) || m.is_match(&card.title) || m.is_match_prose(&card.description)
+}
+
+struct Exact(String);
+
+impl Matcher for Exact {
+    fn is_match(&self, text: &str) -> bool {
+        text.to_lowercase().contains(&self.0)
+    }
+}
+
+struct Fuzzy(String);
+
+impl Matcher for Fuzzy {
+    fn is_match(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        text.contains(&self.0) || in_order(&text, &self.0)
+    }
+
+    fn is_match_prose(&self, text: &str) -> bool {
+        text.to_lowercase().contains(&self.0)
+    }
+}
+
+struct Pattern(Regex);
+
+impl Matcher for Pattern {
+    fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+}
+
+/// The non-space characters of `query` appear in order in `text`.
+fn in_order(text: &str, query: &str) -> bool {
+    let mut chars = text.chars();
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|c| chars.any(|t| t == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_match_titles_and_keep_descriptions_strict() {
+        let card = Card {
+            id: "FLOW-12".into(),
+            title: "Move card between columns".into(),
+            description: "a long text mentioning very little".into(),
+            ..Card::default()
+        };
+        let hits = |mode, q| card_matches(new(mode, q).unwrap().as_ref(), &card);
+
+        assert!(hits(Mode::Fuzzy, "mvcrd"));
+        assert!(!hits(Mode::Fuzzy, "lngtxt"));
+        assert!(!hits(Mode::Exact, "mvcrd"));
+        assert!(hits(Mode::Exact, "CARD BETWEEN"));
+        assert!(hits(Mode::Regex, r"^flow-1\d$"));
+        assert!(!hits(Mode::Regex, r"^card"));
+        assert!(new(Mode::Regex, "(").is_err());
+    }
+}
@@ -1,15 +1,242 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sort::SortKey;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Card {
     pub id: String,
     pub title: String,
+    #[serde(default)]
     pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    /// `YYYY-MM-DD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    /// Any other front matter (local boards) or provider data, e.g. Jira
+    /// timestamps. Scalars are one-element lists.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Vec<String>>,
+}
+
+impl Card {
+    /// Moves the keys with a typed field (`labels`, `priority`, `assignee`,
+    /// `due`) out of `fields` and keeps the rest.
+    pub fn set_fields(&mut self, mut fields: BTreeMap<String, Vec<String>>) {
+        let mut scalar = |key: &str| fields.remove(key).and_then(|v| v.into_iter().next());
+        self.priority = scalar("priority");
+        self.assignee = scalar("assignee");
+        self.due = scalar("due");
+        self.labels = fields.remove("labels").unwrap_or_default();
+        self.fields = fields;
+    }
+
+    /// The values of a typed or free-form field, matched case-insensitively.
+    pub fn values(&self, key: &str) -> Vec<&str> {
+        match key.to_ascii_lowercase().as_str() {
+            "label" | "labels" => self.labels.iter().map(String::as_str).collect(),
+            "priority" => self.priority.as_deref().into_iter().collect(),
+            "assignee" => self.assignee.as_deref().into_iter().collect(),
+            "due" => self.due.as_deref().into_iter().collect(),
+            _ => self
+                .fields
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.iter().map(String::as_str).collect())
+                .unwrap_or_default(),
+        }
+    }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Column {
     pub id: String,
     pub title: String,
+    #[serde(default)]
     pub cards: Vec<Card>,
+    #[serde(default)]
+    pub meta: ColumnMeta,
+}
+
+/// Column properties reported by the backend. The default is unconstrained.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnMeta {
+    /// Moves into the column are refused once it holds this many cards.
+    pub wip_limit: Option<usize>,
+    /// Soft card-count target used by the heatmap; never enforced.
+    pub budget: Option<usize>,
+    /// Cards here are finished work.
+    pub terminal: bool,
+    /// Border color name, e.g. `green` or `#ff8800`.
+    pub color: Option<String>,
+    pub can_create: bool,
+    /// The provider holds more cards than it fetched; see `load_more`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub more: bool,
+    /// Cards moved in are placed in this order rather than at the bottom.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_sort: Option<SortKey>,
+    /// The team's agreement for the column, e.g. its definition of done.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+}
+
+impl Default for ColumnMeta {
+    fn default() -> Self {
+        Self {
+            wip_limit: None,
+            budget: None,
+            terminal: false,
+            color: None,
+            can_create: true,
+            more: false,
+            auto_sort: None,
+            policy: None,
+        }
+    }
 }
 
+impl Column {
+    /// The budget, falling back to the WIP limit.
+    pub fn heat_budget(&self) -> Option<usize> {
+        self.meta.budget.or(self.meta.wip_limit)
+    }
+
+    pub fn at_wip_limit(&self) -> bool {
+        self.meta.wip_limit.is_some_and(|n| self.cards.len() >= n)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     pub columns: Vec<Column>,
+    /// Non-fatal issues found while loading, shown in the problems panel.
+    #[serde(default)]
+    pub problems: Vec<Problem>,
+}
+
+impl Board {
+    /// The column holding the card.
+    pub fn column_of(&self, card_id: &str) -> Option<&Column> {
+        self.columns
+            .iter()
+            .find(|col| col.cards.iter().any(|c| c.id == card_id))
+    }
+
+    /// A column's index by id, or else by title, ignoring case.
+    pub fn column_named(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.id == name).or_else(|| {
+            self.columns
+                .iter()
+                .position(|c| c.id.eq_ignore_ascii_case(name) || c.title.eq_ignore_ascii_case(name))
+        })
+    }
+}
+
+/// A saved filter kept by the provider, e.g. a Jira board's quick filter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuickFilter {
+    pub id: String,
+    pub name: String,
+    /// The provider's query language, e.g. JQL.
+    pub query: String,
+}
+
+/// Someone a card can be assigned to. `id` is what the provider assigns by,
+/// e.g. a Jira account id; local boards use the name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+}
+
+/// A card entering a column, from a local board's activity log or Jira's
+/// changelog. `from` is `None` where the card was created.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub card_id: String,
+    pub from: Option<String>,
+    pub to: String,
+    /// Seconds since the epoch.
+    pub at: u64,
+}
+
+/// A comment that mentions the current user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mention {
+    /// The comment's id, unique across the provider.
+    pub id: String,
+    pub card_id: String,
+    pub author: String,
+    pub body: String,
+}
+
+/// What a card's detail view shows beyond the board load, fetched for one
+/// card at a time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CardDetail {
+    /// Oldest first.
+    pub comments: Vec<Comment>,
+    pub attachments: Vec<Attachment>,
+    /// Fields as label and text, beyond what the board load carries, e.g.
+    /// Jira's reporter or a local card's whole front matter.
+    pub fields: Vec<(String, String)>,
+    /// Commits to the card's file, newest first, on boards kept in git.
+    pub history: Vec<Revision>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Revision {
+    /// Abbreviated hash.
+    pub commit: String,
+    pub at: String,
+    pub author: String,
+    pub subject: String,
+}
+
+impl CardDetail {
+    /// Roughly the bytes it holds, for bounding caches.
+    pub fn size(&self) -> usize {
+        let comments: usize = self
+            .comments
+            .iter()
+            .map(|c| c.author.len() + c.created.len() + c.body.len())
+            .sum();
+        let attachments: usize = self.attachments.iter().map(|a| a.name.len() + 8).sum();
+        let fields: usize = self.fields.iter().map(|(k, v)| k.len() + v.len()).sum();
+        let history: usize = self
+            .history
+            .iter()
+            .map(|r| r.commit.len() + r.at.len() + r.author.len() + r.subject.len())
+            .sum();
+        comments + attachments + fields + history
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    pub author: String,
+    /// As the provider gave it, e.g. an RFC 3339 timestamp.
+    pub created: String,
+    pub body: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    pub name: String,
+    pub bytes: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Problem {
+    #[serde(default)]
+    pub card_id: Option<String>,
+    pub msg: String,
 }
@@ -0,0 +1,553 @@
+//! Keeps a remote board usable through an outage. Every successful load is
+//! cached in `~/.cache/flow/<board>.json`; when the backend can't be reached
+//! the cached board is served instead and moves, archives and editor saves
+//! are queued in the same file, to be sent in order once a call gets through
+//! again.
+
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activity,
+    card_template::Draft,
+    dates,
+    events::{self, Event},
+    freeze,
+    graph::LinkKind,
+    log,
+    model::{Board, Card, CardDetail, Mention, Move, Problem, QuickFilter, User},
+    provider::{Provider, ProviderError},
+};
+
+#[derive(Serialize, Deserialize)]
+struct Cache {
+    /// Unix seconds of the last successful load.
+    saved: u64,
+    board: Board,
+    /// Writes made while offline, oldest first.
+    #[serde(default)]
+    moves: Vec<Queued>,
+}
+
+/// Untagged so caches written when only moves were queued still load.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Queued {
+    Move { card_id: String, to: String },
+    Save { card_id: String, text: String },
+    Archive { archive: String },
+}
+
+impl Queued {
+    fn card_id(&self) -> &str {
+        match self {
+            Queued::Move { card_id, .. } | Queued::Save { card_id, .. } => card_id,
+            Queued::Archive { archive } => archive,
+        }
+    }
+
+    fn send(&self, p: &mut dyn Provider) -> Result<(), ProviderError> {
+        match self {
+            Queued::Move { card_id, to } => p.move_card(card_id, to),
+            Queued::Save { card_id, text } => p.save_card(card_id, text),
+            Queued::Archive { archive } => p.archive_card(archive),
+        }
+    }
+}
+
+/// `$XDG_CACHE_HOME/flow/<key>.json`, or `~/.cache/flow/<key>.json`.
+pub fn cache_path(key: &str) -> Option<PathBuf> {
+    let dir = match std::env::var("XDG_CACHE_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => PathBuf::from(std::env::var("HOME").ok()?).join(".cache"),
+    };
+    Some(
+        dir.join("flow")
+            .join(format!("{}.json", freeze::file_name(key))),
+    )
+}
+
+pub struct Offline {
+    inner: Box<dyn Provider>,
+    path: Option<PathBuf>,
+    offline: bool,
+}
+
+impl Offline {
+    pub fn new(inner: Box<dyn Provider>, path: Option<PathBuf>) -> Self {
+        Self {
+            inner,
+            path,
+            offline: false,
+        }
+    }
+
+    fn read(&self) -> Option<Cache> {
+        let s = fs::read_to_string(self.path.as_ref()?).ok()?;
+        serde_json::from_str(&s).ok()
+    }
+
+    /// Failing to cache only costs the next outage, so it's logged, not raised.
+    fn write(&self, cache: &Cache) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let res = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, serde_json::to_vec(cache)?));
+        if let Err(e) = res {
+            log::write(&format!("offline cache {}: {e}", path.display()));
+        }
+    }
+
+    /// Sends queued writes in order, stopping at the first that can't get
+    /// through. A write the backend refuses is dropped.
+    fn replay(&mut self, cache: &mut Option<Cache>) -> Result<usize, ProviderError> {
+        let Some(c) = cache.as_mut() else {
+            return Ok(0);
+        };
+        let mut sent = 0;
+        while let Some(m) = c.moves.first() {
+            match m.send(self.inner.as_mut()) {
+                Ok(()) => sent += 1,
+                Err(e @ ProviderError::Network { .. }) => return Err(e),
+                Err(e) => log::write(&format!("offline: dropped change to {}: {e}", m.card_id())),
+            }
+            c.moves.remove(0);
+            self.write(c);
+        }
+        Ok(sent)
+    }
+
+    fn went_offline(&mut self, queued: usize) {
+        self.offline = true;
+        events::publish(Event::Offline { queued });
+    }
+
+    /// Sends `write` after anything already queued, or queues it behind them
+    /// when the backend can't be reached.
+    fn send_or_queue(&mut self, write: Queued) -> Result<(), ProviderError> {
+        let mut cache = self.read();
+        let sent = self
+            .replay(&mut cache)
+            .and_then(|_| write.send(self.inner.as_mut()));
+        match (sent, cache) {
+            (Err(ProviderError::Network { .. }), Some(mut cache)) => {
+                cache.moves.push(write);
+                self.write(&cache);
+                self.went_offline(cache.moves.len());
+                Ok(())
+            }
+            (sent, _) => sent,
+        }
+    }
+
+    /// [`Self::send_or_queue`] for a batch: the cards `send` didn't get to
+    /// before the connection dropped are queued and reported done.
+    fn send_all_or_queue(
+        &mut self,
+        card_ids: &[String],
+        done: &mut dyn FnMut(&str),
+        send: impl FnOnce(&mut dyn Provider, &mut dyn FnMut(&str)) -> Result<(), ProviderError>,
+        queued: impl Fn(&str) -> Queued,
+    ) -> Result<(), ProviderError> {
+        let mut cache = self.read();
+        let mut sent = Vec::new();
+        let res = self.replay(&mut cache).and_then(|_| {
+            send(self.inner.as_mut(), &mut |id| {
+                sent.push(id.to_string());
+                done(id);
+            })
+        });
+        match (res, cache) {
+            (Err(ProviderError::Network { .. }), Some(mut cache)) => {
+                for id in card_ids.iter().filter(|id| !sent.contains(id)) {
+                    cache.moves.push(queued(id));
+                    done(id);
+                }
+                self.write(&cache);
+                self.went_offline(cache.moves.len());
+                Ok(())
+            }
+            (res, _) => res,
+        }
+    }
+}
+
+/// The cached board with queued moves applied, noting where it came from.
+fn cached_board(cache: Cache) -> Board {
+    let mut board = cache.board;
+    for m in &cache.moves {
+        let to = match m {
+            Queued::Move { to, .. } => Some(to),
+            Queued::Archive { .. } => None,
+            // The edit shows up once the backend has it.
+            Queued::Save { .. } => continue,
+        };
+        let card = board.columns.iter_mut().find_map(|col| {
+            let i = col.cards.iter().position(|c| c.id == m.card_id())?;
+            Some(col.cards.remove(i))
+        });
+        if let Some(card) = card
+            && let Some(col) = board.columns.iter_mut().find(|c| Some(&c.id) == to)
+        {
+            col.cards.push(card);
+        }
+    }
+    board.problems.push(Problem {
+        card_id: None,
+        msg: format!(
+            "Offline: showing the board as loaded at {}; {} changes queued",
+            dates::format_timestamp(cache.saved),
+            cache.moves.len()
+        ),
+    });
+    board
+}
+
+impl Provider for Offline {
+    fn load_board(&mut self) -> Result<Board, ProviderError> {
+        let mut cache = self.read();
+        let loaded = self
+            .replay(&mut cache)
+            .and_then(|sent| Ok((sent, self.inner.load_board()?)));
+        match loaded {
+            Ok((sent, board)) => {
+                if self.offline {
+                    self.offline = false;
+                    events::publish(Event::Online { replayed: sent });
+                }
+                self.write(&Cache {
+                    saved: dates::now_secs(),
+                    board: board.clone(),
+                    moves: vec![],
+                });
+                Ok(board)
+            }
+            Err(e @ ProviderError::Network { .. }) => {
+                let Some(cache) = cache else {
+                    return Err(e);
+                };
+                log::write(&format!("offline: {e}"));
+                self.went_offline(cache.moves.len());
+                Ok(cached_board(cache))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        self.send_or_queue(Queued::Move {
+            card_id: card_id.to_string(),
+            to: to_col_id.to_string(),
+        })
+    }
+
+    fn move_cards(
+        &mut self,
+        card_ids: &[String],
+        to_col_id: &str,
+        moved: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        self.send_all_or_queue(
+            card_ids,
+            moved,
+            |p, done| p.move_cards(card_ids, to_col_id, done),
+            |id| Queued::Move {
+                card_id: id.to_string(),
+                to: to_col_id.to_string(),
+            },
+        )
+    }
+
+    fn save_card(&mut self, card_id: &str, text: &str) -> Result<(), ProviderError> {
+        self.send_or_queue(Queued::Save {
+            card_id: card_id.to_string(),
+            text: text.to_string(),
+        })
+    }
+
+    fn create_card_with_id(
+        &mut self,
+        to_col_id: &str,
+        card_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        self.inner.create_card_with_id(to_col_id, card_id, title)
+    }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        self.inner.create_card_with_title(to_col_id, title)
+    }
+
+    fn create_card_from(
+        &mut self,
+        to_col_id: &str,
+        draft: &Draft,
+    ) -> Result<String, ProviderError> {
+        self.inner.create_card_from(to_col_id, draft)
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        self.inner.update_card(card_id, title, description)
+    }
+
+    fn card_path(&self, card_id: &str) -> Result<PathBuf, ProviderError> {
+        self.inner.card_path(card_id)
+    }
+
+    fn load_more(&mut self, col_id: &str) -> Result<(), ProviderError> {
+        self.inner.load_more(col_id)
+    }
+
+    fn quick_filters(&mut self) -> Result<Vec<QuickFilter>, ProviderError> {
+        self.inner.quick_filters()
+    }
+
+    fn search(&mut self, query: &str) -> Result<Vec<Card>, ProviderError> {
+        self.inner.search(query)
+    }
+
+    fn pull_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        self.inner.pull_card(card_id, to_col_id)
+    }
+
+    fn reorder_column(&mut self, col_id: &str, order: &[String]) -> Result<(), ProviderError> {
+        self.inner.reorder_column(col_id, order)
+    }
+
+    fn set_quick_filters(&mut self, filters: &[QuickFilter]) -> Result<(), ProviderError> {
+        self.inner.set_quick_filters(filters)
+    }
+
+    fn set_estimate(&mut self, card_id: &str, points: f64) -> Result<(), ProviderError> {
+        self.inner.set_estimate(card_id, points)
+    }
+
+    fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+        self.send_or_queue(Queued::Archive {
+            archive: card_id.to_string(),
+        })
+    }
+
+    fn archive_cards(
+        &mut self,
+        card_ids: &[String],
+        archived: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        self.send_all_or_queue(
+            card_ids,
+            archived,
+            |p, done| p.archive_cards(card_ids, done),
+            |id| Queued::Archive {
+                archive: id.to_string(),
+            },
+        )
+    }
+
+    fn archived_cards(&mut self) -> Result<Vec<Card>, ProviderError> {
+        self.inner.archived_cards()
+    }
+
+    fn card_detail(&mut self, card_id: &str) -> Result<CardDetail, ProviderError> {
+        self.inner.card_detail(card_id)
+    }
+
+    fn mentions(&mut self, minutes: u64) -> Result<Vec<Mention>, ProviderError> {
+        self.inner.mentions(minutes)
+    }
+
+    fn moves(&mut self, since: u64) -> Result<Vec<Move>, ProviderError> {
+        self.inner.moves(since)
+    }
+
+    fn activity(&mut self) -> Result<Vec<activity::Entry>, ProviderError> {
+        self.inner.activity()
+    }
+
+    fn add_comment(&mut self, card_id: &str, body: &str) -> Result<(), ProviderError> {
+        self.inner.add_comment(card_id, body)
+    }
+
+    fn assignable_users(&mut self, card_id: &str) -> Result<Vec<User>, ProviderError> {
+        self.inner.assignable_users(card_id)
+    }
+
+    fn set_assignee(&mut self, card_id: &str, user: Option<&User>) -> Result<(), ProviderError> {
+        self.inner.set_assignee(card_id, user)
+    }
+
+    fn link_cards(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        kind: LinkKind,
+    ) -> Result<(), ProviderError> {
+        self.inner.link_cards(from_id, to_id, kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider_demo::DemoProvider;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    /// The demo board behind a cable that can be pulled.
+    struct Flaky {
+        inner: DemoProvider,
+        up: Rc<Cell<bool>>,
+        archived: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Flaky {
+        fn check(&self) -> Result<(), ProviderError> {
+            if self.up.get() {
+                Ok(())
+            } else {
+                Err(ProviderError::Network {
+                    op: "test".into(),
+                    url: "http://down".into(),
+                    msg: "connection refused".into(),
+                })
+            }
+        }
+    }
+
+    impl Provider for Flaky {
+        fn load_board(&mut self) -> Result<Board, ProviderError> {
+            self.check()?;
+            self.inner.load_board()
+        }
+
+        fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+            self.check()?;
+            self.inner.move_card(card_id, to_col_id)
+        }
+
+        fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+            self.check()?;
+            self.archived.borrow_mut().push(card_id.to_string());
+            Ok(())
+        }
+    }
+
+    fn column_of(board: &Board, card_id: &str) -> String {
+        board
+            .columns
+            .iter()
+            .find(|col| col.cards.iter().any(|c| c.id == card_id))
+            .map(|col| col.id.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn moves_made_offline_are_shown_at_once_and_sent_on_reconnect() {
+        let n = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("flow-offline-test-{n}.json"));
+        let up = Rc::new(Cell::new(true));
+        let mut p = Offline::new(
+            Box::new(Flaky {
+                inner: DemoProvider::new(992, 12),
+                up: Rc::clone(&up),
+                archived: Rc::default(),
+            }),
+            Some(path.clone()),
+        );
+        let board = p.load_board().unwrap();
+        let card = board.columns[0].cards[0].id.clone();
+        let last = board.columns.last().unwrap().id.clone();
+
+        up.set(false);
+        p.move_card(&card, &last).unwrap();
+        let board = p.load_board().unwrap();
+        assert_eq!(column_of(&board, &card), last);
+        assert!(
+            board
+                .problems
+                .iter()
+                .any(|pr| pr.msg.contains("1 changes queued"))
+        );
+
+        up.set(true);
+        let board = p.load_board().unwrap();
+        assert_eq!(column_of(&board, &card), last);
+        assert!(
+            board
+                .problems
+                .iter()
+                .all(|pr| !pr.msg.starts_with("Offline"))
+        );
+        assert!(p.read().unwrap().moves.is_empty());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn batch_moves_and_archives_are_queued_while_offline() {
+        let n = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("flow-offline-batch-{n}.json"));
+        let up = Rc::new(Cell::new(true));
+        let archived = Rc::new(RefCell::new(Vec::new()));
+        let mut p = Offline::new(
+            Box::new(Flaky {
+                inner: DemoProvider::new(993, 12),
+                up: Rc::clone(&up),
+                archived: Rc::clone(&archived),
+            }),
+            Some(path.clone()),
+        );
+        let board = p.load_board().unwrap();
+        let ids: Vec<String> = board.columns[0]
+            .cards
+            .iter()
+            .map(|c| c.id.clone())
+            .collect();
+        let (moved, gone) = (ids[..2].to_vec(), ids[2..3].to_vec());
+        let last = board.columns.last().unwrap().id.clone();
+
+        up.set(false);
+        let mut reported = Vec::new();
+        p.move_cards(&moved, &last, &mut |id| reported.push(id.to_string()))
+            .unwrap();
+        p.archive_cards(&gone, &mut |id| reported.push(id.to_string()))
+            .unwrap();
+        assert_eq!(reported, ids[..3]);
+        let board = p.load_board().unwrap();
+        assert!(moved.iter().all(|id| column_of(&board, id) == last));
+        assert!(
+            board
+                .columns
+                .iter()
+                .all(|c| c.cards.iter().all(|c| c.id != gone[0]))
+        );
+        assert!(archived.borrow().is_empty());
+
+        up.set(true);
+        let board = p.load_board().unwrap();
+        assert!(moved.iter().all(|id| column_of(&board, id) == last));
+        assert_eq!(*archived.borrow(), gone);
+        assert!(p.read().unwrap().moves.is_empty());
+        let _ = fs::remove_file(path);
+    }
+}
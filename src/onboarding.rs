@@ -0,0 +1,385 @@
+use std::{io, path::Path, time::Duration};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::{
+    config::{self, Config, Profile},
+    provider, provider_local, template,
+};
+
+const PROVIDERS: [(&str, &str); 5] = [
+    ("local", "Local board (markdown files on disk)"),
+    ("jira", "Jira Cloud board"),
+    ("linear", "Linear team"),
+    ("github", "GitHub issues, with labels as columns"),
+    ("demo", "Generated demo board (nothing is saved)"),
+];
+
+const STARTER: &str = r#"
+[[columns]]
+id = "todo"
+title = "TO DO"
+
+[[columns]]
+id = "in_progress"
+title = "IN PROGRESS"
+
+[[columns]]
+id = "done"
+title = "DONE"
+"#;
+
+/// True on a first run: no config file, no env setup, and no board where a
+/// local provider would look.
+pub fn needs_onboarding() -> bool {
+    if config::exists()
+        || std::env::var_os("FLOW_PROVIDER").is_some()
+        || std::env::var_os("FLOW_BOARD_PATH").is_some()
+    {
+        return false;
+    }
+    let local = provider_local::LocalProvider::from_env();
+    !local.root().join("board.txt").exists()
+}
+
+pub enum Outcome {
+    Configured,
+    Quit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Step {
+    Provider,
+    Fields,
+    Testing,
+}
+
+struct Field {
+    label: &'static str,
+    value: String,
+    secret: bool,
+}
+
+struct Wizard {
+    step: Step,
+    provider: usize,
+    fields: Vec<Field>,
+    field: usize,
+    error: Option<String>,
+}
+
+impl Wizard {
+    fn new() -> Self {
+        Self {
+            step: Step::Provider,
+            provider: 0,
+            fields: vec![],
+            field: 0,
+            error: None,
+        }
+    }
+
+    fn provider_id(&self) -> &'static str {
+        PROVIDERS[self.provider].0
+    }
+
+    fn fields_for(provider: &str) -> Vec<Field> {
+        let field = |label, value: &str, secret| Field {
+            label,
+            value: value.to_string(),
+            secret,
+        };
+        match provider {
+            "local" => vec![field("Board path", "~/.config/flow/boards/default", false)],
+            "jira" => vec![
+                field("Base URL", "https://", false),
+                field("Email", "", false),
+                field("API token", "", true),
+                field("Board id", "", false),
+            ],
+            "linear" => vec![
+                field("API key", "", true),
+                field("Team key", "", false),
+                field("Project", "", false),
+            ],
+            "github" => vec![
+                field("Token", "", true),
+                field("Repository (owner/name)", "", false),
+            ],
+            _ => vec![],
+        }
+    }
+
+    fn value(&self, label: &str) -> Option<String> {
+        self.fields
+            .iter()
+            .find(|f| f.label == label)
+            .map(|f| f.value.trim().to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn profile(&self) -> Profile {
+        Profile {
+            provider: Some(self.provider_id().to_string()),
+            path: self.value("Board path"),
+            base_url: self.value("Base URL"),
+            email: self.value("Email"),
+            api_token: self
+                .value("API token")
+                .or_else(|| self.value("API key"))
+                .or_else(|| self.value("Token")),
+            board_id: self.value("Board id"),
+            team: self.value("Team key"),
+            project: self.value("Project"),
+            repo: self.value("Repository (owner/name)"),
+            ..Profile::default()
+        }
+    }
+
+    /// Returns false when the user backs out of the wizard.
+    fn key(&mut self, code: KeyCode) -> bool {
+        match self.step {
+            Step::Provider => match code {
+                KeyCode::Esc | KeyCode::Char('q') => return false,
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.provider = (self.provider + 1).min(PROVIDERS.len() - 1)
+                }
+                KeyCode::Char('k') | KeyCode::Up => self.provider = self.provider.saturating_sub(1),
+                KeyCode::Enter => {
+                    self.fields = Self::fields_for(self.provider_id());
+                    self.field = 0;
+                    self.error = None;
+                    self.step = if self.fields.is_empty() {
+                        Step::Testing
+                    } else {
+                        Step::Fields
+                    };
+                }
+                _ => {}
+            },
+            Step::Fields => match code {
+                KeyCode::Esc => {
+                    self.error = None;
+                    self.step = Step::Provider;
+                }
+                KeyCode::Tab | KeyCode::Down => {
+                    self.field = (self.field + 1).min(self.fields.len() - 1)
+                }
+                KeyCode::BackTab | KeyCode::Up => self.field = self.field.saturating_sub(1),
+                KeyCode::Enter if self.field + 1 < self.fields.len() => self.field += 1,
+                KeyCode::Enter => self.step = Step::Testing,
+                KeyCode::Backspace => {
+                    self.fields[self.field].value.pop();
+                }
+                KeyCode::Char(c) => self.fields[self.field].value.push(c),
+                _ => {}
+            },
+            Step::Testing => {}
+        }
+        true
+    }
+
+    /// Checks that the board loads, then writes the config.
+    fn finish(&mut self) -> Result<(), String> {
+        let profile = self.profile();
+        if let Some(path) = &profile.path {
+            prepare_local(&config::expand_home(path))?;
+        }
+        provider::from_profile(&profile)
+            .load_board()
+            .map_err(|e| format!("connection test failed: {e}"))?;
+
+        let mut cfg = config::load()?;
+        cfg.profiles.insert("default".to_string(), profile);
+        cfg.default_profile = Some("default".to_string());
+        save(&cfg)
+    }
+}
+
+fn save(cfg: &Config) -> Result<(), String> {
+    config::save(cfg)
+        .map(|_| ())
+        .map_err(|e| format!("could not write config: {e}"))
+}
+
+/// Creates a starter board at `root` unless one is already there.
+fn prepare_local(root: &Path) -> Result<(), String> {
+    if root.join("board.txt").exists() {
+        return Ok(());
+    }
+    template::import(root, STARTER, false)
+        .map_err(|e| format!("could not create board at {}: {e}", root.display()))
+}
+
+pub fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<Outcome> {
+    let mut wizard = Wizard::new();
+
+    loop {
+        terminal.draw(|f| {
+            draw(f, &wizard);
+            crate::frontend::finish(f);
+        })?;
+
+        if wizard.step == Step::Testing {
+            match wizard.finish() {
+                Ok(()) => return Ok(Outcome::Configured),
+                Err(msg) => {
+                    wizard.error = Some(msg);
+                    wizard.step = if wizard.fields.is_empty() {
+                        Step::Provider
+                    } else {
+                        Step::Fields
+                    };
+                }
+            }
+            continue;
+        }
+
+        if event::poll(Duration::from_millis(50))?
+            && let Event::Key(k) = event::read()?
+            && k.kind == KeyEventKind::Press
+            && !wizard.key(k.code)
+        {
+            return Ok(Outcome::Quit);
+        }
+    }
+}
+
+fn draw(f: &mut Frame, w: &Wizard) {
+    let area = crate::centered(60, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Welcome to flow",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from("No board is configured yet. Let's set one up."),
+        Line::from(""),
+    ];
+
+    let help = match w.step {
+        Step::Provider => {
+            for (i, (_, desc)) in PROVIDERS.iter().enumerate() {
+                let marker = if i == w.provider { "> " } else { "  " };
+                let style = if i == w.provider {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(Span::styled(format!("{marker}{desc}"), style)));
+            }
+            "j/k choose  Enter next  Esc quit"
+        }
+        Step::Fields | Step::Testing => {
+            for (i, field) in w.fields.iter().enumerate() {
+                let value = if field.secret {
+                    "*".repeat(field.value.chars().count())
+                } else {
+                    field.value.clone()
+                };
+                let cursor = if i == w.field && w.step == Step::Fields {
+                    "_"
+                } else {
+                    ""
+                };
+                let style = if i == w.field {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:>10}: ", field.label), style),
+                    Span::raw(format!("{value}{cursor}")),
+                ]));
+            }
+            "Tab/Enter next field  Enter on last field tests and saves  Esc back"
+        }
+    };
+
+    lines.push(Line::from(""));
+    if w.step == Step::Testing {
+        lines.push(Line::from("Testing connection..."));
+    } else if let Some(err) = &w.error {
+        lines.push(Line::from(Span::styled(
+            err.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        help,
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    f.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().title("Setup").borders(Borders::ALL)),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    fn tmp_root() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("flow-onboarding-{}-{nanos}", std::process::id()))
+    }
+
+    #[test]
+    fn jira_fields_become_a_profile() {
+        let mut w = Wizard::new();
+        w.key(KeyCode::Down);
+        w.key(KeyCode::Enter);
+        assert_eq!(w.step, Step::Fields);
+
+        for c in "x.atlassian.net".chars() {
+            w.key(KeyCode::Char(c));
+        }
+        w.key(KeyCode::Enter);
+        w.key(KeyCode::Enter); // leave email empty
+        w.key(KeyCode::Char('t'));
+        w.key(KeyCode::Tab);
+        w.key(KeyCode::Char('7'));
+        w.key(KeyCode::Enter);
+
+        assert_eq!(w.step, Step::Testing);
+        let p = w.profile();
+        assert_eq!(p.provider.as_deref(), Some("jira"));
+        assert_eq!(p.base_url.as_deref(), Some("https://x.atlassian.net"));
+        assert_eq!(p.email, None);
+        assert_eq!(p.api_token.as_deref(), Some("t"));
+        assert_eq!(p.board_id.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn prepare_local_creates_a_loadable_starter_board() {
+        let root = tmp_root();
+        prepare_local(&root).unwrap();
+        // A second run must not clobber the board.
+        prepare_local(&root).unwrap();
+
+        let board = crate::store_fs::load_board(&root).unwrap();
+        let ids: Vec<_> = board.columns.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["todo", "in_progress", "done"]);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
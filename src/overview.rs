@@ -0,0 +1,318 @@
+//! The home screen: every configured profile with a one-line summary, so
+//! people juggling several boards can see where attention is needed.
+
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    config::{self, BoardHandle, Config},
+    dates,
+    model::Board,
+    provider, stats,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub cards: usize,
+    /// Cards outside terminal columns.
+    pub open: usize,
+    pub overdue: usize,
+    pub over_wip: usize,
+}
+
+impl Summary {
+    pub fn of(board: &Board, today: i64) -> Self {
+        let open = board
+            .columns
+            .iter()
+            .filter(|c| !c.meta.terminal)
+            .map(|c| c.cards.len())
+            .sum();
+        Self {
+            cards: board.columns.iter().map(|c| c.cards.len()).sum(),
+            open,
+            overdue: stats::overdue(board, today),
+            over_wip: board
+                .columns
+                .iter()
+                .filter(|c| c.meta.wip_limit.is_some_and(|n| c.cards.len() > n))
+                .count(),
+        }
+    }
+}
+
+struct Entry {
+    name: String,
+    provider: String,
+    board: Result<BoardHandle, String>,
+    /// `None` while loading.
+    summary: Option<Result<Summary, String>>,
+    loaded_at: Option<Instant>,
+}
+
+pub struct Overview {
+    entries: Vec<Entry>,
+    selected: usize,
+    tx: Sender<(usize, Result<Summary, String>)>,
+    rx: Receiver<(usize, Result<Summary, String>)>,
+}
+
+impl Overview {
+    pub fn new(cfg: &Config) -> Self {
+        let entries = cfg
+            .profiles
+            .iter()
+            .map(|(name, raw)| Entry {
+                name: name.clone(),
+                provider: raw.provider().to_string(),
+                board: config::board(cfg, name),
+                summary: None,
+                loaded_at: None,
+            })
+            .collect();
+        let (tx, rx) = mpsc::channel();
+        let selected = cfg
+            .default_profile
+            .as_ref()
+            .and_then(|d| cfg.profiles.keys().position(|n| n == d))
+            .unwrap_or(0);
+        let mut overview = Self {
+            entries,
+            selected,
+            tx,
+            rx,
+        };
+        overview.refresh();
+        overview
+    }
+
+    /// Loads every board in the background; results arrive via `poll`.
+    fn refresh(&mut self) {
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            entry.summary = None;
+            let profile = match &entry.board {
+                Ok(b) => b.profile.clone(),
+                Err(e) => {
+                    entry.summary = Some(Err(format!("config: {e}")));
+                    continue;
+                }
+            };
+            let tx = self.tx.clone();
+            thread::spawn(move || {
+                let res = provider::from_profile(&profile)
+                    .load_board()
+                    .map(|b| Summary::of(&b, dates::today()))
+                    .map_err(|e| e.to_string());
+                let _ = tx.send((i, res));
+            });
+        }
+    }
+
+    fn poll(&mut self) {
+        while let Ok((i, res)) = self.rx.try_recv() {
+            if let Some(entry) = self.entries.get_mut(i) {
+                entry.summary = Some(res);
+                entry.loaded_at = Some(Instant::now());
+            }
+        }
+    }
+}
+
+/// Shows the overview until a board is chosen or the user quits.
+pub fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    overview: &mut Overview,
+) -> io::Result<Option<BoardHandle>> {
+    let mut error = None;
+    loop {
+        overview.poll();
+        terminal.draw(|f| {
+            draw(f, overview, error.as_deref());
+            crate::frontend::finish(f);
+        })?;
+
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        let Event::Key(k) = event::read()? else {
+            continue;
+        };
+        if k.kind != KeyEventKind::Press {
+            continue;
+        }
+        let n = overview.entries.len();
+        match k.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+            KeyCode::Char('j') | KeyCode::Down if n > 0 => {
+                overview.selected = (overview.selected + 1) % n;
+            }
+            KeyCode::Char('k') | KeyCode::Up if n > 0 => {
+                overview.selected = (overview.selected + n - 1) % n;
+            }
+            KeyCode::Char('r') => overview.refresh(),
+            KeyCode::Enter => match overview.entries.get(overview.selected).map(|e| &e.board) {
+                Some(Ok(b)) => return Ok(Some(b.clone())),
+                Some(Err(e)) => error = Some(e.clone()),
+                None => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+fn draw(f: &mut Frame, overview: &Overview, error: Option<&str>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(f.area());
+
+    let name_w = overview
+        .entries
+        .iter()
+        .map(|e| e.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let dim = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = overview
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let mut spans = vec![
+                Span::styled(
+                    format!(" {:<name_w$} ", e.name),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("{:<6} ", e.provider), dim),
+            ];
+            match &e.summary {
+                None => spans.push(Span::styled("loading...", dim)),
+                Some(Err(msg)) => {
+                    spans.push(Span::styled(msg.clone(), Style::default().fg(Color::Red)))
+                }
+                Some(Ok(s)) => {
+                    spans.push(Span::raw(format!(
+                        "{:>4} cards {:>4} open ",
+                        s.cards, s.open
+                    )));
+                    let warn = |n: usize, label: &str| {
+                        let style = if n > 0 {
+                            Style::default().fg(Color::Yellow)
+                        } else {
+                            dim
+                        };
+                        Span::styled(format!("{n:>3} {label} "), style)
+                    };
+                    spans.push(warn(s.overdue, "overdue"));
+                    spans.push(warn(s.over_wip, "over WIP"));
+                }
+            }
+            if let Some(at) = e.loaded_at {
+                spans.push(Span::styled(format!(" {}", ago(at.elapsed())), dim));
+            }
+            let line = Line::from(spans);
+            if i == overview.selected {
+                line.style(Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    let body = if lines.is_empty() {
+        vec![Line::from(" No profiles in the config file.")]
+    } else {
+        lines
+    };
+    f.render_widget(
+        Paragraph::new(body).block(Block::default().borders(Borders::ALL).title(" Boards ")),
+        chunks[0],
+    );
+
+    let footer = match error {
+        Some(e) => Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red))),
+        None => Line::from(Span::styled(
+            "j/k choose  Enter open  r refresh  q quit",
+            dim,
+        )),
+    };
+    f.render_widget(Paragraph::new(footer), chunks[1]);
+}
+
+fn ago(d: Duration) -> String {
+    match d.as_secs() {
+        s if s < 60 => format!("refreshed {s}s ago"),
+        s if s < 3600 => format!("refreshed {}m ago", s / 60),
+        s => format!("refreshed {}h ago", s / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Card, Column, ColumnMeta};
+
+    fn column(id: &str, n: usize, meta: ColumnMeta) -> Column {
+        Column {
+            id: id.to_string(),
+            title: id.to_string(),
+            cards: (0..n)
+                .map(|i| Card {
+                    id: format!("{id}-{i}"),
+                    due: Some("2024-05-01".to_string()),
+                    ..Card::default()
+                })
+                .collect(),
+            meta,
+        }
+    }
+
+    #[test]
+    fn summary_counts_open_overdue_and_over_wip() {
+        let board = Board {
+            columns: vec![
+                column(
+                    "doing",
+                    3,
+                    ColumnMeta {
+                        wip_limit: Some(2),
+                        ..ColumnMeta::default()
+                    },
+                ),
+                column(
+                    "done",
+                    2,
+                    ColumnMeta {
+                        terminal: true,
+                        ..ColumnMeta::default()
+                    },
+                ),
+            ],
+            problems: vec![],
+        };
+
+        assert_eq!(
+            Summary::of(&board, 19_875),
+            Summary {
+                cards: 5,
+                open: 3,
+                overdue: 3,
+                over_wip: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! A filterable list overlay: type to narrow, `↑`/`↓` to choose, `Enter` to
+//! pick, `Esc` to cancel.
+
+use crossterm::event::KeyCode;
+
+use crate::matcher::{self, Mode};
+
+pub struct Picker {
+    pub title: String,
+    pub items: Vec<String>,
+    /// Marked in the list, e.g. the board that is already open.
+    pub current: Option<usize>,
+    pub query: String,
+    /// `Tab` cycles it.
+    pub mode: Mode,
+    /// Index into `visible()`.
+    pub selected: usize,
+}
+
+pub enum Picked {
+    Pending,
+    Chosen(usize),
+    Cancelled,
+}
+
+impl Picker {
+    pub fn new(title: &str, items: Vec<String>, current: Option<usize>) -> Self {
+        Self {
+            title: title.to_string(),
+            items,
+            current,
+            query: String::new(),
+            mode: Mode::default(),
+            selected: 0,
+        }
+    }
+
+    /// Indices of the items matching the query; none while it's an
+    /// unfinished regex.
+    pub fn visible(&self) -> Vec<usize> {
+        let Ok(m) = matcher::new(self.mode, &self.query) else {
+            return vec![];
+        };
+        (0..self.items.len())
+            .filter(|&i| m.is_match(&self.items[i]))
+            .collect()
+    }
+
+    pub fn key(&mut self, code: KeyCode) -> Picked {
+        match code {
+            KeyCode::Esc => return Picked::Cancelled,
+            KeyCode::Enter => {
+                return match self.visible().get(self.selected) {
+                    Some(&i) => Picked::Chosen(i),
+                    None => Picked::Pending,
+                };
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => {
+                let n = self.visible().len();
+                self.selected = (self.selected + 1).min(n.saturating_sub(1));
+            }
+            KeyCode::Tab => {
+                self.mode = self.mode.next();
+                self.selected = 0;
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        Picked::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typing_filters_and_enter_picks_the_original_index() {
+        let items = ["work", "notes", "oss-web"].map(String::from).to_vec();
+        let mut p = Picker::new("Boards", items, None);
+
+        p.key(KeyCode::Char('s'));
+        assert_eq!(p.visible(), vec![1, 2]);
+        p.key(KeyCode::Down);
+        p.key(KeyCode::Down);
+        assert!(matches!(p.key(KeyCode::Enter), Picked::Chosen(2)));
+        assert!(matches!(p.key(KeyCode::Esc), Picked::Cancelled));
+    }
+
+    #[test]
+    fn tab_cycles_the_match_mode() {
+        let items = ["work", "notes", "oss-web"].map(String::from).to_vec();
+        let mut p = Picker::new("Boards", items, None);
+
+        p.query = "owb".into();
+        assert_eq!(p.visible(), vec![2]);
+        p.key(KeyCode::Tab);
+        assert_eq!((p.mode, p.visible()), (Mode::Exact, vec![]));
+        p.key(KeyCode::Tab);
+        p.query = "^(work|notes)$".into();
+        assert_eq!(p.visible(), vec![0, 1]);
+    }
+}
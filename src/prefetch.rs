@@ -0,0 +1,117 @@
+//! Card details fetched ahead of time, while the selection rests on a card,
+//! so the detail popup opens without waiting on the provider.
+
+use std::collections::VecDeque;
+
+use crate::model::CardDetail;
+
+/// What the TUI keeps by default: a few hundred cards' worth of comments.
+pub const DEFAULT_BUDGET: usize = 4 << 20;
+
+/// Least recently used details are dropped once the cached total passes the
+/// budget. The newest entry always stays, however big.
+pub struct DetailCache {
+    /// Oldest use first.
+    entries: VecDeque<(String, CardDetail)>,
+    bytes: usize,
+    budget: usize,
+}
+
+impl DetailCache {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            bytes: 0,
+            budget,
+        }
+    }
+
+    pub fn peek(&self, card_id: &str) -> Option<&CardDetail> {
+        self.entries
+            .iter()
+            .find(|(id, _)| id == card_id)
+            .map(|(_, d)| d)
+    }
+
+    pub fn contains(&self, card_id: &str) -> bool {
+        self.peek(card_id).is_some()
+    }
+
+    /// Marks the card's details as just used.
+    pub fn touch(&mut self, card_id: &str) {
+        if let Some(i) = self.entries.iter().position(|(id, _)| id == card_id)
+            && let Some(entry) = self.entries.remove(i)
+        {
+            self.entries.push_back(entry);
+        }
+    }
+
+    pub fn insert(&mut self, card_id: String, detail: CardDetail) {
+        if let Some(i) = self.entries.iter().position(|(id, _)| *id == card_id)
+            && let Some((_, old)) = self.entries.remove(i)
+        {
+            self.bytes -= old.size();
+        }
+        self.bytes += detail.size();
+        self.entries.push_back((card_id, detail));
+        while self.bytes > self.budget && self.entries.len() > 1 {
+            if let Some((_, old)) = self.entries.pop_front() {
+                self.bytes -= old.size();
+            }
+        }
+    }
+
+    /// Forgets one card's details, e.g. after commenting on it.
+    pub fn remove(&mut self, card_id: &str) {
+        if let Some(i) = self.entries.iter().position(|(id, _)| id == card_id)
+            && let Some((_, old)) = self.entries.remove(i)
+        {
+            self.bytes -= old.size();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+}
+
+impl Default for DetailCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Comment;
+
+    fn detail(body_len: usize) -> CardDetail {
+        CardDetail {
+            comments: vec![Comment {
+                author: String::new(),
+                created: String::new(),
+                body: "x".repeat(body_len),
+            }],
+            ..CardDetail::default()
+        }
+    }
+
+    #[test]
+    fn the_least_recently_used_details_go_first_when_over_budget() {
+        let mut cache = DetailCache::new(250);
+        cache.insert("A-1".into(), detail(100));
+        cache.insert("A-2".into(), detail(100));
+        cache.touch("A-1");
+        cache.insert("A-3".into(), detail(100));
+
+        assert!(cache.contains("A-1"));
+        assert!(!cache.contains("A-2"));
+        assert!(cache.contains("A-3"));
+
+        cache.insert("A-4".into(), detail(1000));
+        assert!(cache.contains("A-4"));
+        assert!(!cache.contains("A-3"));
+    }
+}
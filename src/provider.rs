@@ -1,6 +1,11 @@
 use std::{fmt, io, path::PathBuf};
 
-use crate::model::Board;
+use crate::{
+    activity,
+    card_template::Draft,
+    graph::LinkKind,
+    model::{Board, Card, CardDetail, Mention, Move, QuickFilter, User},
+};
 
 #[derive(Debug)]
 pub enum ProviderError {
@@ -10,11 +15,35 @@ pub enum ProviderError {
     Parse {
         msg: String,
     },
+    /// Missing or invalid provider settings.
+    Config {
+        msg: String,
+    },
+    /// The backend rejected our credentials.
+    Auth {
+        msg: String,
+    },
+    /// The backend could not be reached or failed on its side.
+    Network {
+        op: String,
+        url: String,
+        msg: String,
+    },
+    /// The provider doesn't implement this operation.
+    Unsupported {
+        op: String,
+    },
     Io {
         op: String,
         path: PathBuf,
         source: io::Error,
     },
+    /// `flow lock` froze the board.
+    Locked {
+        until: String,
+    },
+    /// Another process held the board's write lock for too long.
+    Busy,
 }
 
 impl fmt::Display for ProviderError {
@@ -22,9 +51,17 @@ impl fmt::Display for ProviderError {
         match self {
             ProviderError::NotFound { id } => write!(f, "not found: {id}"),
             ProviderError::Parse { msg } => write!(f, "parse error: {msg}"),
+            ProviderError::Config { msg } => write!(f, "config error: {msg}"),
+            ProviderError::Auth { msg } => write!(f, "authentication failed: {msg}"),
+            ProviderError::Network { op, url, msg } => write!(f, "{op} failed for {url}: {msg}"),
+            ProviderError::Unsupported { op } => {
+                write!(f, "{op} not supported by current provider")
+            }
             ProviderError::Io { op, path, source } => {
                 write!(f, "{op} failed for {}: {source}", path.display())
             }
+            ProviderError::Locked { until } => write!(f, "board is locked until {until}"),
+            ProviderError::Busy => write!(f, "board locked by another process"),
         }
     }
 }
@@ -42,22 +79,295 @@ pub trait Provider {
     fn load_board(&mut self) -> Result<Board, ProviderError>;
     fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError>;
 
-    fn create_card(&mut self, _to_col_id: &str) -> Result<String, ProviderError> {
-        Err(ProviderError::Parse {
-            msg: "create_card not supported by current provider".to_string(),
+    /// Creates a card with a caller-chosen id, so imports and sync tooling
+    /// can keep ids stable across boards.
+    fn create_card_with_id(
+        &mut self,
+        _to_col_id: &str,
+        _card_id: &str,
+        _title: &str,
+    ) -> Result<String, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "create_card_with_id".to_string(),
+        })
+    }
+
+    /// Creates a card titled `title` in `to_col_id` and returns its id.
+    fn create_card_with_title(
+        &mut self,
+        _to_col_id: &str,
+        _title: &str,
+    ) -> Result<String, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "create_card".to_string(),
+        })
+    }
+
+    /// Creates a card from a filled-in template in `to_col_id` and returns
+    /// its id.
+    fn create_card_from(
+        &mut self,
+        _to_col_id: &str,
+        _draft: &Draft,
+    ) -> Result<String, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "create_card_from".to_string(),
+        })
+    }
+
+    /// Replaces a card's title and description. Providers without card files
+    /// implement this so `e` can edit through a temp file.
+    fn update_card(
+        &mut self,
+        _card_id: &str,
+        _title: &str,
+        _description: &str,
+    ) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "update_card".to_string(),
+        })
+    }
+
+    /// Sets a card's estimate in points (a Jira story point field, or the
+    /// `estimate` front matter on local boards).
+    fn set_estimate(&mut self, _card_id: &str, _points: f64) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "set_estimate".to_string(),
         })
     }
 
     fn card_path(&self, _card_id: &str) -> Result<PathBuf, ProviderError> {
-        Err(ProviderError::Parse {
-            msg: "edit_card not supported by current provider".to_string(),
+        Err(ProviderError::Unsupported {
+            op: "edit_card".to_string(),
+        })
+    }
+
+    /// Saves the file at [`Provider::card_path`] as edited with `e`: `text`
+    /// is the whole file, front matter and all.
+    fn save_card(&mut self, _card_id: &str, _text: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "save_card".to_string(),
+        })
+    }
+
+    /// Fetches more of a column whose `meta.more` is set on the next load.
+    fn load_more(&mut self, _col_id: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "load_more".to_string(),
+        })
+    }
+
+    fn quick_filters(&mut self) -> Result<Vec<QuickFilter>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "quick_filters".to_string(),
+        })
+    }
+
+    /// Limits later loads to cards matching every one of `filters`.
+    fn set_quick_filters(&mut self, _filters: &[QuickFilter]) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "set_quick_filters".to_string(),
+        })
+    }
+
+    /// Takes a card off the board without deleting it.
+    fn archive_card(&mut self, _card_id: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "archive_card".to_string(),
+        })
+    }
+
+    /// Moves several cards as one operation where the backend has one, else
+    /// one by one. `moved` hears each card as it lands; the first failure
+    /// stops the rest.
+    fn move_cards(
+        &mut self,
+        card_ids: &[String],
+        to_col_id: &str,
+        moved: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        for card_id in card_ids {
+            self.move_card(card_id, to_col_id)?;
+            moved(card_id);
+        }
+        Ok(())
+    }
+
+    /// [`Provider::archive_card`] for several cards, like
+    /// [`Provider::move_cards`].
+    fn archive_cards(
+        &mut self,
+        card_ids: &[String],
+        archived: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        for card_id in card_ids {
+            self.archive_card(card_id)?;
+            archived(card_id);
+        }
+        Ok(())
+    }
+
+    fn archived_cards(&mut self) -> Result<Vec<Card>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "archived_cards".to_string(),
+        })
+    }
+
+    /// Comments, attachments and other fields too slow to fetch for every
+    /// card on load.
+    fn card_detail(&mut self, _card_id: &str) -> Result<CardDetail, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "card_detail".to_string(),
+        })
+    }
+
+    /// Comments mentioning the current user made in the last `minutes`.
+    fn mentions(&mut self, _minutes: u64) -> Result<Vec<Mention>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "mentions".to_string(),
+        })
+    }
+
+    /// Column moves of the cards that changed since `since` (seconds), each
+    /// card's creation included; older moves may come too.
+    fn moves(&mut self, _since: u64) -> Result<Vec<Move>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "moves".to_string(),
+        })
+    }
+
+    /// The board's activity log, oldest first.
+    fn activity(&mut self) -> Result<Vec<activity::Entry>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "activity".to_string(),
+        })
+    }
+
+    fn add_comment(&mut self, _card_id: &str, _body: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "add_comment".to_string(),
+        })
+    }
+
+    /// Cards matching `query` anywhere on the backend, not only those the
+    /// board loaded, most relevant first.
+    fn search(&mut self, _query: &str) -> Result<Vec<Card>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "search".to_string(),
+        })
+    }
+
+    /// Brings a card found by [`Provider::search`] onto the board, in
+    /// `to_col_id`.
+    fn pull_card(&mut self, _card_id: &str, _to_col_id: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "pull_card".to_string(),
+        })
+    }
+
+    /// Puts the cards of `col_id` in `order`, which lists each of them once.
+    fn reorder_column(&mut self, _col_id: &str, _order: &[String]) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "reorder_column".to_string(),
+        })
+    }
+
+    /// Who `card_id` can be assigned to.
+    fn assignable_users(&mut self, _card_id: &str) -> Result<Vec<User>, ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "assignable_users".to_string(),
+        })
+    }
+
+    /// Assigns the card to `user`, or unassigns it with `None`.
+    fn set_assignee(&mut self, _card_id: &str, _user: Option<&User>) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "set_assignee".to_string(),
+        })
+    }
+
+    /// Links `from_id` to `to_id`, e.g. `from_id` blocks `to_id`.
+    fn link_cards(
+        &mut self,
+        _from_id: &str,
+        _to_id: &str,
+        _kind: LinkKind,
+    ) -> Result<(), ProviderError> {
+        Err(ProviderError::Unsupported {
+            op: "link_cards".to_string(),
         })
     }
 }
 
 pub fn from_env() -> Box<dyn Provider> {
-    match std::env::var("FLOW_PROVIDER").ok().as_deref() {
-        Some("jira") => Box::new(crate::provider_jira::JiraProvider::from_env()),
-        _ => Box::new(crate::provider_local::LocalProvider::from_env()),
+    from_profile(&crate::config::active_profile())
+}
+
+/// What archiving a card does on the profile's backend beyond hiding it,
+/// for the confirmation prompt. Jira transitions can fire workflow
+/// post-functions and automation rules the board never shows.
+pub fn archive_effects(profile: &crate::config::Profile) -> Vec<String> {
+    match profile.provider() {
+        "jira" => vec![
+            format!(
+                "transitions the issue to {:?}",
+                profile.archive_transition.as_deref().unwrap_or("Done")
+            ),
+            "runs that transition's post-functions and automation rules".to_string(),
+        ],
+        "github" => vec![
+            "closes the issue".to_string(),
+            "removes its column label".to_string(),
+        ],
+        "linear" => vec!["archives the issue in Linear".to_string()],
+        "demo" => vec![],
+        _ => vec!["moves the card file to archive/".to_string()],
     }
 }
+
+/// Where the card lives on the web: the URL the provider gave it, or its
+/// Jira issue page.
+pub fn card_url(profile: &crate::config::Profile, card: &Card) -> Option<String> {
+    if let Some(url) = card.values("url").first() {
+        return Some(url.to_string());
+    }
+    match (profile.provider(), profile.base_url.as_deref()) {
+        ("jira", Some(base)) if !base.trim().is_empty() => Some(format!(
+            "{}/browse/{}",
+            base.trim().trim_end_matches('/'),
+            card.id
+        )),
+        _ => None,
+    }
+}
+
+pub fn from_profile(profile: &crate::config::Profile) -> Box<dyn Provider> {
+    let (inner, name): (Box<dyn Provider>, _) = match profile.provider() {
+        "jira" => (
+            Box::new(crate::provider_jira::JiraProvider::from_profile(profile)),
+            "jira",
+        ),
+        "github" => (
+            Box::new(crate::provider_github::GithubProvider::from_profile(
+                profile,
+            )),
+            "github",
+        ),
+        "linear" => (
+            Box::new(crate::provider_linear::LinearProvider::from_profile(
+                profile,
+            )),
+            "linear",
+        ),
+        "demo" => (
+            Box::new(crate::provider_demo::DemoProvider::from_env()),
+            "demo",
+        ),
+        _ => (
+            Box::new(crate::provider_local::LocalProvider::from_profile(profile)),
+            "local",
+        ),
+    };
+    let inner = crate::freeze::Frozen::new(inner, crate::freeze::board_key(profile));
+    Box::new(crate::provider_metrics::Metered::new(Box::new(inner), name))
+}
@@ -0,0 +1,318 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{
+    model::{Board, Card, Column, ColumnMeta},
+    provider::{Provider, ProviderError},
+};
+
+const COLUMNS: [(&str, &str); 4] = [
+    ("todo", "TO DO"),
+    ("in_progress", "IN PROGRESS"),
+    ("in_review", "IN REVIEW"),
+    ("done", "DONE"),
+];
+
+const VERBS: [&str; 10] = [
+    "Fix",
+    "Add",
+    "Refactor",
+    "Document",
+    "Speed up",
+    "Remove",
+    "Test",
+    "Polish",
+    "Migrate",
+    "Investigate",
+];
+
+const SUBJECTS: [&str; 12] = [
+    "login flow",
+    "search results",
+    "billing export",
+    "onboarding emails",
+    "dark mode",
+    "API rate limits",
+    "settings page",
+    "CSV import",
+    "notification badges",
+    "audit log",
+    "retry logic",
+    "mobile layout",
+];
+
+const DETAILS: [&str; 6] = [
+    "Reported by two customers this week.",
+    "Blocked on a decision from design.",
+    "Should be a small change behind a feature flag.",
+    "Needs a follow-up ticket for the docs.",
+    "Repro steps are in the linked thread.",
+    "Pair with QA before merging.",
+];
+
+const LABELS: [&str; 5] = ["bug", "ui", "backend", "docs", "customer"];
+
+const PRIORITIES: [&str; 3] = ["Low", "Medium", "High"];
+
+const PEOPLE: [&str; 4] = ["ana", "bo", "chen", "dev"];
+
+/// Procedurally generated board that never touches the filesystem.
+///
+/// Configured with `FLOW_DEMO_SEED` and `FLOW_DEMO_CARDS`. State lives for the
+/// whole process and is shared by every instance with the same settings, so
+/// move workers see the same board as the UI.
+pub struct DemoProvider {
+    key: (u64, usize),
+}
+
+impl DemoProvider {
+    pub fn from_env() -> Self {
+        let seed = env_num("FLOW_DEMO_SEED").unwrap_or(42);
+        let cards = env_num("FLOW_DEMO_CARDS").unwrap_or(24) as usize;
+        Self::new(seed, cards)
+    }
+
+    pub fn new(seed: u64, cards: usize) -> Self {
+        Self { key: (seed, cards) }
+    }
+
+    fn with_board<T>(&self, f: impl FnOnce(&mut Board) -> T) -> T {
+        static BOARDS: OnceLock<Mutex<HashMap<(u64, usize), Board>>> = OnceLock::new();
+        let mut boards = BOARDS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let board = boards
+            .entry(self.key)
+            .or_insert_with(|| generate(self.key.0, self.key.1));
+        f(board)
+    }
+}
+
+fn env_num(name: &str) -> Option<u64> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
+
+impl Provider for DemoProvider {
+    fn load_board(&mut self) -> Result<Board, ProviderError> {
+        Ok(self.with_board(|b| b.clone()))
+    }
+
+    fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        self.with_board(|b| {
+            let dst = b
+                .columns
+                .iter()
+                .position(|c| c.id == to_col_id)
+                .ok_or_else(|| ProviderError::NotFound {
+                    id: to_col_id.to_string(),
+                })?;
+            let (src, idx) = b
+                .columns
+                .iter()
+                .enumerate()
+                .find_map(|(ci, c)| {
+                    c.cards
+                        .iter()
+                        .position(|k| k.id == card_id)
+                        .map(|i| (ci, i))
+                })
+                .ok_or_else(|| ProviderError::NotFound {
+                    id: card_id.to_string(),
+                })?;
+            if src != dst {
+                let card = b.columns[src].cards.remove(idx);
+                b.columns[dst].cards.push(card);
+            }
+            Ok(())
+        })
+    }
+
+    fn create_card_with_id(
+        &mut self,
+        to_col_id: &str,
+        card_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        self.with_board(|b| {
+            if b.columns
+                .iter()
+                .flat_map(|c| &c.cards)
+                .any(|c| c.id == card_id)
+            {
+                return Err(ProviderError::Parse {
+                    msg: format!("card already exists: {card_id}"),
+                });
+            }
+            let col = b
+                .columns
+                .iter_mut()
+                .find(|c| c.id == to_col_id)
+                .ok_or_else(|| ProviderError::NotFound {
+                    id: to_col_id.to_string(),
+                })?;
+            col.cards.push(Card {
+                id: card_id.to_string(),
+                title: title.to_string(),
+                description: String::new(),
+                ..Default::default()
+            });
+            Ok(card_id.to_string())
+        })
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        self.with_board(|b| {
+            let card = b
+                .columns
+                .iter_mut()
+                .flat_map(|c| &mut c.cards)
+                .find(|c| c.id == card_id)
+                .ok_or_else(|| ProviderError::NotFound {
+                    id: card_id.to_string(),
+                })?;
+            card.title = title.to_string();
+            card.description = description.to_string();
+            Ok(())
+        })
+    }
+
+    fn set_estimate(&mut self, card_id: &str, points: f64) -> Result<(), ProviderError> {
+        self.with_board(|b| {
+            let card = b
+                .columns
+                .iter_mut()
+                .flat_map(|c| &mut c.cards)
+                .find(|c| c.id == card_id)
+                .ok_or_else(|| ProviderError::NotFound {
+                    id: card_id.to_string(),
+                })?;
+            card.fields
+                .insert("estimate".to_string(), vec![points.to_string()]);
+            Ok(())
+        })
+    }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let id = self.with_board(|b| {
+            let n = b.columns.iter().map(|c| c.cards.len()).sum::<usize>() + 1;
+            (n..)
+                .map(|n| format!("DEMO-{n}"))
+                .find(|id| !b.columns.iter().flat_map(|c| &c.cards).any(|c| &c.id == id))
+                .unwrap()
+        });
+        self.create_card_with_id(to_col_id, &id, title)
+    }
+}
+
+/// splitmix64: tiny, seedable, good enough for fake data.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[(self.next() % items.len() as u64) as usize]
+    }
+}
+
+pub fn generate(seed: u64, cards: usize) -> Board {
+    let mut rng = Rng(seed);
+    let mut columns: Vec<Column> = COLUMNS
+        .iter()
+        .map(|(id, title)| Column {
+            id: id.to_string(),
+            title: title.to_string(),
+            cards: vec![],
+            meta: ColumnMeta {
+                terminal: *id == "done",
+                ..ColumnMeta::default()
+            },
+        })
+        .collect();
+
+    for n in 1..=cards {
+        let title = format!("{} {}", rng.pick(&VERBS), rng.pick(&SUBJECTS));
+        let description = format!("{}\n\n{}", rng.pick(&DETAILS), rng.pick(&DETAILS));
+        // Skew towards the left so the board looks like real work in flight.
+        let col = match rng.next() % 10 {
+            0..=3 => 0,
+            4..=6 => 1,
+            7 => 2,
+            _ => 3,
+        };
+        let labels = match rng.next() % 3 {
+            0 => vec![],
+            _ => vec![rng.pick(&LABELS).to_string()],
+        };
+        columns[col].cards.push(Card {
+            id: format!("DEMO-{n}"),
+            title,
+            description,
+            labels,
+            priority: Some(rng.pick(&PRIORITIES).to_string()),
+            assignee: (!rng.next().is_multiple_of(4)).then(|| rng.pick(&PEOPLE).to_string()),
+            ..Default::default()
+        });
+    }
+
+    Board {
+        columns,
+        problems: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(b: &Board) -> Vec<Vec<String>> {
+        b.columns
+            .iter()
+            .map(|c| c.cards.iter().map(|k| k.id.clone()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn generation_is_deterministic_per_seed() {
+        let a = generate(7, 30);
+        let b = generate(7, 30);
+        let c = generate(8, 30);
+
+        assert_eq!(ids(&a), ids(&b));
+        assert_ne!(ids(&a), ids(&c));
+        assert_eq!(a.columns.iter().map(|c| c.cards.len()).sum::<usize>(), 30);
+    }
+
+    #[test]
+    fn moves_are_visible_to_other_instances() {
+        let mut ui = DemoProvider::new(991, 10);
+        let mut worker = DemoProvider::new(991, 10);
+
+        let board = ui.load_board().unwrap();
+        let card = board.columns.iter().flat_map(|c| &c.cards).next().unwrap();
+        let card_id = card.id.clone();
+
+        worker.move_card(&card_id, "done").unwrap();
+
+        let board = ui.load_board().unwrap();
+        assert!(board.columns[3].cards.iter().any(|c| c.id == card_id));
+    }
+}
@@ -0,0 +1,485 @@
+//! GitHub Issues with labels as columns: each of the profile's status labels
+//! (`status:todo`, `status:doing`, ...) is a column of one repository, and
+//! moving a card swaps the label. The last column is done work; moving into
+//! it closes the issue and moving out reopens it.
+
+use std::{collections::BTreeMap, io, path::PathBuf, sync::Arc};
+
+use reqwest::{Method, blocking::Client};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::{
+    config::Profile,
+    dates,
+    http::{self, SendWithin},
+    model::{Board, Card, Column, ColumnMeta, Problem},
+    provider::{Provider, ProviderError},
+    sla,
+};
+
+const DEFAULT_URL: &str = "https://api.github.com";
+pub const DEFAULT_LABELS: [&str; 3] = ["status:todo", "status:doing", "status:done"];
+/// Issues fetched per request, and the most a column loads.
+const PAGE: usize = 100;
+const MAX_ISSUES: usize = 500;
+
+pub struct GithubProvider {
+    client: Client,
+    limit: Arc<http::Limit>,
+    url: String,
+    token: String,
+    /// `owner/name`.
+    repo: String,
+    /// One per column, in board order.
+    labels: Vec<String>,
+    sla: BTreeMap<String, u32>,
+    err: Option<String>,
+}
+
+impl GithubProvider {
+    pub fn from_profile(profile: &Profile) -> Self {
+        let mut missing = vec![];
+        let token = match &profile.api_token {
+            Some(v) if !v.trim().is_empty() => v.trim().to_string(),
+            _ => {
+                missing.push("GITHUB_TOKEN");
+                String::new()
+            }
+        };
+        let repo = match &profile.repo {
+            Some(v) if v.trim().contains('/') => v.trim().to_string(),
+            _ => {
+                missing.push("GITHUB_REPOSITORY (owner/name)");
+                String::new()
+            }
+        };
+        let labels = if profile.status_labels.is_empty() {
+            DEFAULT_LABELS.map(str::to_string).to_vec()
+        } else {
+            profile.status_labels.clone()
+        };
+        let url = profile
+            .base_url
+            .as_deref()
+            .unwrap_or(DEFAULT_URL)
+            .trim_end_matches('/')
+            .to_string();
+        Self {
+            client: http::client(&profile.http),
+            limit: http::limit(&url, &profile.http),
+            url,
+            token,
+            repo,
+            labels,
+            sla: profile.sla.clone(),
+            err: (!missing.is_empty()).then(|| format!("missing {}", missing.join(", "))),
+        }
+    }
+
+    fn map_err(&self, op: &str, err: impl ToString) -> ProviderError {
+        ProviderError::Io {
+            op: op.to_string(),
+            path: PathBuf::from(&self.url),
+            source: io::Error::other(err.to_string()),
+        }
+    }
+
+    /// `path` is relative to the repository, e.g. `/issues/12`.
+    fn request<T: DeserializeOwned>(
+        &self,
+        op: &str,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<T, ProviderError> {
+        self.request_api(op, method, &format!("/repos/{}{path}", self.repo), body)
+    }
+
+    /// `path` is relative to the API root, e.g. `/search/issues`.
+    fn request_api<T: DeserializeOwned>(
+        &self,
+        op: &str,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<T, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("github misconfigured: {msg}"),
+            });
+        }
+        let mut req = self
+            .client
+            .request(method, format!("{}{path}", self.url))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "flow");
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let resp = req
+            .send_within(&self.limit)
+            .map_err(|e| ProviderError::Network {
+                op: op.to_string(),
+                url: self.url.clone(),
+                msg: e.to_string(),
+            })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().unwrap_or_default();
+            let msg = format!("status {status}: {body}");
+            return Err(match status.as_u16() {
+                401 | 403 => ProviderError::Auth {
+                    msg: format!("{op}: {msg}"),
+                },
+                404 => ProviderError::Config {
+                    msg: format!("{op}: no repository {:?} for this token", self.repo),
+                },
+                500..=599 => ProviderError::Network {
+                    op: op.to_string(),
+                    url: self.url.clone(),
+                    msg,
+                },
+                _ => self.map_err(op, msg),
+            });
+        }
+        resp.json().map_err(|e| self.map_err(op, e))
+    }
+
+    /// Issues carrying `label`, newest activity first. Only the done column
+    /// keeps closed ones.
+    fn issues(&self, label: &str, done: bool) -> Result<(Vec<Issue>, bool), ProviderError> {
+        let state = if done { "all" } else { "open" };
+        let mut out = vec![];
+        for page in 1..=MAX_ISSUES / PAGE {
+            let path = format!(
+                "/issues?labels={}&state={state}&sort=updated&per_page={PAGE}&page={page}",
+                encode(label)
+            );
+            let batch: Vec<Issue> = self.request("github_issues", Method::GET, &path, None)?;
+            let last = batch.len() < PAGE;
+            out.extend(batch);
+            if last {
+                return Ok((out, false));
+            }
+        }
+        Ok((out, true))
+    }
+
+    fn column_label(&self, col_id: &str) -> Result<&str, ProviderError> {
+        self.labels
+            .iter()
+            .find(|l| *l == col_id)
+            .map(String::as_str)
+            .ok_or_else(|| ProviderError::NotFound {
+                id: col_id.to_string(),
+            })
+    }
+
+    /// The issue's labels with its status labels swapped for `status`.
+    fn relabel(&self, card_id: &str, status: Option<&str>) -> Result<Vec<String>, ProviderError> {
+        let issue: Issue = self.request(
+            "github_issue",
+            Method::GET,
+            &format!("/issues/{card_id}"),
+            None,
+        )?;
+        Ok(issue
+            .labels
+            .into_iter()
+            .map(|l| l.name)
+            .filter(|l| !self.labels.contains(l))
+            .chain(status.map(str::to_string))
+            .collect())
+    }
+}
+
+impl Provider for GithubProvider {
+    fn load_board(&mut self) -> Result<Board, ProviderError> {
+        let mut columns = vec![];
+        let mut problems = vec![];
+        for (i, label) in self.labels.iter().enumerate() {
+            let (issues, truncated) = self.issues(label, i + 1 == self.labels.len())?;
+            if truncated {
+                problems.push(Problem {
+                    card_id: None,
+                    msg: format!("{label}: showing the {MAX_ISSUES} most recently updated issues"),
+                });
+            }
+            columns.push((label.clone(), issues));
+        }
+        let mut board = board_from(columns, &self.labels);
+        board.problems.splice(0..0, problems);
+        sla::apply(&mut board, &self.sla, dates::today());
+        Ok(board)
+    }
+
+    fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        let label = self.column_label(to_col_id)?.to_string();
+        let done = self.labels.last() == Some(&label);
+        let labels = self.relabel(card_id, Some(&label))?;
+        let _: Value = self.request(
+            "github_move",
+            Method::PATCH,
+            &format!("/issues/{card_id}"),
+            Some(json!({ "labels": labels, "state": if done { "closed" } else { "open" } })),
+        )?;
+        Ok(())
+    }
+
+    /// Open issues in the repository matching `query`, labelled or not.
+    fn search(&mut self, query: &str) -> Result<Vec<Card>, ProviderError> {
+        let found: SearchPage = self.request_api(
+            "github_search",
+            Method::GET,
+            &format!(
+                "/search/issues?q={}&sort=updated&per_page=50",
+                encode(&search_query(&self.repo, query))
+            ),
+            None,
+        )?;
+        Ok(found
+            .items
+            .into_iter()
+            .filter(|i| i.pull_request.is_none())
+            .map(|i| card_from(i, &self.labels))
+            .collect())
+    }
+
+    /// Labelling the issue is all it takes to put it on the board.
+    fn pull_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        self.move_card(card_id, to_col_id)
+    }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let label = self.column_label(to_col_id)?;
+        let issue: Issue = self.request(
+            "github_create",
+            Method::POST,
+            "/issues",
+            Some(json!({ "title": title, "labels": [label] })),
+        )?;
+        Ok(issue.number.to_string())
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        let _: Value = self.request(
+            "github_update",
+            Method::PATCH,
+            &format!("/issues/{card_id}"),
+            Some(json!({ "title": title, "body": description })),
+        )?;
+        Ok(())
+    }
+
+    /// Closes the issue and drops its status label, so no column shows it.
+    fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+        let labels = self.relabel(card_id, None)?;
+        let _: Value = self.request(
+            "github_archive",
+            Method::PATCH,
+            &format!("/issues/{card_id}"),
+            Some(json!({ "labels": labels, "state": "closed" })),
+        )?;
+        Ok(())
+    }
+
+    fn add_comment(&mut self, card_id: &str, body: &str) -> Result<(), ProviderError> {
+        let _: Value = self.request(
+            "github_comment",
+            Method::POST,
+            &format!("/issues/{card_id}/comments"),
+            Some(json!({ "body": body })),
+        )?;
+        Ok(())
+    }
+}
+
+fn search_query(repo: &str, query: &str) -> String {
+    format!("repo:{repo} is:issue is:open {}", query.trim())
+}
+
+/// `status:todo` is titled `todo`.
+fn column_title(label: &str) -> &str {
+    label
+        .rsplit_once(':')
+        .map_or(label, |(_, name)| name.trim())
+}
+
+/// An issue with two status labels is shown in the first of their columns.
+/// Pull requests, which the issues API also returns, are left out.
+fn board_from(columns: Vec<(String, Vec<Issue>)>, labels: &[String]) -> Board {
+    let mut seen = BTreeMap::new();
+    let mut problems = vec![];
+    let last = columns.len().saturating_sub(1);
+    let columns = columns
+        .into_iter()
+        .enumerate()
+        .map(|(i, (label, issues))| {
+            let mut cards = vec![];
+            for issue in issues.into_iter().filter(|i| i.pull_request.is_none()) {
+                let id = issue.number.to_string();
+                if let Some(first) = seen.get(&id) {
+                    problems.push(Problem {
+                        card_id: Some(id.clone()),
+                        msg: format!("#{id} has both {first} and {label}; showing it in {first}"),
+                    });
+                    continue;
+                }
+                seen.insert(id, label.clone());
+                cards.push(card_from(issue, labels));
+            }
+            Column {
+                id: label.clone(),
+                title: column_title(&label).to_string(),
+                cards,
+                meta: ColumnMeta {
+                    terminal: i == last,
+                    ..ColumnMeta::default()
+                },
+            }
+        })
+        .collect();
+    Board { columns, problems }
+}
+
+fn card_from(issue: Issue, status_labels: &[String]) -> Card {
+    let fields = [
+        ("created", issue.created_at),
+        ("updated", issue.updated_at),
+        ("url", issue.html_url),
+        ("state", issue.state),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), vec![value]))
+    .collect();
+    Card {
+        id: issue.number.to_string(),
+        title: issue.title,
+        description: issue.body.unwrap_or_default(),
+        labels: issue
+            .labels
+            .into_iter()
+            .map(|l| l.name)
+            .filter(|l| !status_labels.contains(l))
+            .collect(),
+        priority: None,
+        assignee: issue.assignee.map(|a| a.login),
+        due: None,
+        fields,
+    }
+}
+
+/// Percent-encodes a label for a query string; labels often hold `:` and spaces.
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct SearchPage {
+    items: Vec<Issue>,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    state: String,
+    html_url: String,
+    created_at: String,
+    updated_at: String,
+    #[serde(default)]
+    labels: Vec<Label>,
+    assignee: Option<User>,
+    #[serde(default)]
+    pull_request: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_board_returns_config_error_when_missing_env() {
+        let mut p = GithubProvider::from_profile(&Profile::default());
+        let err = p.load_board().err().unwrap();
+
+        assert!(matches!(err, ProviderError::Config { .. }));
+        assert!(err.to_string().contains("GITHUB_TOKEN, GITHUB_REPOSITORY"));
+    }
+
+    #[test]
+    fn status_labels_become_columns_and_are_hidden_on_cards() {
+        let issue = |number: u64, labels: &[&str], pr: bool| -> Issue {
+            serde_json::from_value(json!({
+                "number": number, "title": format!("Issue {number}"), "body": null,
+                "state": "open", "html_url": format!("https://github.com/o/r/issues/{number}"),
+                "created_at": "2024-06-01T09:30:00Z", "updated_at": "2024-06-02T09:30:00Z",
+                "labels": labels.iter().map(|l| json!({ "name": l })).collect::<Vec<_>>(),
+                "assignee": { "login": "ana" },
+                "pull_request": if pr { json!({}) } else { Value::Null },
+            }))
+            .unwrap()
+        };
+        let labels = DEFAULT_LABELS.map(str::to_string).to_vec();
+        let board = board_from(
+            vec![
+                (
+                    labels[0].clone(),
+                    vec![issue(1, &["status:todo", "bug"], false)],
+                ),
+                (
+                    labels[1].clone(),
+                    vec![
+                        issue(1, &["status:doing"], false),
+                        issue(2, &["status:doing"], true),
+                    ],
+                ),
+                (labels[2].clone(), vec![]),
+            ],
+            &labels,
+        );
+
+        let titles: Vec<&str> = board.columns.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["todo", "doing", "done"]);
+        assert!(board.columns[2].meta.terminal);
+        let card = &board.columns[0].cards[0];
+        assert_eq!(
+            (card.id.as_str(), card.labels.as_slice()),
+            ("1", &["bug".to_string()][..])
+        );
+        assert_eq!(card.assignee.as_deref(), Some("ana"));
+        assert!(board.columns[1].cards.is_empty());
+        assert_eq!(board.problems.len(), 1);
+        assert_eq!(encode("status:in progress"), "status%3Ain%20progress");
+    }
+}
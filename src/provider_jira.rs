@@ -1,30 +1,84 @@
-use std::{collections::HashMap, io, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io,
+    path::PathBuf,
+    sync::Arc,
+};
 
-use reqwest::blocking::Client;
+use reqwest::{Method, blocking::Client};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    model::{Board, Card, Column},
+    card_template::Draft,
+    config::{HttpConfig, Profile},
+    dates,
+    graph::LinkKind,
+    http::{self, SendWithin},
+    log,
+    model::{
+        Attachment, Board, Card, CardDetail, Column, ColumnMeta, Comment, Mention, Move, Problem,
+        QuickFilter,
+    },
     provider::{Provider, ProviderError},
+    sla,
 };
 
 pub struct JiraProvider {
     client: Client,
+    limit: Arc<http::Limit>,
     base_url: String,
     email: String,
     api_token: String,
     board_id: Option<String>,
+    sla: BTreeMap<String, u32>,
+    archive_transition: Option<String>,
+    fetch_limits: BTreeMap<String, usize>,
+    /// Cards added to a column's fetch limit by `load_more`.
+    extra: HashMap<String, usize>,
+    /// JQL of the quick filters switched on.
+    quick: Vec<String>,
+    estimate_field: String,
+    /// Narrows the board's filter; see [`board_jql`].
+    jql: Option<String>,
+    issue_type: String,
+    /// Names for the people and issues descriptions refer to by id, kept
+    /// across loads so each account is looked up once.
+    refs: Refs,
     err: Option<String>,
 }
 
-impl JiraProvider {
-    pub fn from_env() -> Self {
-        let base_url = std::env::var("JIRA_BASE_URL").ok();
-        let email = std::env::var("JIRA_EMAIL").ok();
-        let api_token = std::env::var("JIRA_API_TOKEN").ok();
-        let board_id = std::env::var("JIRA_BOARD_ID").ok();
+/// What Jira text refers to by id: people by account id and issues by key,
+/// so mentions read `@Ana` and issue cards `PROJ-42 (Title)`.
+#[derive(Default)]
+struct Refs {
+    people: HashMap<String, String>,
+    titles: HashMap<String, String>,
+}
+
+/// Accounts looked up per load; the rest show as unknown until the next.
+const MAX_PEOPLE_LOOKUPS: usize = 10;
 
-        Self::from_parts(base_url, email, api_token, board_id)
+impl JiraProvider {
+    pub fn from_profile(profile: &Profile) -> Self {
+        let mut p = Self::from_parts(
+            profile.base_url.clone(),
+            profile.email.clone(),
+            profile.api_token.clone(),
+            profile.board_id.clone(),
+        );
+        p.sla = profile.sla.clone();
+        p.archive_transition = profile.archive_transition.clone();
+        p.fetch_limits = profile.fetch_limits.clone();
+        p.jql = profile.jql.clone();
+        p.client = http::client(&profile.http);
+        p.limit = http::limit(&p.base_url, &profile.http);
+        if let Some(field) = &profile.estimate_field {
+            p.estimate_field = field.clone();
+        }
+        if let Some(kind) = &profile.issue_type {
+            p.issue_type = kind.clone();
+        }
+        p
     }
 
     fn from_parts(
@@ -79,10 +133,20 @@ impl JiraProvider {
 
         Self {
             client: Client::new(),
+            limit: http::limit(&base_url, &HttpConfig::default()),
             base_url,
             email,
             api_token,
             board_id,
+            sla: BTreeMap::new(),
+            archive_transition: None,
+            fetch_limits: BTreeMap::new(),
+            extra: HashMap::new(),
+            quick: vec![],
+            estimate_field: DEFAULT_ESTIMATE_FIELD.to_string(),
+            jql: None,
+            issue_type: DEFAULT_ISSUE_TYPE.to_string(),
+            refs: Refs::default(),
             err,
         }
     }
@@ -91,7 +155,32 @@ impl JiraProvider {
         ProviderError::Io {
             op: op.to_string(),
             path: PathBuf::from(&self.base_url),
-            source: io::Error::new(io::ErrorKind::Other, err.to_string()),
+            source: io::Error::other(err.to_string()),
+        }
+    }
+
+    fn network_err(&self, op: &str, err: reqwest::Error) -> ProviderError {
+        ProviderError::Network {
+            op: op.to_string(),
+            url: self.base_url.clone(),
+            msg: err.to_string(),
+        }
+    }
+
+    /// 401/403 are credential problems and 5xx are the server's; anything
+    /// else is reported as a plain failure of `op`.
+    fn status_err(&self, op: &str, status: reqwest::StatusCode, body: String) -> ProviderError {
+        let msg = format!("status {status}: {body}");
+        match status.as_u16() {
+            401 | 403 => ProviderError::Auth {
+                msg: format!("{op}: {msg}"),
+            },
+            500..=599 => ProviderError::Network {
+                op: op.to_string(),
+                url: self.base_url.clone(),
+                msg,
+            },
+            _ => self.map_err(op, msg),
         }
     }
 
@@ -101,13 +190,13 @@ impl JiraProvider {
             .client
             .get(url)
             .basic_auth(&self.email, Some(&self.api_token))
-            .send()
-            .map_err(|e| self.map_err("jira_transitions", e))?;
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_transitions", e))?;
 
         if !resp.status().is_success() {
             let status = resp.status();
             let body = resp.text().unwrap_or_default();
-            return Err(self.map_err("jira_transitions", format!("status {status}: {body}")));
+            return Err(self.status_err("jira_transitions", status, body));
         }
 
         let data: TransitionsResponse = resp
@@ -125,13 +214,13 @@ impl JiraProvider {
             .client
             .get(url)
             .basic_auth(&self.email, Some(&self.api_token))
-            .send()
-            .map_err(|e| self.map_err("jira_board_config", e))?;
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_board_config", e))?;
 
         if !resp.status().is_success() {
             let status = resp.status();
             let body = resp.text().unwrap_or_default();
-            return Err(self.map_err("jira_board_config", format!("status {status}: {body}")));
+            return Err(self.status_err("jira_board_config", status, body));
         }
 
         let body = resp
@@ -144,10 +233,242 @@ impl JiraProvider {
     }
 }
 
+impl JiraProvider {
+    fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        op: &str,
+        path: &str,
+    ) -> Result<T, ProviderError> {
+        let resp = self
+            .client
+            .get(format!("{}{path}", self.base_url))
+            .basic_auth(&self.email, Some(&self.api_token))
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err(op, e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err(op, status, body));
+        }
+        resp.json().map_err(|e| self.map_err(op, e))
+    }
+
+    /// Sends a write whose response body doesn't matter.
+    fn send_json(
+        &self,
+        op: &str,
+        method: Method,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<(), ProviderError> {
+        let resp = self
+            .client
+            .request(method, format!("{}{path}", self.base_url))
+            .basic_auth(&self.email, Some(&self.api_token))
+            .json(&body)
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err(op, e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err(op, status, body));
+        }
+        Ok(())
+    }
+
+    /// Learns the board's issue titles and the names behind its mentions,
+    /// looking up accounts that no assignee has named yet.
+    fn learn_refs(&mut self, issues: &[Issue]) {
+        let mut unnamed = vec![];
+        for issue in issues {
+            self.refs
+                .titles
+                .insert(issue.key.clone(), issue.fields.summary.clone());
+            if let Some(User {
+                account_id: Some(id),
+                display_name,
+            }) = &issue.fields.assignee
+            {
+                self.refs.people.insert(id.clone(), display_name.clone());
+            }
+            if let Some(desc) = &issue.fields.description {
+                unnamed_mentions(desc, &mut unnamed);
+            }
+        }
+        self.name_people(unnamed);
+    }
+
+    /// Looks up the display names of accounts not known yet.
+    fn name_people(&mut self, mut unnamed: Vec<String>) {
+        unnamed.retain(|id| !self.refs.people.contains_key(id));
+        unnamed.sort();
+        unnamed.dedup();
+        for id in unnamed.into_iter().take(MAX_PEOPLE_LOOKUPS) {
+            match self.get_json::<User>("jira_user", &format!("/rest/api/3/user?accountId={id}")) {
+                Ok(user) => {
+                    self.refs.people.insert(id, user.display_name);
+                }
+                Err(e) => {
+                    log::write(&format!("jira: naming mentioned account {id}: {e}"));
+                    break;
+                }
+            }
+        }
+    }
+
+    /// How many cards to fetch for `column`, if it is capped.
+    fn fetch_cap(&self, column: &str) -> Option<usize> {
+        let (_, cap) = self
+            .fetch_limits
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(column))?;
+        Some(cap + self.extra.get(column).copied().unwrap_or(0))
+    }
+
+    /// The issues matching `req`. One that doesn't parse is left out and
+    /// reported in `skipped` rather than failing the page.
+    fn search_issues(
+        &self,
+        req: &SearchRequest,
+        skipped: &mut Vec<Problem>,
+    ) -> Result<Vec<Issue>, ProviderError> {
+        let url = format!("{}/rest/api/3/search/jql", self.base_url);
+        let resp = self
+            .client
+            .post(url)
+            .basic_auth(&self.email, Some(&self.api_token))
+            .json(req)
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_search", e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err("jira_search", status, body));
+        }
+        let data: SearchResponse = resp.json().map_err(|e| self.map_err("jira_search", e))?;
+        Ok(parse_issues(data.issues, skipped))
+    }
+
+    fn transition(&self, card_id: &str, transition_id: String) -> Result<(), ProviderError> {
+        let url = format!("{}/rest/api/3/issue/{card_id}/transitions", self.base_url);
+        let resp = self
+            .client
+            .post(url)
+            .basic_auth(&self.email, Some(&self.api_token))
+            .json(&TransitionRequest {
+                transition: IdOnly { id: transition_id },
+            })
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_transition", e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err("jira_transition", status, body));
+        }
+        Ok(())
+    }
+
+    /// Keys of the board's issues and the user's watched issues updated in
+    /// the last `minutes`.
+    fn recently_updated(&self, minutes: u64) -> Result<Vec<String>, ProviderError> {
+        let board_id = self.board_id.as_deref().unwrap_or_default();
+        let cfg = self.board_config(board_id)?;
+        let jql = format!(
+            "updated >= -{minutes}m AND (filter = {} OR watcher = currentUser()) ORDER BY updated DESC",
+            cfg.filter.id
+        );
+        let issues = self.search_issues(
+            &SearchRequest {
+                jql,
+                fields: vec!["summary".to_string(), "status".to_string()],
+                expand: String::new(),
+                max_results: 50,
+            },
+            &mut vec![],
+        )?;
+        Ok(issues.into_iter().map(|i| i.key).collect())
+    }
+
+    /// Creates an issue of the draft's `issue_type`, or the profile's,
+    /// assigned to the current user.
+    fn create_issue(&self, board_id: &str, draft: &Draft) -> Result<String, ProviderError> {
+        let board: BoardResponse =
+            self.get_json("jira_board", &format!("/rest/agile/1.0/board/{board_id}"))?;
+        let me: Myself = self.get_json("jira_myself", "/rest/api/3/myself")?;
+
+        let req = CreateIssueRequest {
+            fields: CreateIssueFields {
+                project: KeyOnly {
+                    key: board.location.project_key,
+                },
+                summary: draft.title.clone(),
+                issuetype: NameOnly {
+                    name: draft
+                        .front
+                        .get("issue_type")
+                        .unwrap_or(&self.issue_type)
+                        .to_string(),
+                },
+                assignee: AccountOnly {
+                    account_id: me.account_id,
+                },
+                description: (!draft.description.is_empty())
+                    .then(|| adf_from_text(&draft.description)),
+                labels: draft.front.list("labels"),
+                priority: draft.front.get("priority").map(|name| NameOnly {
+                    name: name.to_string(),
+                }),
+            },
+        };
+
+        let url = format!("{}/rest/api/3/issue", self.base_url);
+        let resp = self
+            .client
+            .post(url)
+            .basic_auth(&self.email, Some(&self.api_token))
+            .json(&req)
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_create_issue", e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err("jira_create_issue", status, body));
+        }
+
+        let created: CreatedIssue = resp
+            .json()
+            .map_err(|e| self.map_err("jira_create_issue", e))?;
+        Ok(created.key)
+    }
+
+    /// The id of the board's active sprint, if one is running.
+    fn active_sprint(&self, board_id: &str) -> Result<Option<u64>, ProviderError> {
+        let sprints: SprintPage = self.get_json(
+            "jira_sprints",
+            &format!("/rest/agile/1.0/board/{board_id}/sprint?state=active"),
+        )?;
+        Ok(sprints.values.first().map(|s| s.id))
+    }
+
+    fn add_to_sprint(&self, sprint: u64, card_id: &str) -> Result<(), ProviderError> {
+        self.send_json(
+            "jira_sprint_add",
+            Method::POST,
+            &format!("/rest/agile/1.0/sprint/{sprint}/issue"),
+            serde_json::json!({ "issues": [card_id] }),
+        )
+    }
+}
+
 impl Provider for JiraProvider {
     fn load_board(&mut self) -> Result<Board, ProviderError> {
         if let Some(msg) = &self.err {
-            return Err(ProviderError::Parse {
+            return Err(ProviderError::Config {
                 msg: format!("jira misconfigured: {msg}"),
             });
         }
@@ -155,7 +476,7 @@ impl Provider for JiraProvider {
         let board_id = self
             .board_id
             .as_deref()
-            .ok_or_else(|| ProviderError::Parse {
+            .ok_or_else(|| ProviderError::Config {
                 msg: "jira misconfigured: missing JIRA_BOARD_ID".to_string(),
             })?;
         let cfg = self.board_config(board_id)?;
@@ -168,64 +489,161 @@ impl Provider for JiraProvider {
                 }
             }
         }
-        let jql = format!(
-            "filter={} AND assignee = currentUser() AND sprint in openSprints()",
-            cfg.filter.id
+        let base = with_quick_filters(
+            board_jql(&cfg.filter.id, self.jql.as_deref(), cfg.kanban()),
+            &self.quick,
         );
-
-        let url = format!("{}/rest/api/3/search/jql", self.base_url);
-        let resp = self
-            .client
-            .post(url)
-            .basic_auth(&self.email, Some(&self.api_token))
-            .json(&SearchRequest {
-                jql,
-                fields: vec![
-                    "summary".to_string(),
-                    "description".to_string(),
-                    "status".to_string(),
-                ],
-                max_results: 200,
-            })
-            .send()
-            .map_err(|e| self.map_err("jira_search", e))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().unwrap_or_default();
-            return Err(self.map_err("jira_search", format!("status {status}: {body}")));
+        // Capped columns get their own newest-first query, one past the cap to
+        // tell whether more are left.
+        let mut capped = Vec::new();
+        if let Some(map) = &config_map {
+            for name in &map.order {
+                if let Some(cap) = self.fetch_cap(name)
+                    && let Some(ids) = map.column_to_status.get(name)
+                    && !ids.is_empty()
+                {
+                    capped.push((name.clone(), cap, ids.join(", ")));
+                }
+            }
         }
+        let board_search = |jql: String, max_results: usize| SearchRequest {
+            jql,
+            fields: BOARD_FIELDS
+                .iter()
+                .map(|f| f.to_string())
+                .chain([self.estimate_field.clone()])
+                .collect(),
+            expand: "changelog".to_string(),
+            max_results: max_results as u32,
+        };
 
-        let data: SearchResponse = resp.json().map_err(|e| self.map_err("jira_search", e))?;
+        let mut jql = base.clone();
+        if !capped.is_empty() {
+            let ids: Vec<&str> = capped.iter().map(|(_, _, ids)| ids.as_str()).collect();
+            jql.push_str(&format!(" AND status not in ({})", ids.join(", ")));
+        }
+        // A query that fails is reported and the rest of the board still
+        // loads; the load only fails when nothing does.
+        let mut problems = Vec::new();
+        let mut loaded = false;
+        let (mut issues, failed) = match self.search_issues(&board_search(jql, 200), &mut problems)
+        {
+            Ok(issues) => {
+                loaded = true;
+                (issues, None)
+            }
+            Err(e) => (vec![], Some(e)),
+        };
+        let mut more = HashSet::new();
+        for (name, cap, ids) in &capped {
+            let jql = format!("{base} AND status in ({ids}) ORDER BY updated DESC");
+            match self.search_issues(&board_search(jql, cap + 1), &mut problems) {
+                Ok(mut page) => {
+                    loaded = true;
+                    if page.len() > *cap {
+                        page.truncate(*cap);
+                        more.insert(name.clone());
+                    }
+                    issues.extend(page);
+                }
+                Err(e) => problems.push(Problem {
+                    card_id: None,
+                    msg: format!("{name}: not loaded: {e}"),
+                }),
+            }
+        }
+        if let Some(e) = failed {
+            if !loaded {
+                return Err(e);
+            }
+            problems.push(Problem {
+                card_id: None,
+                msg: format!("only the capped columns loaded: {e}"),
+            });
+        }
+        self.learn_refs(&issues);
 
         let mut columns = HashMap::<String, Vec<Card>>::new();
         let mut order = Vec::new();
 
-        for issue in data.issues {
+        for issue in issues {
             let status_name = issue.fields.status.name;
             let status_id = issue.fields.status.id.clone();
 
-            let column_name = status_to_column
-                .get(&status_id)
-                .cloned()
-                .unwrap_or(status_name);
+            let column_name = match status_to_column.get(&status_id) {
+                Some(name) => name.clone(),
+                None => {
+                    problems.push(Problem {
+                        card_id: Some(issue.key.clone()),
+                        msg: format!(
+                            "{}: status \"{status_name}\" is not mapped to a board column",
+                            issue.key
+                        ),
+                    });
+                    status_name
+                }
+            };
 
             if !columns.contains_key(&column_name) {
                 columns.insert(column_name.clone(), Vec::new());
                 order.push(column_name.clone());
             }
 
-            let desc = jira_description_text(issue.fields.description.as_ref());
+            let desc = jira_description_text(issue.fields.description.as_ref(), &self.refs);
+
+            let mut fields = BTreeMap::new();
+            if let Some(points) = issue
+                .fields
+                .other
+                .get(&self.estimate_field)
+                .and_then(serde_json::Value::as_f64)
+            {
+                fields.insert("estimate".to_string(), vec![points.to_string()]);
+            }
+            for link in &issue.fields.issuelinks {
+                let (description, other) = match (&link.outward_issue, &link.inward_issue) {
+                    (Some(other), _) => (&link.kind.outward, other),
+                    (None, Some(other)) => (&link.kind.inward, other),
+                    (None, None) => continue,
+                };
+                fields
+                    .entry(link_field(description))
+                    .or_default()
+                    .push(other.key.clone());
+            }
+            let status_since = issue
+                .changelog
+                .as_ref()
+                .and_then(last_status_change)
+                .or_else(|| issue.fields.created.clone());
+            for (key, value) in [
+                ("created", issue.fields.created),
+                ("updated", issue.fields.updated),
+                ("status_since", status_since),
+            ] {
+                if let Some(v) = value {
+                    fields.insert(key.to_string(), vec![v]);
+                }
+            }
 
             columns.get_mut(&column_name).unwrap().push(Card {
                 id: issue.key,
                 title: issue.fields.summary,
                 description: desc,
+                labels: issue.fields.labels,
+                priority: issue.fields.priority.map(|p| p.name),
+                assignee: issue.fields.assignee.map(|a| a.display_name),
+                due: issue.fields.duedate,
+                fields,
             });
         }
 
         let mut col_order = Vec::new();
+        let mut limits = HashMap::new();
+        let mut last_column = None;
         if let Some(map) = config_map {
+            limits = map.limits;
+            last_column = map.order.last().cloned();
             for name in map.order {
                 if !col_order.iter().any(|s: &String| s == &name) {
                     col_order.push(name);
@@ -242,19 +660,35 @@ impl Provider for JiraProvider {
         let mut cols = Vec::new();
         for name in col_order {
             let cards = columns.remove(&name).unwrap_or_default();
+            let meta = ColumnMeta {
+                wip_limit: limits.get(&name).copied(),
+                budget: None,
+                terminal: last_column.as_ref() == Some(&name),
+                color: None,
+                can_create: true,
+                more: more.contains(&name),
+                auto_sort: None,
+                policy: None,
+            };
             cols.push(Column {
                 id: name.clone(),
                 title: name,
                 cards,
+                meta,
             });
         }
 
-        Ok(Board { columns: cols })
+        let mut board = Board {
+            columns: cols,
+            problems,
+        };
+        sla::apply(&mut board, &self.sla, dates::today());
+        Ok(board)
     }
 
     fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
         if let Some(msg) = &self.err {
-            return Err(ProviderError::Parse {
+            return Err(ProviderError::Config {
                 msg: format!("jira misconfigured: {msg}"),
             });
         }
@@ -264,10 +698,10 @@ impl Provider for JiraProvider {
         if let Some(board_id) = &self.board_id {
             let cfg = self.board_config(board_id)?;
             let map = board_config_map(&cfg);
-            if let Some(status_ids) = map.column_to_status.get(to_col_id) {
-                if let Some(t) = pick_transition_for_column(&transitions, to_col_id, status_ids) {
-                    transition_id = Some(t.id.clone());
-                }
+            if let Some(status_ids) = map.column_to_status.get(to_col_id)
+                && let Some(t) = pick_transition_for_column(&transitions, to_col_id, status_ids)
+            {
+                transition_id = Some(t.id.clone());
             }
         }
         let transition_id = if let Some(id) = transition_id {
@@ -280,36 +714,589 @@ impl Provider for JiraProvider {
             });
         };
 
-        let url = format!("{}/rest/api/3/issue/{card_id}/transitions", self.base_url);
+        self.transition(card_id, transition_id)
+    }
+
+    fn quick_filters(&mut self) -> Result<Vec<QuickFilter>, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let board_id = self.board_id.as_deref().unwrap_or_default();
+        let page: QuickFilterPage = self.get_json(
+            "jira_quick_filters",
+            &format!("/rest/agile/1.0/board/{board_id}/quickfilter?maxResults=100"),
+        )?;
+        Ok(page
+            .values
+            .into_iter()
+            .map(|q| QuickFilter {
+                id: q.id.to_string(),
+                name: q.name,
+                query: q.jql,
+            })
+            .collect())
+    }
+
+    fn set_quick_filters(&mut self, filters: &[QuickFilter]) -> Result<(), ProviderError> {
+        self.quick = filters.iter().map(|q| q.query.clone()).collect();
+        Ok(())
+    }
+
+    /// Raises a capped column's limit by its configured amount for the next
+    /// load.
+    fn load_more(&mut self, col_id: &str) -> Result<(), ProviderError> {
+        let (_, &step) = self
+            .fetch_limits
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(col_id))
+            .ok_or_else(|| ProviderError::Parse {
+                msg: format!("{col_id} has no fetch limit"),
+            })?;
+        *self.extra.entry(col_id.to_string()).or_default() += step;
+        Ok(())
+    }
+
+    /// Applies the profile's `archive_transition`, or else any transition
+    /// into a status named Done.
+    fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let transitions = self.transitions(card_id)?;
+        let t = pick_archive_transition(&transitions, self.archive_transition.as_deref())
+            .ok_or_else(|| ProviderError::NotFound {
+                id: match &self.archive_transition {
+                    Some(name) => format!("{card_id} transition {name:?}"),
+                    None => format!("{card_id} transition to Done"),
+                },
+            })?;
+        self.transition(card_id, t.id.clone())
+    }
+
+    /// The user's finished issues on the board, most recently updated first.
+    fn archived_cards(&mut self) -> Result<Vec<Card>, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let cfg = self.board_config(self.board_id.as_deref().unwrap_or_default())?;
+        let issues = self.search_issues(
+            &SearchRequest {
+            jql: format!(
+                "filter={} AND assignee = currentUser() AND statusCategory = Done ORDER BY updated DESC",
+                cfg.filter.id
+            ),
+            fields: vec![
+                "summary".to_string(),
+                "description".to_string(),
+                "status".to_string(),
+            ],
+            expand: String::new(),
+            max_results: 100,
+            },
+            &mut vec![],
+        )?;
+        Ok(issues
+            .into_iter()
+            .map(|issue| Card {
+                id: issue.key,
+                title: issue.fields.summary,
+                description: jira_description_text(issue.fields.description.as_ref(), &self.refs),
+                ..Card::default()
+            })
+            .collect())
+    }
+
+    /// Anyone's issues in the board's filter, in any sprint or none, whose
+    /// text matches.
+    fn search(&mut self, query: &str) -> Result<Vec<Card>, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let cfg = self.board_config(self.board_id.as_deref().unwrap_or_default())?;
+        let issues = self.search_issues(
+            &SearchRequest {
+                jql: search_jql(&cfg.filter.id, query),
+                fields: ["summary", "status", "assignee"].map(String::from).to_vec(),
+                expand: String::new(),
+                max_results: 50,
+            },
+            &mut vec![],
+        )?;
+        Ok(issues
+            .into_iter()
+            .map(|issue| {
+                let mut card = Card {
+                    id: issue.key,
+                    title: issue.fields.summary,
+                    assignee: issue.fields.assignee.map(|a| a.display_name),
+                    ..Card::default()
+                };
+                card.fields
+                    .insert("status".to_string(), vec![issue.fields.status.name]);
+                card
+            })
+            .collect())
+    }
+
+    /// Adds the issue to the board's active sprint (a kanban board has none)
+    /// and assigns it to you, since the board shows your issues in open
+    /// sprints, then moves it to `to_col_id` unless it's there already.
+    fn pull_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let board_id = self.board_id.clone().unwrap_or_default();
+        let cfg = self.board_config(&board_id)?;
+        if cfg.kanban().is_none() {
+            let sprint = self
+                .active_sprint(&board_id)?
+                .ok_or_else(|| ProviderError::Config {
+                    msg: format!("board {board_id} has no active sprint to pull {card_id} into"),
+                })?;
+            self.add_to_sprint(sprint, card_id)?;
+        }
+        let me: Myself = self.get_json("jira_myself", "/rest/api/3/myself")?;
+        self.send_json(
+            "jira_assign",
+            Method::PUT,
+            &format!("/rest/api/3/issue/{card_id}/assignee"),
+            serde_json::json!({ "accountId": me.account_id }),
+        )?;
+        let issue: StatusIssue = self.get_json(
+            "jira_issue",
+            &format!("/rest/api/3/issue/{card_id}?fields=status"),
+        )?;
+        let map = board_config_map(&cfg);
+        if map
+            .column_to_status
+            .get(to_col_id)
+            .is_some_and(|ids| ids.contains(&issue.fields.status.id))
+        {
+            return Ok(());
+        }
+        self.move_card(card_id, to_col_id)
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+
+        let url = format!("{}/rest/api/3/issue/{card_id}", self.base_url);
+        let resp = self
+            .client
+            .put(url)
+            .basic_auth(&self.email, Some(&self.api_token))
+            .json(&serde_json::json!({
+                "fields": {
+                    "summary": title,
+                    "description": adf_from_text(description),
+                }
+            }))
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_update_issue", e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err("jira_update_issue", status, body));
+        }
+        Ok(())
+    }
+
+    fn set_estimate(&mut self, card_id: &str, points: f64) -> Result<(), ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+
+        let url = format!("{}/rest/api/3/issue/{card_id}", self.base_url);
+        let resp = self
+            .client
+            .put(url)
+            .basic_auth(&self.email, Some(&self.api_token))
+            .json(&serde_json::json!({ "fields": { &self.estimate_field: points } }))
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_set_estimate", e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err("jira_set_estimate", status, body));
+        }
+        Ok(())
+    }
+
+    fn card_detail(&mut self, card_id: &str) -> Result<CardDetail, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let issue: DetailIssue = self.get_json(
+            "jira_card_detail",
+            &format!(
+                "/rest/api/3/issue/{card_id}?fields=comment,attachment,issuetype,reporter,\
+                 assignee,labels,components,issuelinks,updated"
+            ),
+        )?;
+        let fields = detail_fields(&issue.fields);
+        let comments = issue.fields.comment.map(|p| p.comments).unwrap_or_default();
+        let mut unnamed = vec![];
+        for c in &comments {
+            if let Some(CommentAuthor {
+                account_id: Some(id),
+                display_name,
+            }) = &c.author
+            {
+                self.refs.people.insert(id.clone(), display_name.clone());
+            }
+            unnamed_mentions(&c.body, &mut unnamed);
+        }
+        self.name_people(unnamed);
+        Ok(CardDetail {
+            fields,
+            comments: comments
+                .into_iter()
+                .map(|c| Comment {
+                    author: c.author.map(|a| a.display_name).unwrap_or_default(),
+                    created: c.created,
+                    body: jira_description_text(Some(&c.body), &self.refs),
+                })
+                .collect(),
+            attachments: issue
+                .fields
+                .attachment
+                .into_iter()
+                .map(|a| Attachment {
+                    name: a.filename,
+                    bytes: a.size,
+                })
+                .collect(),
+            ..CardDetail::default()
+        })
+    }
+
+    /// Scans the comments on recently updated issues for ones that mention
+    /// the current user. Jira has no public notifications API, so this is
+    /// one search plus a comment fetch per issue.
+    fn mentions(&mut self, minutes: u64) -> Result<Vec<Mention>, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let me: Myself = self.get_json("jira_myself", "/rest/api/3/myself")?;
+        let oldest = dates::today() - minutes.div_ceil(24 * 60) as i64;
+
+        let mut out = Vec::new();
+        for key in self.recently_updated(minutes)? {
+            let page: CommentPage = self.get_json(
+                "jira_comments",
+                &format!("/rest/api/3/issue/{key}/comment?orderBy=-created&maxResults=20"),
+            )?;
+            for c in page.comments {
+                if dates::parse_date(&c.created).is_some_and(|d| d < oldest)
+                    || c.author.as_ref().and_then(|a| a.account_id.as_deref())
+                        == Some(&me.account_id)
+                    || !mentions_account(&c.body, &me.account_id)
+                {
+                    continue;
+                }
+                out.push(Mention {
+                    id: c.id,
+                    card_id: key.clone(),
+                    author: c.author.map(|a| a.display_name).unwrap_or_default(),
+                    body: jira_description_text(Some(&c.body), &self.refs),
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    /// From the changelogs of the filter's issues updated since `since`.
+    fn moves(&mut self, since: u64) -> Result<Vec<Move>, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let board_id = self.board_id.as_deref().unwrap_or_default();
+        let cfg = self.board_config(board_id)?;
+        let column_of: HashMap<String, String> = board_config_map(&cfg)
+            .column_to_status
+            .into_iter()
+            .flat_map(|(col, ids)| ids.into_iter().map(move |id| (id, col.clone())))
+            .collect();
+        let days = dates::now_secs()
+            .saturating_sub(since)
+            .div_ceil(86_400)
+            .max(1);
+        let issues = self.search_issues(
+            &SearchRequest {
+                jql: format!("filter = {} AND updated >= -{days}d", cfg.filter.id),
+                fields: ["summary", "status", "created"].map(String::from).to_vec(),
+                expand: "changelog".to_string(),
+                max_results: 200,
+            },
+            &mut vec![],
+        )?;
+        let mut moves: Vec<Move> = issues
+            .iter()
+            .flat_map(|i| issue_moves(i, &column_of))
+            .collect();
+        moves.sort_by_key(|m| m.at);
+        Ok(moves)
+    }
+
+    fn add_comment(&mut self, card_id: &str, body: &str) -> Result<(), ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+
+        let url = format!("{}/rest/api/3/issue/{card_id}/comment", self.base_url);
         let resp = self
             .client
             .post(url)
             .basic_auth(&self.email, Some(&self.api_token))
-            .json(&TransitionRequest {
-                transition: IdOnly { id: transition_id },
-            })
-            .send()
-            .map_err(|e| self.map_err("jira_transition", e))?;
+            .json(&serde_json::json!({ "body": adf_from_text(body) }))
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_add_comment", e))?;
 
         if !resp.status().is_success() {
             let status = resp.status();
             let body = resp.text().unwrap_or_default();
-            return Err(self.map_err("jira_transition", format!("status {status}: {body}")));
+            return Err(self.status_err("jira_add_comment", status, body));
         }
+        Ok(())
+    }
+
+    /// The first 200 people the issue can be assigned to; the picker
+    /// narrows them by name.
+    fn assignable_users(
+        &mut self,
+        card_id: &str,
+    ) -> Result<Vec<crate::model::User>, ProviderError> {
+        let users: Vec<User> = self.get_json(
+            "jira_assignable",
+            &format!("/rest/api/3/user/assignable/search?issueKey={card_id}&maxResults=200"),
+        )?;
+        Ok(users
+            .into_iter()
+            .filter_map(|u| {
+                Some(crate::model::User {
+                    id: u.account_id?,
+                    name: u.display_name,
+                })
+            })
+            .collect())
+    }
+
+    fn set_assignee(
+        &mut self,
+        card_id: &str,
+        user: Option<&crate::model::User>,
+    ) -> Result<(), ProviderError> {
+        self.send_json(
+            "jira_assign",
+            Method::PUT,
+            &format!("/rest/api/3/issue/{card_id}/assignee"),
+            serde_json::json!({ "accountId": user.map(|u| &u.id) }),
+        )
+    }
 
+    /// Uses whichever of the site's link types reads as `kind` outward, so
+    /// renamed types still work.
+    fn link_cards(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        kind: LinkKind,
+    ) -> Result<(), ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let types: IssueLinkTypes =
+            self.get_json("jira_link_types", "/rest/api/3/issueLinkType")?;
+        let Some(link_type) = types
+            .issue_link_types
+            .iter()
+            .find(|t| t.outward.eq_ignore_ascii_case(kind.label()))
+        else {
+            return Err(ProviderError::Config {
+                msg: format!("jira has no \"{}\" link type", kind.label()),
+            });
+        };
+
+        // Jira's names read backwards here: the inward issue is the one
+        // doing the blocking.
+        let url = format!("{}/rest/api/3/issueLink", self.base_url);
+        let resp = self
+            .client
+            .post(url)
+            .basic_auth(&self.email, Some(&self.api_token))
+            .json(&serde_json::json!({
+                "type": { "name": link_type.name },
+                "inwardIssue": { "key": from_id },
+                "outwardIssue": { "key": to_id },
+            }))
+            .send_within(&self.limit)
+            .map_err(|e| self.network_err("jira_link", e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(self.status_err("jira_link", status, body));
+        }
         Ok(())
     }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let draft = Draft {
+            title: title.to_string(),
+            ..Draft::default()
+        };
+        self.create_card_from(to_col_id, &draft)
+    }
+
+    /// Creates an issue assigned to the current user, then transitions it
+    /// into `to_col_id` unless that is the board's first column.
+    fn create_card_from(
+        &mut self,
+        to_col_id: &str,
+        draft: &Draft,
+    ) -> Result<String, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("jira misconfigured: {msg}"),
+            });
+        }
+        let board_id = self.board_id.clone().ok_or_else(|| ProviderError::Config {
+            msg: "jira misconfigured: missing JIRA_BOARD_ID".to_string(),
+        })?;
+
+        let key = self.create_issue(&board_id, draft)?;
+
+        let cfg = self.board_config(&board_id)?;
+        // Without a sprint it wouldn't show on a scrum board.
+        if cfg.kanban().is_none()
+            && let Some(sprint) = self.active_sprint(&board_id)?
+        {
+            self.add_to_sprint(sprint, &key)
+                .map_err(|e| ProviderError::Parse {
+                    msg: format!("created {key} but could not add it to the sprint: {e}"),
+                })?;
+        }
+        let first = board_config_map(&cfg).order.into_iter().next();
+        if first.as_deref() != Some(to_col_id) {
+            self.move_card(&key, to_col_id)
+                .map_err(|e| ProviderError::Parse {
+                    msg: format!("created {key} but could not move it to {to_col_id}: {e}"),
+                })?;
+        }
+        Ok(key)
+    }
 }
 
+/// Jira Cloud's "Story point estimate". Company-managed projects often use
+/// another custom field, set with the profile's `estimate_field`.
+const DEFAULT_ESTIMATE_FIELD: &str = "customfield_10016";
+
+/// The issue type of cards created with `n`, unless the profile's
+/// `issue_type` names another.
+const DEFAULT_ISSUE_TYPE: &str = "Task";
+
+const BOARD_FIELDS: &[&str] = &[
+    "summary",
+    "description",
+    "status",
+    "labels",
+    "priority",
+    "assignee",
+    "duedate",
+    "created",
+    "updated",
+    "issuelinks",
+];
+
 #[derive(Deserialize)]
 struct SearchResponse {
-    issues: Vec<Issue>,
+    /// Parsed one by one, so one odd issue doesn't lose the page.
+    issues: Vec<serde_json::Value>,
+}
+
+fn parse_issues(values: Vec<serde_json::Value>, skipped: &mut Vec<Problem>) -> Vec<Issue> {
+    values
+        .into_iter()
+        .filter_map(|value| {
+            let key = value["key"].as_str().unwrap_or("an issue").to_string();
+            serde_json::from_value(value)
+                .map_err(|e| {
+                    skipped.push(Problem {
+                        msg: format!("{key}: left out, unreadable issue data: {e}"),
+                        card_id: None,
+                    })
+                })
+                .ok()
+        })
+        .collect()
 }
 
 #[derive(Deserialize)]
 struct Issue {
     key: String,
     fields: IssueFields,
+    #[serde(default)]
+    changelog: Option<Changelog>,
+}
+
+#[derive(Deserialize)]
+struct Changelog {
+    #[serde(default)]
+    histories: Vec<History>,
+}
+
+#[derive(Deserialize)]
+struct History {
+    created: String,
+    #[serde(default)]
+    items: Vec<HistoryItem>,
+}
+
+#[derive(Deserialize)]
+struct HistoryItem {
+    field: String,
+    /// Status ids, for status changes.
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -317,6 +1304,81 @@ struct IssueFields {
     summary: String,
     description: Option<serde_json::Value>,
     status: Status,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    priority: Option<Priority>,
+    #[serde(default)]
+    assignee: Option<User>,
+    #[serde(default)]
+    duedate: Option<String>,
+    #[serde(default)]
+    created: Option<String>,
+    #[serde(default)]
+    updated: Option<String>,
+    #[serde(default)]
+    issuelinks: Vec<IssueLink>,
+    /// Custom fields, such as the estimate.
+    #[serde(flatten)]
+    other: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueLink {
+    #[serde(rename = "type")]
+    kind: IssueLinkType,
+    #[serde(default)]
+    inward_issue: Option<LinkedIssue>,
+    #[serde(default)]
+    outward_issue: Option<LinkedIssue>,
+}
+
+#[derive(Deserialize)]
+struct IssueLinkType {
+    name: String,
+    inward: String,
+    outward: String,
+}
+
+#[derive(Deserialize)]
+struct LinkedIssue {
+    key: String,
+    #[serde(default)]
+    fields: Option<LinkedFields>,
+}
+
+#[derive(Deserialize)]
+struct LinkedFields {
+    summary: String,
+    status: Status,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueLinkTypes {
+    issue_link_types: Vec<IssueLinkType>,
+}
+
+/// The card field for a link as the issue reads it: `blocks`, `is blocked
+/// by` → `blocked_by`, `relates to` → `relates_to`.
+fn link_field(description: &str) -> String {
+    let words = description.trim().to_lowercase();
+    let words = words.strip_prefix("is ").unwrap_or(&words);
+    words.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+#[derive(Deserialize)]
+struct Priority {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct User {
+    #[serde(rename = "accountId", default)]
+    account_id: Option<String>,
+    #[serde(rename = "displayName")]
+    display_name: String,
 }
 
 #[derive(Deserialize)]
@@ -333,6 +1395,8 @@ struct TransitionsResponse {
 #[derive(Deserialize)]
 struct Transition {
     id: String,
+    #[serde(default)]
+    name: String,
     to: Status,
 }
 
@@ -346,33 +1410,246 @@ struct IdOnly {
     id: String,
 }
 
+#[derive(Deserialize)]
+struct BoardResponse {
+    location: BoardLocation,
+}
+
+#[derive(Deserialize)]
+struct BoardLocation {
+    #[serde(rename = "projectKey")]
+    project_key: String,
+}
+
+#[derive(Deserialize)]
+struct Myself {
+    #[serde(rename = "accountId")]
+    account_id: String,
+}
+
+#[derive(Deserialize)]
+struct SprintPage {
+    values: Vec<Sprint>,
+}
+
+#[derive(Deserialize)]
+struct Sprint {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct StatusIssue {
+    fields: StatusFields,
+}
+
+#[derive(Deserialize)]
+struct StatusFields {
+    status: Status,
+}
+
+#[derive(Serialize)]
+struct CreateIssueRequest {
+    fields: CreateIssueFields,
+}
+
+#[derive(Serialize)]
+struct CreateIssueFields {
+    project: KeyOnly,
+    summary: String,
+    issuetype: NameOnly,
+    assignee: AccountOnly,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<NameOnly>,
+}
+
+#[derive(Serialize)]
+struct KeyOnly {
+    key: String,
+}
+
+#[derive(Serialize)]
+struct NameOnly {
+    name: String,
+}
+
+#[derive(Serialize)]
+struct AccountOnly {
+    #[serde(rename = "accountId")]
+    account_id: String,
+}
+
+#[derive(Deserialize)]
+struct CreatedIssue {
+    key: String,
+}
+
 #[derive(Deserialize)]
 struct BoardConfigResponse {
     #[serde(rename = "columnConfig")]
     column_config: ColumnConfig,
     filter: BoardFilter,
+    /// `scrum` or `kanban`.
+    #[serde(rename = "type", default)]
+    kind: String,
+    /// A kanban board's own narrowing of its filter, e.g. to hide released
+    /// work.
+    #[serde(rename = "subQuery", default)]
+    sub_query: Option<SubQuery>,
+}
+
+impl BoardConfigResponse {
+    /// The sub-query of a kanban board, empty when it has none; `None` for
+    /// boards with sprints.
+    fn kanban(&self) -> Option<&str> {
+        (self.kind == "kanban").then(|| self.sub_query.as_ref().map_or("", |q| q.query.as_str()))
+    }
+}
+
+#[derive(Deserialize)]
+struct SubQuery {
+    #[serde(default)]
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct BoardFilter {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ColumnConfig {
+    columns: Vec<BoardColumn>,
+}
+
+#[derive(Deserialize)]
+struct BoardColumn {
+    name: String,
+    statuses: Vec<IdOnly>,
+    /// Column constraint maximum, present when the board sets one.
+    #[serde(default)]
+    max: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct QuickFilterPage {
+    #[serde(default)]
+    values: Vec<QuickFilterValue>,
+}
+
+#[derive(Deserialize)]
+struct QuickFilterValue {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    jql: String,
+}
+
+#[derive(Deserialize)]
+struct CommentPage {
+    #[serde(default)]
+    comments: Vec<IssueComment>,
+}
+
+#[derive(Deserialize)]
+struct IssueComment {
+    id: String,
+    #[serde(default)]
+    author: Option<CommentAuthor>,
+    body: serde_json::Value,
+    #[serde(default)]
+    created: String,
+}
+
+#[derive(Deserialize)]
+struct DetailIssue {
+    fields: DetailFields,
 }
 
 #[derive(Deserialize)]
-struct BoardFilter {
-    id: String,
+struct DetailFields {
+    #[serde(default)]
+    comment: Option<CommentPage>,
+    #[serde(default)]
+    attachment: Vec<IssueAttachment>,
+    #[serde(default)]
+    issuetype: Option<Priority>,
+    #[serde(default)]
+    reporter: Option<User>,
+    #[serde(default)]
+    assignee: Option<User>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    components: Vec<Priority>,
+    #[serde(default)]
+    issuelinks: Vec<IssueLink>,
+    #[serde(default)]
+    updated: Option<String>,
+}
+
+/// The detail popup's field list; links name their issue's summary and
+/// status, which the board load leaves out.
+fn detail_fields(f: &DetailFields) -> Vec<(String, String)> {
+    let mut fields = vec![];
+    let mut push = |label: &str, value: String| {
+        if !value.is_empty() {
+            fields.push((label.to_string(), value));
+        }
+    };
+    let name = |n: &Option<Priority>| n.as_ref().map(|n| n.name.clone()).unwrap_or_default();
+    let user = |u: &Option<User>| {
+        u.as_ref()
+            .map(|u| u.display_name.clone())
+            .unwrap_or_default()
+    };
+    push("Type", name(&f.issuetype));
+    push("Reporter", user(&f.reporter));
+    push("Assignee", user(&f.assignee));
+    push("Labels", f.labels.join(", "));
+    let components: Vec<&str> = f.components.iter().map(|c| c.name.as_str()).collect();
+    push("Components", components.join(", "));
+    for link in &f.issuelinks {
+        let (description, other) = match (&link.outward_issue, &link.inward_issue) {
+            (Some(other), _) => (&link.kind.outward, other),
+            (None, Some(other)) => (&link.kind.inward, other),
+            (None, None) => continue,
+        };
+        let about = other
+            .fields
+            .as_ref()
+            .map(|o| format!(" {} ({})", o.summary, o.status.name))
+            .unwrap_or_default();
+        push("Link", format!("{description} {}{about}", other.key));
+    }
+    push("Updated", f.updated.clone().unwrap_or_default());
+    fields
 }
 
 #[derive(Deserialize)]
-struct ColumnConfig {
-    columns: Vec<BoardColumn>,
+struct IssueAttachment {
+    filename: String,
+    #[serde(default)]
+    size: u64,
 }
 
 #[derive(Deserialize)]
-struct BoardColumn {
-    name: String,
-    statuses: Vec<IdOnly>,
+struct CommentAuthor {
+    #[serde(rename = "accountId", default)]
+    account_id: Option<String>,
+    #[serde(rename = "displayName", default)]
+    display_name: String,
 }
 
 #[derive(serde::Serialize)]
 struct SearchRequest {
     jql: String,
     fields: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    expand: String,
     #[serde(rename = "maxResults")]
     max_results: u32,
 }
@@ -380,13 +1657,124 @@ struct SearchRequest {
 struct BoardConfigMap {
     order: Vec<String>,
     column_to_status: HashMap<String, Vec<String>>,
+    limits: HashMap<String, usize>,
+}
+
+/// The board's filter narrowed by `jql`, or to your issues in open sprints
+/// without it; a kanban board has no sprints, so there it's your issues
+/// within the board's sub-query. An `ORDER BY` is dropped, since columns are
+/// queried with their own.
+fn board_jql(filter_id: &str, jql: Option<&str>, kanban: Option<&str>) -> String {
+    let jql = jql.map(|q| {
+        let lower = q.to_ascii_lowercase();
+        lower.find("order by").map_or(q, |i| &q[..i]).trim()
+    });
+    match jql {
+        Some("") => format!("filter={filter_id}"),
+        Some(q) => format!("filter={filter_id} AND ({q})"),
+        None => {
+            let scope = match kanban.map(str::trim) {
+                None => " AND sprint in openSprints()".to_string(),
+                Some("") => String::new(),
+                Some(q) => format!(" AND ({q})"),
+            };
+            format!("filter={filter_id} AND assignee = currentUser(){scope}")
+        }
+    }
+}
+
+/// Issues in the board's filter whose summary, description or comments
+/// contain `query`, newest activity first.
+fn search_jql(filter_id: &str, query: &str) -> String {
+    let text = query.trim().replace('\\', "\\\\").replace('"', "\\\"");
+    format!("filter={filter_id} AND text ~ \"{text}\" ORDER BY updated DESC")
+}
+
+/// Narrows `jql` by each quick filter, parenthesized since they may use OR.
+fn with_quick_filters(jql: String, quick: &[String]) -> String {
+    quick
+        .iter()
+        .filter(|q| !q.trim().is_empty())
+        .fold(jql, |acc, q| format!("{acc} AND ({q})"))
+}
+
+/// When the issue last changed status. Histories aren't reliably ordered, so
+/// this takes the latest; the timestamps share one format and sort as text.
+fn last_status_change(changelog: &Changelog) -> Option<String> {
+    changelog
+        .histories
+        .iter()
+        .filter(|h| h.items.iter().any(|i| i.field == "status"))
+        .map(|h| h.created.clone())
+        .max()
+}
+
+/// An issue's status changes as moves between the columns its statuses map
+/// to, starting with its creation into the first. Changes within a column and
+/// into statuses off the board are left out.
+fn issue_moves(issue: &Issue, column_of: &HashMap<String, String>) -> Vec<Move> {
+    let mut changes: Vec<(u64, &HistoryItem)> = issue
+        .changelog
+        .iter()
+        .flat_map(|c| &c.histories)
+        .filter_map(|h| Some((dates::parse_timestamp(&h.created)?, h)))
+        .flat_map(|(at, h)| {
+            h.items
+                .iter()
+                .filter(|i| i.field == "status")
+                .map(move |i| (at, i))
+        })
+        .collect();
+    changes.sort_by_key(|(at, _)| *at);
+
+    let column = |status: Option<&String>| column_of.get(status?).cloned();
+    let first = match changes.first() {
+        Some((_, item)) => item.from.as_ref(),
+        None => Some(&issue.fields.status.id),
+    };
+    let mut moves = vec![];
+    let created = issue
+        .fields
+        .created
+        .as_deref()
+        .and_then(dates::parse_timestamp);
+    let mut current = None;
+    if let (Some(at), Some(to)) = (created, column(first)) {
+        current = Some(to.clone());
+        moves.push(Move {
+            card_id: issue.key.clone(),
+            from: None,
+            to,
+            at,
+        });
+    }
+    for (when, item) in changes {
+        let Some(to) = column(item.to.as_ref()) else {
+            current = None;
+            continue;
+        };
+        if current.as_ref() == Some(&to) {
+            continue;
+        }
+        moves.push(Move {
+            card_id: issue.key.clone(),
+            from: current.replace(to.clone()),
+            to,
+            at: when,
+        });
+    }
+    moves
 }
 
 fn board_config_map(cfg: &BoardConfigResponse) -> BoardConfigMap {
     let mut order = Vec::new();
     let mut column_to_status = HashMap::<String, Vec<String>>::new();
+    let mut limits = HashMap::new();
 
     for col in &cfg.column_config.columns {
+        if let Some(max) = col.max {
+            limits.insert(col.name.clone(), max);
+        }
         if !order.iter().any(|s: &String| s == &col.name) {
             order.push(col.name.clone());
         }
@@ -401,6 +1789,21 @@ fn board_config_map(cfg: &BoardConfigResponse) -> BoardConfigMap {
     BoardConfigMap {
         order,
         column_to_status,
+        limits,
+    }
+}
+
+fn pick_archive_transition<'a>(
+    transitions: &'a [Transition],
+    name: Option<&str>,
+) -> Option<&'a Transition> {
+    match name {
+        Some(name) => transitions
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name) || t.to.name.eq_ignore_ascii_case(name)),
+        None => transitions
+            .iter()
+            .find(|t| t.to.name.eq_ignore_ascii_case("done")),
     }
 }
 
@@ -441,98 +1844,528 @@ fn pick_transition_for_column<'a>(
     first_match
 }
 
-fn jira_description_text(desc: Option<&serde_json::Value>) -> String {
-    let Some(desc) = desc else {
-        return String::new();
-    };
+/// Whether an Atlassian document has an `@` mention of `account_id`.
+fn mentions_account(node: &serde_json::Value, account_id: &str) -> bool {
+    use serde_json::Value;
 
-    match desc {
-        serde_json::Value::String(s) => s.clone(),
-        _ => {
-            let mut out = String::new();
-            let mut state = RichTextState::new();
-            collect_rich_text(desc, &mut out, &mut state);
-            out.lines()
-                .map(str::trim)
-                .filter(|l| !l.is_empty())
-                .collect::<Vec<_>>()
-                .join("\n")
+    match node {
+        Value::Object(map) => {
+            let is_mention = map.get("type").and_then(Value::as_str) == Some("mention")
+                && map
+                    .get("attrs")
+                    .and_then(|a| a.get("id"))
+                    .and_then(Value::as_str)
+                    == Some(account_id);
+            is_mention
+                || map
+                    .get("content")
+                    .is_some_and(|c| mentions_account(c, account_id))
         }
+        Value::Array(arr) => arr.iter().any(|c| mentions_account(c, account_id)),
+        _ => false,
     }
 }
 
-struct RichTextState {
-    at_line_start: bool,
+/// Markdown (the subset [`adf_to_markdown`] writes) back to an Atlassian
+/// document, so editing a card doesn't flatten the issue's formatting.
+fn adf_from_text(text: &str) -> serde_json::Value {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    serde_json::json!({ "type": "doc", "version": 1, "content": adf_blocks(&lines) })
 }
 
-impl RichTextState {
-    fn new() -> Self {
-        Self { at_line_start: true }
+fn adf_blocks(lines: &[&str]) -> Vec<serde_json::Value> {
+    use serde_json::json;
+
+    let mut blocks = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_start();
+        if line.is_empty() {
+            i += 1;
+        } else if let Some(lang) = line.strip_prefix("```") {
+            let start = i + 1;
+            i = start;
+            while i < lines.len() && !lines[i].trim_start().starts_with("```") {
+                i += 1;
+            }
+            let code = lines[start..i].join("\n");
+            i += 1;
+            let mut block = json!({ "type": "codeBlock" });
+            if !lang.is_empty() {
+                block["attrs"] = json!({ "language": lang });
+            }
+            if !code.is_empty() {
+                block["content"] = json!([{ "type": "text", "text": code }]);
+            }
+            blocks.push(block);
+        } else if let Some((level, heading)) = md_heading(line) {
+            blocks.push(json!({
+                "type": "heading",
+                "attrs": { "level": level },
+                "content": adf_inline(heading, &[]),
+            }));
+            i += 1;
+        } else if line == "---" {
+            blocks.push(json!({ "type": "rule" }));
+            i += 1;
+        } else if line.starts_with('>') {
+            let start = i;
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                i += 1;
+            }
+            let quoted: Vec<&str> = lines[start..i]
+                .iter()
+                .map(|l| {
+                    let l = l.trim_start().trim_start_matches('>');
+                    l.strip_prefix(' ').unwrap_or(l)
+                })
+                .collect();
+            blocks.push(json!({ "type": "blockquote", "content": adf_blocks(&quoted) }));
+        } else if list_marker(line).is_some() {
+            blocks.push(adf_list(lines, &mut i));
+        } else {
+            blocks.push(json!({ "type": "paragraph", "content": adf_inline(line, &[]) }));
+            i += 1;
+        }
     }
+    blocks
+}
 
-    fn push_text(&mut self, out: &mut String, text: &str) {
-        out.push_str(text);
-        self.at_line_start = false;
-    }
+fn md_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, rest.trim()))
+}
 
-    fn push_newline(&mut self, out: &mut String) {
-        out.push('\n');
-        self.at_line_start = true;
+/// Whether the item is numbered, and its text.
+fn list_marker(line: &str) -> Option<(bool, &str)> {
+    if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|m| line.strip_prefix(m)) {
+        return Some((false, rest));
     }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = line[digits..].strip_prefix(". ")?;
+    (digits > 0).then_some((true, rest))
 }
 
-fn collect_rich_text(node: &serde_json::Value, out: &mut String, state: &mut RichTextState) {
-    use serde_json::Value;
-
-    match node {
-        Value::Object(map) => {
-            let ty = map.get("type").and_then(Value::as_str);
+/// The list starting at `lines[*i]`. Lines indented deeper than an item
+/// belong to it, nested lists included.
+fn adf_list(lines: &[&str], i: &mut usize) -> serde_json::Value {
+    let indent = |l: &str| l.len() - l.trim_start().len();
+    let depth = indent(lines[*i]);
+    let ordered = list_marker(lines[*i].trim_start()).is_some_and(|(o, _)| o);
+    let mut items = vec![];
+    while *i < lines.len() && indent(lines[*i]) == depth {
+        let Some((o, text)) = list_marker(lines[*i].trim_start()) else {
+            break;
+        };
+        if o != ordered {
+            break;
+        }
+        *i += 1;
+        let start = *i;
+        while *i < lines.len() && !lines[*i].trim().is_empty() && indent(lines[*i]) > depth {
+            *i += 1;
+        }
+        let mut content = vec![serde_json::json!({
+            "type": "paragraph",
+            "content": adf_inline(text, &[]),
+        })];
+        content.extend(adf_blocks(&lines[start..*i]));
+        items.push(serde_json::json!({ "type": "listItem", "content": content }));
+    }
+    let ty = if ordered { "orderedList" } else { "bulletList" };
+    serde_json::json!({ "type": ty, "content": items })
+}
 
-            if let Some(text) = map.get("text").and_then(Value::as_str) {
-                state.push_text(out, text);
+/// Text nodes for one line of inline markdown, each carrying `marks` plus
+/// its own. Unclosed markers stay text.
+fn adf_inline(text: &str, marks: &[serde_json::Value]) -> Vec<serde_json::Value> {
+    use serde_json::json;
+
+    let mut nodes = vec![];
+    let mut plain = String::new();
+    let flush = |plain: &mut String, nodes: &mut Vec<serde_json::Value>| {
+        if !plain.is_empty() {
+            let mut node = json!({ "type": "text", "text": std::mem::take(plain) });
+            if !marks.is_empty() {
+                node["marks"] = json!(marks);
             }
+            nodes.push(node);
+        }
+    };
+    let with = |mark: serde_json::Value| {
+        let mut m = marks.to_vec();
+        m.push(mark);
+        m
+    };
 
-            if ty == Some("hardBreak") {
-                state.push_newline(out);
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let wrapped = ["**", "~~", "`", "*"].into_iter().find_map(|marker| {
+            let body = rest.strip_prefix(marker)?;
+            let end = body
+                .find(marker)
+                .filter(|&e| e > 0 && !body.starts_with(' '))?;
+            let ty = match marker {
+                "**" => "strong",
+                "~~" => "strike",
+                "`" => "code",
+                _ => "em",
+            };
+            let inner = if marker == "`" {
+                // Code only combines with links in ADF.
+                let mut m: Vec<_> = marks
+                    .iter()
+                    .filter(|m| m["type"] == "link")
+                    .cloned()
+                    .collect();
+                m.push(json!({ "type": ty }));
+                vec![json!({ "type": "text", "text": &body[..end], "marks": m })]
+            } else {
+                adf_inline(&body[..end], &with(json!({ "type": ty })))
+            };
+            Some((inner, 2 * marker.len() + end))
+        });
+        let wrapped = wrapped.or_else(|| {
+            let body = rest.strip_prefix('[')?;
+            let close = body.find("](")?;
+            let end = close + body[close..].find(')')?;
+            let label = &body[..close];
+            if label.contains(']') {
+                return None;
             }
+            let href = &body[close + 2..end];
+            let link = json!({ "type": "link", "attrs": { "href": href } });
+            Some((adf_inline(label, &with(link)), end + 2))
+        });
+        match wrapped {
+            Some((inner, used)) => {
+                flush(&mut plain, &mut nodes);
+                nodes.extend(inner);
+                rest = &rest[used..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    flush(&mut plain, &mut nodes);
+    nodes
+}
 
-            if ty == Some("inlineCard") {
-                if let Some(url) = map
-                    .get("attrs")
-                    .and_then(Value::as_object)
-                    .and_then(|attrs| attrs.get("url"))
-                    .and_then(Value::as_str)
+fn jira_description_text(desc: Option<&serde_json::Value>, refs: &Refs) -> String {
+    match desc {
+        None => String::new(),
+        Some(serde_json::Value::String(s)) => wiki_text(s, refs),
+        Some(doc) => adf_to_markdown(doc, refs),
+    }
+}
+
+/// Wiki markup, as older issues and API v2 clients leave it, with
+/// `[~accountid:...]` mentions named and emoticons such as `(y)` drawn.
+fn wiki_text(s: &str, refs: &Refs) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("[~") {
+        out.push_str(&rest[..start]);
+        let body = &rest[start + 2..];
+        let Some(end) = body.find(']') else {
+            out.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let who = &body[..end];
+        out.push_str(&match who.strip_prefix("accountid:") {
+            Some(id) => refs.person(id),
+            None => format!("@{who}"),
+        });
+        rest = &body[end + 1..];
+    }
+    out.push_str(rest);
+    WIKI_EMOTICONS
+        .iter()
+        .fold(out, |text, (from, to)| text.replace(from, to))
+}
+
+const WIKI_EMOTICONS: &[(&str, &str)] = &[
+    ("(y)", "👍"),
+    ("(n)", "👎"),
+    ("(/)", "✅"),
+    ("(x)", "❌"),
+    ("(!)", "⚠️"),
+    ("(i)", "ℹ️"),
+    ("(?)", "❓"),
+    ("(on)", "💡"),
+    ("(*)", "⭐"),
+];
+
+/// Unicode for the common emoji shortcodes, Atlassian's own included, for
+/// emoji nodes that come without their text.
+fn emoji(short_name: &str) -> Option<&'static str> {
+    Some(match short_name.trim_matches(':') {
+        "smile" | "smiley" | "slight_smile" => "🙂",
+        "grinning" | "grin" => "😀",
+        "joy" => "😂",
+        "wink" => "😉",
+        "disappointed" | "frowning" => "🙁",
+        "thumbsup" | "+1" => "👍",
+        "thumbsdown" | "-1" => "👎",
+        "heart" => "❤️",
+        "tada" => "🎉",
+        "rocket" => "🚀",
+        "fire" => "🔥",
+        "eyes" => "👀",
+        "pray" => "🙏",
+        "clap" => "👏",
+        "100" => "💯",
+        "bug" => "🐛",
+        "white_check_mark" | "check_mark" => "✅",
+        "x" | "cross_mark" => "❌",
+        "warning" => "⚠️",
+        "info" => "ℹ️",
+        "question" => "❓",
+        "light_bulb" | "bulb" => "💡",
+        "star" => "⭐",
+        _ => return None,
+    })
+}
+
+impl Refs {
+    /// `@Name` for an account, or a placeholder rather than its raw id.
+    fn person(&self, account_id: &str) -> String {
+        match self.people.get(account_id) {
+            Some(name) => format!("@{name}"),
+            None => "@unknown user".to_string(),
+        }
+    }
+}
+
+/// The issue key an issue URL such as `.../browse/PROJ-42` points at.
+fn issue_key(url: &str) -> Option<&str> {
+    let (_, key) = url.split_once("/browse/")?;
+    let key = key.split(['?', '#', '/']).next()?;
+    let (project, number) = key.split_once('-')?;
+    (!project.is_empty() && !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+        .then_some(key)
+}
+
+/// Account ids mentioned in ADF without a display name, and in wiki markup.
+fn unnamed_mentions(node: &serde_json::Value, out: &mut Vec<String>) {
+    use serde_json::Value;
+
+    match node {
+        Value::String(s) => {
+            for part in s.split("[~accountid:").skip(1) {
+                if let Some((id, _)) = part.split_once(']') {
+                    out.push(id.to_string());
+                }
+            }
+        }
+        Value::Object(map) => {
+            if map.get("type").and_then(Value::as_str) == Some("mention") {
+                let attr = |name: &str| map.get("attrs")?.get(name)?.as_str();
+                if let Some(id) = attr("id")
+                    && attr("text").is_none_or(str::is_empty)
                 {
-                    state.push_text(out, url);
+                    out.push(id.to_string());
                 }
             }
+            if let Some(content) = map.get("content") {
+                unnamed_mentions(content, out);
+            }
+        }
+        Value::Array(arr) => arr.iter().for_each(|c| unnamed_mentions(c, out)),
+        _ => {}
+    }
+}
+
+/// An Atlassian document as markdown: headings, lists, quotes, code blocks,
+/// tables as `|` rows and inline marks. Blank lines are dropped, so each
+/// paragraph is one line.
+fn adf_to_markdown(doc: &serde_json::Value, refs: &Refs) -> String {
+    let mut lines = vec![];
+    let mut fenced = false;
+    for line in adf_block_lines(doc, refs) {
+        if line.trim_start().starts_with("```") {
+            fenced = !fenced;
+        }
+        if fenced || !line.trim().is_empty() {
+            lines.push(line.trim_end().to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+fn adf_block_lines(node: &serde_json::Value, refs: &Refs) -> Vec<String> {
+    use serde_json::Value;
 
-            if ty == Some("listItem") {
-                if state.at_line_start {
-                    state.push_text(out, "- ");
+    let children = adf_children;
+    let blocks = |n: &Value| adf_block_lines(n, refs);
+    let attr = |name: &str| node.get("attrs").and_then(|a| a.get(name));
+    let Some(map) = node.as_object() else {
+        return node
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(blocks)
+            .collect();
+    };
+    let ty = map.get("type").and_then(Value::as_str).unwrap_or_default();
+    let inline_lines = || -> Vec<String> {
+        adf_inline_text(children(node), refs)
+            .lines()
+            .map(|l| l.trim().to_string())
+            .collect()
+    };
+
+    match ty {
+        "paragraph" => inline_lines(),
+        "heading" => {
+            let level = attr("level")
+                .and_then(Value::as_u64)
+                .unwrap_or(1)
+                .clamp(1, 6);
+            let text = inline_lines().join(" ");
+            vec![format!("{} {text}", "#".repeat(level as usize))]
+        }
+        "bulletList" | "orderedList" => {
+            let start = attr("order").and_then(Value::as_u64).unwrap_or(1);
+            let mut lines = vec![];
+            for (n, item) in children(node).iter().enumerate() {
+                let marker = if ty == "orderedList" {
+                    format!("{}. ", start + n as u64)
                 } else {
-                    state.push_newline(out);
-                    state.push_text(out, "- ");
-                }
+                    "- ".to_string()
+                };
+                let pad = " ".repeat(marker.len());
+                let body = blocks(item);
+                let mut body = body.into_iter().filter(|l| !l.trim().is_empty());
+                lines.push(format!("{marker}{}", body.next().unwrap_or_default()));
+                lines.extend(body.map(|l| format!("{pad}{l}")));
+            }
+            lines
+        }
+        "codeBlock" => {
+            let lang = attr("language").and_then(Value::as_str).unwrap_or_default();
+            let mut lines = vec![format!("```{lang}")];
+            let code: String = children(node)
+                .iter()
+                .filter_map(|t| t.get("text").and_then(Value::as_str))
+                .collect();
+            lines.extend(code.lines().map(str::to_string));
+            lines.push("```".to_string());
+            lines
+        }
+        "blockquote" => children(node)
+            .iter()
+            .flat_map(blocks)
+            .map(|l| format!("> {l}"))
+            .collect(),
+        "rule" => vec!["---".to_string()],
+        "tableRow" => {
+            let cells: Vec<String> = children(node)
+                .iter()
+                .map(|cell| blocks(cell).join(" "))
+                .collect();
+            vec![format!("| {} |", cells.join(" | "))]
+        }
+        "expand" | "nestedExpand" => {
+            let mut lines = vec![];
+            if let Some(title) = attr("title").and_then(Value::as_str) {
+                lines.push(format!("**{title}**"));
             }
+            lines.extend(children(node).iter().flat_map(blocks));
+            lines
+        }
+        "mediaSingle" | "mediaGroup" => vec!["[attachment]".to_string()],
+        // doc, panel, table, listItem and anything unknown: their blocks.
+        _ if map.contains_key("content") => children(node).iter().flat_map(blocks).collect(),
+        // An inline node where a block was expected.
+        _ => adf_inline_text(std::slice::from_ref(node), refs)
+            .lines()
+            .map(str::to_string)
+            .collect(),
+    }
+}
 
-            if let Some(Value::Array(content)) = map.get("content") {
-                for child in content {
-                    collect_rich_text(child, out, state);
+fn adf_children(node: &serde_json::Value) -> &[serde_json::Value] {
+    node.get("content")
+        .and_then(serde_json::Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+fn adf_inline_text(nodes: &[serde_json::Value], refs: &Refs) -> String {
+    use serde_json::Value;
+
+    let mut out = String::new();
+    for node in nodes {
+        let attr = |name: &str| {
+            node.get("attrs")
+                .and_then(|a| a.get(name))
+                .and_then(Value::as_str)
+        };
+        match node.get("type").and_then(Value::as_str).unwrap_or_default() {
+            "text" => {
+                let mut text = node
+                    .get("text")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                let marks = node.get("marks").and_then(Value::as_array);
+                let has = |ty: &str| marks.is_some_and(|m| m.iter().any(|m| m["type"] == ty));
+                if has("code") {
+                    text = format!("`{text}`");
+                }
+                for (ty, marker) in [("em", "*"), ("strong", "**"), ("strike", "~~")] {
+                    if has(ty) {
+                        text = format!("{marker}{text}{marker}");
+                    }
+                }
+                let href = marks
+                    .into_iter()
+                    .flatten()
+                    .find(|m| m["type"] == "link")
+                    .and_then(|m| m["attrs"]["href"].as_str());
+                if let Some(href) = href {
+                    text = format!("[{text}]({href})");
                 }
+                out.push_str(&text);
             }
-
-            if matches!(ty, Some("paragraph") | Some("listItem")) {
-                state.push_newline(out);
+            "hardBreak" => out.push('\n'),
+            "mention" => match (attr("text").filter(|t| !t.is_empty()), attr("id")) {
+                (Some(text), _) if text.starts_with('@') => out.push_str(text),
+                (Some(text), _) => out.push_str(&format!("@{text}")),
+                (None, Some(id)) => out.push_str(&refs.person(id)),
+                (None, None) => out.push_str("@unknown user"),
+            },
+            "emoji" => {
+                let short = attr("shortName").unwrap_or_default();
+                let text = attr("text").filter(|t| !t.is_empty());
+                out.push_str(text.or_else(|| emoji(short)).unwrap_or(short))
             }
-        }
-        Value::Array(arr) => {
-            for child in arr {
-                collect_rich_text(child, out, state);
+            "inlineCard" => {
+                let url = attr("url").unwrap_or_default();
+                match issue_key(url) {
+                    // A link, so editing the description keeps it one.
+                    Some(key) => match refs.titles.get(key) {
+                        Some(title) => out.push_str(&format!("[{key} ({title})]({url})")),
+                        None => out.push_str(&format!("[{key}]({url})")),
+                    },
+                    None => out.push_str(url),
+                }
+            }
+            "status" => out.push_str(&format!("[{}]", attr("text").unwrap_or_default())),
+            "date" => {
+                let ms = attr("timestamp").and_then(|t| t.parse::<u64>().ok());
+                if let Some(ms) = ms {
+                    out.push_str(&dates::format_date((ms / 86_400_000) as i64));
+                }
             }
+            _ => out.push_str(&adf_inline_text(adf_children(node), refs)),
         }
-        _ => {}
     }
+    out
 }
 
 #[cfg(test)]
@@ -540,14 +2373,27 @@ mod tests {
     use super::*;
 
     #[test]
-    fn load_board_returns_parse_error_when_missing_env() {
+    fn load_board_returns_config_error_when_missing_env() {
         let mut provider = JiraProvider::from_parts(None, None, None, None);
         let err = match provider.load_board() {
             Ok(_) => panic!("expected load_board to fail"),
             Err(e) => e,
         };
 
-        assert!(matches!(err, ProviderError::Parse { .. }));
+        assert!(matches!(err, ProviderError::Config { .. }));
+    }
+
+    #[test]
+    fn created_issues_take_the_profiles_issue_type() {
+        let profile = Profile {
+            issue_type: Some("Story".to_string()),
+            ..Profile::default()
+        };
+        assert_eq!(JiraProvider::from_profile(&profile).issue_type, "Story");
+        assert_eq!(
+            JiraProvider::from_profile(&Profile::default()).issue_type,
+            DEFAULT_ISSUE_TYPE
+        );
     }
 
     #[test]
@@ -560,6 +2406,7 @@ mod tests {
                         statuses: vec![IdOnly {
                             id: "1".to_string(),
                         }],
+                        max: None,
                     },
                     BoardColumn {
                         name: "In Progress".to_string(),
@@ -571,18 +2418,23 @@ mod tests {
                                 id: "4".to_string(),
                             },
                         ],
+                        max: Some(3),
                     },
                 ],
             },
             filter: BoardFilter {
                 id: "123".to_string(),
             },
+            kind: "scrum".to_string(),
+            sub_query: None,
         };
 
         let map = board_config_map(&cfg);
         assert_eq!(map.order, vec!["To Do", "In Progress"]);
         assert_eq!(map.column_to_status["To Do"], vec!["1"]);
         assert_eq!(map.column_to_status["In Progress"], vec!["3", "4"]);
+        assert_eq!(map.limits.get("In Progress"), Some(&3));
+        assert_eq!(map.limits.get("To Do"), None);
     }
 
     #[test]
@@ -590,6 +2442,7 @@ mod tests {
         let transitions = vec![
             Transition {
                 id: "2".to_string(),
+                name: String::new(),
                 to: Status {
                     id: "2".to_string(),
                     name: "Selected for Development".to_string(),
@@ -597,6 +2450,7 @@ mod tests {
             },
             Transition {
                 id: "1".to_string(),
+                name: String::new(),
                 to: Status {
                     id: "1".to_string(),
                     name: "Open".to_string(),
@@ -610,6 +2464,238 @@ mod tests {
         assert_eq!(t.to.name, "Open");
     }
 
+    #[test]
+    fn load_more_raises_a_capped_columns_limit() {
+        let mut provider = JiraProvider::from_parts(None, None, None, None);
+        provider.fetch_limits = [("done".to_string(), 50)].into();
+
+        assert_eq!(provider.fetch_cap("Done"), Some(50));
+        provider.load_more("Done").unwrap();
+        assert_eq!(provider.fetch_cap("Done"), Some(100));
+        assert_eq!(provider.fetch_cap("In Progress"), None);
+        assert!(provider.load_more("In Progress").is_err());
+    }
+
+    #[test]
+    fn quick_filters_narrow_the_board_query() {
+        let quick = [
+            "priority = High OR labels = hot".to_string(),
+            " ".to_string(),
+        ];
+
+        assert_eq!(
+            with_quick_filters("filter=1".to_string(), &quick),
+            "filter=1 AND (priority = High OR labels = hot)"
+        );
+    }
+
+    #[test]
+    fn configured_jql_replaces_the_sprint_clause_within_the_filter() {
+        assert_eq!(
+            board_jql("7", None, None),
+            "filter=7 AND assignee = currentUser() AND sprint in openSprints()"
+        );
+        assert_eq!(
+            board_jql(
+                "7",
+                Some("assignee is EMPTY OR \"Epic Link\" = X-1 order by rank"),
+                None
+            ),
+            "filter=7 AND (assignee is EMPTY OR \"Epic Link\" = X-1)"
+        );
+        assert_eq!(board_jql("7", Some(" "), None), "filter=7");
+
+        let kanban: BoardConfigResponse = serde_json::from_value(serde_json::json!({
+            "type": "kanban",
+            "filter": { "id": "7" },
+            "subQuery": { "query": "fixVersion in unreleasedVersions() OR fixVersion is EMPTY" },
+            "columnConfig": { "columns": [] },
+        }))
+        .unwrap();
+        assert_eq!(
+            board_jql("7", None, kanban.kanban()),
+            "filter=7 AND assignee = currentUser() \
+             AND (fixVersion in unreleasedVersions() OR fixVersion is EMPTY)"
+        );
+        assert_eq!(
+            board_jql("7", None, Some("")),
+            "filter=7 AND assignee = currentUser()"
+        );
+    }
+
+    #[test]
+    fn search_quotes_the_text_inside_the_board_filter() {
+        assert_eq!(
+            search_jql("10", r#" login "redirect" \ "#),
+            r#"filter=10 AND text ~ "login \"redirect\" \\" ORDER BY updated DESC"#
+        );
+    }
+
+    #[test]
+    fn archive_uses_the_named_transition_or_done() {
+        let t = |id: &str, name: &str, to: &str| Transition {
+            id: id.to_string(),
+            name: name.to_string(),
+            to: Status {
+                id: id.to_string(),
+                name: to.to_string(),
+            },
+        };
+        let transitions = vec![t("1", "Finish", "Done"), t("2", "Shelve", "Archived")];
+
+        assert_eq!(pick_archive_transition(&transitions, None).unwrap().id, "1");
+        assert_eq!(
+            pick_archive_transition(&transitions, Some("shelve"))
+                .unwrap()
+                .id,
+            "2"
+        );
+        assert!(pick_archive_transition(&transitions, Some("Trash")).is_none());
+    }
+
+    #[test]
+    fn last_status_change_ignores_other_fields() {
+        let changelog: Changelog = serde_json::from_value(serde_json::json!({
+            "histories": [
+                {"created": "2024-05-20T10:00:00.000+0000", "items": [{"field": "status"}]},
+                {"created": "2024-05-29T10:00:00.000+0000", "items": [{"field": "labels"}]},
+                {"created": "2024-05-25T10:00:00.000+0000", "items": [{"field": "status"}]}
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            last_status_change(&changelog).as_deref(),
+            Some("2024-05-25T10:00:00.000+0000")
+        );
+    }
+
+    #[test]
+    fn status_changes_become_moves_between_columns() {
+        let issue: Issue = serde_json::from_value(serde_json::json!({
+            "key": "P-1",
+            "fields": {
+                "summary": "S",
+                "status": {"id": "3", "name": "Done"},
+                "created": "2024-06-01T09:00:00.000+0000"
+            },
+            "changelog": {"histories": [
+                {"created": "2024-06-03T09:00:00.000+0000",
+                 "items": [{"field": "status", "from": "2", "to": "3"}]},
+                {"created": "2024-06-02T09:00:00.000+0000",
+                 "items": [{"field": "status", "from": "1", "to": "2"},
+                           {"field": "labels"}]}
+            ]}
+        }))
+        .unwrap();
+        // Statuses 1 and 2 share a column.
+        let column_of = [("1", "To Do"), ("2", "To Do"), ("3", "Done")]
+            .map(|(s, c)| (s.to_string(), c.to_string()))
+            .into();
+
+        let moves = issue_moves(&issue, &column_of);
+        let path: Vec<_> = moves
+            .iter()
+            .map(|m| (m.from.as_deref(), m.to.as_str()))
+            .collect();
+        assert_eq!(path, [(None, "To Do"), (Some("To Do"), "Done")]);
+        assert_eq!(moves[1].at - moves[0].at, 2 * 86_400);
+    }
+
+    #[test]
+    fn an_unreadable_issue_is_left_out_of_its_page() {
+        let issue = |key: &str, status: serde_json::Value| serde_json::json!({ "key": key, "fields": { "summary": "S", "description": null, "status": status } });
+        let mut skipped = vec![];
+        let issues = parse_issues(
+            vec![
+                issue("A-1", serde_json::json!({ "id": "1", "name": "To Do" })),
+                issue("A-2", serde_json::json!("To Do")),
+            ],
+            &mut skipped,
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "A-1");
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].msg.starts_with("A-2: left out"));
+    }
+
+    #[test]
+    fn ids_in_descriptions_read_as_names_titles_and_emoji() {
+        let refs = Refs {
+            people: [("acc-1".to_string(), "Ana".to_string())].into(),
+            titles: [("PROJ-42".to_string(), "Login".to_string())].into(),
+        };
+        let doc = serde_json::json!({ "type": "doc", "content": [{ "type": "paragraph", "content": [
+            { "type": "mention", "attrs": { "id": "acc-1" } },
+            { "type": "text", "text": " see " },
+            { "type": "inlineCard", "attrs": { "url": "https://x.atlassian.net/browse/PROJ-42" } },
+            { "type": "text", "text": " " },
+            { "type": "emoji", "attrs": { "shortName": ":thumbsup:" } },
+            { "type": "mention", "attrs": { "id": "acc-9" } }
+        ] }] });
+        assert_eq!(
+            jira_description_text(Some(&doc), &refs),
+            "@Ana see [PROJ-42 (Login)](https://x.atlassian.net/browse/PROJ-42) 👍@unknown user"
+        );
+
+        let wiki = serde_json::json!("ping [~accountid:acc-1] (y), and [~bob]");
+        assert_eq!(
+            jira_description_text(Some(&wiki), &refs),
+            "ping @Ana 👍, and @bob"
+        );
+        let mut unnamed = vec![];
+        unnamed_mentions(&doc, &mut unnamed);
+        unnamed_mentions(&wiki, &mut unnamed);
+        assert_eq!(unnamed, ["acc-1", "acc-9", "acc-1"]);
+    }
+
+    #[test]
+    fn links_become_fields_named_as_the_issue_reads_them() {
+        let fields: IssueFields = serde_json::from_str(
+            r#"{"summary": "s", "status": {"id": "1", "name": "To Do"}, "issuelinks": [
+                {"type": {"name": "Blocks", "inward": "is blocked by", "outward": "blocks"},
+                 "inwardIssue": {"key": "A-1"}},
+                {"type": {"name": "Relates", "inward": "relates to", "outward": "relates to"},
+                 "outwardIssue": {"key": "A-2"}}]}"#,
+        )
+        .unwrap();
+
+        let link = &fields.issuelinks[0];
+        assert_eq!(link.inward_issue.as_ref().unwrap().key, "A-1");
+        assert_eq!(link_field(&link.kind.inward), "blocked_by");
+        assert_eq!(link_field(&fields.issuelinks[1].kind.outward), "relates_to");
+        assert_eq!(link_field("Is Duplicated By"), "duplicated_by");
+    }
+
+    #[test]
+    fn detail_fields_name_linked_issues_and_skip_empty_ones() {
+        let fields: DetailFields = serde_json::from_str(
+            r#"{"issuetype": {"name": "Bug"}, "reporter": {"displayName": "Ana"},
+                "labels": [], "components": [{"name": "api"}, {"name": "web"}],
+                "issuelinks": [
+                {"type": {"name": "Blocks", "inward": "is blocked by", "outward": "blocks"},
+                 "inwardIssue": {"key": "A-1", "fields": {"summary": "Auth", "status": {"id": "3", "name": "Done"}}}}],
+                "updated": "2024-06-01T09:30:00.000+0000"}"#,
+        )
+        .unwrap();
+
+        let fields = detail_fields(&fields);
+        let fields: Vec<(&str, &str)> = fields
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("Type", "Bug"),
+                ("Reporter", "Ana"),
+                ("Components", "api, web"),
+                ("Link", "is blocked by A-1 Auth (Done)"),
+                ("Updated", "2024-06-01T09:30:00.000+0000"),
+            ]
+        );
+    }
+
     #[test]
     fn jira_description_extracts_text() {
         let desc = serde_json::json!({
@@ -631,7 +2717,10 @@ mod tests {
             ]
         });
 
-        assert_eq!(jira_description_text(Some(&desc)), "Hello\nWorld");
+        assert_eq!(
+            jira_description_text(Some(&desc), &Refs::default()),
+            "Hello\nWorld"
+        );
     }
 
     #[test]
@@ -671,7 +2760,7 @@ mod tests {
         });
 
         assert_eq!(
-            jira_description_text(Some(&desc)),
+            jira_description_text(Some(&desc), &Refs::default()),
             "- First\n- Second"
         );
     }
@@ -691,6 +2780,99 @@ mod tests {
             ]
         });
 
-        assert_eq!(jira_description_text(Some(&desc)), "https://example.com");
+        assert_eq!(
+            jira_description_text(Some(&desc), &Refs::default()),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn finds_mentions_of_the_account_anywhere_in_a_comment() {
+        let body = serde_json::json!({
+            "type": "doc",
+            "content": [{
+                "type": "bulletList",
+                "content": [{
+                    "type": "listItem",
+                    "content": [{
+                        "type": "paragraph",
+                        "content": [
+                            { "type": "text", "text": "can you look, " },
+                            { "type": "mention", "attrs": { "id": "acc-1", "text": "@Ana" } },
+                        ],
+                    }],
+                }],
+            }],
+        });
+
+        assert!(mentions_account(&body, "acc-1"));
+        assert!(!mentions_account(&body, "acc-2"));
+        assert_eq!(
+            jira_description_text(Some(&body), &Refs::default()),
+            "- can you look, @Ana"
+        );
+    }
+
+    #[test]
+    fn jira_description_converts_rich_content_to_markdown() {
+        let text = |t: &str| serde_json::json!({ "type": "text", "text": t });
+        let marked = |t: &str, marks: serde_json::Value| serde_json::json!({ "type": "text", "text": t, "marks": marks });
+        let para = |content: serde_json::Value| serde_json::json!({ "type": "paragraph", "content": content });
+        let desc = serde_json::json!({
+            "type": "doc",
+            "content": [
+                { "type": "heading", "attrs": { "level": 2 }, "content": [text("Plan")] },
+                para(serde_json::json!([
+                    text("Ship "),
+                    marked("now", serde_json::json!([{ "type": "strong" }])),
+                    text(", see "),
+                    marked("docs", serde_json::json!([{ "type": "link", "attrs": { "href": "https://x.io" } }])),
+                    { "type": "hardBreak" },
+                    { "type": "status", "attrs": { "text": "BLOCKED" } },
+                ])),
+                { "type": "orderedList", "content": [{
+                    "type": "listItem",
+                    "content": [
+                        para(serde_json::json!([marked("cfg", serde_json::json!([{ "type": "code" }]))])),
+                        { "type": "bulletList", "content": [{
+                            "type": "listItem", "content": [para(serde_json::json!([text("nested")]))],
+                        }]},
+                    ],
+                }]},
+                { "type": "codeBlock", "attrs": { "language": "sh" }, "content": [text("make\n  run")] },
+                { "type": "blockquote", "content": [para(serde_json::json!([text("quoted")]))] },
+                { "type": "table", "content": [{ "type": "tableRow", "content": [
+                    { "type": "tableCell", "content": [para(serde_json::json!([text("a")]))] },
+                    { "type": "tableCell", "content": [para(serde_json::json!([text("b")]))] },
+                ]}]},
+            ],
+        });
+
+        assert_eq!(
+            jira_description_text(Some(&desc), &Refs::default()),
+            "## Plan\nShip **now**, see [docs](https://x.io)\n[BLOCKED]\n1. `cfg`\n   - nested\n```sh\nmake\n  run\n```\n> quoted\n| a | b |"
+        );
+    }
+
+    #[test]
+    fn markdown_survives_a_trip_through_adf() {
+        let md = "# Title\nSome **bold**, *italic*, ~~gone~~, `code` and [a link](https://x.io)\n- one\n  1. inner\n- two\n```\nfn main() {}\n```\n> quote\n---";
+
+        assert_eq!(
+            jira_description_text(Some(&adf_from_text(md)), &Refs::default()),
+            md
+        );
+    }
+
+    #[test]
+    fn adf_from_text_round_trips_through_description_text() {
+        let text = "First line\n\nSecond line\nThird";
+        let adf = adf_from_text(text);
+
+        assert_eq!(adf["content"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            jira_description_text(Some(&adf), &Refs::default()),
+            "First line\nSecond line\nThird"
+        );
     }
 }
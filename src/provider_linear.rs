@@ -0,0 +1,586 @@
+//! Linear over its GraphQL API. A team's workflow states are the columns,
+//! named as in Linear; its issues (optionally one project's) are the cards.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use reqwest::blocking::Client;
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::{
+    config::Profile,
+    dates,
+    http::{self, SendWithin},
+    model::{Board, Card, Column, ColumnMeta, Problem},
+    provider::{Provider, ProviderError},
+    sla,
+};
+
+const DEFAULT_URL: &str = "https://api.linear.app";
+/// Issues fetched per request, and the most a board loads.
+const PAGE: usize = 100;
+const MAX_ISSUES: usize = 1000;
+
+pub struct LinearProvider {
+    client: Client,
+    limit: Arc<http::Limit>,
+    url: String,
+    api_key: String,
+    /// The team's key, e.g. `ENG`.
+    team: String,
+    project: Option<String>,
+    sla: BTreeMap<String, u32>,
+    /// From the last load: the team's id, its project's, and its states by name.
+    team_id: Option<String>,
+    project_id: Option<String>,
+    states: HashMap<String, String>,
+    err: Option<String>,
+}
+
+impl LinearProvider {
+    pub fn from_profile(profile: &Profile) -> Self {
+        let mut missing = vec![];
+        let api_key = match &profile.api_token {
+            Some(v) if !v.trim().is_empty() => v.trim().to_string(),
+            _ => {
+                missing.push("LINEAR_API_KEY");
+                String::new()
+            }
+        };
+        let team = match &profile.team {
+            Some(v) if !v.trim().is_empty() => v.trim().to_string(),
+            _ => {
+                missing.push("LINEAR_TEAM");
+                String::new()
+            }
+        };
+        let url = profile
+            .base_url
+            .as_deref()
+            .unwrap_or(DEFAULT_URL)
+            .trim_end_matches('/')
+            .to_string();
+        Self {
+            client: http::client(&profile.http),
+            limit: http::limit(&url, &profile.http),
+            url,
+            api_key,
+            team,
+            project: profile.project.clone().filter(|p| !p.trim().is_empty()),
+            sla: profile.sla.clone(),
+            team_id: None,
+            project_id: None,
+            states: HashMap::new(),
+            err: (!missing.is_empty()).then(|| format!("missing {}", missing.join(", "))),
+        }
+    }
+
+    fn map_err(&self, op: &str, err: impl ToString) -> ProviderError {
+        ProviderError::Io {
+            op: op.to_string(),
+            path: PathBuf::from(&self.url),
+            source: io::Error::other(err.to_string()),
+        }
+    }
+
+    /// Runs one query or mutation. GraphQL reports most failures as
+    /// `errors` in a 200 response, so those are checked as well as the status.
+    fn graphql<T: DeserializeOwned>(
+        &self,
+        op: &str,
+        query: &str,
+        variables: Value,
+    ) -> Result<T, ProviderError> {
+        if let Some(msg) = &self.err {
+            return Err(ProviderError::Config {
+                msg: format!("linear misconfigured: {msg}"),
+            });
+        }
+        let resp = self
+            .client
+            .post(format!("{}/graphql", self.url))
+            .header("Authorization", &self.api_key)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send_within(&self.limit)
+            .map_err(|e| ProviderError::Network {
+                op: op.to_string(),
+                url: self.url.clone(),
+                msg: e.to_string(),
+            })?;
+
+        let status = resp.status();
+        match status.as_u16() {
+            401 | 403 => {
+                return Err(ProviderError::Auth {
+                    msg: format!("{op}: status {status}"),
+                });
+            }
+            500..=599 => {
+                return Err(ProviderError::Network {
+                    op: op.to_string(),
+                    url: self.url.clone(),
+                    msg: format!("status {status}"),
+                });
+            }
+            _ => {}
+        }
+        let body: Response<T> = resp.json().map_err(|e| self.map_err(op, e))?;
+        if let Some(e) = body.errors.first() {
+            let code = e.extensions.as_ref().and_then(|x| x.code.as_deref());
+            return Err(match code {
+                Some("AUTHENTICATION_ERROR") => ProviderError::Auth {
+                    msg: format!("{op}: {}", e.message),
+                },
+                _ => self.map_err(op, &e.message),
+            });
+        }
+        body.data
+            .ok_or_else(|| self.map_err(op, "response has no data"))
+    }
+
+    fn load_team(&mut self) -> Result<Vec<State>, ProviderError> {
+        let data: TeamData = self.graphql(
+            "linear_team",
+            TEAM_QUERY,
+            json!({ "team": self.team, "project": self.project.clone().unwrap_or_default() }),
+        )?;
+        let team = data
+            .teams
+            .nodes
+            .into_iter()
+            .next()
+            .ok_or_else(|| ProviderError::Config {
+                msg: format!("linear has no team with key {:?}", self.team),
+            })?;
+        self.project_id = data.projects.nodes.into_iter().next().map(|p| p.id);
+        if let Some(name) = &self.project
+            && self.project_id.is_none()
+        {
+            return Err(ProviderError::Config {
+                msg: format!("linear has no project named {name:?}"),
+            });
+        }
+        self.team_id = Some(team.id);
+        self.states = team
+            .states
+            .nodes
+            .iter()
+            .map(|s| (s.name.clone(), s.id.clone()))
+            .collect();
+        Ok(team.states.nodes)
+    }
+
+    fn state_id(&mut self, col_id: &str) -> Result<String, ProviderError> {
+        if self.states.is_empty() {
+            self.load_team()?;
+        }
+        self.states
+            .get(col_id)
+            .cloned()
+            .ok_or_else(|| ProviderError::NotFound {
+                id: col_id.to_string(),
+            })
+    }
+
+    fn update(&self, op: &str, card_id: &str, input: Value) -> Result<(), ProviderError> {
+        let data: UpdateData = self.graphql(
+            op,
+            "mutation($id: String!, $input: IssueUpdateInput!) { issueUpdate(id: $id, input: $input) { success } }",
+            json!({ "id": card_id, "input": input }),
+        )?;
+        if data.issue_update.success {
+            Ok(())
+        } else {
+            Err(self.map_err(op, format!("{card_id} was not updated")))
+        }
+    }
+}
+
+impl Provider for LinearProvider {
+    fn load_board(&mut self) -> Result<Board, ProviderError> {
+        let states = self.load_team()?;
+        let mut filter = json!({ "team": { "key": { "eq": self.team } } });
+        if let Some(id) = &self.project_id {
+            filter["project"] = json!({ "id": { "eq": id } });
+        }
+
+        let mut issues = vec![];
+        let mut after: Option<String> = None;
+        let mut truncated = false;
+        loop {
+            let data: IssuesData = self.graphql(
+                "linear_issues",
+                ISSUES_QUERY,
+                json!({ "filter": filter, "after": after, "first": PAGE }),
+            )?;
+            issues.extend(data.issues.nodes);
+            if !data.issues.page_info.has_next_page {
+                break;
+            }
+            if issues.len() >= MAX_ISSUES {
+                truncated = true;
+                break;
+            }
+            after = data.issues.page_info.end_cursor;
+        }
+
+        let mut board = board_from(states, issues);
+        if truncated {
+            board.problems.push(Problem {
+                card_id: None,
+                msg: format!("showing the {MAX_ISSUES} most recently updated issues"),
+            });
+        }
+        sla::apply(&mut board, &self.sla, dates::today());
+        Ok(board)
+    }
+
+    fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        let state = self.state_id(to_col_id)?;
+        self.update("linear_move", card_id, json!({ "stateId": state }))
+    }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let state = self.state_id(to_col_id)?;
+        let mut input = json!({ "teamId": self.team_id, "title": title, "stateId": state });
+        if let Some(id) = &self.project_id {
+            input["projectId"] = json!(id);
+        }
+        let data: CreateData = self.graphql(
+            "linear_create",
+            "mutation($input: IssueCreateInput!) { issueCreate(input: $input) { issue { identifier } } }",
+            json!({ "input": input }),
+        )?;
+        data.issue_create
+            .issue
+            .map(|i| i.identifier)
+            .ok_or_else(|| self.map_err("linear_create", "no issue was created"))
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        self.update(
+            "linear_update",
+            card_id,
+            json!({ "title": title, "description": description }),
+        )
+    }
+
+    /// Linear estimates are whole points.
+    fn set_estimate(&mut self, card_id: &str, points: f64) -> Result<(), ProviderError> {
+        if points.fract() != 0.0 {
+            return Err(ProviderError::Parse {
+                msg: format!("linear estimates are whole numbers, not {points}"),
+            });
+        }
+        self.update(
+            "linear_set_estimate",
+            card_id,
+            json!({ "estimate": points as i64 }),
+        )
+    }
+
+    fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+        let _: Value = self.graphql(
+            "linear_archive",
+            "mutation($id: String!) { issueArchive(id: $id) { success } }",
+            json!({ "id": card_id }),
+        )?;
+        Ok(())
+    }
+
+    fn add_comment(&mut self, card_id: &str, body: &str) -> Result<(), ProviderError> {
+        let _: Value = self.graphql(
+            "linear_comment",
+            "mutation($input: CommentCreateInput!) { commentCreate(input: $input) { success } }",
+            json!({ "input": { "issueId": card_id, "body": body } }),
+        )?;
+        Ok(())
+    }
+}
+
+const TEAM_QUERY: &str = "query($team: String!, $project: String!) {
+  teams(filter: { key: { eq: $team } }) {
+    nodes { id states { nodes { id name type position } } }
+  }
+  projects(filter: { name: { eqIgnoreCase: $project } }) { nodes { id } }
+}";
+
+const ISSUES_QUERY: &str = "query($filter: IssueFilter, $after: String, $first: Int) {
+  issues(filter: $filter, after: $after, first: $first, orderBy: updatedAt) {
+    nodes {
+      identifier title description priorityLabel dueDate estimate createdAt updatedAt
+      state { name }
+      assignee { displayName }
+      labels { nodes { name } }
+    }
+    pageInfo { hasNextPage endCursor }
+  }
+}";
+
+/// Columns in workflow order (triage through canceled, then Linear's own
+/// position), with completed and canceled work terminal.
+fn board_from(mut states: Vec<State>, issues: Vec<Issue>) -> Board {
+    const TYPES: [&str; 6] = [
+        "triage",
+        "backlog",
+        "unstarted",
+        "started",
+        "completed",
+        "canceled",
+    ];
+    let rank = |s: &State| {
+        TYPES
+            .iter()
+            .position(|t| *t == s.kind)
+            .unwrap_or(TYPES.len())
+    };
+    states.sort_by(|a, b| {
+        rank(a)
+            .cmp(&rank(b))
+            .then(a.position.total_cmp(&b.position))
+    });
+
+    let mut cards: HashMap<String, Vec<Card>> = HashMap::new();
+    let mut problems = vec![];
+    for issue in issues {
+        let state = issue.state.name.clone();
+        if !states.iter().any(|s| s.name == state) {
+            problems.push(Problem {
+                card_id: Some(issue.identifier.clone()),
+                msg: format!("{}: state \"{state}\" is not on the team", issue.identifier),
+            });
+        }
+        cards.entry(state).or_default().push(card_from(issue));
+    }
+
+    let columns = states
+        .into_iter()
+        .map(|s| Column {
+            cards: cards.remove(&s.name).unwrap_or_default(),
+            meta: ColumnMeta {
+                terminal: matches!(s.kind.as_str(), "completed" | "canceled"),
+                ..ColumnMeta::default()
+            },
+            id: s.name.clone(),
+            title: s.name,
+        })
+        .collect();
+    Board { columns, problems }
+}
+
+fn card_from(issue: Issue) -> Card {
+    let mut fields = BTreeMap::new();
+    for (key, value) in [
+        ("created", issue.created_at),
+        ("updated", issue.updated_at),
+        ("estimate", issue.estimate.map(|e| e.to_string())),
+    ] {
+        if let Some(v) = value {
+            fields.insert(key.to_string(), vec![v]);
+        }
+    }
+    Card {
+        id: issue.identifier,
+        title: issue.title,
+        description: issue.description.unwrap_or_default(),
+        labels: issue.labels.nodes.into_iter().map(|l| l.name).collect(),
+        priority: issue.priority_label.filter(|p| p != "No priority"),
+        assignee: issue.assignee.map(|a| a.display_name),
+        due: issue.due_date,
+        fields,
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphqlError {
+    message: String,
+    #[serde(default)]
+    extensions: Option<Extensions>,
+}
+
+#[derive(Deserialize)]
+struct Extensions {
+    code: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct TeamData {
+    teams: Nodes<Team>,
+    projects: Nodes<IdOnly>,
+}
+
+#[derive(Deserialize)]
+struct Team {
+    id: String,
+    states: Nodes<State>,
+}
+
+#[derive(Deserialize)]
+struct State {
+    id: String,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    position: f64,
+}
+
+#[derive(Deserialize)]
+struct IdOnly {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct IssuesData {
+    issues: IssuePage,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssuePage {
+    nodes: Vec<Issue>,
+    page_info: PageInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Issue {
+    identifier: String,
+    title: String,
+    description: Option<String>,
+    priority_label: Option<String>,
+    due_date: Option<String>,
+    estimate: Option<f64>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    state: IssueState,
+    assignee: Option<User>,
+    labels: Nodes<Label>,
+}
+
+#[derive(Deserialize)]
+struct IssueState {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    display_name: String,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateData {
+    issue_update: Success,
+}
+
+#[derive(Deserialize)]
+struct Success {
+    success: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateData {
+    issue_create: Created,
+}
+
+#[derive(Deserialize)]
+struct Created {
+    issue: Option<CreatedIssue>,
+}
+
+#[derive(Deserialize)]
+struct CreatedIssue {
+    identifier: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_board_returns_config_error_when_missing_env() {
+        let mut p = LinearProvider::from_profile(&Profile::default());
+        let err = p.load_board().err().unwrap();
+
+        assert!(matches!(err, ProviderError::Config { .. }));
+        assert!(err.to_string().contains("LINEAR_API_KEY, LINEAR_TEAM"));
+    }
+
+    #[test]
+    fn states_become_columns_in_workflow_order() {
+        let team: TeamData = serde_json::from_str(
+            r#"{"teams": {"nodes": [{"id": "t", "states": {"nodes": [
+                {"id": "4", "name": "Done", "type": "completed", "position": 0},
+                {"id": "2", "name": "In Review", "type": "started", "position": 2},
+                {"id": "1", "name": "In Progress", "type": "started", "position": 1},
+                {"id": "0", "name": "Todo", "type": "unstarted", "position": 0}]}}]},
+                "projects": {"nodes": []}}"#,
+        )
+        .unwrap();
+        let issues: IssuePage = serde_json::from_str(
+            r#"{"nodes": [{"identifier": "ENG-1", "title": "Fix it", "description": "**now**",
+                "priorityLabel": "Urgent", "dueDate": "2024-06-01", "estimate": 3,
+                "createdAt": "2024-05-01T10:00:00.000Z", "updatedAt": null,
+                "state": {"name": "In Review"}, "assignee": {"displayName": "Ana"},
+                "labels": {"nodes": [{"name": "bug"}]}},
+                {"identifier": "ENG-2", "title": "Plan", "description": null,
+                "priorityLabel": "No priority", "dueDate": null, "estimate": null,
+                "createdAt": null, "updatedAt": null, "state": {"name": "Todo"},
+                "assignee": null, "labels": {"nodes": []}}],
+                "pageInfo": {"hasNextPage": false, "endCursor": null}}"#,
+        )
+        .unwrap();
+        let states = team.teams.nodes.into_iter().next().unwrap().states.nodes;
+
+        let board = board_from(states, issues.nodes);
+        let titles: Vec<&str> = board.columns.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Todo", "In Progress", "In Review", "Done"]);
+        assert!(board.columns[3].meta.terminal);
+
+        let card = &board.columns[2].cards[0];
+        assert_eq!(card.priority.as_deref(), Some("Urgent"));
+        assert_eq!(card.labels, ["bug"]);
+        assert_eq!(card.fields["estimate"], ["3"]);
+        assert_eq!(board.columns[0].cards[0].priority, None);
+    }
+}
@@ -1,45 +1,130 @@
 use std::{
-    io,
+    fs, io,
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 use crate::{
-    model::Board,
+    activity,
+    card_template::Draft,
+    config::{self, Profile},
+    frontmatter, git,
+    ids::IdStrategy,
+    log,
+    model::{Board, Card, CardDetail, Move, User},
     provider::{Provider, ProviderError},
     store_fs,
 };
 
 pub struct LocalProvider {
     root: PathBuf,
+    members: Vec<String>,
+    id_strategy: Result<IdStrategy, String>,
+    /// From `[user]`, stamped on new cards.
+    author: Option<String>,
+    /// Commit each change, from the profile's `git`.
+    git: bool,
 }
 
 impl LocalProvider {
     pub fn from_env() -> Self {
-        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        Self::from_profile(&config::active_profile())
+    }
 
-        if let Ok(p) = std::env::var("FLOW_BOARD_PATH") {
-            return Self {
-                root: PathBuf::from(p),
-            };
+    pub fn from_profile(profile: &Profile) -> Self {
+        let root = root_for(profile);
+        if profile.git
+            && let Err(e) = git::init(&root)
+        {
+            log::write(&format!("git init in {}: {e}", root.display()));
+        }
+        Self {
+            root,
+            members: profile.members.clone(),
+            id_strategy: IdStrategy::from_env(),
+            author: config::user().author().map(str::to_string),
+            git: profile.git,
         }
+    }
 
-        if std::env::var("FLOW_PROVIDER").ok().as_deref() == Some("local") {
-            if let Ok(p) = std::env::var("FLOW_LOCAL_PATH") {
-                return Self {
-                    root: PathBuf::from(p),
-                };
-            }
-            if let Ok(home) = std::env::var("HOME") {
-                return Self {
-                    root: PathBuf::from(home).join(".config/flow/boards/default"),
-                };
-            }
+    /// Records a change that went through in git, when the board is kept
+    /// there. A failed commit doesn't undo the change; the next one picks it
+    /// up.
+    fn commit(&self, op: &str, card: Option<&str>, subject: &str) {
+        if self.git
+            && let Err(e) = git::commit(&self.root, op, card, subject)
+        {
+            log::write(&format!("git commit in {}: {e}", self.root.display()));
         }
+    }
 
-        Self {
-            root: manifest_dir.join("boards/demo"),
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn next_card_id(&self) -> Result<String, ProviderError> {
+        let strategy = self
+            .id_strategy
+            .clone()
+            .map_err(|msg| ProviderError::Parse { msg })?;
+        let existing = store_fs::card_ids(&self.root)
+            .map_err(|e| map_load_err("create_card", &self.root, e))?;
+
+        Ok(strategy
+            .generate(&existing, SystemTime::now())
+            .unwrap_or_else(store_fs::timestamp_id))
+    }
+
+    fn create_with_id(
+        &self,
+        to_col_id: &str,
+        card_id: &str,
+        draft: &Draft,
+    ) -> Result<String, ProviderError> {
+        store_fs::create_card_from(
+            &self.root,
+            to_col_id,
+            card_id,
+            draft,
+            self.author.as_deref(),
+        )
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::InvalidInput | io::ErrorKind::AlreadyExists => ProviderError::Parse {
+                msg: err.to_string(),
+            },
+            io::ErrorKind::WouldBlock => ProviderError::Busy,
+            _ => ProviderError::Io {
+                op: "create_card".to_string(),
+                path: self.root.clone(),
+                source: err,
+            },
+        })?;
+        self.commit(
+            "create",
+            Some(card_id),
+            &format!("Create {card_id} in {to_col_id}: {}", draft.title),
+        );
+        Ok(card_id.to_string())
+    }
+}
+
+fn root_for(profile: &Profile) -> PathBuf {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    if let Some(p) = &profile.path {
+        return config::expand_home(p);
+    }
+
+    if profile.provider.as_deref() == Some("local") {
+        if let Ok(p) = std::env::var("FLOW_LOCAL_PATH") {
+            return PathBuf::from(p);
+        }
+        if let Ok(home) = std::env::var("HOME") {
+            return PathBuf::from(home).join(".config/flow/boards/default");
         }
     }
+
+    manifest_dir.join("boards/demo")
 }
 
 impl Provider for LocalProvider {
@@ -50,14 +135,278 @@ impl Provider for LocalProvider {
     fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
         store_fs::move_card(&self.root, card_id, to_col_id)
             .map_err(|e| map_move_err(card_id, &self.root, e))
+            .inspect(|()| {
+                let subject = format!("Move {card_id} to {to_col_id}");
+                self.commit("move", Some(card_id), &subject);
+            })
     }
 
-    fn create_card(&mut self, to_col_id: &str) -> Result<String, ProviderError> {
-        store_fs::create_card(&self.root, to_col_id).map_err(|err| ProviderError::Io {
-            op: "create_card".to_string(),
-            path: self.root.clone(),
-            source: err,
+    /// Under one hold of the board lock, and one commit.
+    fn move_cards(
+        &mut self,
+        card_ids: &[String],
+        to_col_id: &str,
+        moved: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        let mut n = 0;
+        let res = store_fs::batch(&self.root, || {
+            for card_id in card_ids {
+                store_fs::move_card(&self.root, card_id, to_col_id)
+                    .map_err(|e| map_move_err(card_id, &self.root, e))?;
+                n += 1;
+                moved(card_id);
+            }
+            Ok(())
         })
+        .map_err(|e| map_lock_err(&self.root, e))
+        .and_then(|res| res);
+        if n > 0 {
+            self.commit("move", None, &format!("Move {n} cards to {to_col_id}"));
+        }
+        res
+    }
+
+    fn create_card_with_id(
+        &mut self,
+        to_col_id: &str,
+        card_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let draft = Draft {
+            title: title.to_string(),
+            ..Draft::default()
+        };
+        self.create_with_id(to_col_id, card_id, &draft)
+    }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let card_id = self.next_card_id()?;
+        self.create_card_with_id(to_col_id, &card_id, title)
+    }
+
+    fn create_card_from(
+        &mut self,
+        to_col_id: &str,
+        draft: &Draft,
+    ) -> Result<String, ProviderError> {
+        let card_id = self.next_card_id()?;
+        self.create_with_id(to_col_id, &card_id, draft)
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        store_fs::update_card(&self.root, card_id, title, description)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => ProviderError::NotFound {
+                    id: card_id.to_string(),
+                },
+                io::ErrorKind::WouldBlock => ProviderError::Busy,
+                _ => ProviderError::Io {
+                    op: "update_card".to_string(),
+                    path: self.root.clone(),
+                    source: err,
+                },
+            })
+            .inspect(|()| self.commit("edit", Some(card_id), &format!("Edit {card_id}: {title}")))
+    }
+
+    fn set_estimate(&mut self, card_id: &str, points: f64) -> Result<(), ProviderError> {
+        store_fs::set_field(&self.root, card_id, "estimate", &points.to_string())
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => ProviderError::NotFound {
+                    id: card_id.to_string(),
+                },
+                io::ErrorKind::WouldBlock => ProviderError::Busy,
+                _ => ProviderError::Io {
+                    op: "set_estimate".to_string(),
+                    path: self.root.clone(),
+                    source: err,
+                },
+            })
+            .inspect(|()| {
+                self.commit(
+                    "estimate",
+                    Some(card_id),
+                    &format!("Estimate {card_id} at {points}"),
+                )
+            })
+    }
+
+    fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+        store_fs::archive_card(&self.root, card_id)
+            .map_err(|e| map_archive_err(card_id, &self.root, e))
+            .inspect(|()| self.commit("archive", Some(card_id), &format!("Archive {card_id}")))
+    }
+
+    fn archive_cards(
+        &mut self,
+        card_ids: &[String],
+        archived: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        let mut n = 0;
+        let res = store_fs::batch(&self.root, || {
+            for card_id in card_ids {
+                store_fs::archive_card(&self.root, card_id)
+                    .map_err(|e| map_archive_err(card_id, &self.root, e))?;
+                n += 1;
+                archived(card_id);
+            }
+            Ok(())
+        })
+        .map_err(|e| map_lock_err(&self.root, e))
+        .and_then(|res| res);
+        if n > 0 {
+            self.commit("archive", None, &format!("Archive {n} cards"));
+        }
+        res
+    }
+
+    /// The profile's `members`, or else everyone assigned a card so far and
+    /// the `[user]`.
+    fn assignable_users(&mut self, _card_id: &str) -> Result<Vec<User>, ProviderError> {
+        let mut names = self.members.clone();
+        if names.is_empty() {
+            let board = self.load_board()?;
+            names.extend(
+                board
+                    .columns
+                    .iter()
+                    .flat_map(|c| &c.cards)
+                    .filter_map(|card| card.assignee.clone()),
+            );
+            names.extend(self.author.clone());
+            names.sort();
+            names.dedup();
+        }
+        Ok(names
+            .into_iter()
+            .map(|name| User {
+                id: name.clone(),
+                name,
+            })
+            .collect())
+    }
+
+    fn set_assignee(&mut self, card_id: &str, user: Option<&User>) -> Result<(), ProviderError> {
+        match user {
+            Some(user) => store_fs::set_field(&self.root, card_id, "assignee", &user.name),
+            None => store_fs::clear_field(&self.root, card_id, "assignee"),
+        }
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => ProviderError::NotFound {
+                id: card_id.to_string(),
+            },
+            io::ErrorKind::WouldBlock => ProviderError::Busy,
+            _ => ProviderError::Io {
+                op: "set_assignee".to_string(),
+                path: self.root.clone(),
+                source: err,
+            },
+        })
+        .inspect(|()| {
+            let subject = match user {
+                Some(user) => format!("Assign {card_id} to {}", user.name),
+                None => format!("Unassign {card_id}"),
+            };
+            self.commit("assign", Some(card_id), &subject);
+        })
+    }
+
+    fn reorder_column(&mut self, col_id: &str, order: &[String]) -> Result<(), ProviderError> {
+        store_fs::reorder_column(&self.root, col_id, order)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => ProviderError::Parse {
+                    msg: err.to_string(),
+                },
+                io::ErrorKind::WouldBlock => ProviderError::Busy,
+                _ => ProviderError::Io {
+                    op: "reorder_column".to_string(),
+                    path: self.root.clone(),
+                    source: err,
+                },
+            })
+            .inspect(|()| self.commit("reorder", None, &format!("Reorder {col_id}")))
+    }
+
+    fn archived_cards(&mut self) -> Result<Vec<Card>, ProviderError> {
+        store_fs::archived_cards(&self.root)
+            .map_err(|e| map_load_err("archived_cards", &self.root, e))
+    }
+
+    /// The card file's whole front matter, including keys the board hides,
+    /// its `## Comments` section and, on a board kept in git, its commits.
+    fn card_detail(&mut self, card_id: &str) -> Result<CardDetail, ProviderError> {
+        let path = self.card_path(card_id)?;
+        let raw = fs::read_to_string(&path).map_err(|source| ProviderError::Io {
+            op: "card_detail".to_string(),
+            path,
+            source,
+        })?;
+        let fields = match frontmatter::split(&raw).0 {
+            Some(fm) => fm
+                .fields()
+                .into_iter()
+                .map(|(key, values)| (key, values.join(", ")))
+                .collect(),
+            None => vec![],
+        };
+        let history = if self.git {
+            git::history(&self.root, card_id).unwrap_or_else(|e| {
+                log::write(&format!("git log for {card_id}: {e}"));
+                vec![]
+            })
+        } else {
+            vec![]
+        };
+        Ok(CardDetail {
+            comments: store_fs::comments(&raw),
+            fields,
+            history,
+            ..CardDetail::default()
+        })
+    }
+
+    /// Signed with `[user]`, else the login name.
+    /// Every move in the activity log.
+    fn moves(&mut self, _since: u64) -> Result<Vec<Move>, ProviderError> {
+        Ok(activity::moves(&self.activity()?))
+    }
+
+    fn activity(&mut self) -> Result<Vec<activity::Entry>, ProviderError> {
+        activity::read(&self.root).map_err(|source| ProviderError::Io {
+            op: "activity".to_string(),
+            path: activity::path(&self.root),
+            source,
+        })
+    }
+
+    fn add_comment(&mut self, card_id: &str, body: &str) -> Result<(), ProviderError> {
+        let author = self
+            .author
+            .clone()
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_else(|| "anonymous".to_string());
+        store_fs::add_comment(&self.root, card_id, &author, body)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => ProviderError::NotFound {
+                    id: card_id.to_string(),
+                },
+                io::ErrorKind::WouldBlock => ProviderError::Busy,
+                _ => ProviderError::Io {
+                    op: "add_comment".to_string(),
+                    path: self.root.clone(),
+                    source: err,
+                },
+            })
+            .inspect(|()| self.commit("comment", Some(card_id), &format!("Comment on {card_id}")))
     }
 
     fn card_path(&self, card_id: &str) -> Result<PathBuf, ProviderError> {
@@ -72,6 +421,22 @@ impl Provider for LocalProvider {
             },
         })
     }
+
+    fn save_card(&mut self, card_id: &str, text: &str) -> Result<(), ProviderError> {
+        store_fs::save_card(&self.root, card_id, text)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => ProviderError::NotFound {
+                    id: card_id.to_string(),
+                },
+                io::ErrorKind::WouldBlock => ProviderError::Busy,
+                _ => ProviderError::Io {
+                    op: "save_card".to_string(),
+                    path: self.root.clone(),
+                    source: err,
+                },
+            })
+            .inspect(|()| self.commit("edit", Some(card_id), &format!("Edit {card_id}")))
+    }
 }
 
 fn map_load_err(op: &str, root: &Path, err: io::Error) -> ProviderError {
@@ -87,14 +452,43 @@ fn map_load_err(op: &str, root: &Path, err: io::Error) -> ProviderError {
     }
 }
 
+fn map_archive_err(card_id: &str, root: &Path, err: io::Error) -> ProviderError {
+    match err.kind() {
+        io::ErrorKind::NotFound => ProviderError::NotFound {
+            id: card_id.to_string(),
+        },
+        io::ErrorKind::AlreadyExists => ProviderError::Parse {
+            msg: err.to_string(),
+        },
+        io::ErrorKind::WouldBlock => ProviderError::Busy,
+        _ => ProviderError::Io {
+            op: "archive_card".to_string(),
+            path: root.to_path_buf(),
+            source: err,
+        },
+    }
+}
+
+fn map_lock_err(root: &Path, err: io::Error) -> ProviderError {
+    match err.kind() {
+        io::ErrorKind::WouldBlock => ProviderError::Busy,
+        _ => ProviderError::Io {
+            op: "lock".to_string(),
+            path: root.join(store_fs::LOCK_FILE),
+            source: err,
+        },
+    }
+}
+
 fn map_move_err(card_id: &str, root: &Path, err: io::Error) -> ProviderError {
     match err.kind() {
         io::ErrorKind::NotFound => ProviderError::NotFound {
             id: card_id.to_string(),
         },
-        io::ErrorKind::InvalidData => ProviderError::Parse {
+        io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => ProviderError::Parse {
             msg: err.to_string(),
         },
+        io::ErrorKind::WouldBlock => ProviderError::Busy,
         _ => ProviderError::Io {
             op: "move_card".to_string(),
             path: root.to_path_buf(),
@@ -106,8 +500,8 @@ fn map_move_err(card_id: &str, root: &Path, err: io::Error) -> ProviderError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{dates, frontmatter::FrontMatter};
     use std::{
-        fs,
         path::Path,
         time::{SystemTime, UNIX_EPOCH},
     };
@@ -142,7 +536,13 @@ mod tests {
         let root = tmp_root();
         write(&root.join("board.txt"), "col todo\n");
 
-        let mut provider = LocalProvider { root: root.clone() };
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: Ok(IdStrategy::Provider),
+            author: None,
+            git: false,
+        };
         let err = provider.move_card("X-1", "todo").unwrap_err();
 
         match err {
@@ -152,4 +552,234 @@ mod tests {
 
         fs::remove_dir_all(root).unwrap();
     }
+
+    #[test]
+    fn card_detail_lists_the_whole_front_matter() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "T-1\n");
+        write(
+            &root.join("cols/todo/T-1.md"),
+            "---\nreviewer: sam\nlabels: [ui, bug]\n---\n# One\n",
+        );
+
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: Ok(IdStrategy::Provider),
+            author: None,
+            git: false,
+        };
+        assert_eq!(
+            provider.card_detail("T-1").unwrap().fields,
+            [
+                ("labels".to_string(), "ui, bug".to_string()),
+                ("reviewer".to_string(), "sam".to_string()),
+            ]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn create_card_from_writes_the_drafts_front_matter_and_description() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "");
+
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: Ok(IdStrategy::Provider),
+            author: None,
+            git: false,
+        };
+        let mut front = FrontMatter::default();
+        front.set_list("labels", &["bug".to_string()]);
+        let id = provider
+            .create_card_from(
+                "todo",
+                &Draft {
+                    title: "Login loops".to_string(),
+                    description: "## Steps to reproduce".to_string(),
+                    front,
+                },
+            )
+            .unwrap();
+
+        let card = provider.load_board().unwrap().columns[0].cards[0].clone();
+        assert_eq!(card.id, id);
+        assert_eq!(card.title, "Login loops");
+        assert_eq!(card.labels, ["bug"]);
+        assert_eq!(card.description, "## Steps to reproduce");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn comments_are_kept_out_of_the_description() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "T-1\n");
+        write(&root.join("cols/todo/T-1.md"), "# One\n\nBody\n");
+
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: Ok(IdStrategy::Provider),
+            author: Some("Ana Lopez".to_string()),
+            git: false,
+        };
+        provider.add_comment("T-1", "First").unwrap();
+        provider
+            .add_comment("T-1", "Second\n\nin two parts")
+            .unwrap();
+        provider.update_card("T-1", "One", "New body").unwrap();
+
+        let comments = provider.card_detail("T-1").unwrap().comments;
+        let bodies: Vec<_> = comments.iter().map(|c| c.body.as_str()).collect();
+        assert_eq!(bodies, ["First", "Second\n\nin two parts"]);
+        assert_eq!(comments[0].author, "Ana Lopez");
+        assert!(dates::parse_date(&comments[0].created).is_some());
+        let board = provider.load_board().unwrap();
+        assert_eq!(board.columns[0].cards[0].description, "New body");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn assigning_writes_the_front_matter_and_unassigning_removes_it() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "T-1\nT-2\n");
+        write(
+            &root.join("cols/todo/T-1.md"),
+            "---\nassignee: Ben\n---\n# One\n",
+        );
+        write(&root.join("cols/todo/T-2.md"), "# Two\n");
+
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: Ok(IdStrategy::Provider),
+            author: Some("Ana Lopez".to_string()),
+            git: false,
+        };
+        let names = |p: &mut LocalProvider| -> Vec<String> {
+            p.assignable_users("T-2")
+                .unwrap()
+                .into_iter()
+                .map(|u| u.name)
+                .collect()
+        };
+        assert_eq!(names(&mut provider), ["Ana Lopez", "Ben"]);
+        provider.members = vec!["Cy".to_string()];
+        assert_eq!(names(&mut provider), ["Cy"]);
+
+        let cy = User {
+            id: "Cy".to_string(),
+            name: "Cy".to_string(),
+        };
+        provider.set_assignee("T-2", Some(&cy)).unwrap();
+        provider.set_assignee("T-1", None).unwrap();
+        let board = provider.load_board().unwrap();
+        let assignees: Vec<_> = board.columns[0]
+            .cards
+            .iter()
+            .map(|c| c.assignee.as_deref())
+            .collect();
+        assert_eq!(assignees, [None, Some("Cy")]);
+        assert!(
+            !fs::read_to_string(root.join("cols/todo/T-1.md"))
+                .unwrap()
+                .contains("assignee")
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn create_card_uses_configured_id_strategy() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "FLOW-7\n");
+        write(&root.join("cols/todo/FLOW-7.md"), "# Seven\n");
+
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: IdStrategy::parse("seq:FLOW"),
+            author: None,
+            git: false,
+        };
+
+        assert_eq!(
+            provider.create_card_with_title("todo", "Eight").unwrap(),
+            "FLOW-8"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn create_card_with_title_writes_the_title() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "");
+
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: IdStrategy::parse("seq:T"),
+            author: None,
+            git: false,
+        };
+        let id = provider
+            .create_card_with_title("todo", "Write release notes")
+            .unwrap();
+
+        let board = provider.load_board().unwrap();
+        let card = &board.columns[0].cards[0];
+        assert_eq!(
+            (card.id.as_str(), card.title.as_str()),
+            (id.as_str(), "Write release notes")
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn an_editor_save_is_its_own_commit() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\n");
+        write(&root.join("cols/todo/A-1.md"), "# A\n");
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.name", "Ada"],
+            &["config", "user.email", "ada@example.com"],
+        ] {
+            let ok = std::process::Command::new("git")
+                .args(args)
+                .current_dir(&root)
+                .status()
+                .unwrap();
+            assert!(ok.success());
+        }
+        git::commit(&root, "init", None, "Start board history").unwrap();
+
+        let mut provider = LocalProvider {
+            root: root.clone(),
+            members: vec![],
+            id_strategy: Ok(IdStrategy::Provider),
+            author: None,
+            git: true,
+        };
+        provider.save_card("A-1", "# A, edited\n").unwrap();
+
+        let history = git::history(&root, "A-1").unwrap();
+        assert_eq!(history[0].subject, "Edit A-1");
+
+        fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -0,0 +1,430 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    activity,
+    card_template::Draft,
+    events::{self, Event},
+    graph::LinkKind,
+    log,
+    model::{Board, Card, CardDetail, Mention, Move, QuickFilter, User},
+    provider::{Provider, ProviderError},
+};
+
+#[derive(Clone, Copy, Default)]
+pub struct OpStats {
+    pub count: u64,
+    pub errors: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl OpStats {
+    pub fn avg(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total.div_f64(self.count as f64)
+        }
+    }
+}
+
+type Registry = BTreeMap<(&'static str, &'static str), OpStats>;
+
+// Shared by every wrapped provider, including the ones move workers create,
+// and keyed by provider name as well as operation.
+fn registry() -> &'static Mutex<Registry> {
+    static METRICS: OnceLock<Mutex<Registry>> = OnceLock::new();
+    METRICS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Per-operation stats recorded so far across providers, sorted by
+/// operation name.
+pub fn snapshot() -> Vec<(&'static str, OpStats)> {
+    let Ok(m) = registry().lock() else {
+        return vec![];
+    };
+    let mut ops: BTreeMap<&'static str, OpStats> = BTreeMap::new();
+    for ((_, op), s) in m.iter() {
+        let o = ops.entry(op).or_default();
+        o.count += s.count;
+        o.errors += s.errors;
+        o.total += s.total;
+        o.max = o.max.max(s.max);
+    }
+    ops.into_iter().collect()
+}
+
+fn record(provider: &'static str, op: &'static str, elapsed: Duration, ok: bool) {
+    if let Ok(mut m) = registry().lock() {
+        let s = m.entry((provider, op)).or_default();
+        s.count += 1;
+        s.total += elapsed;
+        s.max = s.max.max(elapsed);
+        if !ok {
+            s.errors += 1;
+        }
+    }
+}
+
+/// Wraps any provider, timing each call, counting failures and publishing
+/// board [`Event`]s.
+pub struct Metered {
+    inner: Box<dyn Provider>,
+    name: &'static str,
+}
+
+impl Metered {
+    pub fn new(inner: Box<dyn Provider>, name: &'static str) -> Self {
+        Self { inner, name }
+    }
+
+    fn observe<T>(
+        &self,
+        op: &'static str,
+        start: Instant,
+        res: Result<T, ProviderError>,
+    ) -> Result<T, ProviderError> {
+        let elapsed = start.elapsed();
+        record(self.name, op, elapsed, res.is_ok());
+
+        let ms = elapsed.as_millis();
+        match &res {
+            Ok(_) => log::write(&format!("{} {op} {ms}ms ok", self.name)),
+            Err(e) => log::write(&format!("{} {op} {ms}ms error: {e}", self.name)),
+        }
+        if let Err(e) = &res
+            && !matches!(e, ProviderError::Unsupported { .. })
+        {
+            events::publish(Event::Error {
+                op,
+                msg: e.to_string(),
+            });
+        }
+        res
+    }
+}
+
+fn publish_edited(card_id: &str, what: &str) {
+    events::publish(Event::CardEdited {
+        card_id: card_id.to_string(),
+        what: what.to_string(),
+    });
+}
+
+fn publish_created(card_id: &str, column: &str) {
+    events::publish(Event::CardCreated {
+        card_id: card_id.to_string(),
+        column: column.to_string(),
+    });
+}
+
+impl Provider for Metered {
+    fn load_board(&mut self) -> Result<Board, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.load_board();
+        if let Ok(board) = &res {
+            events::publish(Event::Refreshed {
+                cards: board.columns.iter().map(|c| c.cards.len()).sum(),
+            });
+        }
+        self.observe("load_board", start, res)
+    }
+
+    fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.move_card(card_id, to_col_id);
+        if res.is_ok() {
+            events::publish(Event::CardMoved {
+                card_id: card_id.to_string(),
+                to: to_col_id.to_string(),
+            });
+        }
+        self.observe("move_card", start, res)
+    }
+
+    fn create_card_with_id(
+        &mut self,
+        to_col_id: &str,
+        card_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.create_card_with_id(to_col_id, card_id, title);
+        if let Ok(id) = &res {
+            publish_created(id, to_col_id);
+        }
+        self.observe("create_card_with_id", start, res)
+    }
+
+    fn create_card_with_title(
+        &mut self,
+        to_col_id: &str,
+        title: &str,
+    ) -> Result<String, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.create_card_with_title(to_col_id, title);
+        if let Ok(id) = &res {
+            publish_created(id, to_col_id);
+        }
+        self.observe("create_card_with_title", start, res)
+    }
+
+    fn create_card_from(
+        &mut self,
+        to_col_id: &str,
+        draft: &Draft,
+    ) -> Result<String, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.create_card_from(to_col_id, draft);
+        if let Ok(id) = &res {
+            publish_created(id, to_col_id);
+        }
+        self.observe("create_card_from", start, res)
+    }
+
+    fn update_card(
+        &mut self,
+        card_id: &str,
+        title: &str,
+        description: &str,
+    ) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.update_card(card_id, title, description);
+        if res.is_ok() {
+            publish_edited(card_id, "text");
+        }
+        self.observe("update_card", start, res)
+    }
+
+    fn card_path(&self, card_id: &str) -> Result<PathBuf, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.card_path(card_id);
+        self.observe("card_path", start, res)
+    }
+
+    fn save_card(&mut self, card_id: &str, text: &str) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.save_card(card_id, text);
+        if res.is_ok() {
+            publish_edited(card_id, "file");
+        }
+        self.observe("save_card", start, res)
+    }
+
+    fn load_more(&mut self, col_id: &str) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.load_more(col_id);
+        self.observe("load_more", start, res)
+    }
+
+    fn quick_filters(&mut self) -> Result<Vec<QuickFilter>, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.quick_filters();
+        self.observe("quick_filters", start, res)
+    }
+
+    fn search(&mut self, query: &str) -> Result<Vec<Card>, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.search(query);
+        self.observe("search", start, res)
+    }
+
+    fn pull_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.pull_card(card_id, to_col_id);
+        self.observe("pull_card", start, res)
+    }
+
+    fn reorder_column(&mut self, col_id: &str, order: &[String]) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.reorder_column(col_id, order);
+        self.observe("reorder_column", start, res)
+    }
+
+    fn set_quick_filters(&mut self, filters: &[QuickFilter]) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.set_quick_filters(filters);
+        self.observe("set_quick_filters", start, res)
+    }
+
+    fn set_estimate(&mut self, card_id: &str, points: f64) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.set_estimate(card_id, points);
+        if res.is_ok() {
+            publish_edited(card_id, "estimate");
+        }
+        self.observe("set_estimate", start, res)
+    }
+
+    fn archive_card(&mut self, card_id: &str) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.archive_card(card_id);
+        if res.is_ok() {
+            events::publish(Event::CardArchived {
+                card_id: card_id.to_string(),
+            });
+        }
+        self.observe("archive_card", start, res)
+    }
+
+    fn move_cards(
+        &mut self,
+        card_ids: &[String],
+        to_col_id: &str,
+        moved: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.move_cards(card_ids, to_col_id, &mut |card_id| {
+            events::publish(Event::CardMoved {
+                card_id: card_id.to_string(),
+                to: to_col_id.to_string(),
+            });
+            moved(card_id);
+        });
+        self.observe("move_cards", start, res)
+    }
+
+    fn archive_cards(
+        &mut self,
+        card_ids: &[String],
+        archived: &mut dyn FnMut(&str),
+    ) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.archive_cards(card_ids, &mut |card_id| {
+            events::publish(Event::CardArchived {
+                card_id: card_id.to_string(),
+            });
+            archived(card_id);
+        });
+        self.observe("archive_cards", start, res)
+    }
+
+    fn archived_cards(&mut self) -> Result<Vec<Card>, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.archived_cards();
+        self.observe("archived_cards", start, res)
+    }
+
+    fn card_detail(&mut self, card_id: &str) -> Result<CardDetail, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.card_detail(card_id);
+        self.observe("card_detail", start, res)
+    }
+
+    fn mentions(&mut self, minutes: u64) -> Result<Vec<Mention>, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.mentions(minutes);
+        self.observe("mentions", start, res)
+    }
+
+    fn moves(&mut self, since: u64) -> Result<Vec<Move>, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.moves(since);
+        self.observe("moves", start, res)
+    }
+
+    fn activity(&mut self) -> Result<Vec<activity::Entry>, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.activity();
+        self.observe("activity", start, res)
+    }
+
+    fn add_comment(&mut self, card_id: &str, body: &str) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.add_comment(card_id, body);
+        if res.is_ok() {
+            publish_edited(card_id, "comment");
+        }
+        self.observe("add_comment", start, res)
+    }
+
+    fn assignable_users(&mut self, card_id: &str) -> Result<Vec<User>, ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.assignable_users(card_id);
+        self.observe("assignable_users", start, res)
+    }
+
+    fn set_assignee(&mut self, card_id: &str, user: Option<&User>) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.set_assignee(card_id, user);
+        if res.is_ok() {
+            publish_edited(card_id, "assignee");
+        }
+        self.observe("set_assignee", start, res)
+    }
+
+    fn link_cards(
+        &mut self,
+        from_id: &str,
+        to_id: &str,
+        kind: LinkKind,
+    ) -> Result<(), ProviderError> {
+        let start = Instant::now();
+        let res = self.inner.link_cards(from_id, to_id, kind);
+        if res.is_ok() {
+            publish_edited(from_id, "links");
+        }
+        self.observe("link_cards", start, res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Board;
+
+    struct Flaky {
+        calls: u32,
+    }
+
+    impl Provider for Flaky {
+        fn load_board(&mut self) -> Result<Board, ProviderError> {
+            self.calls += 1;
+            if self.calls.is_multiple_of(2) {
+                return Err(ProviderError::Parse { msg: "bad".into() });
+            }
+            Ok(Board {
+                columns: vec![],
+                problems: vec![],
+            })
+        }
+
+        fn move_card(&mut self, _card_id: &str, _to_col_id: &str) -> Result<(), ProviderError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn metered_counts_calls_and_errors() {
+        // A name no other test meters under, so their calls don't count here.
+        let name = "metered-counts-test";
+        let stats = |op| registry().lock().unwrap().get(&(name, op)).copied();
+        let mut p = Metered::new(Box::new(Flaky { calls: 0 }), name);
+
+        assert!(p.load_board().is_ok());
+        assert!(p.load_board().is_err());
+
+        let after = stats("load_board").unwrap();
+        assert_eq!((after.count, after.errors), (2, 1));
+        assert!(
+            snapshot()
+                .iter()
+                .any(|(op, s)| *op == "load_board" && s.count >= 2)
+        );
+    }
+
+    #[test]
+    fn average_survives_counts_past_u32() {
+        let s = OpStats {
+            count: 1 << 32,
+            total: Duration::from_secs(1 << 33),
+            ..OpStats::default()
+        };
+        assert_eq!(s.avg(), Duration::from_secs(2));
+    }
+}
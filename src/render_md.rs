@@ -0,0 +1,204 @@
+//! Just enough markdown for card descriptions: headings, bullet and numbered
+//! lists, quotes, fenced code, and `**bold**`, `*italic*`, `` `code` `` and
+//! `[links](url)` inline. Anything else is shown as written.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+pub fn render(text: &str) -> Vec<Line<'static>> {
+    let code = Style::default().fg(Color::Yellow);
+    let mut lines = vec![];
+    let mut fenced = false;
+    for raw in text.lines() {
+        let trimmed = raw.trim_start();
+        if trimmed.starts_with("```") {
+            fenced = !fenced;
+            continue;
+        }
+        if fenced {
+            lines.push(Line::from(Span::styled(format!("  {raw}"), code)));
+            continue;
+        }
+
+        let indent = &raw[..raw.len() - trimmed.len()];
+        if let Some((level, heading)) = heading(trimmed) {
+            let mut style = Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD);
+            if level == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            lines.push(Line::from(inline(heading, style)));
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|b| trimmed.strip_prefix(b))
+        {
+            let mut spans = vec![Span::raw(format!("{indent}• "))];
+            spans.extend(inline(item, Style::default()));
+            lines.push(Line::from(spans));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            let style = Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC);
+            let mut spans = vec![Span::styled(format!("{indent}│ "), style)];
+            spans.extend(inline(quote.trim_start(), style));
+            lines.push(Line::from(spans));
+        } else if ["---", "***", "___"].contains(&trimmed.trim_end()) {
+            lines.push(Line::from(Span::styled(
+                "─".repeat(20),
+                Style::default().fg(Color::DarkGray),
+            )));
+        } else {
+            // Numbered items keep their numbers, so they need nothing special.
+            let mut spans = vec![Span::raw(indent.to_string())];
+            spans.extend(inline(trimmed, Style::default()));
+            lines.push(Line::from(spans));
+        }
+    }
+    lines
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, rest.trim()))
+}
+
+/// Inline markup within one line. A marker without a closing partner is text.
+fn inline(text: &str, base: Style) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let styled = match c {
+            '`' => rest[1..].find('`').map(|end| {
+                let span = Span::styled(rest[1..=end].to_string(), base.fg(Color::Yellow));
+                (vec![span], end + 2)
+            }),
+            '*' | '_' => emphasis(rest, &plain, base),
+            '[' => link(rest, base),
+            _ => None,
+        };
+        match styled {
+            Some((styled, used)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                spans.extend(styled);
+                rest = &rest[used..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+/// `**bold**` or `*italic*` (or with `_`) at the start of `rest`, with the
+/// bytes it covers. `_` inside a word, as in `snake_case`, is left alone.
+fn emphasis(rest: &str, before: &str, base: Style) -> Option<(Vec<Span<'static>>, usize)> {
+    let marker = &rest[..1];
+    if marker == "_" && before.chars().last().is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+    let (marker, modifier) = if rest[1..].starts_with(marker) {
+        (&rest[..2], Modifier::BOLD)
+    } else {
+        (marker, Modifier::ITALIC)
+    };
+    let body = &rest[marker.len()..];
+    if body.starts_with(' ') {
+        return None;
+    }
+    let end = body.find(marker).filter(|&end| end > 0)?;
+    Some((
+        inline(&body[..end], base.add_modifier(modifier)),
+        2 * marker.len() + end,
+    ))
+}
+
+/// `[text](url)`: the text underlined, the url dimmed after it.
+fn link(rest: &str, base: Style) -> Option<(Vec<Span<'static>>, usize)> {
+    let close = rest.find("](")?;
+    let end = close + rest[close..].find(')')?;
+    let text = &rest[1..close];
+    if text.contains(']') {
+        return None;
+    }
+    let url = &rest[close + 2..end];
+    let mut spans = inline(
+        text,
+        base.fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+    );
+    spans.push(Span::styled(format!(" ({url})"), base.fg(Color::DarkGray)));
+    Some((spans, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn renders_block_markup() {
+        let lines = render("# Plan\n- one\n  * two\n> said\n```\nlet x = 1;\n```\n1. first");
+
+        let texts: Vec<String> = lines.iter().map(text).collect();
+        assert_eq!(
+            texts,
+            [
+                "Plan",
+                "• one",
+                "  • two",
+                "│ said",
+                "  let x = 1;",
+                "1. first"
+            ]
+        );
+        assert!(
+            lines[0].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::BOLD)
+        );
+    }
+
+    #[test]
+    fn styles_inline_markup_and_leaves_stray_markers() {
+        let spans = inline(
+            "a **b** *c* `d` [e](http://x) snake_case 2 * 3",
+            Style::default(),
+        );
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(
+            texts,
+            [
+                "a ",
+                "b",
+                " ",
+                "c",
+                " ",
+                "d",
+                " ",
+                "e",
+                " (http://x)",
+                " snake_case 2 * 3"
+            ]
+        );
+        assert!(spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert!(spans[3].style.add_modifier.contains(Modifier::ITALIC));
+        assert_eq!(spans[5].style.fg, Some(Color::Yellow));
+        assert!(spans[7].style.add_modifier.contains(Modifier::UNDERLINED));
+    }
+}
@@ -0,0 +1,163 @@
+//! `O` reorders a column the way `git rebase -i` reorders commits: the
+//! column's cards are written to a buffer one per line, and the lines as
+//! saved are the new order. A line may start with `drop` to archive its card
+//! or `move-to:<column>` to send it elsewhere.
+
+use crate::model::{Board, Column};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// The cards staying in the column, top first.
+    pub order: Vec<String>,
+    /// Cards to move out, with the id of the column each goes to.
+    pub moves: Vec<(String, String)>,
+    pub drops: Vec<String>,
+}
+
+impl Plan {
+    /// Nothing changes: the order is as it was and no card leaves.
+    pub fn is_noop(&self, col: &Column) -> bool {
+        self.moves.is_empty()
+            && self.drops.is_empty()
+            && self.order.iter().eq(col.cards.iter().map(|c| &c.id))
+    }
+}
+
+/// The buffer for `col`: a line per card, then how to edit it.
+pub fn script(col: &Column, board: &Board) -> String {
+    let mut out = String::new();
+    for card in &col.cards {
+        out += &format!("{} {}\n", card.id, card.title);
+    }
+    let others: Vec<&str> = board
+        .columns
+        .iter()
+        .filter(|c| c.id != col.id)
+        .map(|c| c.id.as_str())
+        .collect();
+    out += &format!(
+        "\n# Reorder {} ({} cards) by reordering the lines above; the first is the top.\n\
+         #\n\
+         # Start a line with a directive to take its card out of the column:\n\
+         # drop <id>              archive the card\n\
+         # move-to:<column> <id>  move it to another column ({})\n\
+         #\n\
+         # A card whose line is removed stays, below the others.\n\
+         # Empty the buffer to change nothing.\n",
+        col.title,
+        col.cards.len(),
+        others.join(", ")
+    );
+    out
+}
+
+/// Reads the edited buffer back. `None` when it was emptied, which cancels.
+pub fn parse(text: &str, col: &Column, board: &Board) -> Result<Option<Plan>, String> {
+    let mut plan = Plan::default();
+    let mut seen = vec![];
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let first = words.next().unwrap_or_default();
+        let (directive, id) = if first == "drop" || first.starts_with("move-to:") {
+            (Some(first), words.next().unwrap_or_default())
+        } else {
+            (None, first)
+        };
+        let at = || format!("line {}", n + 1);
+        if !col.cards.iter().any(|c| c.id == id) {
+            return Err(format!("{}: {id:?} is not in {}", at(), col.title));
+        }
+        if seen.contains(&id) {
+            return Err(format!("{}: {id} is listed twice", at()));
+        }
+        seen.push(id);
+        match directive {
+            None => plan.order.push(id.to_string()),
+            Some("drop") => plan.drops.push(id.to_string()),
+            Some(d) => {
+                let name = d.trim_start_matches("move-to:");
+                let dst = board
+                    .column_named(name)
+                    .map(|i| &board.columns[i])
+                    .ok_or_else(|| format!("{}: no column {name:?}", at()))?;
+                if dst.id != col.id {
+                    plan.moves.push((id.to_string(), dst.id.clone()));
+                } else {
+                    plan.order.push(id.to_string());
+                }
+            }
+        }
+    }
+    if seen.is_empty() {
+        return Ok(None);
+    }
+    plan.order.extend(
+        col.cards
+            .iter()
+            .filter(|c| !seen.contains(&c.id.as_str()))
+            .map(|c| c.id.clone()),
+    );
+    Ok(Some(plan))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Card, ColumnMeta};
+
+    fn board() -> Board {
+        let col = |id: &str, title: &str, cards: &[&str]| Column {
+            id: id.into(),
+            title: title.into(),
+            cards: cards
+                .iter()
+                .map(|id| Card {
+                    id: id.to_string(),
+                    title: format!("Card {id}"),
+                    ..Card::default()
+                })
+                .collect(),
+            meta: ColumnMeta::default(),
+        };
+        Board {
+            columns: vec![
+                col("todo", "To do", &["A-1", "A-2", "A-3", "A-4"]),
+                col("done", "Done", &[]),
+            ],
+            problems: vec![],
+        }
+    }
+
+    #[test]
+    fn edited_lines_become_the_order_and_directives_take_cards_out() {
+        let board = board();
+        let col = &board.columns[0];
+        let text = script(col, &board);
+        assert!(text.starts_with("A-1 Card A-1\nA-2 Card A-2\n"));
+        assert!(parse(&text, col, &board).unwrap().unwrap().is_noop(col));
+
+        let plan = parse(
+            "A-3 Card A-3\ndrop A-2 Card A-2\nA-1\nmove-to:Done A-4 x\n",
+            col,
+            &board,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(plan.order, ["A-3", "A-1"]);
+        assert_eq!(plan.drops, ["A-2"]);
+        assert_eq!(plan.moves, [("A-4".to_string(), "done".to_string())]);
+
+        // Removed lines stay, below the rest.
+        let plan = parse("A-4\n", col, &board).unwrap().unwrap();
+        assert_eq!(plan.order, ["A-4", "A-1", "A-2", "A-3"]);
+
+        assert_eq!(parse("# nothing\n\n", col, &board), Ok(None));
+        assert!(parse("A-9\n", col, &board).is_err());
+        assert!(parse("A-1\nA-1\n", col, &board).is_err());
+        assert!(parse("move-to:nowhere A-1\n", col, &board).is_err());
+    }
+}
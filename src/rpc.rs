@@ -0,0 +1,262 @@
+//! Line-delimited JSON-RPC 2.0 for editor plugins. Each request is one JSON
+//! object per line; each response is one line. See README "Editor RPC".
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::{Value, json};
+
+use crate::{
+    cli::{ErrorClass, Failure},
+    dates, filter,
+    model::{Board, Card, Column},
+    provider::Provider,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Provider failures; `data.kind` carries the CLI error class.
+const SERVER_ERROR: i64 = -32000;
+
+/// Answers requests from `input` until EOF.
+pub fn serve(
+    provider: &mut dyn Provider,
+    input: impl BufRead,
+    mut out: impl Write,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(resp) = handle(provider, &line) {
+            writeln!(out, "{resp}")?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// The response line for one request, or `None` for notifications.
+pub fn handle(provider: &mut dyn Provider, line: &str) -> Option<String> {
+    let req: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return Some(error(Value::Null, PARSE_ERROR, &e.to_string(), None)),
+    };
+    let id = req.get("id").cloned();
+    let Some(method) = req.get("method").and_then(Value::as_str) else {
+        return Some(error(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "missing method",
+            None,
+        ));
+    };
+    let params = req.get("params").cloned().unwrap_or(Value::Null);
+
+    let res = call(provider, method, &params);
+    let id = id?;
+    Some(match res {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+        Err(RpcError::Method) => error(
+            id,
+            METHOD_NOT_FOUND,
+            &format!("unknown method {method}"),
+            None,
+        ),
+        Err(RpcError::Params(msg)) => error(id, INVALID_PARAMS, &msg, None),
+        Err(RpcError::Failed(f)) => error(id, SERVER_ERROR, &f.msg, Some(f.class)),
+    })
+}
+
+enum RpcError {
+    Method,
+    Params(String),
+    Failed(Failure),
+}
+
+impl<E: Into<Failure>> From<E> for RpcError {
+    fn from(e: E) -> Self {
+        RpcError::Failed(e.into())
+    }
+}
+
+fn call(provider: &mut dyn Provider, method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "board.get" => {
+            let board = provider.load_board()?;
+            let columns: Vec<Value> = board
+                .columns
+                .iter()
+                .map(|col| {
+                    let cards: Vec<Value> = col
+                        .cards
+                        .iter()
+                        .map(|c| json!({ "id": c.id, "title": c.title }))
+                        .collect();
+                    json!({ "id": col.id, "title": col.title, "cards": cards })
+                })
+                .collect();
+            Ok(json!({ "columns": columns }))
+        }
+        "cards.list" => {
+            let filter = match params.get("filter").and_then(Value::as_str) {
+                Some(f) => {
+                    Some(filter::parse(f).map_err(|e| RpcError::Params(format!("filter: {e}")))?)
+                }
+                None => None,
+            };
+            let board = provider.load_board()?;
+            let today = dates::today();
+            let cards: Vec<Value> = board
+                .columns
+                .iter()
+                .flat_map(|col| col.cards.iter().map(move |c| (col, c)))
+                .filter(|(col, c)| filter.as_ref().is_none_or(|f| f.matches(col, c, today)))
+                .map(|(col, c)| json!({ "id": c.id, "column": col.id, "title": c.title }))
+                .collect();
+            Ok(Value::Array(cards))
+        }
+        "card.get" => {
+            let id = str_param(params, "id")?;
+            let board = provider.load_board()?;
+            let (col, card) = find(&board, id)?;
+            Ok(card_json(col, card))
+        }
+        "card.path" => {
+            let id = str_param(params, "id")?;
+            let path = provider.card_path(id)?;
+            Ok(json!({ "path": path }))
+        }
+        "card.move" => {
+            let id = str_param(params, "id")?;
+            let column = str_param(params, "column")?;
+            provider.move_card(id, column)?;
+            Ok(json!({ "id": id, "column": column }))
+        }
+        _ => Err(RpcError::Method),
+    }
+}
+
+fn str_param<'a>(params: &'a Value, key: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::Params(format!("missing string param {key}")))
+}
+
+fn find<'a>(board: &'a Board, id: &str) -> Result<(&'a Column, &'a Card), RpcError> {
+    board
+        .columns
+        .iter()
+        .find_map(|col| col.cards.iter().find(|c| c.id == id).map(|c| (col, c)))
+        .ok_or_else(|| {
+            RpcError::Failed(Failure {
+                class: ErrorClass::NotFound,
+                msg: format!("not found: {id}"),
+            })
+        })
+}
+
+fn card_json(col: &Column, card: &Card) -> Value {
+    json!({
+        "id": card.id,
+        "column": col.id,
+        "title": card.title,
+        "description": card.description,
+        "labels": card.labels,
+        "priority": card.priority,
+        "assignee": card.assignee,
+        "due": card.due,
+        "fields": card.fields,
+    })
+}
+
+fn error(id: Value, code: i64, msg: &str, class: Option<ErrorClass>) -> String {
+    let mut err = json!({ "code": code, "message": msg });
+    if let Some(class) = class {
+        err["data"] = json!({ "kind": class.name() });
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": err }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{model::ColumnMeta, provider::ProviderError};
+
+    struct Fake {
+        moved: Vec<(String, String)>,
+    }
+
+    impl Provider for Fake {
+        fn load_board(&mut self) -> Result<Board, ProviderError> {
+            Ok(Board {
+                columns: vec![Column {
+                    id: "todo".to_string(),
+                    title: "TODO".to_string(),
+                    cards: vec![Card {
+                        id: "A-1".to_string(),
+                        title: "One".to_string(),
+                        description: "Body".to_string(),
+                        ..Default::default()
+                    }],
+                    meta: ColumnMeta::default(),
+                }],
+                problems: vec![],
+            })
+        }
+
+        fn move_card(&mut self, card_id: &str, to_col_id: &str) -> Result<(), ProviderError> {
+            self.moved
+                .push((card_id.to_string(), to_col_id.to_string()));
+            Ok(())
+        }
+    }
+
+    fn ask(p: &mut Fake, req: &str) -> Value {
+        serde_json::from_str(&handle(p, req).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn card_get_and_move() {
+        let mut p = Fake { moved: vec![] };
+
+        let resp = ask(
+            &mut p,
+            r#"{"jsonrpc":"2.0","id":1,"method":"card.get","params":{"id":"A-1"}}"#,
+        );
+        assert_eq!(resp["result"]["description"], "Body");
+        assert_eq!(resp["result"]["column"], "todo");
+
+        ask(
+            &mut p,
+            r#"{"jsonrpc":"2.0","id":2,"method":"card.move","params":{"id":"A-1","column":"done"}}"#,
+        );
+        assert_eq!(p.moved, vec![("A-1".to_string(), "done".to_string())]);
+    }
+
+    #[test]
+    fn errors_use_json_rpc_codes() {
+        let mut p = Fake { moved: vec![] };
+
+        let resp = ask(&mut p, r#"{"jsonrpc":"2.0","id":1,"method":"nope"}"#);
+        assert_eq!(resp["error"]["code"], METHOD_NOT_FOUND);
+
+        let resp = ask(
+            &mut p,
+            r#"{"jsonrpc":"2.0","id":2,"method":"card.get","params":{"id":"X-9"}}"#,
+        );
+        assert_eq!(resp["error"]["code"], SERVER_ERROR);
+        assert_eq!(resp["error"]["data"]["kind"], "not_found");
+
+        let resp = ask(
+            &mut p,
+            r#"{"jsonrpc":"2.0","id":3,"method":"card.path","params":{"id":"A-1"}}"#,
+        );
+        assert_eq!(resp["error"]["code"], SERVER_ERROR);
+
+        assert!(handle(&mut p, r#"{"jsonrpc":"2.0","method":"board.get"}"#).is_none());
+    }
+}
@@ -0,0 +1,593 @@
+//! `flow serve`: a small read-mostly HTTP API over the active board, with an
+//! optional Prometheus `/metrics` endpoint.
+//!
+//! - `GET /` — the board as a read-only HTML page
+//! - `GET /board` — the board as JSON (the `flow view` schema)
+//! - `GET /cards/{id}` — one card
+//! - `POST /cards/{id}/move` with `{"column": "..."}` — queued, answers 202
+//! - `GET /metrics` — only with `--metrics`
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::Receiver,
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    app::App,
+    backup, config, dates,
+    events::{self, Event},
+    frontend::{Frontend, Html},
+    model::Board,
+    provider::{self, Provider},
+    provider_metrics::{self, OpStats},
+    stats,
+    worker::{Job, MoveOutcome, Reply, Worker},
+};
+
+/// Requests are served one at a time, so an idle connection (a browser's
+/// preconnect) holds up the rest for at most this long.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn run(addr: &str, metrics: bool) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    eprintln!("flow serve: listening on http://{local}");
+    backup::spawn(
+        config::load().unwrap_or_default().backup,
+        config::active_profile(),
+    );
+
+    let mut server = Server {
+        local,
+        provider: provider::from_env(),
+        worker: Worker::spawn(provider::from_env),
+        queued: 0,
+        failed_moves: 0,
+        metrics,
+        events: events::subscribe(),
+        event_counts: BTreeMap::new(),
+    };
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        if let Err(e) = stream
+            .set_read_timeout(Some(READ_TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(READ_TIMEOUT)))
+        {
+            eprintln!("flow serve: {e}");
+            continue;
+        }
+        server.drain();
+        let resp = match read_request(&mut stream) {
+            Ok(req) => server.route(&req),
+            Err(e) => Response::text(400, &format!("bad request: {e}")),
+        };
+        if let Err(e) = resp.write_to(&mut stream) {
+            eprintln!("flow serve: {e}");
+        }
+    }
+    Ok(())
+}
+
+struct Server {
+    local: SocketAddr,
+    provider: Box<dyn Provider>,
+    /// Moves run here so a slow backend doesn't hold up reads.
+    worker: Worker,
+    queued: usize,
+    failed_moves: u64,
+    metrics: bool,
+    events: Receiver<Event>,
+    event_counts: BTreeMap<&'static str, u64>,
+}
+
+impl Server {
+    fn drain(&mut self) {
+        while let Ok(reply) = self.worker.try_recv() {
+            if let Reply::Moved(outcome) = reply {
+                self.queued = self.queued.saturating_sub(1);
+                if let MoveOutcome::Failed { card_id, msg, .. } = outcome {
+                    self.failed_moves += 1;
+                    eprintln!("flow serve: move {card_id} failed: {msg}");
+                }
+            }
+        }
+        for event in self.events.try_iter() {
+            *self.event_counts.entry(event.kind()).or_default() += 1;
+        }
+    }
+
+    fn route(&mut self, req: &Request) -> Response {
+        let segments: Vec<&str> = req.path.trim_matches('/').split('/').collect();
+        match (req.method.as_str(), segments.as_slice()) {
+            ("GET", [""]) => match self.provider.load_board() {
+                Ok(board) => {
+                    let mut html = Html::default();
+                    let _ = html.draw(&App::new(board));
+                    Response {
+                        status: 200,
+                        content_type: "text/html; charset=utf-8",
+                        body: html.page.into_bytes(),
+                    }
+                }
+                Err(e) => Response::text(502, &e.to_string()),
+            },
+            ("GET", ["board"]) => match self.provider.load_board() {
+                Ok(board) => Response::json(200, &serde_json::to_value(&board).unwrap_or_default()),
+                Err(e) => Response::text(502, &e.to_string()),
+            },
+            ("GET", ["cards", id]) => {
+                let board = match self.provider.load_board() {
+                    Ok(b) => b,
+                    Err(e) => return Response::text(502, &e.to_string()),
+                };
+                let found = board.columns.iter().find_map(|col| {
+                    let card = col.cards.iter().find(|c| c.id == *id)?;
+                    let mut v = serde_json::to_value(card).ok()?;
+                    v["column"] = json!(col.id);
+                    Some(v)
+                });
+                match found {
+                    Some(v) => Response::json(200, &v),
+                    None => Response::text(404, &format!("not found: {id}")),
+                }
+            }
+            ("POST", ["cards", id, "move"]) => {
+                if let Some(refused) = refuse_write(req, self.local) {
+                    return refused;
+                }
+                let column = serde_json::from_slice::<serde_json::Value>(&req.body)
+                    .ok()
+                    .and_then(|v| v.get("column")?.as_str().map(str::to_string));
+                let Some(column) = column else {
+                    return Response::text(400, "expected {\"column\": \"...\"}");
+                };
+                match self.provider.load_board() {
+                    Ok(board) => {
+                        if let Some(refused) = refuse_move(&board, id, &column) {
+                            return refused;
+                        }
+                    }
+                    Err(e) => return Response::text(502, &e.to_string()),
+                }
+                self.worker.submit(Job::Move {
+                    card_id: id.to_string(),
+                    from: None,
+                    dst: column.clone(),
+                    reconcile: false,
+                });
+                self.queued += 1;
+                Response::json(
+                    202,
+                    &json!({ "id": id, "column": column, "queued": self.queued }),
+                )
+            }
+            ("GET", ["metrics"]) if self.metrics => {
+                let board = self.provider.load_board().ok();
+                let body = metrics_text(
+                    board.as_ref(),
+                    dates::today(),
+                    self.queued,
+                    self.failed_moves,
+                    &provider_metrics::snapshot(),
+                    &self.event_counts,
+                );
+                Response {
+                    status: 200,
+                    content_type: "text/plain; version=0.0.4",
+                    body: body.into_bytes(),
+                }
+            }
+            _ => Response::text(404, "not found"),
+        }
+    }
+}
+
+/// Prometheus text exposition. Without a board (the load failed) only the
+/// queue and provider counters are reported, plus `flow_board_up 0`.
+fn metrics_text(
+    board: Option<&Board>,
+    today: i64,
+    queue_depth: usize,
+    failed_moves: u64,
+    ops: &[(&str, OpStats)],
+    events: &BTreeMap<&str, u64>,
+) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+        for (labels, value) in samples {
+            out.push_str(&format!("{name}{labels} {value}\n"));
+        }
+    };
+
+    metric(
+        "flow_board_up",
+        "gauge",
+        "Whether the last board load succeeded.",
+        vec![(String::new(), (board.is_some() as u8).to_string())],
+    );
+    if let Some(board) = board {
+        let per_column = |f: &dyn Fn(&crate::model::Column) -> Option<usize>| {
+            board
+                .columns
+                .iter()
+                .filter_map(|c| {
+                    Some((
+                        format!("{{column=\"{}\"}}", escape(&c.id)),
+                        f(c)?.to_string(),
+                    ))
+                })
+                .collect()
+        };
+        metric(
+            "flow_cards",
+            "gauge",
+            "Cards per column.",
+            per_column(&|c| Some(c.cards.len())),
+        );
+        metric(
+            "flow_wip_limit",
+            "gauge",
+            "WIP limit per column.",
+            per_column(&|c| c.meta.wip_limit),
+        );
+        let overdue = stats::overdue(board, today);
+        metric(
+            "flow_cards_overdue",
+            "gauge",
+            "Cards past their due date outside terminal columns.",
+            vec![(String::new(), overdue.to_string())],
+        );
+    }
+    metric(
+        "flow_move_queue_depth",
+        "gauge",
+        "Moves accepted but not yet applied.",
+        vec![(String::new(), queue_depth.to_string())],
+    );
+    metric(
+        "flow_move_failures_total",
+        "counter",
+        "Queued moves the provider rejected.",
+        vec![(String::new(), failed_moves.to_string())],
+    );
+    let op_label = |op: &str| format!("{{op=\"{}\"}}", escape(op));
+    metric(
+        "flow_provider_requests_total",
+        "counter",
+        "Provider calls by operation.",
+        ops.iter()
+            .map(|(op, s)| (op_label(op), s.count.to_string()))
+            .collect(),
+    );
+    metric(
+        "flow_provider_errors_total",
+        "counter",
+        "Failed provider calls by operation.",
+        ops.iter()
+            .map(|(op, s)| (op_label(op), s.errors.to_string()))
+            .collect(),
+    );
+    metric(
+        "flow_events_total",
+        "counter",
+        "Board events published since the server started.",
+        events
+            .iter()
+            .map(|(kind, n)| (format!("{{kind=\"{kind}\"}}"), n.to_string()))
+            .collect(),
+    );
+    out
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+struct Request {
+    method: String,
+    path: String,
+    /// Header names are lowercased.
+    headers: BTreeMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+/// Any page the user visits can POST here, so writes must be JSON, which
+/// a browser only sends cross-origin after a CORS preflight we never
+/// answer, and carry no other site's `Origin`. The `Host` must name this
+/// server by a loopback or its bound address, so a rebound DNS name
+/// pointing at us doesn't count as same-origin.
+fn refuse_write(req: &Request, local: SocketAddr) -> Option<Response> {
+    let json = req
+        .header("content-type")
+        .and_then(|t| t.split(';').next())
+        .is_some_and(|t| t.trim().eq_ignore_ascii_case("application/json"));
+    if !json {
+        return Some(Response::text(
+            415,
+            "expected Content-Type: application/json",
+        ));
+    }
+    let Some(host) = req.header("host").filter(|h| own_host(h, local)) else {
+        return Some(Response::text(403, "unexpected Host"));
+    };
+    let own = format!("http://{host}");
+    match req.header("origin") {
+        Some(origin) if origin != own => {
+            Some(Response::text(403, "cross-origin requests are refused"))
+        }
+        _ => None,
+    }
+}
+
+fn own_host(host: &str, local: SocketAddr) -> bool {
+    if let Ok(addr) = host.parse::<SocketAddr>() {
+        return addr.port() == local.port() && (addr.ip().is_loopback() || addr == local);
+    }
+    let (name, port) = host.rsplit_once(':').unwrap_or((host, "80"));
+    name.eq_ignore_ascii_case("localhost") && port.parse() == Ok(local.port())
+}
+
+/// Moves are answered before the worker runs them, so anything it would
+/// certainly refuse is caught here rather than logged after a 202.
+fn refuse_move(board: &Board, card_id: &str, column: &str) -> Option<Response> {
+    if !board.columns.iter().any(|c| c.id == column) {
+        return Some(Response::text(
+            404,
+            &format!("no column {column:?} on the board"),
+        ));
+    }
+    if !board
+        .columns
+        .iter()
+        .flat_map(|c| &c.cards)
+        .any(|c| c.id == card_id)
+    {
+        return Some(Response::text(404, &format!("not found: {card_id}")));
+    }
+    None
+}
+
+fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    parse_request(BufReader::new(stream))
+}
+
+fn parse_request(mut reader: impl BufRead) -> io::Result<Request> {
+    let bad = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts
+        .next()
+        .ok_or_else(|| bad("empty request"))?
+        .to_string();
+    let target = parts.next().ok_or_else(|| bad("missing path"))?;
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let mut headers = BTreeMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let len: usize = match headers.get("content-length") {
+        Some(n) => n.parse().map_err(|_| bad("bad content-length"))?,
+        None => 0,
+    };
+    if len > 1 << 20 {
+        return Err(bad("body too large"));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{body}\n").into_bytes(),
+        }
+    }
+
+    fn json(status: u16, body: &serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            415 => "Unsupported Media Type",
+            502 => "Bad Gateway",
+            _ => "",
+        };
+        write!(
+            out,
+            "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        )?;
+        out.write_all(&self.body)?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Card, Column, ColumnMeta};
+
+    fn card(id: &str, due: Option<&str>) -> Card {
+        Card {
+            id: id.to_string(),
+            title: id.to_string(),
+            due: due.map(str::to_string),
+            ..Card::default()
+        }
+    }
+
+    #[test]
+    fn metrics_report_columns_overdue_queue_and_provider_counters() {
+        let board = Board {
+            columns: vec![
+                Column {
+                    id: "doing".to_string(),
+                    title: "DOING".to_string(),
+                    cards: vec![card("A-1", Some("2024-05-01")), card("A-2", None)],
+                    meta: ColumnMeta {
+                        wip_limit: Some(3),
+                        ..ColumnMeta::default()
+                    },
+                },
+                Column {
+                    id: "done".to_string(),
+                    title: "DONE".to_string(),
+                    cards: vec![card("A-3", Some("2024-05-01"))],
+                    meta: ColumnMeta {
+                        terminal: true,
+                        ..ColumnMeta::default()
+                    },
+                },
+            ],
+            problems: vec![],
+        };
+        let ops = [(
+            "load_board",
+            OpStats {
+                count: 4,
+                errors: 1,
+                total: Duration::ZERO,
+                max: Duration::ZERO,
+            },
+        )];
+
+        let events = [("card_moved", 5)].into();
+        let text = metrics_text(Some(&board), 19_875, 2, 0, &ops, &events);
+
+        for line in [
+            "flow_board_up 1",
+            "flow_cards{column=\"doing\"} 2",
+            "flow_cards{column=\"done\"} 1",
+            "flow_wip_limit{column=\"doing\"} 3",
+            "flow_cards_overdue 1",
+            "flow_move_queue_depth 2",
+            "flow_provider_errors_total{op=\"load_board\"} 1",
+            "flow_events_total{kind=\"card_moved\"} 5",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing {line:?} in\n{text}"
+            );
+        }
+        assert!(!text.contains("flow_wip_limit{column=\"done\"}"));
+    }
+
+    #[test]
+    fn parse_request_reads_body_by_content_length() {
+        let raw = "POST /cards/A-1/move?x=1 HTTP/1.1\r\nHost: x\r\nContent-Length: 18\r\n\r\n{\"column\":\"done\"}\n";
+        let req = parse_request(raw.as_bytes()).unwrap();
+
+        assert_eq!(
+            (req.method.as_str(), req.path.as_str()),
+            ("POST", "/cards/A-1/move")
+        );
+        assert_eq!(req.body, b"{\"column\":\"done\"}\n");
+    }
+
+    #[test]
+    fn writes_need_json_from_no_other_origin() {
+        let req = |headers: &str| {
+            let raw =
+                format!("POST /cards/A-1/move HTTP/1.1\r\nHost: 127.0.0.1:9464\r\n{headers}\r\n");
+            parse_request(raw.as_bytes()).unwrap()
+        };
+        let local: SocketAddr = "127.0.0.1:9464".parse().unwrap();
+        let status = |r: &Request| refuse_write(r, local).map(|resp| resp.status);
+
+        assert_eq!(status(&req("Content-Type: text/plain\r\n")), Some(415));
+        assert_eq!(
+            status(&req(
+                "Content-Type: application/json\r\nOrigin: https://evil.example\r\n"
+            )),
+            Some(403)
+        );
+        assert_eq!(
+            status(&req("content-type: application/json; charset=utf-8\r\n")),
+            None
+        );
+        assert_eq!(
+            status(&req(
+                "Content-Type: application/json\r\nOrigin: http://127.0.0.1:9464\r\n"
+            )),
+            None
+        );
+
+        let rebound = |host: &str| {
+            let raw = format!(
+                "POST /cards/A-1/move HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n\r\n"
+            );
+            status(&parse_request(raw.as_bytes()).unwrap())
+        };
+        assert_eq!(rebound("evil.example:9464"), Some(403));
+        assert_eq!(rebound("127.0.0.1:80"), Some(403));
+        assert_eq!(rebound("localhost:9464"), None);
+        assert_eq!(rebound("[::1]:9464"), None);
+    }
+
+    #[test]
+    fn moves_to_unknown_columns_or_cards_are_refused_up_front() {
+        let board = Board {
+            columns: vec![Column {
+                id: "todo".to_string(),
+                title: "TODO".to_string(),
+                cards: vec![card("A-1", None)],
+                meta: ColumnMeta::default(),
+            }],
+            problems: vec![],
+        };
+        let status = |id: &str, col: &str| refuse_move(&board, id, col).map(|r| r.status);
+        assert_eq!(status("A-1", "todo"), None);
+        assert_eq!(status("A-1", "../../escaped"), Some(404));
+        assert_eq!(status("A-9", "todo"), Some(404));
+    }
+}
@@ -0,0 +1,207 @@
+//! `flow show`: one card on stdout, for shell workflows and fzf previews.
+
+use crate::{
+    dates,
+    model::{Card, Column},
+};
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// The card as markdown, the way it would be written to a card file.
+pub fn plain(col: &Column, card: &Card) -> String {
+    let mut out = format!("# {}\n\n", card.title);
+    out.push_str(&format!("id: {}\ncolumn: {}\n", card.id, col.title));
+    for (k, v) in metadata(card) {
+        out.push_str(&format!("{k}: {v}\n"));
+    }
+    if !card.description.trim().is_empty() {
+        out.push('\n');
+        out.push_str(card.description.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Title, metadata table and description wrapped to `width`, with ANSI
+/// styling unless `color` is false.
+pub fn pretty(col: &Column, card: &Card, width: usize, color: bool) -> String {
+    let style = |code: &str, s: &str| {
+        if color {
+            format!("{code}{s}{RESET}")
+        } else {
+            s.to_string()
+        }
+    };
+    let width = width.max(20);
+    let mut out = String::new();
+
+    out.push_str(&style(BOLD, &card.title));
+    out.push('\n');
+
+    let mut rows = vec![("id", card.id.clone()), ("column", col.title.clone())];
+    let today = dates::today();
+    for (k, v) in metadata(card) {
+        let v = if dates::FIELDS.contains(&k) {
+            dates::show(&v, today)
+        } else {
+            v
+        };
+        rows.push((k, v));
+    }
+    if let Some((done, total)) = checklist(&card.description) {
+        rows.push(("checklist", format!("{done}/{total} done")));
+    }
+    let key_w = rows.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    for (k, v) in rows {
+        let key = format!("{k:<key_w$}");
+        out.push_str(&format!("{}  {v}\n", style(DIM, &key)));
+    }
+
+    if card.description.trim().is_empty() {
+        return out;
+    }
+    out.push('\n');
+    for line in card.description.trim_end().lines() {
+        let trimmed = line.trim_start();
+        if let Some(h) = trimmed.strip_prefix('#') {
+            out.push_str(&style(BOLD, h.trim_start_matches('#').trim()));
+            out.push('\n');
+        } else if let Some((checked, text)) = task(trimmed) {
+            let (mark, code) = if checked {
+                ("✔", GREEN)
+            } else {
+                ("☐", DIM)
+            };
+            push_wrapped(&mut out, &format!("{} ", style(code, mark)), 2, text, width);
+        } else if let Some(text) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            push_wrapped(&mut out, &format!("{} ", style(CYAN, "•")), 2, text, width);
+        } else {
+            push_wrapped(&mut out, "", 0, line, width);
+        }
+    }
+    out
+}
+
+/// The typed metadata, then every other field, as display rows.
+fn metadata(card: &Card) -> Vec<(&str, String)> {
+    let mut rows = vec![];
+    if !card.labels.is_empty() {
+        rows.push(("labels", card.labels.join(", ")));
+    }
+    for (k, v) in [
+        ("priority", &card.priority),
+        ("assignee", &card.assignee),
+        ("due", &card.due),
+    ] {
+        if let Some(v) = v {
+            rows.push((k, v.clone()));
+        }
+    }
+    rows.extend(card.fields.iter().map(|(k, v)| (k.as_str(), v.join(", "))));
+    rows
+}
+
+/// `(done, total)` over `- [ ]` / `- [x]` items, if there are any.
+pub fn checklist(description: &str) -> Option<(usize, usize)> {
+    let items: Vec<bool> = description
+        .lines()
+        .filter_map(|l| task(l.trim_start()).map(|(checked, _)| checked))
+        .collect();
+    (!items.is_empty()).then(|| (items.iter().filter(|c| **c).count(), items.len()))
+}
+
+fn task(line: &str) -> Option<(bool, &str)> {
+    let rest = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))?;
+    if let Some(t) = rest.strip_prefix("[ ] ") {
+        Some((false, t))
+    } else {
+        rest.strip_prefix("[x] ")
+            .or_else(|| rest.strip_prefix("[X] "))
+            .map(|t| (true, t))
+    }
+}
+
+/// Word-wraps `text` after `prefix` (of visible width `indent`), indenting
+/// continuation lines to match.
+fn push_wrapped(out: &mut String, prefix: &str, indent: usize, text: &str, width: usize) {
+    if text.trim().is_empty() {
+        out.push('\n');
+        return;
+    }
+    let pad = " ".repeat(indent);
+    let mut lead = prefix;
+    let mut line = String::new();
+    let mut len = 0;
+    for word in text.split_whitespace() {
+        let wlen = word.chars().count();
+        if len > 0 && indent + len + 1 + wlen > width {
+            out.push_str(&format!("{lead}{line}\n"));
+            (lead, line, len) = (&pad, String::new(), 0);
+        }
+        if len > 0 {
+            line.push(' ');
+            len += 1;
+        }
+        line.push_str(word);
+        len += wlen;
+    }
+    out.push_str(&format!("{lead}{line}\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ColumnMeta;
+
+    fn card(description: &str) -> (Column, Card) {
+        let col = Column {
+            id: "doing".to_string(),
+            title: "DOING".to_string(),
+            cards: vec![],
+            meta: ColumnMeta::default(),
+        };
+        let card = Card {
+            id: "A-1".to_string(),
+            title: "Ship it".to_string(),
+            description: description.to_string(),
+            labels: vec!["bug".to_string(), "ui".to_string()],
+            ..Card::default()
+        };
+        (col, card)
+    }
+
+    #[test]
+    fn pretty_shows_metadata_checklist_and_wraps() {
+        let (col, card) =
+            card("## Steps\n- [x] write it\n- [ ] test it and then make sure it really works\n");
+        let out = pretty(&col, &card, 30, false);
+
+        assert_eq!(
+            out,
+            "Ship it\n\
+             id         A-1\n\
+             column     DOING\n\
+             labels     bug, ui\n\
+             checklist  1/2 done\n\
+             \n\
+             Steps\n\
+             ✔ write it\n\
+             ☐ test it and then make sure\n  it really works\n"
+        );
+    }
+
+    #[test]
+    fn checklist_is_none_without_tasks() {
+        assert_eq!(checklist("- plain bullet\ntext"), None);
+        assert_eq!(checklist("* [X] a\n* [ ] b\n- [ ] c"), Some((1, 3)));
+    }
+}
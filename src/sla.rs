@@ -0,0 +1,81 @@
+//! Time-in-status SLAs. A profile's `[profiles.NAME.sla]` table maps a
+//! column id or title to the days a card may stay there; cards that know when
+//! they entered their status (a `status_since` field) are checked against it.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    dates,
+    model::{Board, Card, Column},
+};
+
+/// Sets `status_days` on every card with a `status_since` date, and
+/// `sla = breached` on those over their column's limit.
+pub fn apply(board: &mut Board, limits: &BTreeMap<String, u32>, today: i64) {
+    for col in &mut board.columns {
+        let limit = limit_for(col, limits);
+        for card in &mut col.cards {
+            let Some(days) = days_in_status(card, today) else {
+                continue;
+            };
+            card.fields
+                .insert("status_days".to_string(), vec![days.to_string()]);
+            if limit.is_some_and(|l| days > i64::from(l)) {
+                card.fields
+                    .insert("sla".to_string(), vec!["breached".to_string()]);
+            }
+        }
+    }
+}
+
+pub fn is_breached(card: &Card) -> bool {
+    card.fields
+        .get("sla")
+        .is_some_and(|v| v.iter().any(|s| s == "breached"))
+}
+
+fn days_in_status(card: &Card, today: i64) -> Option<i64> {
+    let since = dates::parse_date(card.fields.get("status_since")?.first()?)?;
+    Some(today - since)
+}
+
+fn limit_for(col: &Column, limits: &BTreeMap<String, u32>) -> Option<u32> {
+    limits
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(&col.id) || k.eq_ignore_ascii_case(&col.title))
+        .map(|(_, v)| *v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ColumnMeta;
+
+    #[test]
+    fn flags_cards_over_their_columns_limit() {
+        let card = |id: &str, since: &str| Card {
+            id: id.to_string(),
+            fields: [("status_since".to_string(), vec![since.to_string()])].into(),
+            ..Card::default()
+        };
+        let mut board = Board {
+            columns: vec![Column {
+                id: "review".to_string(),
+                title: "In Review".to_string(),
+                cards: vec![
+                    card("A-1", "2024-05-27T09:00:00.000+0000"),
+                    card("A-2", "2024-05-30"),
+                ],
+                meta: ColumnMeta::default(),
+            }],
+            problems: vec![],
+        };
+
+        apply(&mut board, &[("in review".to_string(), 3)].into(), 19_875);
+
+        let cards = &board.columns[0].cards;
+        assert!(is_breached(&cards[0]));
+        assert_eq!(cards[0].fields["status_days"], vec!["5"]);
+        assert!(!is_breached(&cards[1]));
+    }
+}
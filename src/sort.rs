@@ -0,0 +1,111 @@
+//! Column `auto-sort`: a column that declares a sort key places every card
+//! moved into it at its sorted position instead of the bottom.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Card;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortKey {
+    /// `id`, `title`, or any field `Card::values` knows.
+    pub field: String,
+    pub desc: bool,
+}
+
+impl SortKey {
+    /// `"priority desc"`, `"due"` or `"due asc"`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut words = s.split_whitespace();
+        let field = words.next()?.to_string();
+        let desc = match words.next().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => return None,
+        };
+        words.next().is_none().then_some(Self { field, desc })
+    }
+
+    /// Where `card` goes among `cards`, which are assumed sorted already:
+    /// after everything that sorts before or level with it.
+    pub fn insert_at(&self, cards: &[Card], card: &Card) -> usize {
+        cards
+            .iter()
+            .position(|c| self.cmp(card, c) == Ordering::Less)
+            .unwrap_or(cards.len())
+    }
+
+    /// Cards without the field sort last in either direction.
+    fn cmp(&self, a: &Card, b: &Card) -> Ordering {
+        match (self.value(a), self.value(b)) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(x), Some(y)) => {
+                let ord = compare(&self.field, x, y);
+                if self.desc { ord.reverse() } else { ord }
+            }
+        }
+    }
+
+    fn value<'a>(&self, card: &'a Card) -> Option<&'a str> {
+        match self.field.to_ascii_lowercase().as_str() {
+            "id" => Some(&card.id),
+            "title" => Some(&card.title),
+            _ => card.values(&self.field).into_iter().next(),
+        }
+    }
+}
+
+/// Priorities by importance, numbers numerically, anything else as text.
+fn compare(field: &str, a: &str, b: &str) -> Ordering {
+    if field.eq_ignore_ascii_case("priority")
+        && let (Some(x), Some(y)) = (priority_rank(a), priority_rank(b))
+    {
+        // Rank 0 is the most important, so it's the largest.
+        return y.cmp(&x);
+    }
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+fn priority_rank(p: &str) -> Option<u8> {
+    Some(match p.to_ascii_lowercase().as_str() {
+        "blocker" | "critical" | "urgent" | "p0" => 0,
+        "highest" | "p1" => 1,
+        "high" | "p2" => 2,
+        "medium" | "normal" | "p3" => 3,
+        "low" | "p4" => 4,
+        "lowest" | "trivial" | "p5" => 5,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_cards_by_priority_with_unset_ones_last() {
+        let card = |id: &str, p: Option<&str>| Card {
+            id: id.to_string(),
+            priority: p.map(str::to_string),
+            ..Card::default()
+        };
+        let key = SortKey::parse("priority desc").unwrap();
+        let cards = [
+            card("A", Some("Highest")),
+            card("B", Some("High")),
+            card("C", Some("Low")),
+            card("D", None),
+        ];
+
+        assert_eq!(key.insert_at(&cards, &card("E", Some("High"))), 2);
+        assert_eq!(key.insert_at(&cards, &card("F", Some("Blocker"))), 0);
+        assert_eq!(key.insert_at(&cards, &card("G", None)), 4);
+        assert_eq!(SortKey::parse("due sideways"), None);
+    }
+}
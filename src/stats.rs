@@ -0,0 +1,277 @@
+//! Board health numbers for `flow stats` and the `t` overlay.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{
+    dates,
+    model::{Board, Card, Move},
+};
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Stats {
+    pub total: usize,
+    pub columns: Vec<ColumnStats>,
+    pub throughput: Throughput,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ColumnStats {
+    pub id: String,
+    pub title: String,
+    pub count: usize,
+    pub wip_limit: Option<usize>,
+    pub over_wip: bool,
+    pub terminal: bool,
+    /// Mean days since each card's `created` (or else `updated`) date, over
+    /// the cards that have one.
+    pub avg_age_days: Option<f64>,
+    /// Mean days cards stayed in the column, over the stays that ended (or
+    /// are ongoing) within the throughput window. Needs the board's history.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_days_in: Option<f64>,
+}
+
+/// Cards moved into a terminal column within the last `days` days; without
+/// history, cards in terminal columns updated then.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Throughput {
+    pub days: i64,
+    pub done: usize,
+}
+
+pub fn compute(board: &Board, today: i64, window: i64) -> Stats {
+    let columns = board
+        .columns
+        .iter()
+        .map(|col| {
+            let ages: Vec<i64> = col
+                .cards
+                .iter()
+                .filter_map(|c| date(c, "created").or_else(|| date(c, "updated")))
+                .map(|d| today - d)
+                .collect();
+            let avg_age_days = (!ages.is_empty()).then(|| {
+                let avg = ages.iter().sum::<i64>() as f64 / ages.len() as f64;
+                (avg * 10.0).round() / 10.0
+            });
+            ColumnStats {
+                id: col.id.clone(),
+                title: col.title.clone(),
+                count: col.cards.len(),
+                wip_limit: col.meta.wip_limit,
+                over_wip: col.meta.wip_limit.is_some_and(|n| col.cards.len() > n),
+                terminal: col.meta.terminal,
+                avg_age_days,
+                avg_days_in: None,
+            }
+        })
+        .collect();
+
+    let done = board
+        .columns
+        .iter()
+        .filter(|col| col.meta.terminal)
+        .flat_map(|col| &col.cards)
+        .filter(|c| date(c, "updated").is_some_and(|d| today - d < window))
+        .count();
+
+    Stats {
+        total: board.columns.iter().map(|c| c.cards.len()).sum(),
+        columns,
+        throughput: Throughput { days: window, done },
+    }
+}
+
+impl Stats {
+    /// Takes throughput and time in column from `moves`, oldest first, as
+    /// of `now` (seconds). No moves leaves the estimates from the board.
+    pub fn with_moves(mut self, board: &Board, moves: &[Move], now: u64) -> Self {
+        if moves.is_empty() {
+            return self;
+        }
+        let window_start = now.saturating_sub(self.throughput.days.max(0) as u64 * 86_400);
+        let terminal: HashSet<&str> = board
+            .columns
+            .iter()
+            .filter(|c| c.meta.terminal)
+            .map(|c| c.id.as_str())
+            .collect();
+        let column_of: HashMap<&str, &str> = board
+            .columns
+            .iter()
+            .flat_map(|col| col.cards.iter().map(|c| (c.id.as_str(), col.id.as_str())))
+            .collect();
+
+        let mut by_card = BTreeMap::<&str, Vec<&Move>>::new();
+        for m in moves {
+            by_card.entry(&m.card_id).or_default().push(m);
+        }
+        let mut stays = HashMap::<&str, Vec<u64>>::new();
+        let mut done = HashSet::new();
+        for (card, moves) in &by_card {
+            for (i, m) in moves.iter().enumerate() {
+                if terminal.contains(m.to.as_str()) && m.at >= window_start {
+                    done.insert(*card);
+                }
+                // The last stay runs to now if the card is still there.
+                let end = match moves.get(i + 1) {
+                    Some(next) => next.at,
+                    None if column_of.get(card) == Some(&m.to.as_str()) => now,
+                    None => continue,
+                };
+                if end >= window_start {
+                    stays
+                        .entry(&m.to)
+                        .or_default()
+                        .push(end.saturating_sub(m.at));
+                }
+            }
+        }
+
+        for col in &mut self.columns {
+            col.avg_days_in = stays.get(col.id.as_str()).map(|secs| {
+                let avg = secs.iter().sum::<u64>() as f64 / secs.len() as f64 / 86_400.0;
+                (avg * 10.0).round() / 10.0
+            });
+        }
+        self.throughput.done = done.len();
+        self
+    }
+}
+
+/// Cards with a `due` date before `today`, outside terminal columns.
+pub fn overdue(board: &Board, today: i64) -> usize {
+    board
+        .columns
+        .iter()
+        .filter(|c| !c.meta.terminal)
+        .flat_map(|c| &c.cards)
+        .filter(|card| {
+            card.due
+                .as_deref()
+                .and_then(dates::parse_date)
+                .is_some_and(|due| due < today)
+        })
+        .count()
+}
+
+fn date(card: &Card, key: &str) -> Option<i64> {
+    dates::parse_date(card.fields.get(key)?.first()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Column, ColumnMeta};
+
+    fn card(id: &str, created: Option<&str>, updated: &str) -> Card {
+        let mut fields = std::collections::BTreeMap::new();
+        if let Some(c) = created {
+            fields.insert("created".to_string(), vec![c.to_string()]);
+        }
+        fields.insert("updated".to_string(), vec![updated.to_string()]);
+        Card {
+            id: id.to_string(),
+            title: id.to_string(),
+            fields,
+            ..Card::default()
+        }
+    }
+
+    fn column(id: &str, cards: Vec<Card>, meta: ColumnMeta) -> Column {
+        Column {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            cards,
+            meta,
+        }
+    }
+
+    // 2024-06-01
+    const TODAY: i64 = 19_875;
+
+    #[test]
+    fn counts_ages_and_throughput() {
+        let board = Board {
+            columns: vec![
+                column(
+                    "doing",
+                    vec![
+                        card("A-1", Some("2024-05-30"), "2024-06-01"),
+                        card("A-2", None, "2024-05-31"),
+                    ],
+                    ColumnMeta {
+                        wip_limit: Some(1),
+                        ..ColumnMeta::default()
+                    },
+                ),
+                column(
+                    "done",
+                    vec![
+                        card("A-3", None, "2024-05-28T10:00:00.000+0000"),
+                        card("A-4", None, "2024-05-01"),
+                    ],
+                    ColumnMeta {
+                        terminal: true,
+                        ..ColumnMeta::default()
+                    },
+                ),
+            ],
+            problems: vec![],
+        };
+
+        let stats = compute(&board, TODAY, 7);
+
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.columns[0].avg_age_days, Some(1.5));
+        assert!(stats.columns[0].over_wip);
+        assert!(!stats.columns[1].over_wip);
+        assert_eq!(stats.throughput, Throughput { days: 7, done: 1 });
+    }
+
+    #[test]
+    fn history_gives_throughput_and_time_in_column() {
+        let board = Board {
+            columns: vec![
+                column(
+                    "doing",
+                    vec![card("B-2", None, "2024-06-01")],
+                    ColumnMeta::default(),
+                ),
+                column(
+                    "done",
+                    vec![card("B-1", None, "2024-06-01")],
+                    ColumnMeta {
+                        terminal: true,
+                        ..ColumnMeta::default()
+                    },
+                ),
+            ],
+            problems: vec![],
+        };
+        let day = 86_400;
+        let now = TODAY as u64 * day;
+        let step = |card: &str, from: Option<&str>, to: &str, days_ago: u64| Move {
+            card_id: card.to_string(),
+            from: from.map(str::to_string),
+            to: to.to_string(),
+            at: now - days_ago * day,
+        };
+        let moves = [
+            step("B-1", None, "doing", 5),
+            step("B-2", None, "doing", 4),
+            step("B-1", Some("doing"), "done", 2),
+            // Archived long ago: its stay in done has no end to count.
+            step("B-9", None, "done", 1),
+        ];
+
+        let stats = compute(&board, TODAY, 7).with_moves(&board, &moves, now);
+
+        assert_eq!(stats.throughput.done, 2);
+        // B-1 stayed 3 days, B-2 has been there 4.
+        assert_eq!(stats.columns[0].avg_days_in, Some(3.5));
+        assert_eq!(stats.columns[1].avg_days_in, Some(2.0));
+    }
+}
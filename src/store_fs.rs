@@ -1,103 +1,808 @@
 use std::{
-    fs, io,
+    cell::RefCell,
+    collections::HashSet,
+    fs,
+    io::{self, Write},
     path::{Path, PathBuf},
-    time::{SystemTime, UNIX_EPOCH},
+    thread,
+    time::{Duration, Instant},
 };
 
-use crate::model::{Board, Card, Column};
+use crate::{
+    activity,
+    card_template::Draft,
+    dates,
+    frontmatter::{self, FrontMatter},
+    log,
+    model::{Board, Card, Column, ColumnMeta, Comment, Problem},
+    sort::SortKey,
+};
+
+/// Held while a mutation runs; scripts editing the board can take it with
+/// `flock .flow/lock <command>`.
+pub const LOCK_FILE: &str = ".flow/lock";
+const LOCK_WAIT: Duration = Duration::from_secs(2);
+
+thread_local! {
+    /// Canonical roots of the boards whose lock this thread holds.
+    static LOCK_HELD: RefCell<HashSet<PathBuf>> = RefCell::new(HashSet::new());
+}
+
+pub struct ColumnDef {
+    pub id: String,
+    pub title: String,
+    /// Front matter keys every card in this column must have.
+    pub require: Vec<String>,
+    /// Attributes this version doesn't understand, preserved on rewrite.
+    pub extra: Vec<(String, String)>,
+}
+
+impl ColumnDef {
+    pub fn new(id: &str, title: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            require: vec![],
+            extra: vec![],
+        }
+    }
+}
 
 pub fn load_board(root: &Path) -> io::Result<Board> {
-    let txt = fs::read_to_string(root.join("board.txt"))?;
     let mut cols = Vec::new();
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+    let defs = read_columns(root)?;
+
+    for def in &defs {
+        let cards = load_cards(root, def, &mut seen, &mut problems);
+        cols.push(Column {
+            id: def.id.clone(),
+            title: def.title.clone(),
+            cards,
+            meta: column_meta(def, &mut problems),
+        });
+    }
+
+    find_orphans(root, &defs, &seen, &mut problems);
+
+    Ok(Board {
+        columns: cols,
+        problems,
+    })
+}
+
+/// Reads `wip=N`, `budget=N`, `terminal=true`, `color=NAME`, `create=false`,
+/// `auto-sort="FIELD [asc|desc]"` and `policy="TEXT"` from a column's
+/// attributes. Bad values are
+/// reported and ignored.
+fn column_meta(def: &ColumnDef, problems: &mut Vec<Problem>) -> ColumnMeta {
+    let mut meta = ColumnMeta::default();
+    for (k, v) in &def.extra {
+        let ok = match k.as_str() {
+            "wip" => v.parse().map(|n| meta.wip_limit = Some(n)).is_ok(),
+            "budget" => v.parse().map(|n| meta.budget = Some(n)).is_ok(),
+            "terminal" => v.parse().map(|b| meta.terminal = b).is_ok(),
+            "create" => v.parse().map(|b| meta.can_create = b).is_ok(),
+            "auto-sort" => SortKey::parse(v)
+                .map(|k| meta.auto_sort = Some(k))
+                .is_some(),
+            "color" => {
+                meta.color = Some(v.clone());
+                true
+            }
+            "policy" => {
+                meta.policy = Some(v.clone()).filter(|p| !p.trim().is_empty());
+                true
+            }
+            _ => true,
+        };
+        if !ok {
+            problems.push(Problem {
+                card_id: None,
+                msg: format!("column {}: invalid {k}={v}", def.id),
+            });
+        }
+    }
+    meta
+}
+
+/// Reports card files no order.txt lists and column directories board.txt
+/// doesn't declare.
+fn find_orphans(
+    root: &Path,
+    defs: &[ColumnDef],
+    listed: &HashSet<String>,
+    problems: &mut Vec<Problem>,
+) {
+    let cols_dir = root.join("cols");
+    let Ok(entries) = fs::read_dir(&cols_dir) else {
+        return;
+    };
+
+    let mut dirs: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .collect();
+    dirs.sort();
+
+    for dir in dirs {
+        if !defs.iter().any(|d| d.id == dir) {
+            problems.push(Problem {
+                card_id: None,
+                msg: format!("cols/{dir} is not a column in board.txt"),
+            });
+            continue;
+        }
+
+        // An unreadable directory is reported by `load_cards`.
+        let Ok(entries) = fs::read_dir(cols_dir.join(&dir)) else {
+            continue;
+        };
+        let mut ids: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter_map(|name| name.strip_suffix(".md").map(|s| s.to_string()))
+            .filter(|id| !listed.contains(id))
+            .collect();
+        ids.sort();
+
+        for id in ids {
+            problems.push(Problem {
+                msg: format!("{id}: cols/{dir}/{id}.md is not listed in order.txt"),
+                card_id: Some(id),
+            });
+        }
+    }
+}
+
+pub fn read_columns(root: &Path) -> io::Result<Vec<ColumnDef>> {
+    let txt = fs::read_to_string(root.join("board.txt"))?;
+    let mut defs = Vec::new();
 
     for line in txt.lines().map(str::trim).filter(|l| !l.is_empty()) {
         let Some(rest) = line.strip_prefix("col ") else {
             continue;
         };
-        let (id, title) = parse_col(rest)?;
-        let cards = load_cards(root, &id)?;
-        cols.push(Column { id, title, cards });
+        defs.push(parse_col(rest)?);
     }
 
-    Ok(Board { columns: cols })
+    Ok(defs)
 }
 
-fn parse_col(rest: &str) -> io::Result<(String, String)> {
-    let mut it = rest.splitn(2, ' ');
-    let Some(id) = it.next() else {
+pub fn write_columns(root: &Path, defs: &[ColumnDef]) -> io::Result<()> {
+    for def in defs {
+        validate_id(&def.id, "column")?;
+        // board.txt has no escapes, so a quote would end the value early.
+        let values = std::iter::once(&def.title).chain(def.extra.iter().map(|(_, v)| v));
+        if let Some(v) = values.into_iter().find(|v| v.contains(['"', '\n'])) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "column {}: quotes and line breaks can't go in {v:?}",
+                    def.id
+                ),
+            ));
+        }
+    }
+    locked(root, || {
+        let mut s = String::new();
+        for def in defs {
+            s.push_str(&format!("col {} \"{}\"", def.id, def.title));
+            if !def.require.is_empty() {
+                s.push_str(&format!(" require={}", def.require.join(",")));
+            }
+            for (k, v) in &def.extra {
+                if v.contains(char::is_whitespace) {
+                    s.push_str(&format!(" {k}=\"{v}\""));
+                } else {
+                    s.push_str(&format!(" {k}={v}"));
+                }
+            }
+            s.push('\n');
+        }
+        fs::create_dir_all(root)?;
+        write_atomic(root.join("board.txt"), s)
+    })
+}
+
+/// Parses `<id> ["Title"] [key=value ...]`. Unquoted titles may span several
+/// words; the first `key=value` token starts the attributes.
+fn parse_col(rest: &str) -> io::Result<ColumnDef> {
+    let mut tokens = tokenize(rest).into_iter();
+    let Some((id, _)) = tokens.next() else {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
             "missing column id",
         ));
     };
-    let title = it.next().unwrap_or(id).trim().trim_matches('"');
-    Ok((id.to_string(), title.to_string()))
+
+    let mut title = Vec::new();
+    let mut def = ColumnDef::new(&id, &id);
+    for (tok, quoted) in tokens {
+        let attr = if quoted { None } else { tok.split_once('=') };
+        match attr {
+            Some(("require", v)) => {
+                def.require = v
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+                    .collect();
+            }
+            Some((k, v)) if !k.is_empty() => def.extra.push((k.to_string(), v.to_string())),
+            _ if def.require.is_empty() && def.extra.is_empty() => title.push(tok),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unexpected {tok:?} after column attributes"),
+                ));
+            }
+        }
+    }
+    if !title.is_empty() {
+        def.title = title.join(" ");
+    }
+    Ok(def)
+}
+
+/// Splits on whitespace, honouring double quotes. Returns each token and
+/// whether it was entirely quoted.
+fn tokenize(s: &str) -> Vec<(String, bool)> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let (mut in_quotes, mut started, mut whole_quoted) = (false, false, false);
+
+    for c in s.chars() {
+        match c {
+            '"' => {
+                if !started {
+                    whole_quoted = true;
+                }
+                in_quotes = !in_quotes;
+                started = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if started {
+                    out.push((std::mem::take(&mut cur), whole_quoted));
+                }
+                (started, whole_quoted) = (false, false);
+            }
+            c => {
+                if !started {
+                    whole_quoted = false;
+                }
+                cur.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        out.push((cur, whole_quoted));
+    }
+    out
 }
 
-fn load_cards(root: &Path, col_id: &str) -> io::Result<Vec<Card>> {
-    let dir = root.join("cols").join(col_id);
+/// A column's cards. Files that can't be read are reported and left out, so
+/// one bad card doesn't keep the board from loading.
+fn load_cards(
+    root: &Path,
+    def: &ColumnDef,
+    seen: &mut HashSet<String>,
+    problems: &mut Vec<Problem>,
+) -> Vec<Card> {
+    let dir = root.join("cols").join(&def.id);
     let order_path = dir.join("order.txt");
     if !order_path.exists() {
-        return Ok(vec![]);
+        return vec![];
     }
 
-    let order = fs::read_to_string(order_path)?;
+    let order = match fs::read_to_string(order_path) {
+        Ok(order) => order,
+        Err(e) => {
+            problems.push(Problem {
+                card_id: None,
+                msg: format!(
+                    "column {}: cols/{}/order.txt unreadable: {e}",
+                    def.id, def.id
+                ),
+            });
+            return vec![];
+        }
+    };
     let mut cards = Vec::new();
 
     for id in order.lines().map(str::trim).filter(|l| !l.is_empty()) {
-        let raw = fs::read_to_string(dir.join(format!("{id}.md")))?;
+        if !seen.insert(id.to_string()) {
+            problems.push(Problem {
+                card_id: Some(id.to_string()),
+                msg: format!("{id}: listed more than once (again in {})", def.title),
+            });
+            continue;
+        }
+
+        let raw = match fs::read_to_string(dir.join(format!("{id}.md"))) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                problems.push(Problem {
+                    card_id: None,
+                    msg: format!(
+                        "{id}: listed in cols/{}/order.txt but file is missing",
+                        def.id
+                    ),
+                });
+                continue;
+            }
+            Err(e) => {
+                problems.push(Problem {
+                    card_id: None,
+                    msg: format!("{id}: cols/{}/{id}.md unreadable: {e}", def.id),
+                });
+                continue;
+            }
+        };
+        let missing = missing_fields(&raw, &def.require);
+        if !missing.is_empty() {
+            problems.push(Problem {
+                card_id: Some(id.to_string()),
+                msg: format!(
+                    "{id}: missing {} (required in {})",
+                    missing.join(", "),
+                    def.title
+                ),
+            });
+        }
         let (title, desc) = parse_md(&raw, id);
-        cards.push(Card {
+        let mut fields = frontmatter::split(&raw)
+            .0
+            .map(|fm| fm.fields())
+            .unwrap_or_default();
+        // The file name is the id; a front matter copy is only for other tools.
+        fields.remove("id");
+        if !fields.contains_key("updated")
+            && let Ok(modified) =
+                fs::metadata(dir.join(format!("{id}.md"))).and_then(|m| m.modified())
+        {
+            let day = dates::day_of(modified);
+            fields.insert("updated".to_string(), vec![dates::format_date(day)]);
+        }
+        let mut card = Card {
             id: id.to_string(),
             title,
             description: desc,
-        });
+            ..Card::default()
+        };
+        card.set_fields(fields);
+        cards.push(card);
     }
 
-    Ok(cards)
+    cards
+}
+
+fn missing_fields(raw: &str, require: &[String]) -> Vec<String> {
+    if require.is_empty() {
+        return vec![];
+    }
+    let fm = frontmatter::split(raw).0.unwrap_or_default();
+    require.iter().filter(|key| !fm.has(key)).cloned().collect()
 }
 
-fn parse_md(raw: &str, fallback: &str) -> (String, String) {
+/// Splits a card file into its title (first line, `# ` stripped) and body.
+pub fn parse_md(raw: &str, fallback: &str) -> (String, String) {
+    let (_, raw) = frontmatter::split(raw);
     let mut lines = raw.lines();
     let first = lines.next().unwrap_or("");
     let title = first.strip_prefix("# ").unwrap_or(first).trim();
     let title = if title.is_empty() { fallback } else { title };
 
-    let rest = raw[first.len()..].trim().to_string();
-    (title.to_string(), rest)
+    let (rest, _) = split_comments(&raw[first.len()..]);
+    (title.to_string(), rest.trim().to_string())
+}
+
+/// Splits a card body at its `## Comments` heading, which `flow` appends to.
+pub fn split_comments(body: &str) -> (&str, &str) {
+    let mut at = 0;
+    for line in body.split_inclusive('\n') {
+        if line.trim_end() == COMMENTS {
+            return (&body[..at], &body[at + line.len()..]);
+        }
+        at += line.len();
+    }
+    (body, "")
+}
+
+const COMMENTS: &str = "## Comments";
+
+/// The comments under a card file's `## Comments` heading, each a
+/// `### author, timestamp` heading and its text.
+pub fn comments(raw: &str) -> Vec<Comment> {
+    let (_, body) = frontmatter::split(raw);
+    let mut out: Vec<Comment> = vec![];
+    for line in split_comments(body).1.lines() {
+        if let Some(head) = line.strip_prefix("### ") {
+            let (author, created) = head.rsplit_once(", ").unwrap_or((head, ""));
+            out.push(Comment {
+                author: author.trim().to_string(),
+                created: created.trim().to_string(),
+                body: String::new(),
+            });
+        } else if let Some(c) = out.last_mut() {
+            c.body.push_str(line);
+            c.body.push('\n');
+        }
+    }
+    for c in &mut out {
+        c.body = c.body.trim().to_string();
+    }
+    out
+}
+
+/// Appends a comment to the card file, starting its `## Comments` section
+/// if need be.
+pub fn add_comment(root: &Path, card_id: &str, author: &str, text: &str) -> io::Result<()> {
+    locked(root, || {
+        let path = card_path(root, card_id)?;
+        let mut raw = fs::read_to_string(&path)?;
+        if !frontmatter::split(&raw)
+            .1
+            .lines()
+            .any(|l| l.trim_end() == COMMENTS)
+        {
+            raw = format!("{}\n\n{COMMENTS}\n", raw.trim_end());
+        }
+        let created = dates::format_timestamp(dates::now_secs());
+        let raw = format!(
+            "{}\n\n### {author}, {created}\n\n{}\n",
+            raw.trim_end(),
+            text.trim()
+        );
+        write_atomic(&path, touch(&raw))?;
+        record_edit(root, card_id, "comment");
+        Ok(())
+    })
+}
+
+/// The inverse of [`parse_md`]: a card file with `fm` (if any) kept as is.
+pub fn render_md(fm: Option<&FrontMatter>, title: &str, description: &str) -> String {
+    let body = match description.trim() {
+        "" => format!("# {title}\n\n"),
+        d => format!("# {title}\n\n{d}\n"),
+    };
+    frontmatter::join(fm, &body)
+}
+
+/// Stamps `updated` on a card file that has front matter; others are left
+/// alone so plain markdown cards stay plain.
+pub fn touch(raw: &str) -> String {
+    match frontmatter::split(raw) {
+        (Some(mut fm), body) => {
+            fm.set("updated", &dates::format_timestamp(dates::now_secs()));
+            frontmatter::join(Some(&fm), body)
+        }
+        (None, _) => raw.to_string(),
+    }
+}
+
+/// Rewrites a card's title and description, keeping its front matter and
+/// comments.
+pub fn update_card(root: &Path, card_id: &str, title: &str, description: &str) -> io::Result<()> {
+    locked(root, || {
+        let path = card_path(root, card_id)?;
+        let raw = fs::read_to_string(&path)?;
+        let (fm, body) = frontmatter::split(&raw);
+        let mut out = render_md(fm.as_ref(), title, description);
+        let comments = split_comments(body).1.trim();
+        if !comments.is_empty() {
+            out = format!("{}\n\n{COMMENTS}\n\n{comments}\n", out.trim_end());
+        }
+        write_atomic(&path, touch(&out))?;
+        record_edit(root, card_id, "text");
+        Ok(())
+    })
+}
+
+/// Sets one front matter field, giving a plain card front matter.
+pub fn set_field(root: &Path, card_id: &str, key: &str, value: &str) -> io::Result<()> {
+    locked(root, || {
+        let path = card_path(root, card_id)?;
+        let raw = fs::read_to_string(&path)?;
+        let (fm, body) = frontmatter::split(&raw);
+        let mut fm = fm.unwrap_or_default();
+        fm.set(key, value);
+        write_atomic(&path, touch(&frontmatter::join(Some(&fm), body)))?;
+        record_edit(root, card_id, key);
+        Ok(())
+    })
+}
+
+/// Removes one front matter field; a card without it is left as it is.
+pub fn clear_field(root: &Path, card_id: &str, key: &str) -> io::Result<()> {
+    locked(root, || {
+        let path = card_path(root, card_id)?;
+        let raw = fs::read_to_string(&path)?;
+        let (Some(mut fm), body) = frontmatter::split(&raw) else {
+            return Ok(());
+        };
+        if !fm.has(key) {
+            return Ok(());
+        }
+        fm.remove(key);
+        write_atomic(&path, touch(&frontmatter::join(Some(&fm), body)))?;
+        record_edit(root, card_id, key);
+        Ok(())
+    })
 }
 
 pub fn move_card(root: &Path, card_id: &str, to_col_id: &str) -> io::Result<()> {
-    let col_ids = list_columns(root)?;
-    let src = find_card_column(root, &col_ids, card_id)?
-        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "card not found"))?;
+    validate_id(card_id, "card")?;
+    validate_id(to_col_id, "column")?;
+    locked(root, || {
+        let defs = read_columns(root)?;
+        let Some(dst) = defs.iter().find(|d| d.id == to_col_id) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no column {to_col_id:?} on the board"),
+            ));
+        };
+        let col_ids: Vec<String> = defs.iter().map(|d| d.id.clone()).collect();
+        let src = find_card_column(root, &col_ids, card_id)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "card not found"))?;
 
-    if src == to_col_id {
-        return Ok(());
+        if src == to_col_id {
+            return Ok(());
+        }
+
+        let raw = fs::read_to_string(root.join("cols").join(&src).join(format!("{card_id}.md")))?;
+        let missing = missing_fields(&raw, &dst.require);
+        if !missing.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{card_id} is missing {} (required in {})",
+                    missing.join(", "),
+                    dst.title
+                ),
+            ));
+        }
+
+        let src_dir = root.join("cols").join(&src);
+        let dst_dir = root.join("cols").join(to_col_id);
+        fs::create_dir_all(&dst_dir)?;
+
+        // Listed in the new column first, so a crash in between leaves the card
+        // listed twice rather than nowhere; `repair` drops the stale entry.
+        order_append(&dst_dir.join("order.txt"), card_id)?;
+        if let Err(e) = fs::rename(
+            src_dir.join(format!("{card_id}.md")),
+            dst_dir.join(format!("{card_id}.md")),
+        ) {
+            let _ = order_remove(&dst_dir.join("order.txt"), card_id);
+            return Err(e);
+        }
+        sync_dir(&src_dir)?;
+        sync_dir(&dst_dir)?;
+        order_remove(&src_dir.join("order.txt"), card_id)?;
+        if let Some(key) = column_meta(dst, &mut vec![]).auto_sort {
+            sort_into_place(root, dst, card_id, &key)?;
+        }
+        record(
+            root,
+            card_id,
+            activity::Kind::Moved {
+                from: Some(src),
+                to: to_col_id.to_string(),
+            },
+        );
+
+        Ok(())
+    })
+}
+
+fn record_edit(root: &Path, card_id: &str, what: &str) {
+    record(
+        root,
+        card_id,
+        activity::Kind::Edited {
+            what: what.to_string(),
+        },
+    );
+}
+
+/// Appends to the activity log. The change is made by now, so a log that
+/// can't be written is only reported.
+fn record(root: &Path, card_id: &str, kind: activity::Kind) {
+    if let Err(e) = activity::append(root, &activity::Entry::now(card_id, kind)) {
+        log::write(&format!("activity: {e}"));
     }
+}
 
-    let src_dir = root.join("cols").join(&src);
-    let dst_dir = root.join("cols").join(to_col_id);
-    fs::create_dir_all(&dst_dir)?;
+/// Moves `card_id` from the end of the column's order to its `key` position.
+/// Order entries that don't load stay where they are.
+fn sort_into_place(root: &Path, def: &ColumnDef, card_id: &str, key: &SortKey) -> io::Result<()> {
+    let mut cards = load_cards(root, def, &mut HashSet::new(), &mut vec![]);
+    let Some(card) = cards
+        .iter()
+        .position(|c| c.id == card_id)
+        .map(|i| cards.remove(i))
+    else {
+        return Ok(());
+    };
+    let path = root.join("cols").join(&def.id).join("order.txt");
+    let mut order: Vec<String> = fs::read_to_string(&path)?
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && *l != card_id)
+        .map(str::to_string)
+        .collect();
+    let at = cards
+        .get(key.insert_at(&cards, &card))
+        .and_then(|next| order.iter().position(|id| *id == next.id))
+        .unwrap_or(order.len());
+    order.insert(at, card.id);
+    write_atomic(path, order.join("\n") + "\n")
+}
 
-    fs::rename(
-        src_dir.join(format!("{card_id}.md")),
-        dst_dir.join(format!("{card_id}.md")),
-    )?;
+/// Rewrites the column's `order.txt` to `order`. Cards listed there that
+/// `order` leaves out, e.g. added meanwhile, keep their order after it.
+pub fn reorder_column(root: &Path, col_id: &str, order: &[String]) -> io::Result<()> {
+    locked(root, || {
+        validate_id(col_id, "column")?;
+        let path = root.join("cols").join(col_id).join("order.txt");
+        let current = card_ids_in(&root.join("cols").join(col_id))?;
+        if let Some(id) = order.iter().find(|id| !current.contains(id)) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{id} is not in {col_id}"),
+            ));
+        }
+        let mut lines: Vec<&str> = order.iter().map(String::as_str).collect();
+        lines.extend(
+            current
+                .iter()
+                .map(String::as_str)
+                .filter(|id| !order.iter().any(|o| o == id)),
+        );
+        let mut s = lines.join("\n");
+        s.push('\n');
+        write_atomic(path, s)
+    })
+}
 
-    order_remove(&src_dir.join("order.txt"), card_id)?;
-    order_append(&dst_dir.join("order.txt"), card_id)?;
+/// `author`, when known, is kept as `created_by`.
+pub fn create_card(
+    root: &Path,
+    to_col_id: &str,
+    id: &str,
+    title: &str,
+    author: Option<&str>,
+) -> io::Result<()> {
+    let draft = Draft {
+        title: title.to_string(),
+        ..Draft::default()
+    };
+    create_card_from(root, to_col_id, id, &draft, author)
+}
 
-    Ok(())
+/// Creates a card from a filled-in template or an import, keeping its front
+/// matter.
+pub fn create_card_from(
+    root: &Path,
+    to_col_id: &str,
+    id: &str,
+    draft: &Draft,
+    author: Option<&str>,
+) -> io::Result<()> {
+    locked(root, || {
+        validate_id(id, "card")?;
+        let col_ids = list_columns(root)?;
+        if find_card_column(root, &col_ids, id)?.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("card already exists: {id}"),
+            ));
+        }
+
+        let dir = root.join("cols").join(to_col_id);
+        fs::create_dir_all(&dir)?;
+        let mut fm = draft.front.clone();
+        fm.set("id", id);
+        // Imported cards bring their own.
+        if !fm.has("created") {
+            fm.set("created", &dates::format_timestamp(dates::now_secs()));
+        }
+        if let Some(author) = author {
+            fm.set("created_by", author);
+        }
+        write_atomic(
+            dir.join(format!("{id}.md")),
+            render_md(Some(&fm), &draft.title, &draft.description),
+        )?;
+        order_append(&dir.join("order.txt"), id)?;
+        record(
+            root,
+            id,
+            activity::Kind::Created {
+                column: to_col_id.to_string(),
+            },
+        );
+        Ok(())
+    })
 }
 
-pub fn create_card(root: &Path, to_col_id: &str) -> io::Result<String> {
-    let id = format!("CARD-{}", now_millis());
-    let dir = root.join("cols").join(to_col_id);
-    fs::create_dir_all(&dir)?;
-    fs::write(dir.join(format!("{id}.md")), "# New card\n\n")?;
-    order_append(&dir.join("order.txt"), &id)?;
-    Ok(id)
+/// Every card id, archived ones included so generated ids never repeat.
+pub fn card_ids(root: &Path) -> io::Result<Vec<String>> {
+    let mut ids = Vec::new();
+    for col_id in list_columns(root)? {
+        ids.extend(card_ids_in(&root.join("cols").join(col_id))?);
+    }
+    ids.extend(archived_ids(root)?);
+    Ok(ids)
+}
+
+/// Moves a card file into `archive/` and drops it from its column's order.
+pub fn archive_card(root: &Path, card_id: &str) -> io::Result<()> {
+    locked(root, || {
+        let path = card_path(root, card_id)?;
+        let dir = root.join("archive");
+        fs::create_dir_all(&dir)?;
+        let dst = dir.join(format!("{card_id}.md"));
+        if dst.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("already archived: {card_id}"),
+            ));
+        }
+        fs::rename(&path, dst)?;
+        sync_dir(path.parent().unwrap_or(root))?;
+        sync_dir(&dir)?;
+        order_remove(&path.with_file_name("order.txt"), card_id)?;
+        record(root, card_id, activity::Kind::Archived);
+        Ok(())
+    })
+}
+
+/// Cards in `archive/`, by id.
+pub fn archived_cards(root: &Path) -> io::Result<Vec<Card>> {
+    let dir = root.join("archive");
+    let mut cards = Vec::new();
+    for id in archived_ids(root)? {
+        let raw = fs::read_to_string(dir.join(format!("{id}.md")))?;
+        let (title, description) = parse_md(&raw, &id);
+        let mut card = Card {
+            id,
+            title,
+            description,
+            ..Card::default()
+        };
+        let mut fields = frontmatter::split(&raw)
+            .0
+            .map(|fm| fm.fields())
+            .unwrap_or_default();
+        fields.remove("id");
+        card.set_fields(fields);
+        cards.push(card);
+    }
+    Ok(cards)
+}
+
+fn archived_ids(root: &Path) -> io::Result<Vec<String>> {
+    let dir = root.join("archive");
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut ids: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|e| {
+            let name = e.ok()?.file_name().into_string().ok()?;
+            Some(name.strip_suffix(".md")?.to_string())
+        })
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+pub fn timestamp_id() -> String {
+    format!("CARD-{}", dates::now_millis())
 }
 
 pub fn card_path(root: &Path, card_id: &str) -> io::Result<PathBuf> {
@@ -107,23 +812,273 @@ pub fn card_path(root: &Path, card_id: &str) -> io::Result<PathBuf> {
     Ok(root.join("cols").join(src).join(format!("{card_id}.md")))
 }
 
-fn now_millis() -> u128 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis()
+/// Replaces a card's file with `text` as edited, wherever the card is now.
+pub fn save_card(root: &Path, card_id: &str, text: &str) -> io::Result<()> {
+    locked(root, || write_atomic(card_path(root, card_id)?, text))
 }
 
-fn list_columns(root: &Path) -> io::Result<Vec<String>> {
-    let txt = fs::read_to_string(root.join("board.txt"))?;
-    Ok(txt
+/// Renames a column id (and optionally its title), moving its directory along.
+pub fn rename_column(root: &Path, old: &str, new: &str, title: Option<&str>) -> io::Result<()> {
+    locked(root, || {
+        validate_id(new, "column")?;
+        let mut defs = read_columns(root)?;
+        if defs.iter().any(|d| d.id == new) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("column already exists: {new}"),
+            ));
+        }
+        let def = defs
+            .iter_mut()
+            .find(|d| d.id == old)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no column: {old}")))?;
+        def.id = new.to_string();
+        if let Some(title) = title {
+            def.title = title.to_string();
+        }
+
+        let old_dir = root.join("cols").join(old);
+        let new_dir = root.join("cols").join(new);
+        if new_dir.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", new_dir.display()),
+            ));
+        }
+        let moved_dir = old_dir.exists();
+        if moved_dir {
+            fs::rename(&old_dir, &new_dir)?;
+        }
+
+        if let Err(err) = write_columns(root, &defs) {
+            if moved_dir {
+                let _ = fs::rename(&new_dir, &old_dir);
+            }
+            return Err(err);
+        }
+        Ok(())
+    })
+}
+
+/// Replaces a label in every card's front matter. Either all cards are
+/// rewritten or, on failure, the ones already written are restored.
+/// Returns the number of cards changed.
+pub fn rename_label(root: &Path, old: &str, new: &str) -> io::Result<usize> {
+    locked(root, || {
+        let mut edits = Vec::new();
+
+        for col_id in list_columns(root)? {
+            let dir = root.join("cols").join(&col_id);
+            for id in card_ids_in(&dir)? {
+                let path = dir.join(format!("{id}.md"));
+                let Ok(raw) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let (Some(mut fm), body) = frontmatter::split(&raw) else {
+                    continue;
+                };
+                let labels = fm.list("labels");
+                if !labels.iter().any(|l| l == old) {
+                    continue;
+                }
+
+                let mut renamed: Vec<String> = Vec::new();
+                for l in labels {
+                    let l = if l == old { new.to_string() } else { l };
+                    if !renamed.contains(&l) {
+                        renamed.push(l);
+                    }
+                }
+                fm.set_list("labels", &renamed);
+                let updated = frontmatter::join(Some(&fm), body);
+                edits.push((path, raw, updated));
+            }
+        }
+
+        for (i, (path, _, updated)) in edits.iter().enumerate() {
+            if let Err(err) = write_atomic(path, updated) {
+                for (path, original, _) in &edits[..i] {
+                    let _ = write_atomic(path, original);
+                }
+                return Err(err);
+            }
+        }
+
+        Ok(edits.len())
+    })
+}
+
+/// Ids name files and directories, so they can't leave them.
+pub fn validate_id(id: &str, what: &str) -> io::Result<()> {
+    if id.is_empty()
+        || id == "."
+        || id.contains("..")
+        || id.contains(|c: char| c.is_whitespace() || matches!(c, '/' | '\\' | '"'))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid {what} id: {id:?}"),
+        ));
+    }
+    Ok(())
+}
+
+fn card_ids_in(dir: &Path) -> io::Result<Vec<String>> {
+    let order_path = dir.join("order.txt");
+    if !order_path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(fs::read_to_string(order_path)?
         .lines()
-        .filter_map(|l| l.trim().strip_prefix("col "))
-        .filter_map(|rest| rest.split_whitespace().next())
-        .map(|s| s.to_string())
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
         .collect())
 }
 
+/// Runs `f` holding the board's write lock, an advisory lock on
+/// [`LOCK_FILE`] that the OS drops if its holder dies, so there is nothing
+/// stale to clean up. Waits up to [`LOCK_WAIT`] for another flow, or a
+/// script under `flock(1)`, to finish. Nested calls reuse the held lock.
+fn locked<T>(root: &Path, f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    batch(root, f)?
+}
+
+/// Runs several changes under one hold of the board's write lock, so no
+/// other writer sees the board half way through them.
+pub fn batch<T>(root: &Path, f: impl FnOnce() -> T) -> io::Result<T> {
+    let path = root.join(LOCK_FILE);
+    fs::create_dir_all(path.parent().unwrap_or(root))?;
+    let key = fs::canonicalize(root)?;
+    if LOCK_HELD.with_borrow(|held| held.contains(&key)) {
+        return Ok(f());
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(fs::TryLockError::WouldBlock) if start.elapsed() < LOCK_WAIT => {
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "board locked by another process",
+                ));
+            }
+            Err(fs::TryLockError::Error(e)) => return Err(e),
+        }
+    }
+    let _held = Held::new(key);
+    Ok(f())
+}
+
+/// Marks the board's lock held on this thread until dropped, even by a
+/// panic the worker catches, so later writes don't skip taking it.
+struct Held(PathBuf);
+
+impl Held {
+    fn new(root: PathBuf) -> Self {
+        LOCK_HELD.with_borrow_mut(|held| held.insert(root.clone()));
+        Held(root)
+    }
+}
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        LOCK_HELD.with_borrow_mut(|held| held.remove(&self.0));
+    }
+}
+
+/// Replaces `path` through a synced temporary file, so a crash leaves
+/// either the old contents or the new, never a torn file.
+fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+    let tmp = path.with_file_name(format!(".{name}.tmp"));
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents.as_ref())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    sync_dir(path.parent().unwrap_or(Path::new(".")))
+}
+
+/// Makes renames and new files in `dir` durable. Only Unix can open a
+/// directory to sync it.
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// What [`repair`] changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Repaired {
+    /// Card files no `order.txt` listed, now at the end of their column's.
+    pub listed: Vec<String>,
+    /// `order.txt` entries for files that aren't there, or repeats, dropped,
+    /// as `column/id`.
+    pub dropped: Vec<String>,
+}
+
+/// Reconciles the order files with the card files, e.g. after a crash mid
+/// move or files copied in by hand: every card file in a column is listed
+/// once, in the column it's in. Directories board.txt doesn't declare are
+/// left alone.
+pub fn repair(root: &Path) -> io::Result<Repaired> {
+    locked(root, || {
+        let mut repaired = Repaired::default();
+        let mut seen = HashSet::new();
+        for col_id in list_columns(root)? {
+            let dir = root.join("cols").join(&col_id);
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut order = vec![];
+            for id in card_ids_in(&dir)? {
+                if dir.join(format!("{id}.md")).is_file() && seen.insert(id.clone()) {
+                    order.push(id);
+                } else {
+                    repaired.dropped.push(format!("{col_id}/{id}"));
+                }
+            }
+            let mut orphans: Vec<String> = fs::read_dir(&dir)?
+                .filter_map(Result::ok)
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter_map(|name| name.strip_suffix(".md").map(str::to_string))
+                .filter(|id| !order.contains(id) && validate_id(id, "card").is_ok())
+                .collect();
+            orphans.sort();
+            for id in orphans {
+                if seen.insert(id.clone()) {
+                    order.push(id.clone());
+                    repaired.listed.push(id);
+                }
+            }
+            let fresh = order.join("\n") + "\n";
+            if fs::read_to_string(dir.join("order.txt")).ok().as_deref() != Some(fresh.as_str()) {
+                write_atomic(dir.join("order.txt"), fresh)?;
+            }
+        }
+        Ok(repaired)
+    })
+}
+
+fn list_columns(root: &Path) -> io::Result<Vec<String>> {
+    Ok(read_columns(root)?.into_iter().map(|def| def.id).collect())
+}
+
 fn find_card_column(root: &Path, cols: &[String], card_id: &str) -> io::Result<Option<String>> {
     for c in cols {
         if root
@@ -151,7 +1106,7 @@ fn order_remove(path: &Path, id: &str) -> io::Result<()> {
     }
     let mut s = out.join("\n");
     s.push('\n');
-    fs::write(path, s)
+    write_atomic(path, s)
 }
 
 fn order_append(path: &Path, id: &str) -> io::Result<()> {
@@ -173,7 +1128,7 @@ fn order_append(path: &Path, id: &str) -> io::Result<()> {
     let mut s = lines.join("\n");
     s.push('\n');
     fs::create_dir_all(path.parent().unwrap())?;
-    fs::write(path, s)
+    write_atomic(path, s)
 }
 
 #[cfg(test)]
@@ -222,12 +1177,33 @@ mod tests {
         fs::remove_dir_all(root).unwrap();
     }
 
+    #[test]
+    fn an_unreadable_card_is_reported_and_the_rest_load() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\nA-2\n");
+        write(&root.join("cols/todo/A-1.md"), "# One\n");
+        fs::write(root.join("cols/todo/A-2.md"), [0xff, 0xfe, b'#']).unwrap();
+
+        let board = load_board(&root).unwrap();
+        assert_eq!(board.columns[0].cards.len(), 1);
+        assert_eq!(board.problems.len(), 1);
+        assert!(
+            board.problems[0]
+                .msg
+                .starts_with("A-2: cols/todo/A-2.md unreadable")
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
     #[test]
     fn create_card_persists_file_and_order() {
         let root = tmp_root();
         write(&root.join("board.txt"), "col todo\n");
 
-        let id = create_card(&root, "todo").unwrap();
+        let id = timestamp_id();
+        create_card(&root, "todo", &id, "New card", Some("Ana Lopez")).unwrap();
         assert!(
             root.join("cols")
                 .join("todo")
@@ -237,6 +1213,336 @@ mod tests {
 
         let order = fs::read_to_string(root.join("cols/todo/order.txt")).unwrap();
         assert!(order.lines().any(|l| l == id));
+        let raw = fs::read_to_string(root.join(format!("cols/todo/{id}.md"))).unwrap();
+        let fm = frontmatter::split(&raw).0.unwrap();
+        assert_eq!(fm.get("id"), Some(id.as_str()));
+        assert!(
+            fm.get("created")
+                .is_some_and(|c| dates::parse_date(c).is_some())
+        );
+        assert_eq!(fm.get("created_by"), Some("Ana Lopez"));
+        assert_eq!(parse_md(&raw, &id).0, "New card");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn update_card_keeps_unknown_front_matter() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\n");
+        write(
+            &root.join("cols/todo/A-1.md"),
+            "---\naliases: [login]\nobsidian:\n  cssclass: wide\n---\n# Old\n\nOld body\n",
+        );
+
+        update_card(&root, "A-1", "New", "New body").unwrap();
+
+        let raw = fs::read_to_string(root.join("cols/todo/A-1.md")).unwrap();
+        assert!(
+            raw.starts_with("---\naliases: [login]\nobsidian:\n  cssclass: wide\nupdated: "),
+            "{raw}"
+        );
+        assert!(raw.ends_with("---\n# New\n\nNew body\n"), "{raw}");
+        let card = &load_board(&root).unwrap().columns[0].cards[0];
+        assert_eq!(card.fields["aliases"], vec!["login"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn archive_moves_the_file_out_of_the_board() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\nA-2\n");
+        write(&root.join("cols/todo/A-1.md"), "# One\n");
+        write(&root.join("cols/todo/A-2.md"), "# Two\n");
+
+        archive_card(&root, "A-1").unwrap();
+
+        let board = load_board(&root).unwrap();
+        assert_eq!(board.columns[0].cards.len(), 1);
+        assert!(board.problems.is_empty());
+        let archived = archived_cards(&root).unwrap();
+        assert_eq!(
+            (archived[0].id.as_str(), archived[0].title.as_str()),
+            ("A-1", "One")
+        );
+        assert_eq!(card_ids(&root).unwrap(), vec!["A-2", "A-1"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn create_card_rejects_existing_id() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\ncol done\n");
+        write(&root.join("cols/done/order.txt"), "A-1\n");
+        write(&root.join("cols/done/A-1.md"), "# A\n");
+
+        let err = create_card(&root, "todo", "A-1", "New card", None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(card_ids(&root).unwrap(), vec!["A-1"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rename_column_moves_directory_and_updates_board() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo \"TO DO\"\ncol done\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\n");
+        write(&root.join("cols/todo/A-1.md"), "# A\n");
+
+        rename_column(&root, "todo", "backlog", None).unwrap();
+
+        let b = load_board(&root).unwrap();
+        assert_eq!(b.columns[0].id, "backlog");
+        assert_eq!(b.columns[0].title, "TO DO");
+        assert_eq!(b.columns[0].cards[0].id, "A-1");
+        assert!(!root.join("cols/todo").exists());
+
+        let err = rename_column(&root, "backlog", "done", None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rename_label_rewrites_front_matter_and_keeps_body() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\nA-2\n");
+        write(
+            &root.join("cols/todo/A-1.md"),
+            "---\nlabels: [bug, ui]\n---\n# A\n",
+        );
+        write(&root.join("cols/todo/A-2.md"), "# No labels\n");
+
+        assert_eq!(rename_label(&root, "bug", "defect").unwrap(), 1);
+
+        let raw = fs::read_to_string(root.join("cols/todo/A-1.md")).unwrap();
+        assert_eq!(raw, "---\nlabels: [defect, ui]\n---\n# A\n");
+        assert_eq!(load_board(&root).unwrap().columns[0].cards[0].title, "A");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn column_attributes_become_metadata() {
+        let root = tmp_root();
+        write(
+            &root.join("board.txt"),
+            "col todo \"To do\" wip=2 budget=1 color=yellow create=false\ncol done \"Done\" terminal=true wip=many policy=\"Reviewed and deployed\"\n",
+        );
+        write(&root.join("cols/todo/order.txt"), "");
+        write(&root.join("cols/done/order.txt"), "");
+
+        let board = load_board(&root).unwrap();
+        let todo = &board.columns[0].meta;
+        assert_eq!(todo.wip_limit, Some(2));
+        assert_eq!(todo.budget, Some(1));
+        assert_eq!(todo.color.as_deref(), Some("yellow"));
+        assert!(!todo.can_create);
+        assert!(board.columns[1].meta.terminal);
+        assert_eq!(board.columns[1].meta.wip_limit, None);
+        assert_eq!(
+            board.columns[1].meta.policy.as_deref(),
+            Some("Reviewed and deployed")
+        );
+        assert!(board.problems.iter().any(|p| p.msg.contains("wip=many")));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn parse_col_reads_title_and_attributes() {
+        let def = parse_col("done \"DONE NOW\" require=completed,reviewer wip=3").unwrap();
+        assert_eq!(def.id, "done");
+        assert_eq!(def.title, "DONE NOW");
+        assert_eq!(def.require, vec!["completed", "reviewer"]);
+        assert_eq!(def.extra, vec![("wip".to_string(), "3".to_string())]);
+
+        let def = parse_col("todo To Do").unwrap();
+        assert_eq!(def.title, "To Do");
+    }
+
+    #[test]
+    fn required_fields_are_reported_on_load_and_block_moves() {
+        let root = tmp_root();
+        write(
+            &root.join("board.txt"),
+            "col todo\ncol done \"DONE\" require=completed,reviewer\n",
+        );
+        write(&root.join("cols/todo/order.txt"), "A-1\n");
+        write(
+            &root.join("cols/todo/A-1.md"),
+            "---\ncompleted: 2024-06-12\n---\n# A\n",
+        );
+        write(&root.join("cols/done/order.txt"), "A-2\n");
+        write(&root.join("cols/done/A-2.md"), "# B\n");
+
+        let b = load_board(&root).unwrap();
+        assert_eq!(b.problems.len(), 1);
+        assert_eq!(b.problems[0].card_id.as_deref(), Some("A-2"));
+
+        let err = move_card(&root, "A-1", "done").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("reviewer"));
+        assert!(root.join("cols/todo/A-1.md").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn moves_into_an_auto_sorted_column_land_in_order() {
+        let root = tmp_root();
+        write(
+            &root.join("board.txt"),
+            "col todo\ncol triage \"Triage\" auto-sort=\"priority desc\"\n",
+        );
+        write(&root.join("cols/todo/order.txt"), "A-1\n");
+        write(
+            &root.join("cols/todo/A-1.md"),
+            "---\npriority: High\n---\n# A\n",
+        );
+        write(&root.join("cols/triage/order.txt"), "B-1\nGONE\nB-2\n");
+        write(
+            &root.join("cols/triage/B-1.md"),
+            "---\npriority: Highest\n---\n# B1\n",
+        );
+        write(
+            &root.join("cols/triage/B-2.md"),
+            "---\npriority: Low\n---\n# B2\n",
+        );
+
+        move_card(&root, "A-1", "triage").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(root.join("cols/triage/order.txt")).unwrap(),
+            "B-1\nGONE\nA-1\nB-2\n"
+        );
+        let b = load_board(&root).unwrap();
+        assert_eq!(b.columns[1].meta.auto_sort, SortKey::parse("priority desc"));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn load_reports_missing_duplicate_and_orphan_cards() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\ncol done\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\nGONE\n");
+        write(&root.join("cols/todo/A-1.md"), "# A\n");
+        write(&root.join("cols/todo/STRAY.md"), "# Stray\n");
+        write(&root.join("cols/done/order.txt"), "A-1\n");
+        write(&root.join("cols/old/X.md"), "# X\n");
+
+        let b = load_board(&root).unwrap();
+        assert_eq!(b.columns[0].cards.len(), 1);
+        assert!(b.columns[1].cards.is_empty());
+
+        let msgs: Vec<_> = b.problems.iter().map(|p| p.msg.as_str()).collect();
+        assert_eq!(msgs.len(), 4);
+        assert!(msgs[0].starts_with("GONE: "));
+        assert!(msgs[1].starts_with("A-1: listed more than once"));
+        assert!(msgs[2].starts_with("cols/old"));
+        assert!(msgs[3].starts_with("STRAY: "));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn mutations_wait_for_the_board_lock() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\ncol done\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\n");
+        write(&root.join("cols/todo/A-1.md"), "# A\n");
+        write(&root.join(LOCK_FILE), "");
+
+        let held = fs::File::open(root.join(LOCK_FILE)).unwrap();
+        held.lock().unwrap();
+        let err = move_card(&root, "A-1", "done").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        let err = save_card(&root, "A-1", "# Edited\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(load_board(&root).unwrap().problems.is_empty());
+
+        held.unlock().unwrap();
+        let panicked = std::panic::catch_unwind(|| batch(&root, || panic!("boom")));
+        assert!(panicked.is_err() && LOCK_HELD.with_borrow(HashSet::is_empty));
+        // Holding one board's lock doesn't let a write to another skip its.
+        let other = tmp_root();
+        write(&other.join("board.txt"), "col todo\n");
+        write(&other.join("cols/todo/B-1.md"), "# B\n");
+        write(&other.join(LOCK_FILE), "");
+        let held = fs::File::open(other.join(LOCK_FILE)).unwrap();
+        held.lock().unwrap();
+        let err = batch(&root, || save_card(&other, "B-1", "# Edited\n"))
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        drop(held);
+        fs::remove_dir_all(other).unwrap();
+        move_card(&root, "A-1", "done").unwrap();
+        // The editor's save lands wherever the card went meanwhile.
+        save_card(&root, "A-1", "# Edited\n").unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("cols/done/A-1.md")).unwrap(),
+            "# Edited\n"
+        );
+        assert!(!root.join("cols/todo/A-1.md").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn moves_stay_between_the_boards_columns() {
+        let root = tmp_root();
+        let board = root.join("board");
+        write(&board.join("board.txt"), "col todo\ncol done\n");
+        write(&board.join("cols/todo/order.txt"), "A-1\n");
+        write(&board.join("cols/todo/A-1.md"), "# A\n");
+
+        for (card, col) in [
+            ("A-1", "../../escaped"),
+            ("A-1", "../.."),
+            ("../../../outside/victim", "done"),
+            ("A-1", "nowhere"),
+        ] {
+            let err = move_card(&board, card, col).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{card} to {col}");
+        }
+        assert_eq!(
+            fs::read_dir(&root).unwrap().count(),
+            1,
+            "nothing is written beside the board"
+        );
+        assert!(!board.join("cols/nowhere").exists());
+        assert!(!board.join("cols/done").exists());
+        assert!(board.join("cols/todo/A-1.md").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn repair_lists_orphans_and_drops_dangling_entries() {
+        let root = tmp_root();
+        write(&root.join("board.txt"), "col todo\ncol done\n");
+        write(&root.join("cols/todo/order.txt"), "A-1\nGONE\n");
+        write(&root.join("cols/todo/A-1.md"), "# A\n");
+        write(&root.join("cols/todo/STRAY.md"), "# Stray\n");
+        write(&root.join("cols/done/order.txt"), "A-1\n");
+
+        let repaired = repair(&root).unwrap();
+        assert_eq!(repaired.listed, ["STRAY"]);
+        assert_eq!(repaired.dropped, ["todo/GONE", "done/A-1"]);
+        assert_eq!(
+            fs::read_to_string(root.join("cols/todo/order.txt")).unwrap(),
+            "A-1\nSTRAY\n"
+        );
+        assert!(load_board(&root).unwrap().problems.is_empty());
+        assert_eq!(repair(&root).unwrap(), Repaired::default());
 
         fs::remove_dir_all(root).unwrap();
     }
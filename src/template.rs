@@ -0,0 +1,173 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::store_fs::{self, ColumnDef};
+
+/// Shareable board structure. Cards are never part of a template.
+#[derive(Serialize, Deserialize)]
+pub struct Template {
+    pub columns: Vec<TemplateColumn>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TemplateColumn {
+    pub id: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub require: Vec<String>,
+    /// The rest of the column's `board.txt` attributes: `wip`, `terminal`,
+    /// `policy` and so on.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+pub fn export(root: &Path) -> io::Result<String> {
+    let columns = store_fs::read_columns(root)?
+        .into_iter()
+        .map(|def| TemplateColumn {
+            id: def.id,
+            title: def.title,
+            require: def.require,
+            attributes: def.extra.into_iter().collect(),
+        })
+        .collect();
+
+    toml::to_string_pretty(&Template { columns }).map_err(io::Error::other)
+}
+
+pub fn import(root: &Path, src: &str, force: bool) -> io::Result<()> {
+    let template: Template =
+        toml::from_str(src).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    validate(&template)?;
+
+    if root.join("board.txt").exists() && !force {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{} already has a board.txt (use --force to overwrite)",
+                root.display()
+            ),
+        ));
+    }
+
+    for col in &template.columns {
+        let dir = root.join("cols").join(&col.id);
+        fs::create_dir_all(&dir)?;
+        let order = dir.join("order.txt");
+        if !order.exists() {
+            fs::write(order, "")?;
+        }
+    }
+
+    let defs: Vec<ColumnDef> = template
+        .columns
+        .into_iter()
+        .map(|col| ColumnDef {
+            require: col.require,
+            extra: col.attributes.into_iter().collect(),
+            ..ColumnDef::new(&col.id, &col.title)
+        })
+        .collect();
+    store_fs::write_columns(root, &defs)
+}
+
+fn validate(template: &Template) -> io::Result<()> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    if template.columns.is_empty() {
+        return Err(invalid("template has no columns".to_string()));
+    }
+
+    let mut seen = HashSet::new();
+    for col in &template.columns {
+        store_fs::validate_id(&col.id, "column").map_err(|e| invalid(e.to_string()))?;
+        if col.title.contains('"') {
+            return Err(invalid(format!("invalid column title: {:?}", col.title)));
+        }
+        if !seen.insert(col.id.as_str()) {
+            return Err(invalid(format!("duplicate column id: {}", col.id)));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    fn tmp_root() -> PathBuf {
+        let n = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("flow-template-test-{n}"))
+    }
+
+    #[test]
+    fn export_then_import_round_trips_columns() {
+        let src = tmp_root();
+        fs::create_dir_all(&src).unwrap();
+        fs::write(
+            src.join("board.txt"),
+            "col todo \"TO DO\" wip=3 policy=\"Pull, don't push\"\ncol done \"DONE\" require=reviewer terminal=true\n",
+        )
+        .unwrap();
+
+        let toml = export(&src).unwrap();
+
+        let dst = tmp_root();
+        import(&dst, &toml, false).unwrap();
+
+        let cols = store_fs::read_columns(&dst).unwrap();
+        let ids: Vec<_> = cols.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["todo", "done"]);
+        assert_eq!(cols[0].title, "TO DO");
+        assert_eq!(cols[1].require, vec!["reviewer"]);
+        let board = store_fs::load_board(&dst).unwrap();
+        assert_eq!(board.columns[0].meta.wip_limit, Some(3));
+        assert_eq!(
+            board.columns[0].meta.policy.as_deref(),
+            Some("Pull, don't push")
+        );
+        assert!(board.columns[1].meta.terminal);
+        assert!(dst.join("cols/done/order.txt").exists());
+
+        fs::remove_dir_all(src).unwrap();
+        fs::remove_dir_all(dst).unwrap();
+    }
+
+    #[test]
+    fn import_refuses_to_overwrite_without_force() {
+        let root = tmp_root();
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("board.txt"), "col todo\n").unwrap();
+
+        let src = "[[columns]]\nid = \"doing\"\ntitle = \"Doing\"\n";
+        let err = import(&root, src, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+        import(&root, src, true).unwrap();
+        assert_eq!(store_fs::read_columns(&root).unwrap()[0].id, "doing");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn import_rejects_duplicate_column_ids() {
+        let src =
+            "[[columns]]\nid = \"a\"\ntitle = \"A\"\n\n[[columns]]\nid = \"a\"\ntitle = \"B\"\n";
+        let err = import(&tmp_root(), src, false).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
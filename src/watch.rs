@@ -0,0 +1,76 @@
+//! Watches a local board's directory, so changes made outside flow (an
+//! editor, a script, `git pull`) reload the board without `r`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+pub struct BoardWatcher {
+    // Dropping it ends the watch.
+    _watcher: RecommendedWatcher,
+    changes: Receiver<()>,
+}
+
+pub fn watch(root: &Path) -> notify::Result<BoardWatcher> {
+    let (tx, changes) = mpsc::channel();
+    let base = root.to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        if let Ok(event) = res
+            && !matches!(event.kind, EventKind::Access(_))
+            && event.paths.iter().any(|p| board_file(&base, p))
+        {
+            let _ = tx.send(());
+        }
+    })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(BoardWatcher {
+        _watcher: watcher,
+        changes,
+    })
+}
+
+impl BoardWatcher {
+    /// Whether a board file changed since the last call.
+    pub fn changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+}
+
+/// Card files, `order.txt` and `board.txt`; not flow's own activity log or
+/// instance lock.
+fn board_file(root: &Path, path: &Path) -> bool {
+    let rel = path
+        .strip_prefix(root)
+        .map_or(PathBuf::from(path), PathBuf::from);
+    if rel.components().any(|c| c.as_os_str() == ".flow") {
+        return false;
+    }
+    match rel.file_name().and_then(|n| n.to_str()) {
+        Some("order.txt" | "board.txt") => true,
+        Some(name) => name.ends_with(".md"),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_board_files_count() {
+        let root = Path::new("/boards/team");
+        for (path, counts) in [
+            ("cols/todo/T-1.md", true),
+            ("cols/todo/order.txt", true),
+            ("board.txt", true),
+            ("cols/todo/.T-1.md.swp", false),
+            (".flow/events.jsonl", false),
+            (".flow-instance", false),
+        ] {
+            assert_eq!(board_file(root, &root.join(path)), counts, "{path}");
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+    },
+    thread,
+};
+
+use crate::{
+    model::{Board, CardDetail, Mention, QuickFilter},
+    provider::Provider,
+};
+
+pub enum Job {
+    /// With `reconcile`, a successful move is followed by a reload so the UI
+    /// can settle on the provider's view once the queue drains. With `from`,
+    /// a fresh load must still show the card there, or the move isn't sent.
+    Move {
+        card_id: String,
+        from: Option<String>,
+        dst: String,
+        reconcile: bool,
+    },
+    Refresh,
+    /// Raises a capped column's fetch limit, then reloads.
+    LoadMore {
+        col_id: String,
+    },
+    /// Switches the provider's quick filters, then reloads.
+    QuickFilters {
+        filters: Vec<QuickFilter>,
+    },
+    /// Comments mentioning the user in the last `minutes`.
+    Mentions {
+        minutes: u64,
+    },
+    Detail {
+        card_id: String,
+    },
+    /// Several cards moved or archived in one provider call, reporting
+    /// progress as each lands, then reloading.
+    Batch {
+        card_ids: Vec<String>,
+        op: BatchOp,
+    },
+}
+
+pub enum BatchOp {
+    Move { dst: String },
+    Archive,
+}
+
+pub enum Reply {
+    Moved(MoveOutcome),
+    Refreshed(Result<Board, String>),
+    Mentions(Result<Vec<Mention>, String>),
+    Detail {
+        card_id: String,
+        detail: Result<CardDetail, String>,
+    },
+    /// `done` of a batch's cards have landed.
+    BatchProgress {
+        done: usize,
+    },
+    /// A batch finished: the cards it got through, why it stopped short if
+    /// it did, and the board after it.
+    Batched {
+        done: Vec<String>,
+        error: Option<String>,
+        board: Option<Board>,
+    },
+}
+
+/// What a move reports. Boards are fresh loads the UI reconciles with,
+/// keeping the cursor on the same card.
+pub enum MoveOutcome {
+    Done {
+        card_id: String,
+    },
+    Reconciled {
+        card_id: String,
+        board: Board,
+    },
+    Failed {
+        card_id: String,
+        msg: String,
+        board: Option<Board>,
+    },
+    /// The card left `from` since the move was queued: it's in `found`, or
+    /// gone when that's `None`. Nothing was sent.
+    Conflict {
+        card_id: String,
+        from: String,
+        dst: String,
+        found: Option<String>,
+        board: Board,
+    },
+}
+
+/// A long-lived thread owning one provider, so moves and refreshes reuse its
+/// configuration and HTTP client. Jobs run in submission order.
+pub struct Worker {
+    jobs: Sender<(u64, Job)>,
+    replies: Receiver<(u64, Reply)>,
+    epoch: Arc<AtomicU64>,
+}
+
+impl Worker {
+    /// `make` builds the provider on the worker thread, and again if a job
+    /// panics and leaves the old one in an unknown state.
+    pub fn spawn<F>(make: F) -> Self
+    where
+        F: Fn() -> Box<dyn Provider> + Send + 'static,
+    {
+        let (jobs, job_rx) = mpsc::channel::<(u64, Job)>();
+        let (reply_tx, replies) = mpsc::channel();
+        let epoch = Arc::new(AtomicU64::new(0));
+        let current = Arc::clone(&epoch);
+
+        thread::spawn(move || {
+            let mut provider = make();
+            for (job_epoch, job) in job_rx {
+                if job_epoch < current.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let mut progress = |reply| {
+                    let _ = reply_tx.send((job_epoch, reply));
+                };
+                let reply = match panic::catch_unwind(AssertUnwindSafe(|| {
+                    run(provider.as_mut(), &job, &mut progress)
+                })) {
+                    Ok(reply) => reply,
+                    Err(_) => {
+                        provider = make();
+                        panicked(job)
+                    }
+                };
+                if reply_tx.send((job_epoch, reply)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            jobs,
+            replies,
+            epoch,
+        }
+    }
+
+    pub fn submit(&self, job: Job) {
+        let _ = self.jobs.send((self.epoch.load(Ordering::SeqCst), job));
+    }
+
+    /// Drops jobs that haven't started yet and discards replies to anything
+    /// submitted so far.
+    pub fn cancel(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn try_recv(&self) -> Result<Reply, TryRecvError> {
+        loop {
+            let (job_epoch, reply) = self.replies.try_recv()?;
+            if job_epoch >= self.epoch.load(Ordering::SeqCst) {
+                return Ok(reply);
+            }
+        }
+    }
+}
+
+/// `progress` passes on replies sent before the job's last.
+fn run(p: &mut dyn Provider, job: &Job, progress: &mut dyn FnMut(Reply)) -> Reply {
+    match job {
+        Job::Batch { card_ids, op } => {
+            let mut done = vec![];
+            let mut landed = |card_id: &str| {
+                done.push(card_id.to_string());
+                progress(Reply::BatchProgress { done: done.len() });
+            };
+            let res = match op {
+                BatchOp::Move { dst } => p.move_cards(card_ids, dst, &mut landed),
+                BatchOp::Archive => p.archive_cards(card_ids, &mut landed),
+            };
+            Reply::Batched {
+                done,
+                error: res.err().map(|e| e.to_string()),
+                board: p.load_board().ok(),
+            }
+        }
+        Job::Refresh => Reply::Refreshed(p.load_board().map_err(|e| e.to_string())),
+        Job::LoadMore { col_id } => Reply::Refreshed(
+            p.load_more(col_id)
+                .and_then(|()| p.load_board())
+                .map_err(|e| e.to_string()),
+        ),
+        Job::QuickFilters { filters } => Reply::Refreshed(
+            p.set_quick_filters(filters)
+                .and_then(|()| p.load_board())
+                .map_err(|e| e.to_string()),
+        ),
+        Job::Mentions { minutes } => {
+            Reply::Mentions(p.mentions(*minutes).map_err(|e| e.to_string()))
+        }
+        Job::Detail { card_id } => Reply::Detail {
+            card_id: card_id.clone(),
+            detail: p.card_detail(card_id).map_err(|e| e.to_string()),
+        },
+        Job::Move {
+            card_id,
+            from,
+            dst,
+            reconcile,
+        } => {
+            let card_id = card_id.clone();
+            if let Some(from) = from {
+                let board = match p.load_board() {
+                    Ok(board) => board,
+                    Err(e) => {
+                        return Reply::Moved(MoveOutcome::Failed {
+                            card_id,
+                            msg: e.to_string(),
+                            board: None,
+                        });
+                    }
+                };
+                let found = board.column_of(&card_id).map(|c| c.id.clone());
+                if found.as_ref() != Some(from) {
+                    return Reply::Moved(MoveOutcome::Conflict {
+                        card_id,
+                        from: from.clone(),
+                        dst: dst.clone(),
+                        found,
+                        board,
+                    });
+                }
+            }
+            Reply::Moved(match p.move_card(&card_id, dst) {
+                Ok(()) if !reconcile => MoveOutcome::Done { card_id },
+                Ok(()) => match p.load_board() {
+                    Ok(board) => MoveOutcome::Reconciled { card_id, board },
+                    Err(_) => MoveOutcome::Done { card_id },
+                },
+                Err(move_err) => MoveOutcome::Failed {
+                    card_id,
+                    msg: move_err.to_string(),
+                    board: p.load_board().ok(),
+                },
+            })
+        }
+    }
+}
+
+fn panicked(job: Job) -> Reply {
+    let msg = "worker panicked".to_string();
+    match job {
+        Job::Refresh | Job::LoadMore { .. } | Job::QuickFilters { .. } => {
+            Reply::Refreshed(Err(msg))
+        }
+        Job::Mentions { .. } => Reply::Mentions(Err(msg)),
+        Job::Detail { card_id } => Reply::Detail {
+            card_id,
+            detail: Err(msg),
+        },
+        Job::Move { card_id, .. } => Reply::Moved(MoveOutcome::Failed {
+            card_id,
+            msg,
+            board: None,
+        }),
+        Job::Batch { .. } => Reply::Batched {
+            done: vec![],
+            error: Some(msg),
+            board: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::{Card, Column, ColumnMeta},
+        provider::ProviderError,
+    };
+    use std::{
+        sync::atomic::AtomicUsize,
+        time::{Duration, Instant},
+    };
+
+    static BUILT: AtomicUsize = AtomicUsize::new(0);
+
+    struct Fake;
+
+    impl Provider for Fake {
+        fn load_board(&mut self) -> Result<Board, ProviderError> {
+            Ok(Board {
+                columns: vec![Column {
+                    id: "todo".to_string(),
+                    title: "TODO".to_string(),
+                    cards: vec![Card {
+                        id: "A-1".to_string(),
+                        title: "One".to_string(),
+                        description: String::new(),
+                        ..Default::default()
+                    }],
+                    meta: ColumnMeta::default(),
+                }],
+                problems: vec![],
+            })
+        }
+
+        fn move_card(&mut self, card_id: &str, _to_col_id: &str) -> Result<(), ProviderError> {
+            match card_id {
+                "boom" => panic!("boom"),
+                "A-1" => Ok(()),
+                _ => Err(ProviderError::NotFound {
+                    id: card_id.to_string(),
+                }),
+            }
+        }
+    }
+
+    fn recv(w: &Worker) -> Reply {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match w.try_recv() {
+                Ok(r) => return r,
+                Err(TryRecvError::Empty) if Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(1))
+                }
+                Err(e) => panic!("no reply: {e:?}"),
+            }
+        }
+    }
+
+    fn mv(card_id: &str) -> Job {
+        Job::Move {
+            card_id: card_id.to_string(),
+            from: None,
+            dst: "todo".to_string(),
+            reconcile: true,
+        }
+    }
+
+    #[test]
+    fn one_provider_serves_every_job_until_a_panic() {
+        let w = Worker::spawn(|| {
+            BUILT.fetch_add(1, Ordering::SeqCst);
+            Box::new(Fake)
+        });
+
+        w.submit(mv("A-1"));
+        w.submit(Job::Refresh);
+        w.submit(mv("X-9"));
+        assert!(matches!(
+            recv(&w),
+            Reply::Moved(MoveOutcome::Reconciled { .. })
+        ));
+        assert!(matches!(recv(&w), Reply::Refreshed(Ok(_))));
+        assert!(matches!(
+            recv(&w),
+            Reply::Moved(MoveOutcome::Failed { board: Some(_), .. })
+        ));
+        assert_eq!(BUILT.load(Ordering::SeqCst), 1);
+
+        w.submit(mv("boom"));
+        let Reply::Moved(MoveOutcome::Failed { msg, .. }) = recv(&w) else {
+            panic!("expected failure");
+        };
+        assert_eq!(msg, "worker panicked");
+        assert_eq!(BUILT.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn cancel_discards_earlier_jobs() {
+        let w = Worker::spawn(|| Box::new(Fake));
+
+        w.submit(Job::Refresh);
+        w.cancel();
+        w.submit(mv("A-1"));
+
+        assert!(matches!(
+            recv(&w),
+            Reply::Moved(MoveOutcome::Reconciled { .. })
+        ));
+    }
+
+    #[test]
+    fn a_queued_move_is_held_back_when_the_card_moved_meanwhile() {
+        let w = Worker::spawn(|| Box::new(Fake));
+
+        w.submit(Job::Move {
+            card_id: "A-1".to_string(),
+            from: Some("doing".to_string()),
+            dst: "done".to_string(),
+            reconcile: false,
+        });
+
+        let Reply::Moved(MoveOutcome::Conflict { card_id, found, .. }) = recv(&w) else {
+            panic!("expected a conflict");
+        };
+        assert_eq!((card_id.as_str(), found.as_deref()), ("A-1", Some("todo")));
+    }
+
+    #[test]
+    fn a_batch_reports_each_card_then_stops_at_a_failure() {
+        let w = Worker::spawn(|| Box::new(Fake));
+
+        w.submit(Job::Batch {
+            card_ids: vec!["A-1".to_string(), "X-9".to_string(), "A-1".to_string()],
+            op: BatchOp::Move {
+                dst: "done".to_string(),
+            },
+        });
+
+        assert!(matches!(recv(&w), Reply::BatchProgress { done: 1 }));
+        let Reply::Batched { done, error, board } = recv(&w) else {
+            panic!("expected the batch to finish");
+        };
+        assert_eq!(done, ["A-1"]);
+        assert!(error.is_some_and(|e| e.contains("X-9")) && board.is_some());
+    }
+}